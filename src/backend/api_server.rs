@@ -1,52 +1,527 @@
-use actix_web::{web, App, HttpServer, HttpResponse, Error};
+use actix_web::{web, App, HttpRequest, HttpServer, HttpResponse, Error};
 use actix_cors::Cors;
+use base64::Engine;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::signature::Keypair;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::str::FromStr;
+use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
 
-use crate::pump_fun::PumpFunClient;
+use crate::bundle_queue::{BundleQueue, QueueFull, QueueLimits, QueueTicket, QueuedBundle};
+use crate::config::Config;
+use crate::jito_bundle::{bundle_tip_lamports, JitoBundleClient};
+use crate::deadline::{Deadline, DeadlineExceeded, DEADLINE_HEADER};
+use crate::idempotency::{CachedResponse, Claim, IdempotencyCache, IDEMPOTENCY_HEADER};
+use crate::error::PumpFunError;
+use crate::keys;
+use crate::logging::Secret;
+use crate::fee_autopilot::FeeAutopilot;
+use crate::metadata::{FetchError, UploadError};
+use crate::price_stream::{self, PriceFeed};
+use crate::pump_fun::{validate_wallet_ids, PumpFunClient};
+use crate::rpc::{CircuitState, RpcPool};
+use crate::sequence::WalletIdSequence;
+use crate::trade_log::{self, ExportFormat, TradeAction, TradeLog, TradeRecord};
 use crate::types::*;
+use crate::wallet_store::WalletStore;
 
+/// Everything the handlers share.
+///
+/// Shared through `web::Data` without an outer lock, so requests run concurrently; only
+/// the wallet store and idempotency cache, which requests change, sit behind locks.
 pub struct ApiState {
     pub pump_fun_client: PumpFunClient,
-    pub rpc_client: RpcClient,
+    pub rpc_client: RpcPool,
+    pub request_deadline: Duration, // Used when the client sends no X-Deadline-Ms header
+    pub include_bundle_details: bool, // Add route and fee breakdown to bundle responses
+    pub jito_client: JitoBundleClient,
+    pub wallet_ids: WalletIdSequence,
+    pub wallets: RwLock<WalletStore>, // Wallets created or registered through /api/wallet, keyed by wallet id
+    pub allow_raw_key: bool, // Accept deprecated private keys in creation requests
+    pub idempotency: Mutex<IdempotencyCache>, // Responses to recent Idempotency-Key requests
+    pub trade_log: TradeLog, // Every trade attempted per wallet, including failures
+    pub price_feed: PriceFeed, // Where /ws/token/{mint}/price reads curve updates from
+    pub bundle_queue: Arc<BundleQueue>, // Paces every bundle, whether from /api/bundle/queue or a trade
+    pub admin_token: Option<Secret>, // Bearer token /api/admin endpoints require
+    pub treasury: Option<Pubkey>, // Where /api/admin/sweep sends swept SOL
 }
 
+/// Largest JSON body accepted by most endpoints
+const MAX_JSON_PAYLOAD_BYTES: usize = 64 * 1024;
+
+/// Largest JSON body accepted by token creation, which may carry a base64 image
+const MAX_CREATE_PAYLOAD_BYTES: usize = 8 * 1024 * 1024;
+
+/// How long a response is replayed for a repeated idempotency key
+const IDEMPOTENCY_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Most idempotency keys remembered at once
+const IDEMPOTENCY_CAPACITY: usize = 1024;
+
+/// Largest page any list endpoint returns at once
+const MAX_PAGE_SIZE: usize = 100;
+
+/// Longest string accepted in any metadata field, well above Pump.Fun's own limits
+const MAX_METADATA_FIELD_LEN: usize = 512;
+
+/// Most extra links a token's metadata may carry
+const MAX_EXTRA_LINKS: usize = 10;
+
 // Use the shared CreateTokenRequest from types.rs
 
-#[derive(Serialize)]
+/// The machine-readable contract for the trading endpoints, served at `/openapi.json`.
+///
+/// Schemas are derived from the request and response types; each listed handler documents
+/// its own route, so the two change together.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Pump Swap Bot API", description = "Token creation and bundled trading on Pump.Fun"),
+    paths(create_token, buy_tokens, sell_tokens, bundle_status),
+    components(schemas(
+        TokenMetadata,
+        CreateTokenRequest,
+        TokenProgram,
+        MintExtension,
+        CreateTokenResponse,
+        TokenCreationData,
+        BuyRequest,
+        SellRequest,
+        SubmitStrategy,
+        BundleResponse,
+        BundleData,
+        SubmissionRoute,
+        SellResponse,
+        WalletTradesData,
+        WalletTradeResult,
+        TransactionResult,
+        ConfirmationStatus,
+        BundleStatusResponse,
+        BundleStatusData,
+        ErrorResponse,
+    ))
+)]
+pub struct ApiDoc;
+
+/// The envelope of every failed request
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub success: bool,
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<serde_json::Value>, // Always null
+    pub error: String,
+}
+
+impl ErrorResponse {
+    pub fn new(error: impl Into<String>) -> Self {
+        Self { success: false, data: None, error: error.into() }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct CreateTokenResponse {
     pub success: bool,
     pub data: Option<TokenCreationData>,
     pub error: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TokenCreationData {
     pub token_address: String,
     pub transaction_id: String,
     pub metadata: TokenMetadata,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serialized_tx: Option<String>,
+    /// Metadata that was accepted but may put buyers off, e.g. a missing website
+    pub warnings: Vec<String>,
 }
 
 // Use the shared BuyRequest from types.rs
 
 // Use the shared SellRequest from types.rs
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct BundleResponse {
     pub success: bool,
     pub data: Option<BundleData>,
     pub error: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct BundleData {
     pub bundle_id: String,
     pub status: String,
     pub transactions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route: Option<SubmissionRoute>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tip_lamports: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_fee_lamports: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serialized_tx: Option<String>,
+    /// Wallets left out of the trade, e.g. unknown wallet IDs, each with its error
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed_wallets: Vec<WalletTradeResult>,
+}
+
+impl BundleData {
+    /// Builds the response payload for a submitted trade.
+    /// 
+    /// The route and fee breakdown are only included when `include_details` is set.
+    pub fn from_result(
+        result: &TransactionResult,
+        route: SubmissionRoute,
+        tip_lamports: u64,
+        include_details: bool,
+    ) -> Self {
+        let bundle_id = result.bundle_id.clone()
+            .unwrap_or_else(|| format!("bundle_{}", Uuid::new_v4().to_string().replace("-", "")));
+        let fee_lamports = (result.fee_paid.unwrap_or_default() * 1e9) as u64;
+
+        Self {
+            bundle_id,
+            // Only dry runs carry the unsent transaction
+            status: match (&result.serialized_tx, result.confirmation) {
+                (Some(_), _) => "dry_run",
+                (None, Some(ConfirmationStatus::Confirmed)) => "confirmed",
+                (None, Some(ConfirmationStatus::Submitted)) => "submitted",
+                (None, None) => "pending",
+            }.to_string(),
+            // Bundles list every transaction they carried; anything else has the one signature
+            transactions: if result.signatures.is_empty() {
+                result.signature.iter().cloned().collect()
+            } else {
+                result.signatures.clone()
+            },
+            route: include_details.then_some(route),
+            tip_lamports: include_details.then_some(tip_lamports),
+            total_fee_lamports: include_details.then_some(fee_lamports + tip_lamports),
+            serialized_tx: result.serialized_tx.clone(),
+            failed_wallets: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SellResponse {
+    pub success: bool,
+    pub data: Option<WalletTradesData>,
+    pub error: Option<String>,
+}
+
+/// Per-wallet outcomes of a trade whose wallets each sign their own transaction
+#[derive(Serialize, ToSchema)]
+pub struct WalletTradesData {
+    pub wallets: Vec<WalletTradeResult>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WalletTradeResult {
+    pub wallet_id: String,
+    #[serde(flatten)]
+    pub result: TransactionResult,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BundleStatusResponse {
+    pub success: bool,
+    pub data: Option<BundleStatusData>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BundleStatusData {
+    pub bundle_id: String,
+    pub status: String,
+    pub transactions: Vec<String>,
+    pub block_number: u64,
+    pub slot: u64,
+}
+
+#[derive(Deserialize)]
+pub struct WalletQuery {
+    pub mint: Option<String>,
+}
+
+/// `?from=&to=` date range of a history export, in Unix seconds, both inclusive
+#[derive(Deserialize)]
+pub struct HistoryExportQuery {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+/// `?offset=&limit=` pagination shared by every list endpoint
+#[derive(Deserialize)]
+pub struct PageQuery {
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_page_limit")]
+    pub limit: usize,
+}
+
+fn default_page_limit() -> usize {
+    20
+}
+
+impl PageQuery {
+    /// The requested page size, held between one and `MAX_PAGE_SIZE`.
+    fn capped_limit(&self) -> usize {
+        self.limit.clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SellQuoteQuery {
+    pub tokens: f64,
+    pub commitment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct QuoteQuery {
+    pub sol: Option<f64>,
+    pub tokens: Option<f64>,
+    pub commitment: Option<String>,
+}
+
+/// The commitment a quote's curve is read at: the one asked for, or the RPC client's own.
+fn quote_commitment(requested: Option<&str>, rpc_client: &RpcPool) -> Result<CommitmentConfig, String> {
+    match requested {
+        None => Ok(rpc_client.commitment()),
+        Some("processed") => Ok(CommitmentConfig::processed()),
+        Some("confirmed") => Ok(CommitmentConfig::confirmed()),
+        Some("finalized") => Ok(CommitmentConfig::finalized()),
+        Some(other) => Err(format!(
+            "Invalid commitment \"{}\": expected processed, confirmed or finalized",
+            other
+        )),
+    }
+}
+
+/// Reads a token's bonding curve for a quote at `commitment`.
+async fn quote_curve(
+    state: &web::Data<ApiState>,
+    token_mint: Pubkey,
+    commitment: CommitmentConfig,
+) -> anyhow::Result<BondingCurveData> {
+    state.pump_fun_client
+        .get_bonding_curve_data_with_commitment(&token_mint, commitment, &state.rpc_client)
+        .await
+}
+
+/// Scopes a request's `Idempotency-Key` header to its path, if it sent one.
+fn idempotency_key(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(IDEMPOTENCY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|key| !key.is_empty())
+        .map(|key| format!("{} {}", req.path(), key))
+}
+
+/// Runs `handler` at most once per idempotency key, replaying its response for retries.
+/// 
+/// Requests without a key always run. A retry that arrives while the first attempt is
+/// still running gets 409 rather than starting a second submission.
+async fn idempotent<F>(
+    key: Option<String>,
+    state: web::Data<ApiState>,
+    handler: F,
+) -> Result<HttpResponse, Error>
+where
+    F: std::future::Future<Output = Result<HttpResponse, Error>>,
+{
+    let Some(key) = key else {
+        return handler.await;
+    };
+
+    match state.idempotency.lock().unwrap().claim(&key) {
+        Claim::Started => {}
+        Claim::InFlight => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": "A request with this idempotency key is still in progress"
+            })));
+        }
+        Claim::Cached(cached) => {
+            return Ok(HttpResponse::build(cached.status)
+                .content_type("application/json")
+                .body(cached.body));
+        }
+    }
+
+    let response = match handler.await {
+        Ok(response) => response,
+        Err(e) => {
+            state.idempotency.lock().unwrap().release(&key);
+            return Err(e);
+        }
+    };
+    let status = response.status();
+    // Nothing was attempted while the RPC was unavailable, so a retry should run afresh
+    if status == actix_web::http::StatusCode::SERVICE_UNAVAILABLE {
+        state.idempotency.lock().unwrap().release(&key);
+        return Ok(response);
+    }
+    let body = match actix_web::body::to_bytes(response.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            state.idempotency.lock().unwrap().release(&key);
+            return Err(actix_web::error::ErrorInternalServerError(e.to_string()));
+        }
+    };
+    state.idempotency.lock().unwrap().complete(&key, CachedResponse { status, body: body.clone() });
+
+    Ok(HttpResponse::build(status).content_type("application/json").body(body))
+}
+
+/// Resolves the deadline for a request from its `X-Deadline-Ms` header, falling back to the default.
+fn request_deadline(req: &HttpRequest, default: Duration) -> Deadline {
+    let budget = req
+        .headers()
+        .get(DEADLINE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(default);
+    Deadline::after(budget)
+}
+
+/// Maps an operation error to a response, reporting blown deadlines as 504.
+fn operation_error_response(e: anyhow::Error, action: &str) -> HttpResponse {
+    if e.downcast_ref::<UploadError>().is_some() {
+        return HttpResponse::BadGateway().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": format!("Failed to {}: {}", action, e)
+        }));
+    }
+    if e.downcast_ref::<QueueFull>().is_some() {
+        return HttpResponse::TooManyRequests().json(ErrorResponse::new(format!("Failed to {}: {}", action, e)));
+    }
+    if e.downcast_ref::<DeadlineExceeded>().is_some() {
+        return HttpResponse::GatewayTimeout().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": format!("Failed to {}: {}", action, e)
+        }));
+    }
+    // Rejections the caller can fix are worded by the error itself, not its outer context
+    if let Some(error) = e.downcast_ref::<PumpFunError>().filter(|error| error.is_caller_error()) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Failed to {}: {}", action, error)));
+    }
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "success": false,
+        "data": null,
+        "error": format!("Failed to {}: {}", action, e)
+    }))
+}
+
+/// A fast 503 for requests needing the RPC while every node's circuit breaker is open.
+fn rpc_unavailable_response(state: &ApiState) -> Option<HttpResponse> {
+    if state.rpc_client.circuit_state() != CircuitState::Open {
+        return None;
+    }
+    Some(HttpResponse::ServiceUnavailable().json(ErrorResponse::new(format!(
+        "Solana RPC is failing; retry in {}s",
+        state.rpc_client.retry_after().as_secs().max(1)
+    ))))
+}
+
+/// Refuses a request to an admin endpoint unless it carries the configured admin token as
+/// `Authorization: Bearer <token>`. With no token configured, admin endpoints refuse everyone.
+fn admin_unauthorized_response(req: &HttpRequest, state: &ApiState) -> Option<HttpResponse> {
+    let Some(expected) = state.admin_token.as_deref() else {
+        return Some(HttpResponse::Forbidden().json(ErrorResponse::new(
+            "Admin endpoints are disabled; set admin_token to enable them",
+        )));
+    };
+    let presented = req.headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(token) if tokens_match(token, expected) => None,
+        _ => Some(
+            HttpResponse::Unauthorized()
+                .insert_header((actix_web::http::header::WWW_AUTHENTICATE, "Bearer"))
+                .json(ErrorResponse::new("Admin authorization required")),
+        ),
+    }
+}
+
+/// Compares tokens in time independent of where they differ, so timing can't reveal a prefix.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Where a trade is submitted and the tip it pays, in lamports.
+fn submission_route(via_bundle: bool, jito_client: &JitoBundleClient) -> (SubmissionRoute, u64) {
+    if via_bundle {
+        (SubmissionRoute::Jito, (jito_client.tip_amount() * 1e9) as u64)
+    } else {
+        (SubmissionRoute::Rpc, 0)
+    }
+}
+
+/// Appends a trade to each wallet's history, whether or not it went through.
+/// 
+/// `amounts` line up with `wallet_ids` and may be empty. Dry runs aren't trades and are skipped.
+fn record_trade(
+    state: &ApiState,
+    action: TradeAction,
+    token_address: Option<&str>,
+    wallet_ids: &[String],
+    amounts: &[f64],
+    outcome: &anyhow::Result<TransactionResult>,
+) {
+    if matches!(outcome, Ok(result) if result.serialized_tx.is_some()) {
+        return;
+    }
+    let records: Vec<TradeRecord> = wallet_ids
+        .iter()
+        .enumerate()
+        .map(|(i, wallet_id)| TradeRecord::from_outcome(action, wallet_id, token_address, amounts.get(i).copied(), outcome))
+        .collect();
+    if let Err(e) = state.trade_log.append(&records) {
+        warn!("Failed to record {:?} in the trade log: {}", action, e);
+    }
+}
+
+/// Records a trade whose wallets each have their own outcome; dry runs are skipped.
+fn record_wallet_trades(
+    state: &ApiState,
+    action: TradeAction,
+    token_address: Option<&str>,
+    wallet_ids: &[String],
+    amounts: &[f64],
+    outcome: &anyhow::Result<Vec<TransactionResult>>,
+) {
+    let results = match outcome {
+        Ok(results) => results,
+        Err(e) => {
+            let outcome = Err(anyhow::anyhow!(e.to_string()));
+            return record_trade(state, action, token_address, wallet_ids, amounts, &outcome);
+        }
+    };
+    let records: Vec<TradeRecord> = wallet_ids
+        .iter()
+        .zip(results)
+        .enumerate()
+        .filter(|(_, (_, result))| result.serialized_tx.is_none())
+        .map(|(i, (wallet_id, result))| {
+            TradeRecord::from_outcome(action, wallet_id, token_address, amounts.get(i).copied(), &Ok(result.clone()))
+        })
+        .collect();
+    if let Err(e) = state.trade_log.append(&records) {
+        warn!("Failed to record {:?} in the trade log: {}", action, e);
+    }
 }
 
 async fn health_check() -> Result<HttpResponse, Error> {
@@ -57,47 +532,173 @@ async fn health_check() -> Result<HttpResponse, Error> {
     })))
 }
 
+/// Prometheus metrics: the compute unit price transactions currently carry, as set by the
+/// fee autopilot or the fixed config. Zero means no priority fee.
+async fn metrics(state: web::Data<ApiState>) -> Result<HttpResponse, Error> {
+    let price = state.pump_fun_client.compute_unit_price().unwrap_or(0);
+    let body = format!(
+        "# HELP pump_swap_priority_fee_micro_lamports Compute unit price transactions currently carry\n\
+         # TYPE pump_swap_priority_fee_micro_lamports gauge\n\
+         pump_swap_priority_fee_micro_lamports {}\n",
+        price
+    );
+    Ok(HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(body))
+}
+
+/// Readiness check: unlike `/health`, only succeeds when the RPC node and the Jito block
+/// engine both answer, so load balancers stop routing trades to an instance that can't place them.
+async fn readiness_check(state: web::Data<ApiState>) -> Result<HttpResponse, Error> {
+
+    // An open breaker already knows every node is failing; don't wait on them again
+    let rpc_circuit = state.rpc_client.circuit_state();
+    let rpc_error = match rpc_circuit {
+        CircuitState::Open => Some(format!(
+            "Solana RPC circuit breaker is open; retrying in {}s",
+            state.rpc_client.retry_after().as_secs()
+        )),
+        _ => state.rpc_client.nonblocking().get_version().await.err().map(|e| format!("Solana RPC unreachable: {}", e)),
+    };
+    let jito_error = state.jito_client.ping().await.err().map(|e| e.to_string());
+
+    let data = serde_json::json!({
+        "rpc": if rpc_error.is_none() { "ok" } else { "down" },
+        "rpc_circuit": rpc_circuit,
+        "jito": if jito_error.is_none() { "ok" } else { "down" },
+    });
+    let errors: Vec<String> = rpc_error.into_iter().chain(jito_error).collect();
+    if errors.is_empty() {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": data,
+            "error": null
+        })));
+    }
+    for error in &errors {
+        warn!("Readiness check failed: {}", error);
+    }
+    Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+        "success": false,
+        "data": data,
+        "error": errors.join("; ")
+    })))
+}
+
+async fn version_info() -> Result<HttpResponse, Error> {
+    let features: Vec<&str> = env!("ENABLED_FEATURES")
+        .split(',')
+        .filter(|f| !f.is_empty())
+        .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "git_commit": env!("GIT_COMMIT"),
+            "build_timestamp": env!("BUILD_TIMESTAMP").parse::<u64>().unwrap_or_default(),
+            "features": features
+        },
+        "error": null
+    })))
+}
+
+/// Creates a Pump.Fun token, pinning an uploaded image first if one is given
+#[utoipa::path(
+    post,
+    path = "/api/token/create",
+    tag = "token",
+    request_body = CreateTokenRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response to a repeated key"),
+        ("X-Deadline-Ms" = Option<u64>, Header, description = "Gives up after this many milliseconds"),
+    ),
+    responses(
+        (status = 200, description = "Token created, or built without sending for a dry run", body = CreateTokenResponse),
+        (status = 400, description = "Invalid request, or the creation failed", body = ErrorResponse),
+        (status = 503, description = "Solana RPC is failing", body = ErrorResponse),
+    )
+)]
 async fn create_token(
+    req: HttpRequest,
+    request: web::Json<CreateTokenRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    let key = idempotency_key(&req);
+    idempotent(key, state.clone(), create_token_once(req, request, state)).await
+}
+
+async fn create_token_once(
+    req: HttpRequest,
     request: web::Json<CreateTokenRequest>,
-    state: web::Data<Arc<Mutex<ApiState>>>,
+    state: web::Data<ApiState>,
 ) -> Result<HttpResponse, Error> {
-    let state_guard = state.lock().await;
+    if let Err(e) = validate_metadata_lengths(&request.metadata) {
+        return Ok(validation_error_response(e));
+    }
+
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+    let deadline = request_deadline(&req, state.request_deadline);
     
-    // Decode the private key
-    let creator_keypair = match decode_keypair(&request.private_key) {
+    let creator_keypair = match resolve_signer(&state, &request.wallet_id, request.private_key.as_deref()).await {
         Ok(keypair) => keypair,
-        Err(e) => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "success": false,
-                "data": null,
-                "error": format!("Invalid private key: {}", e)
-            })));
-        }
+        Err(e) => return Ok(validation_error_response(e)),
+    };
+    let nonce_account = match request.nonce_account.as_deref().map(Pubkey::from_str).transpose() {
+        Ok(nonce_account) => nonce_account,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse::new(format!("Invalid nonce account: {}", e)))),
     };
 
-    // Validate the wallet belongs to the user (in production, you'd check this against a database)
-    if request.wallet_id.is_empty() {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "data": null,
-            "error": "Wallet ID is required"
-        })));
-    }
+    // Create real Pump.Fun token, pinning the image first if one was uploaded
+    let outcome = match &request.image_data {
+        Some(image_data) => {
+            let image = match base64::engine::general_purpose::STANDARD.decode(image_data) {
+                Ok(image) => image,
+                Err(e) => {
+                    return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                        "success": false,
+                        "data": null,
+                        "error": format!("Invalid image data: {}", e)
+                    })));
+                }
+            };
+            let mime = request.image_mime.as_deref().unwrap_or("image/png");
+            deadline.run(state.pump_fun_client.create_token_with_image(
+                request.metadata.clone(),
+                &request.mint_options(),
+                image,
+                mime,
+                &creator_keypair,
+                nonce_account.as_ref(),
+                &state.rpc_client,
+                request.dry_run,
+            )).await
+        }
+        None => {
+            deadline.run(state.pump_fun_client.create_token(
+                request.metadata.clone(),
+                &request.mint_options(),
+                &creator_keypair,
+                nonce_account.as_ref(),
+                &state.rpc_client,
+                request.dry_run,
+            )).await
+        }
+    };
+    let mint = outcome.as_ref().ok().and_then(|result| result.mint.clone());
+    record_trade(&state, TradeAction::Create, mint.as_deref(), std::slice::from_ref(&request.wallet_id), &[], &outcome);
 
-    // Create real Pump.Fun token
-    match state_guard.pump_fun_client.create_token(
-        request.metadata.clone(),
-        &creator_keypair,
-        &state_guard.rpc_client,
-    ).await {
+    match outcome {
         Ok(result) => {
             if result.success {
                 let response = CreateTokenResponse {
                     success: true,
                     data: Some(TokenCreationData {
-                        token_address: result.signature.clone().unwrap_or_default(), // Use signature as token address for now
+                        token_address: result.mint.unwrap_or_default(),
                         transaction_id: result.signature.unwrap_or_default(),
                         metadata: request.metadata.clone(),
+                        serialized_tx: result.serialized_tx,
+                        warnings: metadata_warnings(&state, &request.metadata),
                     }),
                     error: None,
                 };
@@ -110,54 +711,85 @@ async fn create_token(
                 })))
             }
         }
-        Err(e) => {
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "data": null,
-                "error": format!("Failed to create token: {}", e)
-            })))
-        }
+        Err(e) => Ok(operation_error_response(e, "create token")),
     }
 }
 
-async fn buy_tokens(
-    request: web::Json<BuyRequest>,
-    state: web::Data<Arc<Mutex<ApiState>>>,
+async fn launch_token(
+    req: HttpRequest,
+    request: web::Json<LaunchRequest>,
+    state: web::Data<ApiState>,
 ) -> Result<HttpResponse, Error> {
-    let state_guard = state.lock().await;
-    
-    // Validate request
-    if request.solAmounts.len() != request.walletIds.len() {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "data": null,
-            "error": "Number of SOL amounts must match number of wallet IDs"
-        })));
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
     }
-    
-    if request.solAmounts.len() > 16 {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "data": null,
-            "error": "Maximum 16 wallets allowed per bundle"
-        })));
+    // The launch bundle also carries the creation and tip transactions
+    let max_wallets = state.pump_fun_client.max_launch_wallets();
+    if let Err(e) = validate_metadata_lengths(&request.metadata)
+        .and_then(|_| validate_wallet_lists(request.buy_sol_amounts.len(), &request.buy_wallet_ids, max_wallets, "SOL amounts"))
+    {
+        return Ok(validation_error_response(e));
     }
-    
-    // Call Pump.Fun client for buy tokens
-    match state_guard.pump_fun_client.buy_tokens(
-        request.into_inner(),
-        &state_guard.rpc_client,
-    ).await {
+
+    let deadline = request_deadline(&req, state.request_deadline);
+    let include_bundle_details = state.include_bundle_details;
+
+    let creator_keypair = match resolve_signer(&state, &request.wallet_id, request.private_key.as_deref()).await {
+        Ok(keypair) => keypair,
+        Err(e) => return Ok(validation_error_response(e)),
+    };
+
+    // Launch wallets must have been created on this server
+    let wallet_store = state.wallets.read().await;
+    let mut wallets = Vec::with_capacity(request.buy_wallet_ids.len());
+    for wallet_id in &request.buy_wallet_ids {
+        match wallet_store.get_keypair(wallet_id) {
+            Some(keypair) => wallets.push((wallet_id.as_str(), keypair)),
+            None => {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "data": null,
+                    "error": format!("Unknown wallet ID: {}", wallet_id)
+                })));
+            }
+        }
+    }
+
+    // A delayed snipe can't share the creation's bundle
+    let (route, tip_lamports) = submission_route(request.snipe_delay_ms.is_none(), &state.jito_client);
+    let outcome = match request.snipe_delay_ms {
+        Some(delay_ms) => deadline.run(state.pump_fun_client.create_then_snipe(
+            request.metadata.clone(),
+            &creator_keypair,
+            &request.buy_sol_amounts,
+            &wallets,
+            delay_ms,
+            &state.rpc_client,
+        )).await,
+        None => deadline.run(state.pump_fun_client.create_and_snipe(
+            request.metadata.clone(),
+            &creator_keypair,
+            &request.buy_sol_amounts,
+            &wallets,
+            &state.jito_client,
+            &state.rpc_client,
+        )).await,
+    };
+    let mint = outcome.as_ref().ok().and_then(|result| result.mint.clone());
+    record_trade(&state, TradeAction::Launch, mint.as_deref(), std::slice::from_ref(&request.wallet_id), &[], &outcome);
+    record_trade(&state, TradeAction::Buy, mint.as_deref(), &request.buy_wallet_ids, &request.buy_sol_amounts, &outcome);
+
+    match outcome {
         Ok(result) => {
             if result.success {
-                let bundle_id = format!("bundle_{}", Uuid::new_v4().to_string().replace("-", ""));
                 let response = BundleResponse {
                     success: true,
-                    data: Some(BundleData {
-                        bundle_id,
-                        status: "pending".to_string(),
-                        transactions: vec![],
-                    }),
+                    data: Some(BundleData::from_result(
+                        &result,
+                        route,
+                        tip_lamports,
+                        include_bundle_details,
+                    )),
                     error: None,
                 };
                 Ok(HttpResponse::Ok().json(response))
@@ -169,142 +801,2955 @@ async fn buy_tokens(
                 })))
             }
         }
-        Err(e) => {
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+        Err(e) => Ok(operation_error_response(e, "launch token")),
+    }
+}
+
+/// Buys a token from each listed wallet
+#[utoipa::path(
+    post,
+    path = "/api/bundle/buy",
+    tag = "bundle",
+    request_body = BuyRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response to a repeated key"),
+        ("X-Deadline-Ms" = Option<u64>, Header, description = "Gives up after this many milliseconds"),
+    ),
+    responses(
+        (status = 200, description = "Buy submitted", body = BundleResponse),
+        (status = 400, description = "Invalid request, or the buy failed", body = ErrorResponse),
+        (status = 503, description = "Solana RPC is failing", body = ErrorResponse),
+    )
+)]
+async fn buy_tokens(
+    req: HttpRequest,
+    request: web::Json<BuyRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    let key = idempotency_key(&req);
+    idempotent(key, state.clone(), buy_tokens_once(req, request, state)).await
+}
+
+async fn buy_tokens_once(
+    req: HttpRequest,
+    request: web::Json<BuyRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+
+    // Validate request
+    let max_wallets = state.pump_fun_client.config.max_wallets_per_bundle;
+    if let Err(e) = validate_wallet_lists(request.sol_amounts.len(), &request.wallet_ids, max_wallets, "SOL amounts") {
+        return Ok(validation_error_response(e));
+    }
+
+    let deadline = request_deadline(&req, state.request_deadline);
+    let include_bundle_details = state.include_bundle_details;
+    
+    let (route, tip_lamports) = submission_route(request.submit_strategy().is_bundle(), &state.jito_client);
+
+    // Call Pump.Fun client for buy tokens
+    let request = request.into_inner();
+    let outcome = deadline.run(state.pump_fun_client.buy_tokens(
+        request.clone(),
+        &*state.wallets.read().await,
+        &state.rpc_client,
+        Some(&state.jito_client),
+    )).await;
+    record_wallet_trades(
+        &state,
+        TradeAction::Buy,
+        Some(&request.token_address),
+        &request.wallet_ids,
+        &request.sol_amounts,
+        &outcome,
+    );
+
+    match outcome {
+        // The wallets that bought share one submission; any left out are listed with their errors
+        Ok(results) => match results.iter().find(|result| result.success) {
+            Some(bought) => {
+                let mut data = BundleData::from_result(bought, route, tip_lamports, include_bundle_details);
+                data.failed_wallets = request.wallet_ids.iter()
+                    .zip(&results)
+                    .filter(|(_, result)| !result.success)
+                    .map(|(wallet_id, result)| WalletTradeResult { wallet_id: wallet_id.clone(), result: result.clone() })
+                    .collect();
+                Ok(HttpResponse::Ok().json(BundleResponse { success: true, data: Some(data), error: None }))
+            }
+            None => Ok(HttpResponse::BadRequest().json(serde_json::json!({
                 "success": false,
                 "data": null,
-                "error": format!("Failed to buy tokens: {}", e)
-            })))
+                "error": buy_failure_message(&request.wallet_ids, &results)
+            }))),
+        },
+        Err(e) => Ok(operation_error_response(e, "buy tokens")),
+    }
+}
+
+/// Describes a buy in which no wallet succeeded: the shared error when every wallet hit
+/// the same one, else each wallet's own.
+fn buy_failure_message(wallet_ids: &[String], results: &[TransactionResult]) -> String {
+    let error = |result: &TransactionResult| result.error.clone().unwrap_or_else(|| "Unknown error".to_string());
+    match results.split_first() {
+        Some((first, rest)) if rest.iter().all(|result| result.error == first.error) => error(first),
+        _ => {
+            let errors: Vec<String> = wallet_ids.iter()
+                .zip(results)
+                .map(|(wallet_id, result)| format!("{}: {}", wallet_id, error(result)))
+                .collect();
+            format!("Buy failed for every wallet: {}", errors.join("; "))
         }
     }
 }
 
+/// Sells a token from each listed wallet, each in its own transaction
+#[utoipa::path(
+    post,
+    path = "/api/bundle/sell",
+    tag = "bundle",
+    request_body = SellRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response to a repeated key"),
+        ("X-Deadline-Ms" = Option<u64>, Header, description = "Gives up after this many milliseconds"),
+    ),
+    responses(
+        (status = 200, description = "At least one wallet sold; each reports its own outcome", body = SellResponse),
+        (status = 400, description = "Invalid request, or every wallet failed", body = ErrorResponse),
+        (status = 503, description = "Solana RPC is failing", body = ErrorResponse),
+    )
+)]
 async fn sell_tokens(
+    req: HttpRequest,
     request: web::Json<SellRequest>,
-    state: web::Data<Arc<Mutex<ApiState>>>,
+    state: web::Data<ApiState>,
 ) -> Result<HttpResponse, Error> {
-    let state_guard = state.lock().await;
-    
-    // Validate request
-    if request.tokenAmounts.len() != request.walletIds.len() {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "data": null,
-            "error": "Number of token amounts must match number of wallet IDs"
-        })));
+    let key = idempotency_key(&req);
+    idempotent(key, state.clone(), sell_tokens_once(req, request, state)).await
+}
+
+async fn sell_tokens_once(
+    req: HttpRequest,
+    request: web::Json<SellRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
     }
-    
-    if request.tokenAmounts.len() > 16 {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "data": null,
-            "error": "Maximum 16 wallets allowed per bundle"
-        })));
+
+    // Validate request
+    let max_wallets = state.pump_fun_client.config.max_wallets_per_bundle;
+    if let Err(e) = request.check_amount_unit()
+        .and_then(|_| validate_wallet_lists(request.amount_count(), &request.wallet_ids, max_wallets, "token amounts"))
+    {
+        return Ok(validation_error_response(e));
     }
-    
-    // Call Pump.Fun client for sell tokens
-    match state_guard.pump_fun_client.sell_tokens(
-        request.into_inner(),
-        &state_guard.rpc_client,
-    ).await {
-        Ok(result) => {
-            if result.success {
-                let bundle_id = format!("bundle_{}", Uuid::new_v4().to_string().replace("-", ""));
-                let response = BundleResponse {
-                    success: true,
-                    data: Some(BundleData {
-                        bundle_id,
-                        status: "pending".to_string(),
-                        transactions: vec![],
-                    }),
-                    error: None,
-                };
-                Ok(HttpResponse::Ok().json(response))
-            } else {
-                Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                    "success": false,
-                    "data": null,
-                    "error": result.error.unwrap_or_else(|| "Unknown error".to_string())
-                })))
-            }
+
+    let deadline = request_deadline(&req, state.request_deadline);
+
+    // Whole-token amounts become base units up front, so history records one unit
+    let request = match state.pump_fun_client.resolve_token_amounts(request.into_inner(), &state.rpc_client).await {
+        Ok(request) => request,
+        Err(e) if e.downcast_ref::<PumpFunError>().is_some() => {
+            return Ok(validation_error_response(e.to_string()));
         }
-        Err(e) => {
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+        Err(e) => return Ok(operation_error_response(e, "resolve token amounts")),
+    };
+
+    // Call Pump.Fun client for sell tokens; each wallet sells on its own
+    let outcome = deadline.run(state.pump_fun_client.sell_tokens(
+        request.clone(),
+        &*state.wallets.read().await,
+        &state.rpc_client,
+        Some(&state.jito_client),
+    )).await;
+    let token_amounts: Vec<f64> = request.token_amounts.iter().map(|&amount| amount as f64).collect();
+    record_wallet_trades(
+        &state,
+        TradeAction::Sell,
+        Some(&request.token_address),
+        &request.wallet_ids,
+        &token_amounts,
+        &outcome,
+    );
+
+    match outcome {
+        // The sell succeeds if any wallet did; the rest report their own errors
+        Ok(results) if results.iter().any(|result| result.success) => {
+            let wallets = request.wallet_ids.into_iter()
+                .zip(results)
+                .map(|(wallet_id, result)| WalletTradeResult { wallet_id, result })
+                .collect();
+            Ok(HttpResponse::Ok().json(SellResponse {
+                success: true,
+                data: Some(WalletTradesData { wallets }),
+                error: None,
+            }))
+        }
+        Ok(results) => {
+            let errors: Vec<String> = request.wallet_ids.iter()
+                .zip(&results)
+                .map(|(wallet_id, result)| {
+                    format!("{}: {}", wallet_id, result.error.as_deref().unwrap_or("Unknown error"))
+                })
+                .collect();
+            Ok(HttpResponse::BadRequest().json(serde_json::json!({
                 "success": false,
                 "data": null,
-                "error": format!("Failed to sell tokens: {}", e)
+                "error": format!("Sell failed for every wallet: {}", errors.join("; "))
             })))
         }
+        Err(e) => Ok(operation_error_response(e, "sell tokens")),
     }
 }
 
-async fn bundle_status(
-    bundle_id: web::Path<String>,
-    state: web::Data<Arc<Mutex<ApiState>>>,
+async fn create_wallet(
+    state: web::Data<ApiState>,
 ) -> Result<HttpResponse, Error> {
-    let _state_guard = state.lock().await;
-    
-    // For now, return mock response
-    // In production, this would:
-    // 1. Query Jito API for bundle status
-    // 2. Return real status and transaction data
-    
-    let response = serde_json::json!({
+    store_wallet(&state, Keypair::new()).await
+}
+
+/// Stores a caller's existing wallet, or a new one, so later requests sign with its
+/// `wallet_id` instead of sending the key each time
+async fn register_wallet(
+    request: web::Json<RegisterWalletRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    let keypair = match request.into_inner() {
+        RegisterWalletRequest::PrivateKey { private_key } => {
+            decode_keypair(&private_key).map_err(|e| format!("Invalid private key: {}", e))
+        }
+        RegisterWalletRequest::KeypairFile { keypair_file } => {
+            keys::keypair_from_file_bytes(&keypair_file).map_err(|e| format!("Invalid keypair file: {}", e))
+        }
+        RegisterWalletRequest::Generate => Ok(Keypair::new()),
+    };
+    match keypair {
+        Ok(keypair) => store_wallet(&state, keypair).await,
+        Err(e) => Ok(validation_error_response(e)),
+    }
+}
+
+/// Stores a wallet under the next wallet id, answering with the id and address only.
+async fn store_wallet(state: &ApiState, keypair: Keypair) -> Result<HttpResponse, Error> {
+    let wallet_id = match state.wallet_ids.next_id() {
+        Ok(wallet_id) => wallet_id,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Failed to assign wallet id: {}", e)
+            })));
+        }
+    };
+    let address = keypair.pubkey().to_string();
+    if let Err(e) = state.wallets.write().await.add_wallet(wallet_id.clone(), keypair) {
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": format!("Failed to store wallet: {}", e)
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
         "data": {
-            "bundle_id": bundle_id.to_string(),
-            "status": "accepted",
-            "transactions": [],
-            "block_number": 12345678,
-            "slot": 12345678
+            "wallet_id": wallet_id,
+            "address": address
         },
         "error": null
-    });
-    
-    Ok(HttpResponse::Ok().json(response))
+    })))
 }
 
-fn decode_keypair(private_key: &str) -> Result<Keypair, Box<dyn std::error::Error>> {
-    let decoded = bs58::decode(private_key)
-        .into_vec()?;
-    
-    if decoded.len() != 64 {
-        return Err("Invalid private key length".into());
+async fn wallet_history(
+    wallet_id: web::Path<String>,
+    query: web::Query<PageQuery>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+
+    match state.trade_log.history(&wallet_id, query.offset, query.capped_limit()) {
+        Ok(page) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": page,
+            "error": null
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": format!("Failed to read trade history: {}", e)
+        }))),
     }
+}
 
-    Ok(Keypair::from_bytes(&decoded)?)
+/// Downloads a wallet's trades, oldest first, as CSV or as a JSON array.
+async fn export_wallet_history(
+    path: web::Path<(String, String)>,
+    query: web::Query<HistoryExportQuery>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    let (wallet_id, extension) = path.into_inner();
+    let Some(format) = ExportFormat::from_extension(&extension) else {
+        return Ok(HttpResponse::NotFound().json(ErrorResponse::new(format!(
+            "Unknown export format '{}'; expected csv or json",
+            extension
+        ))));
+    };
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Ok(validation_error_response(format!("Invalid date range: from {} is after to {}", from, to)));
+        }
+    }
+
+    let records = match state.trade_log.export(&wallet_id, query.from, query.to) {
+        Ok(records) => records,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Failed to read trade history: {}", e)
+            })));
+        }
+    };
+
+    // One chunk per record, so large histories aren't rendered into a single buffer
+    let chunks: Vec<String> = match format {
+        ExportFormat::Csv => std::iter::once(trade_log::csv_header())
+            .chain(records.iter().map(TradeRecord::csv_row))
+            .collect(),
+        ExportFormat::Json => {
+            let mut chunks = Vec::with_capacity(records.len() + 2);
+            chunks.push("[".to_string());
+            for (i, record) in records.iter().enumerate() {
+                let separator = if i == 0 { "" } else { "," };
+                chunks.push(format!("{}{}", separator, serde_json::to_string(record)?));
+            }
+            chunks.push("]".to_string());
+            chunks
+        }
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            actix_web::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"trade-history.{}\"", extension),
+        ))
+        .streaming(futures::stream::iter(
+            chunks.into_iter().map(|chunk| Ok::<_, Error>(actix_web::web::Bytes::from(chunk))),
+        )))
 }
 
-pub async fn start_api_server(
-    pump_fun_client: PumpFunClient,
-) -> std::io::Result<()> {
-    // Initialize Solana RPC client
-    let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
-    
-    // Create API state
-    let state = Arc::new(Mutex::new(ApiState {
-        pump_fun_client,
-        rpc_client,
-    }));
-    
-    println!("Starting API server on http://127.0.0.1:8080");
-    
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header();
-        
-        App::new()
-            .wrap(cors)
-            .app_data(web::Data::new(state.clone()))
-            .route("/health", web::get().to(health_check))
-            .route("/api/token/create", web::post().to(create_token))
-            .route("/api/bundle/buy", web::post().to(buy_tokens))
-            .route("/api/bundle/sell", web::post().to(sell_tokens))
-            .route("/api/bundle/status/{bundle_id}", web::get().to(bundle_status))
-    })
-    .bind("127.0.0.1:8080")?
-    .run()
-    .await
-} 
\ No newline at end of file
+async fn wallet_info(
+    address: web::Path<String>,
+    query: web::Query<WalletQuery>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+
+    let wallet = match Pubkey::from_str(&address) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid wallet address: {}", e)
+            })));
+        }
+    };
+    let token_mint = match query.mint.as_deref().map(Pubkey::from_str).transpose() {
+        Ok(token_mint) => token_mint,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid token address: {}", e)
+            })));
+        }
+    };
+
+    match state.pump_fun_client.get_wallet_info(
+        &wallet,
+        token_mint.as_ref(),
+        &state.rpc_client,
+    ).await {
+        Ok(info) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": info,
+            "error": null
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": format!("Failed to get wallet info: {}", e)
+        }))),
+    }
+}
+
+/// Reports the fee wallet's balance and whether it can keep collecting fees. Admin only.
+async fn fee_wallet_status(
+    req: HttpRequest,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = admin_unauthorized_response(&req, &state) {
+        return Ok(response);
+    }
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+
+    match state.pump_fun_client.fee_wallet_status(&state.rpc_client).await {
+        Ok(status) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": status,
+            "error": null
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": format!("Failed to check fee wallet: {}", e)
+        }))),
+    }
+}
+
+/// Sweeps SOL from stored wallets back to the configured treasury, leaving each its
+/// rent-exempt minimum and a fee buffer. Admin only.
+async fn sweep_wallets(
+    req: HttpRequest,
+    request: web::Json<SweepRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = admin_unauthorized_response(&req, &state) {
+        return Ok(response);
+    }
+    let Some(treasury) = state.treasury else {
+        return Ok(HttpResponse::ServiceUnavailable().json(ErrorResponse::new(
+            "Sweeping is disabled; set treasury_address to enable it",
+        )));
+    };
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+
+    let mut keypairs = Vec::with_capacity(request.wallet_ids.len());
+    for wallet_id in &request.wallet_ids {
+        match resolve_signer(&state, wallet_id, None).await {
+            Ok(keypair) => keypairs.push(keypair),
+            Err(e) => return Ok(validation_error_response(e)),
+        }
+    }
+    let wallets: Vec<&Keypair> = keypairs.iter().collect();
+
+    let client = &state.pump_fun_client;
+    match client.sweep_wallets(&request, &wallets, &treasury, &state.rpc_client, Some(&state.jito_client)).await {
+        Ok(sweep) if sweep.transaction.success => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": sweep,
+            "error": null
+        }))),
+        Ok(sweep) => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": sweep.transaction.error.clone(),
+            "data": sweep
+        }))),
+        Err(e) => Ok(operation_error_response(e, "sweep wallets")),
+    }
+}
+
+async fn sell_all_tokens(
+    req: HttpRequest,
+    request: web::Json<SellAllRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+    let deadline = request_deadline(&req, state.request_deadline);
+    let include_bundle_details = state.include_bundle_details;
+
+    let token_mint = match Pubkey::from_str(&request.token_address) {
+        Ok(token_mint) => token_mint,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid token address: {}", e)
+            })));
+        }
+    };
+
+    // Exiting wallets must have been created on this server
+    let wallet_store = state.wallets.read().await;
+    let mut wallets = Vec::with_capacity(request.wallet_ids.len());
+    for wallet_id in &request.wallet_ids {
+        match wallet_store.get_keypair(wallet_id) {
+            Some(keypair) => wallets.push((wallet_id.as_str(), keypair)),
+            None => {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "data": null,
+                    "error": format!("Unknown wallet ID: {}", wallet_id)
+                })));
+            }
+        }
+    }
+
+    let (route, tip_lamports) = submission_route(true, &state.jito_client);
+    let tip_lamports = (tip_lamports as f64 * state.pump_fun_client.config.exit_tip_multiplier) as u64;
+    let outcome = deadline.run(state.pump_fun_client.sell_all(
+        &token_mint,
+        &wallets,
+        &state.jito_client,
+        &state.rpc_client,
+    )).await;
+    record_trade(&state, TradeAction::SellAll, Some(&request.token_address), &request.wallet_ids, &[], &outcome);
+
+    match outcome {
+        Ok(result) => {
+            if result.success {
+                let response = BundleResponse {
+                    success: true,
+                    data: Some(BundleData::from_result(
+                        &result,
+                        route,
+                        tip_lamports,
+                        include_bundle_details,
+                    )),
+                    error: None,
+                };
+                Ok(HttpResponse::Ok().json(response))
+            } else {
+                // Bundles accepted before the failure may still land, so their ids go back
+                Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "data": result.bundle_id.as_ref().map(|bundle_id| serde_json::json!({ "bundle_id": bundle_id })),
+                    "error": result.error.unwrap_or_else(|| "Unknown error".to_string())
+                })))
+            }
+        }
+        Err(e) => Ok(operation_error_response(e, "sell all tokens")),
+    }
+}
+
+async fn token_holders(
+    mint: web::Path<String>,
+    query: web::Query<PageQuery>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+
+    let token_mint = match Pubkey::from_str(&mint) {
+        Ok(token_mint) => token_mint,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid token address: {}", e)
+            })));
+        }
+    };
+
+    match state.pump_fun_client.get_top_holders(&token_mint, &state.rpc_client).await {
+        Ok(holders) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": Page::new(holders, query.offset, query.capped_limit()),
+            "error": null
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": format!("Failed to get token holders: {}", e)
+        }))),
+    }
+}
+
+/// Streams a token's bonding curve price over a WebSocket, one JSON frame per change.
+async fn token_price_socket(
+    req: HttpRequest,
+    payload: web::Payload,
+    mint: web::Path<String>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    let token_mint = match Pubkey::from_str(&mint) {
+        Ok(token_mint) => token_mint,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid token address: {}", e)
+            })));
+        }
+    };
+    price_stream::serve(&req, payload, token_mint, state)
+}
+
+/// Looks up a token's name, symbol and image from its on-chain and off-chain metadata.
+///
+/// A mint without a metadata account is a 404; an unreachable metadata host is a 502.
+async fn token_metadata(
+    mint: web::Path<String>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+
+    let token_mint = match Pubkey::from_str(&mint) {
+        Ok(token_mint) => token_mint,
+        Err(e) => return Ok(validation_error_response(format!("Invalid token address: {}", e))),
+    };
+
+    match state.pump_fun_client.token_metadata(&token_mint, &state.rpc_client).await {
+        Ok(token) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": token,
+            "error": null
+        }))),
+        Err(e) if matches!(e.downcast_ref(), Some(PumpFunError::MetadataNotFound { .. })) => {
+            Ok(HttpResponse::NotFound().json(ErrorResponse::new(e.to_string())))
+        }
+        Err(e) if e.downcast_ref::<FetchError>().is_some() => {
+            Ok(HttpResponse::BadGateway().json(ErrorResponse::new(e.to_string())))
+        }
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse::new(format!(
+            "Failed to get token metadata: {}",
+            e
+        )))),
+    }
+}
+
+/// Prices a buy or sell end to end (trading fees, Jito tip and network fees) without
+/// submitting anything.
+async fn estimate_bundle_cost(
+    request: web::Json<EstimateRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+    let client = &state.pump_fun_client;
+
+    let max_wallets = client.config.max_wallets_per_bundle;
+    let validation = match &*request {
+        EstimateRequest::Buy(buy) => {
+            validate_wallet_lists(buy.sol_amounts.len(), &buy.wallet_ids, max_wallets, "SOL amounts")
+        }
+        EstimateRequest::Sell(sell) => {
+            sell.check_amount_unit()
+                .and_then(|_| validate_wallet_lists(sell.amount_count(), &sell.wallet_ids, max_wallets, "token amounts"))
+        }
+    };
+    if let Err(e) = validation {
+        return Ok(validation_error_response(e));
+    }
+
+    let estimate = match request.into_inner() {
+        EstimateRequest::Buy(buy) => client.estimate_buy_cost(&buy, &state.jito_client),
+        EstimateRequest::Sell(sell) => {
+            let token_mint = match Pubkey::from_str(&sell.token_address) {
+                Ok(token_mint) => token_mint,
+                Err(e) => return Ok(validation_error_response(format!("Invalid token address: {}", e))),
+            };
+            let sell = match client.resolve_token_amounts(sell, &state.rpc_client).await {
+                Ok(sell) => sell,
+                Err(e) if e.downcast_ref::<PumpFunError>().is_some() => {
+                    return Ok(validation_error_response(e.to_string()));
+                }
+                Err(e) => {
+                    return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                        "success": false,
+                        "data": null,
+                        "error": format!("Failed to resolve token amounts: {}", e)
+                    })));
+                }
+            };
+            let bonding_curve = match client.get_bonding_curve_data(&token_mint, &state.rpc_client).await {
+                Ok(bonding_curve) => bonding_curve,
+                Err(e) => {
+                    return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                        "success": false,
+                        "data": null,
+                        "error": format!("Failed to get bonding curve data: {}", e)
+                    })));
+                }
+            };
+            client.estimate_sell_cost(&sell, &bonding_curve, &state.jito_client)
+        }
+    };
+
+    match estimate {
+        Ok(estimate) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": estimate,
+            "error": null
+        }))),
+        Err(e) => Ok(validation_error_response(e.to_string())),
+    }
+}
+
+/// Reports what a buy would fill and cost against the live curve, from the expected fill
+/// down to the worst its slippage accepts, without submitting anything.
+async fn simulate_buy(
+    request: web::Json<BuyRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+    let client = &state.pump_fun_client;
+    let max_wallets = client.config.max_wallets_per_bundle;
+    if let Err(e) = validate_wallet_lists(request.sol_amounts.len(), &request.wallet_ids, max_wallets, "SOL amounts") {
+        return Ok(validation_error_response(e));
+    }
+
+    let token_mint = match Pubkey::from_str(&request.token_address) {
+        Ok(token_mint) => token_mint,
+        Err(e) => return Ok(validation_error_response(format!("Invalid token address: {}", e))),
+    };
+    let bonding_curve = match client.get_bonding_curve_data(&token_mint, &state.rpc_client).await {
+        Ok(bonding_curve) => bonding_curve,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Failed to get bonding curve data: {}", e)
+            })));
+        }
+    };
+
+    match client.simulate_trade(&request, &bonding_curve, &state.jito_client) {
+        Ok(simulation) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": simulation,
+            "error": null
+        }))),
+        Err(e) => Ok(validation_error_response(e.to_string())),
+    }
+}
+
+async fn token_sell_quote(
+    mint: web::Path<String>,
+    query: web::Query<SellQuoteQuery>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+    let commitment = match quote_commitment(query.commitment.as_deref(), &state.rpc_client) {
+        Ok(commitment) => commitment,
+        Err(e) => return Ok(validation_error_response(e)),
+    };
+
+    let token_mint = match Pubkey::from_str(&mint) {
+        Ok(token_mint) => token_mint,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid token address: {}", e)
+            })));
+        }
+    };
+
+    let bonding_curve = match quote_curve(&state, token_mint, commitment).await {
+        Ok(bonding_curve) => bonding_curve,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Failed to get bonding curve data: {}", e)
+            })));
+        }
+    };
+
+    match state.pump_fun_client.sell_quote(query.tokens, &bonding_curve) {
+        Ok(quote) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": quote,
+            "error": null
+        }))),
+        Err(e) => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": e.to_string()
+        }))),
+    }
+}
+
+async fn token_quote(
+    mint: web::Path<String>,
+    query: web::Query<QuoteQuery>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = rpc_unavailable_response(&state) {
+        return Ok(response);
+    }
+
+    let input = match (query.sol, query.tokens) {
+        (Some(sol), None) => QuoteInput::Sol(sol),
+        (None, Some(tokens)) => QuoteInput::Tokens(tokens),
+        _ => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": "Provide exactly one of `sol` or `tokens`"
+            })));
+        }
+    };
+    let commitment = match quote_commitment(query.commitment.as_deref(), &state.rpc_client) {
+        Ok(commitment) => commitment,
+        Err(e) => return Ok(validation_error_response(e)),
+    };
+
+    let token_mint = match Pubkey::from_str(&mint) {
+        Ok(token_mint) => token_mint,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid token address: {}", e)
+            })));
+        }
+    };
+
+    let bonding_curve = match quote_curve(&state, token_mint, commitment).await {
+        Ok(bonding_curve) => bonding_curve,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Failed to get bonding curve data: {}", e)
+            })));
+        }
+    };
+
+    match state.pump_fun_client.quote(input, &bonding_curve) {
+        Ok(quote) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": quote,
+            "error": null
+        }))),
+        Err(e) => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": e.to_string()
+        }))),
+    }
+}
+
+/// Reports how far a submitted bundle has got
+#[utoipa::path(
+    get,
+    path = "/api/bundle/status/{bundle_id}",
+    tag = "bundle",
+    params(("bundle_id" = String, Path, description = "Bundle id a trade returned")),
+    responses((status = 200, description = "Bundle status", body = BundleStatusResponse))
+)]
+async fn bundle_status(bundle_id: web::Path<String>) -> Result<HttpResponse, Error> {
+    // For now, return mock response
+    // In production, this would:
+    // 1. Query Jito API for bundle status
+    // 2. Return real status and transaction data
+    
+    let response = BundleStatusResponse {
+        success: true,
+        data: Some(BundleStatusData {
+            bundle_id: bundle_id.into_inner(),
+            status: "accepted".to_string(),
+            transactions: Vec::new(),
+            block_number: 12345678,
+            slot: 12345678,
+        }),
+        error: None,
+    };
+    
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Queues a signed bundle for paced submission and returns at once with an id to poll.
+/// 
+/// Under burst load queued bundles go to the block engine a few at a time, those tipping
+/// more going first. The queue is shared with the server's own trades, so only admins may
+/// add to it, and a full queue answers 429.
+async fn queue_bundle(
+    req: HttpRequest,
+    request: web::Json<QueueBundleRequest>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = admin_unauthorized_response(&req, &state) {
+        return Ok(response);
+    }
+    let request = request.into_inner();
+    // Reject what the block engine would, before it takes up a place in the queue
+    if let Err(e) = state.jito_client.preflight(&request.transactions) {
+        return Ok(validation_error_response(format!("Invalid bundle: {:#}", e)));
+    }
+    let priority = bundle_tip_lamports(&request.transactions);
+    let queue_id = match state.bundle_queue.enqueue(request.transactions, priority) {
+        Ok(queue_id) => queue_id,
+        Err(e) => return Ok(HttpResponse::TooManyRequests().json(ErrorResponse::new(e.to_string()))),
+    };
+    let status = state.bundle_queue.status(&queue_id).unwrap_or(QueuedBundle::Submitting);
+
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "success": true,
+        "data": QueueTicket { queue_id, status },
+        "error": null
+    })))
+}
+
+/// Reports where a bundle queued through `/api/bundle/queue` has got.
+async fn queued_bundle_status(
+    queue_id: web::Path<String>,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, Error> {
+    let queue_id = queue_id.into_inner();
+    match state.bundle_queue.status(&queue_id) {
+        Some(status) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": QueueTicket { queue_id, status },
+            "error": null
+        }))),
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new(format!("No queued bundle {}", queue_id)))),
+    }
+}
+
+/// Serves the OpenAPI document describing the trading endpoints.
+async fn openapi_document() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Checks a request's per-wallet amounts line up with its distinct wallet ids and stay within
+/// bundle limits.
+fn validate_wallet_lists(amounts: usize, wallet_ids: &[String], max_wallets: usize, amount_kind: &str) -> Result<(), String> {
+    validate_wallet_ids(amounts, wallet_ids, amount_kind)?;
+    if wallet_ids.len() > max_wallets {
+        return Err(format!("Maximum {} wallets allowed per bundle", max_wallets));
+    }
+    Ok(())
+}
+
+/// The non-fatal findings of validating `metadata`, for a creation that went ahead anyway.
+fn metadata_warnings(state: &ApiState, metadata: &TokenMetadata) -> Vec<String> {
+    let mut validation = ValidationResult::new();
+    state.pump_fun_client.validate_token_metadata(metadata, &mut validation);
+    validation.warnings
+}
+
+/// Rejects absurdly long metadata strings before any deeper validation or network calls.
+fn validate_metadata_lengths(metadata: &TokenMetadata) -> Result<(), String> {
+    let fields = [
+        ("name", Some(&metadata.name)),
+        ("symbol", Some(&metadata.symbol)),
+        ("description", Some(&metadata.description)),
+        ("image_url", Some(&metadata.image_url)),
+        ("telegram_link", metadata.telegram_link.as_ref()),
+        ("twitter_link", metadata.twitter_link.as_ref()),
+        ("website", metadata.website.as_ref()),
+        ("discord", metadata.discord.as_ref()),
+        ("uri", metadata.uri.as_ref()),
+    ];
+    for (field, value) in fields {
+        if value.is_some_and(|value| value.len() > MAX_METADATA_FIELD_LEN) {
+            return Err(format!("Metadata {} must be at most {} characters", field, MAX_METADATA_FIELD_LEN));
+        }
+    }
+    if metadata.extra_links.len() > MAX_EXTRA_LINKS {
+        return Err(format!("At most {} extra links are allowed", MAX_EXTRA_LINKS));
+    }
+    for (label, link) in &metadata.extra_links {
+        if label.len() > MAX_METADATA_FIELD_LEN || link.len() > MAX_METADATA_FIELD_LEN {
+            return Err(format!("Metadata extra link {} must be at most {} characters", label, MAX_METADATA_FIELD_LEN));
+        }
+    }
+    Ok(())
+}
+
+fn validation_error_response(error: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse::new(error))
+}
+
+/// JSON extractor settings capping the body at `limit` bytes.
+/// 
+/// Malformed and oversized bodies get the usual error envelope rather than actix's plain text.
+fn json_config(limit: usize) -> web::JsonConfig {
+    web::JsonConfig::default()
+        .limit(limit)
+        .error_handler(|err, _req| {
+            let response = HttpResponse::build(actix_web::ResponseError::status_code(&err)).json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Invalid request body: {}", err)
+            }));
+            actix_web::error::InternalError::from_response(err, response).into()
+        })
+}
+
+/// Resolves the keypair that signs a creation request.
+/// 
+/// Signers come from the wallet store by `wallet_id`. A raw `private_key` is only accepted
+/// while `allow_raw_key` is set, to give existing clients time to move to stored wallets.
+async fn resolve_signer(state: &ApiState, wallet_id: &str, private_key: Option<&str>) -> Result<Keypair, String> {
+    if let Some(private_key) = private_key {
+        if !state.allow_raw_key {
+            return Err(
+                "Raw private keys are not accepted; create a wallet with POST /api/wallet/create or register this key with POST /api/wallet/register, and pass its wallet_id"
+                    .to_string(),
+            );
+        }
+        warn!("Request for wallet {} sent a raw private key; raw keys are deprecated", wallet_id);
+        return decode_keypair(private_key).map_err(|e| format!("Invalid private key: {}", e));
+    }
+
+    if wallet_id.is_empty() {
+        return Err("Wallet ID is required".to_string());
+    }
+    state
+        .wallets
+        .read()
+        .await
+        .get_keypair(wallet_id)
+        .map(|keypair| keypair.insecure_clone())
+        .ok_or_else(|| {
+            format!(
+                "Unknown wallet ID: {}; create a wallet with POST /api/wallet/create first",
+                wallet_id
+            )
+        })
+}
+
+fn decode_keypair(private_key: &str) -> Result<Keypair, Box<dyn std::error::Error>> {
+    let decoded = bs58::decode(private_key)
+        .into_vec()?;
+    
+    if decoded.len() != 64 {
+        return Err("Invalid private key length".into());
+    }
+
+    Ok(Keypair::from_bytes(&decoded)?)
+}
+
+/// Builds the CORS layer for the configured origin allowlist.
+/// 
+/// An empty list only admits pages served from localhost; `"*"` admits any origin and is
+/// meant for development.
+fn cors_layer(allowed_origins: &[String]) -> Cors {
+    let cors = Cors::default().allow_any_method().allow_any_header();
+
+    if allowed_origins.iter().any(|origin| origin == "*") {
+        return cors.allow_any_origin();
+    }
+    if allowed_origins.is_empty() {
+        return cors.allowed_origin_fn(|origin, _| is_localhost_origin(origin.as_bytes()));
+    }
+    allowed_origins
+        .iter()
+        .fold(cors, |cors, origin| cors.allowed_origin(origin))
+}
+
+fn is_localhost_origin(origin: &[u8]) -> bool {
+    ["http://localhost", "http://127.0.0.1"].iter().any(|host| {
+        origin
+            .strip_prefix(host.as_bytes())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(b":"))
+    })
+}
+
+impl ApiState {
+    /// Builds the server state, opening the wallet store, wallet id sequence and trade log
+    /// named in `config`.
+    pub fn new(
+        pump_fun_client: PumpFunClient,
+        rpc_client: RpcPool,
+        jito_client: JitoBundleClient,
+        config: &Config,
+    ) -> anyhow::Result<Self> {
+        // The queue sends through a client of its own; every other bundle waits in it
+        let defaults = QueueLimits::default();
+        let bundle_queue = Arc::new(BundleQueue::new(
+            Arc::new(jito_client.clone()),
+            QueueLimits {
+                concurrency: config.bundle_queue_concurrency.unwrap_or(defaults.concurrency),
+                capacity: config.bundle_queue_capacity.unwrap_or(defaults.capacity),
+                ..defaults
+            },
+        ));
+        let mut jito_client = jito_client;
+        jito_client.set_queue(bundle_queue.clone());
+        let treasury = config.treasury_address.as_deref()
+            .map(Pubkey::from_str)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid treasury_address: {}", e))?;
+        Ok(Self {
+            pump_fun_client,
+            rpc_client,
+            request_deadline: Duration::from_millis(config.request_deadline_ms),
+            include_bundle_details: config.include_bundle_details,
+            jito_client,
+            wallet_ids: WalletIdSequence::open(&config.wallet_sequence_path)?,
+            wallets: RwLock::new(WalletStore::load(&config.wallet_store_path, &config.encryption_key)?),
+            allow_raw_key: config.allow_raw_key,
+            idempotency: Mutex::new(IdempotencyCache::new(IDEMPOTENCY_TTL, IDEMPOTENCY_CAPACITY)),
+            trade_log: TradeLog::open(&config.trade_log_path)?,
+            price_feed: PriceFeed {
+                ws_url: config.solana_ws_url.clone(),
+                poll_interval: Duration::from_millis(config.price_poll_interval_ms),
+            },
+            bundle_queue,
+            admin_token: config.admin_token.clone(),
+            treasury,
+        })
+    }
+}
+
+pub async fn start_api_server(
+    mut pump_fun_client: PumpFunClient,
+    jito_client: JitoBundleClient,
+    config: Config,
+) -> std::io::Result<()> {
+    // Initialize Solana RPC client
+    let rpc_client = RpcPool::from_config(&config);
+
+    // Surface fee wallet problems before taking traffic
+    if config.check_fee_wallet_on_startup {
+        match pump_fun_client.fee_wallet_status(&rpc_client).await {
+            Ok(status) if status.issues.is_empty() => {
+                info!("Fee wallet {} ready with {} SOL", status.address, status.balance);
+            }
+            Ok(status) => {
+                for issue in &status.issues {
+                    warn!("Fee wallet {}: {}", status.address, issue);
+                }
+            }
+            Err(e) => warn!("Fee wallet check failed: {}", e),
+        }
+    }
+    
+    info!("Submitting bundles to {} with a {} SOL tip", jito_client.bundle_url(), jito_client.tip_amount());
+
+    if let Some(percentile) = config.priority_fee_percentile {
+        let accounts = if config.priority_fee_accounts.is_empty() {
+            vec![pump_fun_client.fee_address]
+        } else {
+            config.priority_fee_accounts.iter()
+                .map(|account| Pubkey::from_str(account))
+                .collect::<Result<_, _>>()
+                .map_err(|e| std::io::Error::other(format!("Invalid priority fee account: {}", e)))?
+        };
+        let autopilot = Arc::new(FeeAutopilot::new(
+            accounts,
+            percentile,
+            pump_fun_client.config.max_priority_fee_micro_lamports,
+        ));
+        // Sampled on a pool of its own, so failed samples never trip the API pool's circuit breakers
+        autopilot.clone().spawn(RpcPool::from_config(&config), Duration::from_secs(config.priority_fee_refresh_secs));
+        info!("Pricing compute units at p{} of recent prioritization fees", percentile);
+        pump_fun_client.fee_autopilot = Some(autopilot);
+    }
+
+    // Create API state
+    if config.allow_raw_key {
+        warn!("allow_raw_key is set; creation requests may send private keys over HTTP");
+    }
+
+    let state = ApiState::new(pump_fun_client, rpc_client, jito_client, &config)
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    let state = web::Data::new(state);
+    
+    if config.allowed_origins.iter().any(|origin| origin == "*") {
+        warn!("CORS allows any origin; only use this in development");
+    }
+
+    println!("Starting API server on http://127.0.0.1:8080");
+    
+    HttpServer::new(move || {
+        App::new()
+            .wrap(cors_layer(&config.allowed_origins))
+            .app_data(state.clone())
+            .app_data(json_config(MAX_JSON_PAYLOAD_BYTES))
+            .route("/health", web::get().to(health_check))
+            .route("/health/ready", web::get().to(readiness_check))
+            .route("/openapi.json", web::get().to(openapi_document))
+            .route("/metrics", web::get().to(metrics))
+            .service(
+                web::resource("/api/token/create")
+                    .app_data(json_config(MAX_CREATE_PAYLOAD_BYTES))
+                    .route(web::post().to(create_token)),
+            )
+            .route("/api/token/launch", web::post().to(launch_token))
+            .route("/api/token/{mint}/quote", web::get().to(token_quote))
+            .route("/api/token/{mint}/sell-quote", web::get().to(token_sell_quote))
+            .route("/api/token/{mint}/holders", web::get().to(token_holders))
+            .route("/api/token/{mint}/metadata", web::get().to(token_metadata))
+            .route("/ws/token/{mint}/price", web::get().to(token_price_socket))
+            .route("/api/bundle/buy", web::post().to(buy_tokens))
+            .route("/api/bundle/sell", web::post().to(sell_tokens))
+            .route("/api/bundle/sell_all", web::post().to(sell_all_tokens))
+            .route("/api/bundle/estimate", web::get().to(estimate_bundle_cost))
+            .route("/api/bundle/simulate", web::post().to(simulate_buy))
+            .route("/api/bundle/status/{bundle_id}", web::get().to(bundle_status))
+            .route("/api/bundle/queue", web::post().to(queue_bundle))
+            .route("/api/bundle/queue/{id}", web::get().to(queued_bundle_status))
+            .route("/api/wallet/create", web::post().to(create_wallet))
+            .route("/api/wallet/register", web::post().to(register_wallet))
+            .route("/api/wallet/{address}", web::get().to(wallet_info))
+            .route("/api/wallet/{id}/history", web::get().to(wallet_history))
+            .route("/api/wallet/{id}/history.{format}", web::get().to(export_wallet_history))
+            .route("/api/admin/fees/wallet", web::get().to(fee_wallet_status))
+            .route("/api/admin/sweep", web::post().to(sweep_wallets))
+            .configure(|cfg| {
+                if config.expose_version_endpoint {
+                    cfg.route("/api/version", web::get().to(version_info));
+                }
+            })
+    })
+    .bind("127.0.0.1:8080")?
+    .run()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use solana_client::client_error::ClientError;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Builds a `getAccountInfo` mock response carrying the given account data.
+    fn mock_account_info(data: &[u8]) -> serde_json::Value {
+        serde_json::json!({
+            "context": { "slot": 1 },
+            "value": {
+                "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+                "executable": false,
+                "lamports": 1_000_000,
+                "owner": PumpFunConfig::default().program_id,
+                "rentEpoch": 0,
+                "space": data.len()
+            }
+        })
+    }
+
+    /// Builds API state backed by a mock RPC client.
+    fn mock_state(mocks: HashMap<RpcRequest, serde_json::Value>) -> web::Data<ApiState> {
+        web::Data::new(mock_api_state(mocks))
+    }
+
+    fn mock_api_state(mocks: HashMap<RpcRequest, serde_json::Value>) -> ApiState {
+        let config = PumpFunConfig::default();
+        ApiState {
+            pump_fun_client: PumpFunClient::new(config.program_id, config.fee_address),
+            rpc_client: RpcPool::new_mock_with_mocks("succeeds", mocks),
+            request_deadline: Duration::from_secs(30),
+            include_bundle_details: true,
+            jito_client: JitoBundleClient::new("http://127.0.0.1:1".to_string()),
+            wallet_ids: WalletIdSequence::open(
+                std::env::temp_dir().join(format!("wallet-seq-{}", Uuid::new_v4())),
+            )
+            .unwrap(),
+            wallets: RwLock::new(
+                WalletStore::load(std::env::temp_dir().join(format!("wallets-{}.enc", Uuid::new_v4())), "test-key")
+                    .unwrap(),
+            ),
+            allow_raw_key: false,
+            idempotency: Mutex::new(IdempotencyCache::new(IDEMPOTENCY_TTL, IDEMPOTENCY_CAPACITY)),
+            trade_log: TradeLog::open(std::env::temp_dir().join(format!("trades-{}.jsonl", Uuid::new_v4())))
+                .unwrap(),
+            price_feed: PriceFeed { ws_url: None, poll_interval: Duration::from_millis(100) },
+            bundle_queue: Arc::new(BundleQueue::new(
+                Arc::new(JitoBundleClient::new("http://127.0.0.1:1".to_string())),
+                QueueLimits { concurrency: 1, ..QueueLimits::default() },
+            )),
+            admin_token: None,
+            treasury: None,
+        }
+    }
+
+    fn mock_curve_state() -> web::Data<ApiState> {
+        let curve = crate::pump_fun::tests::curve_account_bytes(1000.0, 1000000.0);
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, mock_account_info(&curve));
+        mock_state(mocks)
+    }
+
+    #[actix_web::test]
+    async fn test_request_deadline_header() {
+        let default = Duration::from_secs(30);
+
+        let req = test::TestRequest::default()
+            .insert_header((DEADLINE_HEADER, "250"))
+            .to_http_request();
+        assert!(request_deadline(&req, default).remaining() <= Duration::from_millis(250));
+
+        let req = test::TestRequest::default()
+            .insert_header((DEADLINE_HEADER, "soon"))
+            .to_http_request();
+        assert!(request_deadline(&req, default).remaining() > Duration::from_secs(29));
+    }
+
+    #[actix_web::test]
+    async fn test_deadline_exceeded_maps_to_gateway_timeout() {
+        let err: anyhow::Error = DeadlineExceeded { budget: Duration::from_millis(5) }.into();
+        assert_eq!(operation_error_response(err, "buy tokens").status(), 504);
+
+        let err = anyhow::anyhow!("RPC unavailable");
+        assert_eq!(operation_error_response(err, "buy tokens").status(), 500);
+
+        // A wallet drained since the pre-check is the caller's to fix
+        let err = anyhow::Error::new(PumpFunError::InsufficientFunds {
+            wallet: "w".to_string(),
+            detail: "not enough SOL".to_string(),
+        })
+        .context("Failed to send transaction");
+        assert_eq!(operation_error_response(err, "create token").status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_bundle_data_includes_route_and_fees() {
+        let result = TransactionResult {
+            success: true,
+            signature: Some("5igna7ure".to_string()),
+            bundle_id: Some("bundle_abc".to_string()),
+            error: None,
+            fee_paid: Some(0.005),
+            serialized_tx: None,
+            confirmation: None,
+            mint: None,
+            signatures: Vec::new(),
+        };
+
+        let data = BundleData::from_result(&result, SubmissionRoute::Jito, 10_000, true);
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["route"], "jito");
+        assert_eq!(json["tip_lamports"], 10_000);
+        assert_eq!(json["total_fee_lamports"], 5_000_000 + 10_000);
+        assert_eq!(json["transactions"], serde_json::json!(["5igna7ure"]));
+        assert_eq!(json["bundle_id"], "bundle_abc");
+
+        // Details are omitted unless enabled
+        let data = BundleData::from_result(&result, SubmissionRoute::Jito, 10_000, false);
+        let json = serde_json::to_value(&data).unwrap();
+        assert!(json.get("route").is_none());
+        assert!(json.get("tip_lamports").is_none());
+
+        // A bundle lists every transaction it carried
+        let bundled = TransactionResult { signatures: vec!["5igna7ure".to_string(), "t1p".to_string()], ..result };
+        let json = serde_json::to_value(BundleData::from_result(&bundled, SubmissionRoute::Jito, 10_000, false)).unwrap();
+        assert_eq!(json["transactions"], serde_json::json!(["5igna7ure", "t1p"]));
+    }
+
+    // The blocking RpcClient needs a multi-threaded runtime
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quote_endpoint() {
+        let mint = Pubkey::new_unique();
+        let app = test::init_service(
+            App::new()
+                .app_data(mock_curve_state())
+                .route("/api/token/{mint}/quote", web::get().to(token_quote)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/token/{}/quote?sol=1.5", mint))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["sol_amount"], 1.5);
+        assert!(body["data"]["token_amount"].as_f64().unwrap() > 0.0);
+        assert!(body["data"]["price_impact_pct"].as_f64().unwrap() > 0.0);
+    }
+
+    /// An RPC node whose account reads wait, up to a second, until two have arrived
+    #[derive(Clone, Default)]
+    struct OverlapNode {
+        arrived: Arc<AtomicUsize>,
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl RpcSender for OverlapNode {
+        async fn send(&self, request: RpcRequest, _: serde_json::Value) -> Result<serde_json::Value, ClientError> {
+            if request == RpcRequest::GetVersion {
+                return Ok(serde_json::json!({ "solana-core": "1.18.26", "feature-set": 0 }));
+            }
+            self.arrived.fetch_add(1, Ordering::SeqCst);
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            let give_up = std::time::Instant::now() + Duration::from_secs(1);
+            while self.arrived.load(Ordering::SeqCst) < 2 && std::time::Instant::now() < give_up {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(mock_account_info(&crate::pump_fun::tests::curve_account_bytes(1000.0, 1000000.0)))
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "overlap".to_string()
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_quotes_run_in_parallel() {
+        let node = OverlapNode::default();
+        let state = web::Data::new(ApiState {
+            rpc_client: RpcPool::new(vec![node.clone()], 5, Duration::from_secs(30), false),
+            ..mock_api_state(HashMap::new())
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/api/token/{mint}/quote", web::get().to(token_quote)),
+        )
+        .await;
+        let quote = || {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/token/{}/quote?sol=1", Pubkey::new_unique()))
+                .to_request();
+            test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req)
+        };
+
+        // Both quotes share one single-threaded runtime, as requests on one actix worker do
+        let (first, second) = futures::join!(quote(), quote());
+        assert_eq!(first["success"], true);
+        assert_eq!(second["success"], true);
+        // Both curve reads were in flight at once; reads blocking the worker would have run them in turn
+        assert_eq!(node.max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    /// An RPC node serving a bonding curve to every account read, recording the commitment
+    /// each read asked for; anything else gets the mock defaults
+    #[derive(Clone)]
+    struct CurveNode {
+        inner: Arc<solana_rpc_client::mock_sender::MockSender>,
+        commitments: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Default for CurveNode {
+        fn default() -> Self {
+            Self {
+                inner: Arc::new(solana_rpc_client::mock_sender::MockSender::new("succeeds")),
+                commitments: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RpcSender for CurveNode {
+        async fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value, ClientError> {
+            if request != RpcRequest::GetAccountInfo {
+                return self.inner.send(request, params).await;
+            }
+            let commitment = params[1]["commitment"].as_str().unwrap_or_default().to_string();
+            self.commitments.lock().unwrap().push(commitment);
+            Ok(mock_account_info(&crate::pump_fun::tests::curve_account_bytes(1000.0, 1000000.0)))
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "curve".to_string()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_buys_sign_with_the_stored_wallets() {
+        let mut api_state = ApiState {
+            rpc_client: RpcPool::new(vec![CurveNode::default()], 5, Duration::from_secs(30), false),
+            ..mock_api_state(HashMap::new())
+        };
+        api_state.pump_fun_client.config.verify_mint_owner = false;
+        let state = web::Data::new(api_state);
+        let mut addresses = Vec::new();
+        for wallet_id in ["w-000001", "w-000002"] {
+            let wallet = Keypair::new();
+            addresses.push(wallet.pubkey());
+            state.wallets.write().await.add_wallet(wallet_id.to_string(), wallet).unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/bundle/buy", web::post().to(buy_tokens)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/bundle/buy")
+            .set_json(serde_json::json!({
+                "tokenAddress": Pubkey::new_unique().to_string(),
+                "solAmounts": [0.1, 0.2, 0.3],
+                "walletIds": ["w-000001", "w-missing", "w-000002"],
+                "userId": 1,
+                "dryRun": true
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+
+        // The unknown wallet is left out, and the stored ones sign for themselves
+        let failed = body["data"]["failed_wallets"].as_array().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0]["wallet_id"], "w-missing");
+        assert_eq!(failed[0]["error"], "Unknown wallet ID: w-missing");
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(body["data"]["serialized_tx"].as_str().unwrap())
+            .unwrap();
+        let transaction: solana_sdk::transaction::Transaction = bincode::deserialize(&bytes).unwrap();
+        let signers = &transaction.message.account_keys[..transaction.message.header.num_required_signatures as usize];
+        assert_eq!(signers, addresses.as_slice());
+        assert!(transaction.verify().is_ok());
+
+        // With no stored wallet there is nothing to buy with
+        let req = test::TestRequest::post()
+            .uri("/api/bundle/buy")
+            .set_json(serde_json::json!({
+                "tokenAddress": Pubkey::new_unique().to_string(),
+                "solAmounts": [0.1],
+                "walletIds": ["w-missing"],
+                "userId": 1
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Unknown wallet ID: w-missing");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quotes_read_the_curve_at_the_requested_commitment() {
+        let node = CurveNode::default();
+        let state = web::Data::new(ApiState {
+            rpc_client: RpcPool::new(vec![node.clone()], 5, Duration::from_secs(30), false),
+            ..mock_api_state(HashMap::new())
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/api/token/{mint}/quote", web::get().to(token_quote))
+                .route("/api/token/{mint}/sell-quote", web::get().to(token_sell_quote)),
+        )
+        .await;
+        let mint = Pubkey::new_unique();
+
+        for query in ["quote?sol=1&commitment=processed", "sell-quote?tokens=10&commitment=finalized", "quote?sol=1"] {
+            let req = test::TestRequest::get().uri(&format!("/api/token/{}/{}", mint, query)).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 200, "{}", query);
+        }
+        // Without the param, the read keeps the client's default commitment
+        assert_eq!(*node.commitments.lock().unwrap(), vec!["processed", "finalized", "finalized"]);
+
+        for query in ["quote?sol=1&commitment=recent", "sell-quote?tokens=10&commitment=Confirmed"] {
+            let req = test::TestRequest::get().uri(&format!("/api/token/{}/{}", mint, query)).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400, "{}", query);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert!(body["error"].as_str().unwrap().contains("commitment"));
+        }
+        assert_eq!(node.commitments.lock().unwrap().len(), 3, "rejected quotes never reach the RPC");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quote_endpoint_rejects_bad_input() {
+        let mint = Pubkey::new_unique();
+
+        for query in ["sol=0", "tokens=-10", "sol=1&tokens=1", "tokens=5000000"] {
+            // Mock responses are consumed on use, so each request gets a fresh app
+            let app = test::init_service(
+                App::new()
+                    .app_data(mock_curve_state())
+                    .route("/api/token/{mint}/quote", web::get().to(token_quote)),
+            )
+            .await;
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/token/{}/quote?{}", mint, query))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400, "query `{}` should be rejected", query);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sell_quote_endpoint() {
+        let mint = Pubkey::new_unique();
+        let sell_quote = |query: &'static str| async move {
+            // Mock responses are consumed on use, so each request gets a fresh app
+            let app = test::init_service(
+                App::new()
+                    .app_data(mock_curve_state())
+                    .route("/api/token/{mint}/sell-quote", web::get().to(token_sell_quote)),
+            )
+            .await;
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/token/{}/sell-quote?{}", mint, query))
+                .to_request();
+            test::call_service(&app, req).await
+        };
+
+        let resp = sell_quote("tokens=10000").await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let data = &body["data"];
+        assert_eq!(data["token_amount"], 10000.0);
+        let sol_out = data["sol_out"].as_f64().unwrap();
+        assert!(sol_out > 0.0);
+        assert!(data["fee"].as_f64().unwrap() > 0.0);
+        // The curve started at 0.001 SOL per token, and selling pushes it down
+        assert!(data["price_after"].as_f64().unwrap() < 0.001);
+
+        // Selling returns less than buying the same tokens costs
+        let app = test::init_service(
+            App::new()
+                .app_data(mock_curve_state())
+                .route("/api/token/{mint}/quote", web::get().to(token_quote)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/token/{}/quote?tokens=10000", mint))
+            .to_request();
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+        assert!(sol_out < body["data"]["sol_amount"].as_f64().unwrap());
+
+        let resp = sell_quote("tokens=-10").await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["error"].as_str().unwrap().contains("greater than zero"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fee_wallet_endpoint() {
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetBalance,
+            serde_json::json!({ "context": { "slot": 1 }, "value": 2_500_000_000u64 }),
+        );
+        mocks.insert(RpcRequest::GetMinimumBalanceForRentExemption, serde_json::json!(890_880));
+        let admin_state = |mocks| web::Data::new(ApiState {
+            admin_token: Some(Secret::new("admin-secret")),
+            ..mock_api_state(mocks)
+        });
+        let status = |authorization: Option<&str>| {
+            let mut req = test::TestRequest::get().uri("/api/admin/fees/wallet");
+            if let Some(authorization) = authorization {
+                req = req.insert_header(("Authorization", authorization));
+            }
+            req.to_request()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(admin_state(mocks))
+                .route("/api/admin/fees/wallet", web::get().to(fee_wallet_status)),
+        )
+        .await;
+
+        assert_eq!(test::call_service(&app, status(None)).await.status(), 401);
+        assert_eq!(test::call_service(&app, status(Some("Bearer wrong-secret"))).await.status(), 401);
+
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, status(Some("Bearer admin-secret"))).await;
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["address"], PumpFunConfig::default().fee_address);
+        assert_eq!(body["data"]["balance"], 2.5);
+        assert_eq!(body["data"]["ready"], true);
+        assert_eq!(body["data"]["sweep_recommended"], false);
+
+        // An unfunded wallet is reported as not ready
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetBalance, serde_json::json!({ "context": { "slot": 1 }, "value": 0 }));
+        mocks.insert(RpcRequest::GetMinimumBalanceForRentExemption, serde_json::json!(890_880));
+        let app = test::init_service(
+            App::new()
+                .app_data(admin_state(mocks))
+                .route("/api/admin/fees/wallet", web::get().to(fee_wallet_status)),
+        )
+        .await;
+
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, status(Some("Bearer admin-secret"))).await;
+        assert_eq!(body["data"]["ready"], false);
+        assert_eq!(body["data"]["issues"].as_array().unwrap().len(), 1);
+
+        // Without a configured token, the endpoint is closed to everyone
+        let app = test::init_service(
+            App::new()
+                .app_data(mock_state(HashMap::new()))
+                .route("/api/admin/fees/wallet", web::get().to(fee_wallet_status)),
+        )
+        .await;
+        assert_eq!(test::call_service(&app, status(Some("Bearer admin-secret"))).await.status(), 403);
+    }
+
+    fn mock_token_account(owner: &Pubkey, mint: &Pubkey, amount: u64) -> serde_json::Value {
+        use solana_sdk::program_pack::Pack;
+
+        let account = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        mock_account_info(&data)
+    }
+
+    async fn get_wallet(mocks: HashMap<RpcRequest, serde_json::Value>, uri: &str) -> serde_json::Value {
+        let app = test::init_service(
+            App::new()
+                .app_data(mock_state(mocks))
+                .route("/api/wallet/{address}", web::get().to(wallet_info)),
+        )
+        .await;
+        let req = test::TestRequest::get().uri(uri).to_request();
+        test::call_and_read_body_json(&app, req).await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wallet_endpoint_balances() {
+        let wallet = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let balance = serde_json::json!({ "context": { "slot": 1 }, "value": 1_500_000_000u64 });
+
+        // SOL only
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetBalance, balance.clone());
+        let body = get_wallet(mocks, &format!("/api/wallet/{}", wallet)).await;
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["address"], wallet.to_string());
+        assert_eq!(body["data"]["balance"], 1.5);
+        assert!(body["data"]["token_balance"].is_null());
+
+        // Existing associated token account
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetBalance, balance.clone());
+        mocks.insert(RpcRequest::GetAccountInfo, mock_token_account(&wallet, &mint, 12_345));
+        let body = get_wallet(mocks, &format!("/api/wallet/{}?mint={}", wallet, mint)).await;
+        assert_eq!(body["data"]["token_balance"], 12_345);
+
+        // No associated token account: the default mock returns no account
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetBalance, balance);
+        let body = get_wallet(mocks, &format!("/api/wallet/{}?mint={}", wallet, mint)).await;
+        assert_eq!(body["success"], true);
+        assert!(body["data"]["token_balance"].is_null());
+    }
+
+    #[actix_web::test]
+    async fn test_create_wallet_assigns_sequential_ids() {
+        let state = mock_state(HashMap::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/wallet/create", web::post().to(create_wallet)),
+        )
+        .await;
+
+        for expected in ["w-000001", "w-000002"] {
+            let req = test::TestRequest::post().uri("/api/wallet/create").to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["data"]["wallet_id"], expected);
+
+            let address = state.wallets.read().await.get_keypair(expected).unwrap().pubkey().to_string();
+            assert_eq!(body["data"]["address"], address);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_register_wallet_stores_each_kind_of_key_without_echoing_it() {
+        let state = mock_state(HashMap::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/wallet/register", web::post().to(register_wallet)),
+        )
+        .await;
+        let register = |body: serde_json::Value| {
+            test::TestRequest::post().uri("/api/wallet/register").set_json(body).to_request()
+        };
+
+        let imported = Keypair::new();
+        let private_key = bs58::encode(imported.to_bytes()).into_string();
+        let from_file = Keypair::new();
+        let seed_file = Keypair::new();
+        let cases = [
+            (serde_json::json!({ "mode": "private_key", "private_key": private_key }), Some(&imported), private_key.clone()),
+            (
+                serde_json::json!({ "mode": "keypair_file", "keypair_file": from_file.to_bytes().to_vec() }),
+                Some(&from_file),
+                serde_json::to_string(&from_file.to_bytes().to_vec()).unwrap(),
+            ),
+            (
+                serde_json::json!({ "mode": "keypair_file", "keypair_file": seed_file.secret().to_bytes().to_vec() }),
+                Some(&seed_file),
+                serde_json::to_string(&seed_file.secret().to_bytes().to_vec()).unwrap(),
+            ),
+            (serde_json::json!({ "mode": "generate" }), None, String::new()),
+        ];
+        for (i, (body, keypair, secret)) in cases.into_iter().enumerate() {
+            let resp = test::call_service(&app, register(body)).await;
+            assert_eq!(resp.status(), 200);
+            let text = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            let body: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+            let wallet_id = format!("w-{:06}", i + 1);
+            assert_eq!(body["data"]["wallet_id"], wallet_id.as_str());
+            let stored = state.wallets.read().await.get_keypair(&wallet_id).unwrap().insecure_clone();
+            assert_eq!(body["data"]["address"], stored.pubkey().to_string());
+            if let Some(keypair) = keypair {
+                assert_eq!(stored.pubkey(), keypair.pubkey());
+            }
+
+            // Neither the key as sent nor the stored key comes back
+            assert_eq!(body["data"].as_object().unwrap().len(), 2);
+            if !secret.is_empty() {
+                assert!(!text.contains(&secret));
+            }
+            assert!(!text.contains(&bs58::encode(stored.to_bytes()).into_string()));
+        }
+
+        for (body, error) in [
+            (serde_json::json!({ "mode": "private_key", "private_key": "not-base58!" }), "Invalid private key"),
+            (serde_json::json!({ "mode": "keypair_file", "keypair_file": [1, 2, 3] }), "3 bytes"),
+        ] {
+            let resp = test::call_service(&app, register(body)).await;
+            assert_eq!(resp.status(), 400);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert!(body["error"].as_str().unwrap().contains(error), "{}", body);
+        }
+        assert!(state.wallets.read().await.get_keypair("w-000005").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_version_endpoint() {
+        let app = test::init_service(
+            App::new().route("/api/version", web::get().to(version_info)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/version").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body["data"]["git_commit"].is_string());
+        assert!(body["data"]["build_timestamp"].is_u64());
+        assert!(body["data"]["features"].is_array());
+    }
+
+    #[actix_web::test]
+    async fn test_metrics_report_the_autopilot_price() {
+        async fn priority_fee(state: &web::Data<ApiState>) -> String {
+            let app = test::init_service(
+                App::new()
+                    .app_data(state.clone())
+                    .route("/metrics", web::get().to(metrics)),
+            )
+            .await;
+            let resp = test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+            assert!(resp.status().is_success());
+            let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            body.lines()
+                .find_map(|line| line.strip_prefix("pump_swap_priority_fee_micro_lamports "))
+                .expect("no priority fee gauge")
+                .to_string()
+        }
+
+        assert_eq!(priority_fee(&mock_state(HashMap::new())).await, "0");
+        let mut api_state = mock_api_state(HashMap::new());
+        api_state.pump_fun_client.config.priority_fee_micro_lamports = Some(5_000);
+
+        // The autopilot's sample replaces the fixed price once it has one
+        let autopilot = Arc::new(FeeAutopilot::new(Vec::new(), 75.0, 1_000_000));
+        api_state.pump_fun_client.fee_autopilot = Some(autopilot.clone());
+        let state = web::Data::new(api_state);
+        assert_eq!(priority_fee(&state).await, "5000");
+        autopilot.record(&[100, 200, 300, 400]);
+        assert_eq!(priority_fee(&state).await, "300");
+    }
+
+    #[actix_web::test]
+    async fn test_cors_allowlist() {
+        async fn allow_origin(allowed: &[&str], origin: &str) -> Option<String> {
+            let allowed: Vec<String> = allowed.iter().map(|o| o.to_string()).collect();
+            let app = test::init_service(
+                App::new()
+                    .wrap(cors_layer(&allowed))
+                    .route("/health", web::get().to(health_check)),
+            )
+            .await;
+            let req = test::TestRequest::get()
+                .uri("/health")
+                .insert_header(("Origin", origin))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            resp.headers()
+                .get("access-control-allow-origin")
+                .map(|value| value.to_str().unwrap().to_string())
+        }
+
+        let allowed = ["https://app.example.com"];
+        assert_eq!(
+            allow_origin(&allowed, "https://app.example.com").await.as_deref(),
+            Some("https://app.example.com")
+        );
+        assert_eq!(allow_origin(&allowed, "https://evil.example.com").await, None);
+
+        // No allowlist admits localhost only
+        assert!(allow_origin(&[], "http://localhost:3000").await.is_some());
+        assert_eq!(allow_origin(&[], "http://localhost.evil.com").await, None);
+        assert_eq!(allow_origin(&[], "https://app.example.com").await, None);
+
+        // Wildcard is an explicit opt-in
+        assert!(allow_origin(&["*"], "https://anywhere.example.com").await.is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_wallet_list_validation() {
+        let ids = |count: usize| (1..=count).map(|i| format!("w-{:06}", i)).collect::<Vec<_>>();
+        assert!(validate_wallet_lists(4, &ids(4), 16, "SOL amounts").is_ok());
+        assert_eq!(
+            validate_wallet_lists(3, &ids(4), 16, "SOL amounts").unwrap_err(),
+            "Number of SOL amounts (3) must match number of wallet IDs (4)"
+        );
+        assert!(validate_wallet_lists(17, &ids(17), 16, "SOL amounts").is_err());
+
+        let mut duplicated = ids(3);
+        duplicated.push("w-000002".to_string());
+        assert_eq!(
+            validate_wallet_lists(4, &duplicated, 16, "SOL amounts").unwrap_err(),
+            "Wallet ID w-000002 is listed more than once"
+        );
+
+        let mut metadata = crate::pump_fun::tests::valid_metadata();
+        assert!(validate_metadata_lengths(&metadata).is_ok());
+        metadata.telegram_link = Some("t".repeat(MAX_METADATA_FIELD_LEN + 1));
+        assert!(validate_metadata_lengths(&metadata).unwrap_err().contains("telegram_link"));
+    }
+
+    #[actix_web::test]
+    async fn test_buy_rejects_oversized_and_mismatched_payloads() {
+        let app = test::init_service(
+            App::new()
+                .app_data(mock_state(HashMap::new()))
+                .app_data(json_config(MAX_JSON_PAYLOAD_BYTES))
+                .route("/api/bundle/buy", web::post().to(buy_tokens)),
+        )
+        .await;
+
+        let buy = |wallets: usize, amounts: usize| {
+            serde_json::json!({
+                "tokenAddress": Pubkey::new_unique().to_string(),
+                "solAmounts": vec![0.1; amounts],
+                "walletIds": (0..wallets).map(|n| format!("w-{:06}", n)).collect::<Vec<_>>(),
+                "userId": 1
+            })
+        };
+
+        let req = test::TestRequest::post().uri("/api/bundle/buy").set_json(buy(4, 3)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["error"].as_str().unwrap().contains("must match"));
+
+        let req = test::TestRequest::post().uri("/api/bundle/buy").set_json(buy(17, 17)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+
+        // A body over the limit is refused before it's parsed
+        let req = test::TestRequest::post().uri("/api/bundle/buy").set_json(buy(10_000, 10_000)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 413);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["success"], false);
+    }
+
+    #[actix_web::test]
+    async fn test_create_token_rejects_raw_keys_by_default() {
+        let state = mock_state(HashMap::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/token/create", web::post().to(create_token)),
+        )
+        .await;
+
+        let metadata = crate::pump_fun::tests::valid_metadata();
+        let raw_key = bs58::encode(Keypair::new().to_bytes()).into_string();
+        let req = test::TestRequest::post()
+            .uri("/api/token/create")
+            .set_json(serde_json::json!({
+                "metadata": metadata,
+                "user_id": 1,
+                "wallet_id": "w-000001",
+                "private_key": raw_key
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["error"].as_str().unwrap().contains("/api/wallet/create"));
+
+        // Without a key, the wallet must already be in the store
+        let req = test::TestRequest::post()
+            .uri("/api/token/create")
+            .set_json(serde_json::json!({
+                "metadata": metadata,
+                "user_id": 1,
+                "wallet_id": "w-000001"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["error"].as_str().unwrap().contains("Unknown wallet ID"));
+
+        // Opting in lets the raw key through
+        let permissive = ApiState { allow_raw_key: true, ..mock_api_state(HashMap::new()) };
+        assert!(resolve_signer(&permissive, "w-000001", Some(&raw_key)).await.is_ok());
+        state.wallets.write().await.add_wallet("w-000001".to_string(), Keypair::new()).unwrap();
+        assert!(resolve_signer(&state, "w-000001", None).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_create_token_returns_the_mint_address() {
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetBalance,
+            serde_json::json!({ "context": { "slot": 1 }, "value": 10_000_000_000u64 }),
+        );
+        let state = mock_state(mocks);
+        state.wallets.write().await.add_wallet("w-000001".to_string(), Keypair::new()).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/token/create", web::post().to(create_token)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/token/create")
+            .set_json(serde_json::json!({
+                "metadata": crate::pump_fun::tests::valid_metadata(),
+                "user_id": 1,
+                "wallet_id": "w-000001",
+                "dry_run": true
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let token_address = body["data"]["token_address"].as_str().unwrap();
+        let transaction_id = body["data"]["transaction_id"].as_str().unwrap();
+        assert!(Pubkey::from_str(token_address).is_ok());
+        assert_ne!(token_address, transaction_id);
+        // The test metadata has no website, which is flagged but not fatal
+        let warnings = body["data"]["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].as_str().unwrap().contains("website"));
+
+        // The mint signed the creation, so it is among the transaction's signers
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(body["data"]["serialized_tx"].as_str().unwrap())
+            .unwrap();
+        let transaction: solana_sdk::transaction::Transaction = bincode::deserialize(&bytes).unwrap();
+        let signers = &transaction.message.account_keys[..transaction.message.header.num_required_signatures as usize];
+        assert!(signers.contains(&Pubkey::from_str(token_address).unwrap()));
+
+        let history = state.trade_log.history("w-000001", 0, 10).unwrap();
+        assert!(history.items.is_empty(), "dry runs are not recorded");
+    }
+
+    // The blocking RpcClient needs a multi-threaded runtime
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_repeated_idempotency_key_replays_the_first_response() {
+        // The first buy sees a non-mint account; once that mock is used up the RPC reports
+        // no account at all, so a second submission would fail differently
+        let mut not_a_mint = mock_account_info(&[0u8; 82]);
+        not_a_mint["value"]["owner"] = serde_json::json!(solana_sdk::system_program::id().to_string());
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, not_a_mint);
+
+        let state = mock_state(mocks);
+        state.wallets.write().await.add_wallet("w-000001".to_string(), Keypair::new()).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/api/bundle/buy", web::post().to(buy_tokens)),
+        )
+        .await;
+        let buy = serde_json::json!({
+            "tokenAddress": Pubkey::new_unique().to_string(),
+            "solAmounts": [0.1],
+            "walletIds": ["w-000001"],
+            "userId": 1
+        });
+        let send = |key: Option<&str>| {
+            let mut req = test::TestRequest::post().uri("/api/bundle/buy").set_json(&buy);
+            if let Some(key) = key {
+                req = req.insert_header((IDEMPOTENCY_HEADER, key));
+            }
+            req.to_request()
+        };
+
+        let first: serde_json::Value = test::call_and_read_body_json(&app, send(Some("retry-1"))).await;
+        assert!(first["error"].as_str().unwrap().contains("owned by"));
+
+        let replayed: serde_json::Value = test::call_and_read_body_json(&app, send(Some("retry-1"))).await;
+        assert_eq!(replayed, first);
+
+        // Without the key the request really runs again
+        let rerun: serde_json::Value = test::call_and_read_body_json(&app, send(None)).await;
+        assert!(rerun["error"].as_str().unwrap().contains("does not exist"));
+    }
+
+    // The blocking RpcClient needs a multi-threaded runtime
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_simulate_reports_a_buy_without_submitting_it() {
+        let state = mock_curve_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/bundle/simulate", web::post().to(simulate_buy)),
+        )
+        .await;
+        let simulate = |wallet_ids: serde_json::Value| {
+            test::TestRequest::post()
+                .uri("/api/bundle/simulate")
+                .set_json(serde_json::json!({
+                    "tokenAddress": Pubkey::new_unique().to_string(),
+                    "solAmounts": [0.5, 1.5],
+                    "walletIds": wallet_ids,
+                    "userId": 1,
+                    "viaBundle": true
+                }))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, simulate(serde_json::json!(["w-000001", "w-000002"]))).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let data = &body["data"];
+        let total = |name: &str| data[name].as_f64().unwrap();
+        assert!(total("total_worst_case_tokens") > 0.0);
+        assert!(total("total_worst_case_tokens") <= total("total_expected_tokens"));
+        assert!(total("price_impact_pct") > 0.0);
+        assert!(data["costs"]["trading_fee"].as_f64().unwrap() > 0.0);
+        assert!(data["costs"]["total_fees"].as_f64().unwrap() > 0.0);
+        assert!(data["rejection"].is_null());
+        // Nothing was traded
+        assert!(state.trade_log.history("w-000001", 0, 10).unwrap().items.is_empty());
+
+        let resp = test::call_service(&app, simulate(serde_json::json!(["w-000001"]))).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_bundle_estimate_sums_a_multi_wallet_buy() {
+        let state = mock_state(HashMap::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/bundle/estimate", web::get().to(estimate_bundle_cost)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/bundle/estimate")
+            .set_json(serde_json::json!({
+                "tokenAddress": Pubkey::new_unique().to_string(),
+                "solAmounts": [0.5, 1.0, 1.5],
+                "walletIds": ["w-000001", "w-000002", "w-000003"],
+                "userId": 1,
+                "viaBundle": true
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let data = &body["data"];
+        let field = |name: &str| data[name].as_f64().unwrap();
+
+        assert!((field("base_amount") - 3.0).abs() < 1e-9);
+        // The default schedule charges a flat 0.5%
+        assert!((field("trading_fee") - 0.015).abs() < 1e-9);
+        assert!(field("jito_tip") > 0.0);
+        assert!(field("network_fee") > 0.0);
+        assert!((field("total_fees") - (field("trading_fee") + field("jito_tip") + field("network_fee"))).abs() < 1e-12);
+        assert!((field("total_amount") - (field("base_amount") + field("total_fees"))).abs() < 1e-12);
+        assert!(data["transaction_count"].as_u64().unwrap() >= 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sell_reports_each_wallet_and_succeeds_partially() {
+        let mint = Pubkey::new_unique();
+        let client = PumpFunClient::new(PumpFunConfig::default().program_id, PumpFunConfig::default().fee_address);
+        let (mocks, _) = crate::pump_fun::tests::graduated_pool_mocks(&client, &mint);
+        let mut api_state = mock_api_state(mocks);
+        api_state.pump_fun_client.config.verify_mint_owner = false;
+        api_state.wallets.get_mut().add_wallet("w-000001".to_string(), Keypair::new()).unwrap();
+        let state = web::Data::new(api_state);
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/bundle/sell", web::post().to(sell_tokens)),
+        )
+        .await;
+        let sell = |wallet_ids: &[&str]| {
+            test::TestRequest::post()
+                .uri("/api/bundle/sell")
+                .set_json(serde_json::json!({
+                    "tokenAddress": mint.to_string(),
+                    "tokenAmounts": vec![1_000; wallet_ids.len()],
+                    "walletIds": wallet_ids,
+                    "userId": 1
+                }))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, sell(&["w-000001", "w-missing"])).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["success"], true);
+        let wallets = body["data"]["wallets"].as_array().unwrap();
+        assert_eq!(wallets[0]["wallet_id"], "w-000001");
+        assert_eq!(wallets[0]["success"], true);
+        assert!(wallets[0]["signature"].is_string());
+        assert_eq!(wallets[1]["wallet_id"], "w-missing");
+        assert_eq!(wallets[1]["success"], false);
+        assert_eq!(wallets[1]["error"], "Unknown wallet ID: w-missing");
+
+        // Each wallet's outcome is logged separately
+        assert!(state.trade_log.history("w-000001", 0, 10).unwrap().items[0].success);
+        assert!(!state.trade_log.history("w-missing", 0, 10).unwrap().items[0].success);
+
+        // A sell where no wallet succeeds is rejected, naming each failure
+        let resp = test::call_service(&app, sell(&["w-x", "w-y"])).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body["error"],
+            "Sell failed for every wallet: w-x: Unknown wallet ID: w-x; w-y: Unknown wallet ID: w-y"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_configured_wallet_limit_governs_every_path() {
+        let mut api_state = mock_api_state(HashMap::new());
+        api_state.pump_fun_client.config.max_wallets_per_bundle = 3;
+        api_state.jito_client.set_max_transactions(api_state.pump_fun_client.config.max_wallets_per_bundle);
+        let state = web::Data::new(api_state);
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/bundle/buy", web::post().to(buy_tokens))
+                .route("/api/bundle/sell", web::post().to(sell_tokens))
+                .route("/api/token/launch", web::post().to(launch_token)),
+        )
+        .await;
+
+        // Request validation
+        for (uri, amounts) in [("/api/bundle/buy", "solAmounts"), ("/api/bundle/sell", "tokenAmounts")] {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .set_json(serde_json::json!({
+                    "tokenAddress": Pubkey::new_unique().to_string(),
+                    amounts: [1, 1, 1, 1],
+                    "walletIds": ["w-1", "w-2", "w-3", "w-4"],
+                    "userId": 1
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["error"], "Maximum 3 wallets allowed per bundle");
+        }
+
+        // A launch leaves room in the bundle for the creation and tip transactions
+        let req = test::TestRequest::post()
+            .uri("/api/token/launch")
+            .set_json(serde_json::json!({
+                "metadata": {
+                    "name": "Test Token",
+                    "symbol": "TEST",
+                    "description": "A test token",
+                    "image_url": "https://example.com/image.png"
+                },
+                "user_id": 1,
+                "wallet_id": "w-1",
+                "buy_sol_amounts": [0.5, 0.5],
+                "buy_wallet_ids": ["w-1", "w-2"]
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Maximum 1 wallets allowed per bundle");
+
+        // Bundle submission
+        let err = state.jito_client.validate_transactions(&vec!["dGVzdA==".to_string(); 4]).unwrap_err();
+        assert!(err.to_string().contains("Maximum 3 transactions"));
+
+        // Exit bundles are split at the same size
+        let wallets: Vec<Keypair> = (0..7).map(|_| Keypair::new()).collect();
+        let holdings: Vec<(&str, &Keypair, u64)> = wallets.iter().map(|wallet| ("w", wallet, 1_000)).collect();
+        let curve = crate::pump_fun::parse_bonding_curve(
+            &Pubkey::new_unique(),
+            &crate::pump_fun::tests::curve_account_bytes(1000.0, 1000000.0),
+        )
+        .unwrap();
+        let (bundles, _) = state
+            .pump_fun_client
+            .build_sell_all_bundles(&Pubkey::new_unique(), &holdings, &curve, 1_000, solana_sdk::hash::Hash::new_unique())
+            .unwrap();
+        assert_eq!(bundles.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 3, 1]);
+    }
+
+    /// Starts a stand-in block engine that answers any request.
+    fn start_mock_jito() -> String {
+        let server = HttpServer::new(|| App::new().default_service(web::to(HttpResponse::MethodNotAllowed)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let addr = server.addrs()[0];
+        tokio::spawn(server.run());
+        format!("http://{}/api/v1/bundles", addr)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_token_metadata_distinguishes_missing_and_unreachable_metadata() {
+        use crate::metadata::tests::{metaplex_account_bytes, start_mock_metadata_host};
+
+        let mint = Pubkey::new_unique();
+        let request = || test::TestRequest::get().uri(&format!("/api/token/{}/metadata", mint)).to_request();
+        let status_for = |state: web::Data<ApiState>| async move {
+            let app = test::init_service(
+                App::new()
+                    .app_data(state)
+                    .route("/api/token/{mint}/metadata", web::get().to(token_metadata)),
+            )
+            .await;
+            let resp = test::call_service(&app, request()).await;
+            let status = resp.status().as_u16();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, body)
+        };
+
+        let (status, body) = status_for(mock_state(HashMap::new())).await;
+        assert_eq!(status, 404);
+        assert!(body["error"].as_str().unwrap().contains("no metadata account"));
+
+        let host = start_mock_metadata_host(None);
+        let data = metaplex_account_bytes(&mint, "Test Token", "TEST", &format!("{}/m.json", host), &Pubkey::new_unique());
+        let state = mock_state(HashMap::from([(
+            RpcRequest::GetAccountInfo,
+            serde_json::json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "data": [base64::engine::general_purpose::STANDARD.encode(&data), "base64"],
+                    "executable": false,
+                    "lamports": 1_000_000,
+                    "owner": crate::metadata::TOKEN_METADATA_PROGRAM_ID.to_string(),
+                    "rentEpoch": 0,
+                    "space": data.len()
+                }
+            }),
+        )]));
+        let (status, body) = status_for(state).await;
+        assert_eq!(status, 502);
+        assert!(body["error"].as_str().unwrap().starts_with("Metadata fetch failed"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_readiness_reports_each_dependency() {
+        async fn ready(state: &web::Data<ApiState>) -> (u16, serde_json::Value) {
+            let app = test::init_service(
+                App::new()
+                    .app_data(state.clone())
+                    .route("/health/ready", web::get().to(readiness_check)),
+            )
+            .await;
+            let resp = test::call_service(&app, test::TestRequest::get().uri("/health/ready").to_request()).await;
+            (resp.status().as_u16(), test::read_body_json(resp).await)
+        }
+
+        let with_jito = || ApiState { jito_client: JitoBundleClient::new(start_mock_jito()), ..mock_api_state(HashMap::new()) };
+        let (status, body) = ready(&web::Data::new(with_jito())).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"], serde_json::json!({ "rpc": "ok", "rpc_circuit": "closed", "jito": "ok" }));
+
+        // RPC down
+        let state = web::Data::new(ApiState { rpc_client: RpcPool::new_mock("fails"), ..with_jito() });
+        let (status, body) = ready(&state).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["data"], serde_json::json!({ "rpc": "down", "rpc_circuit": "closed", "jito": "ok" }));
+        assert!(body["error"].as_str().unwrap().contains("Solana RPC"));
+
+        // Jito down; nothing listens on the mock state's default block engine
+        let state = mock_state(HashMap::new());
+        let (status, body) = ready(&state).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["data"], serde_json::json!({ "rpc": "ok", "rpc_circuit": "closed", "jito": "down" }));
+        assert!(body["error"].as_str().unwrap().contains("Jito"));
+
+        // An open breaker reports the RPC down without calling it
+        let state = web::Data::new(with_jito());
+        for _ in 0..5 {
+            state.rpc_client.breakers()[0].record_failure();
+        }
+        let (status, body) = ready(&state).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["data"], serde_json::json!({ "rpc": "down", "rpc_circuit": "open", "jito": "ok" }));
+        assert!(body["error"].as_str().unwrap().contains("circuit breaker is open"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_open_rpc_breaker_fails_requests_fast_with_503() {
+        let state = mock_curve_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/token/{mint}/quote", web::get().to(token_quote))
+                .route("/api/bundle/buy", web::post().to(buy_tokens))
+                .route("/api/wallet/{id}/history", web::get().to(wallet_history)),
+        )
+        .await;
+        let quote = || {
+            test::TestRequest::get()
+                .uri(&format!("/api/token/{}/quote?sol=1", Pubkey::new_unique()))
+                .to_request()
+        };
+        assert_eq!(test::call_service(&app, quote()).await.status(), 200);
+
+        for _ in 0..5 {
+            state.rpc_client.breakers()[0].record_failure();
+        }
+        let resp = test::call_service(&app, quote()).await;
+        assert_eq!(resp.status(), 503);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["error"].as_str().unwrap().starts_with("Solana RPC is failing; retry in"));
+
+        // A refused trade isn't replayed for its idempotency key, so the retry runs
+        let buy = || {
+            test::TestRequest::post()
+                .uri("/api/bundle/buy")
+                .insert_header((IDEMPOTENCY_HEADER, "breaker-test"))
+                .set_json(serde_json::json!({
+                    "tokenAddress": Pubkey::new_unique().to_string(),
+                    "solAmounts": [0.1],
+                    "walletIds": ["w-000001"],
+                    "userId": 1
+                }))
+                .to_request()
+        };
+        assert_eq!(test::call_service(&app, buy()).await.status(), 503);
+        state.rpc_client.breakers()[0].record_success();
+        assert_ne!(test::call_service(&app, buy()).await.status(), 503);
+
+        // Endpoints that don't need the RPC keep working while it is open
+        for _ in 0..5 {
+            state.rpc_client.breakers()[0].record_failure();
+        }
+        let history = test::TestRequest::get().uri("/api/wallet/w-000001/history").to_request();
+        assert_eq!(test::call_service(&app, history).await.status(), 200);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_trades_show_up_in_wallet_history() {
+        let state = mock_state(HashMap::new());
+        for wallet_id in ["w-000001", "w-000002"] {
+            state.wallets.write().await.add_wallet(wallet_id.to_string(), Keypair::new()).unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/bundle/buy", web::post().to(buy_tokens))
+                .route("/api/wallet/{id}/history", web::get().to(wallet_history)),
+        )
+        .await;
+
+        // The mock RPC has no mint account, so each buy is refused
+        for _ in 0..3 {
+            let req = test::TestRequest::post()
+                .uri("/api/bundle/buy")
+                .set_json(serde_json::json!({
+                    "tokenAddress": Pubkey::new_unique().to_string(),
+                    "solAmounts": [0.1, 0.2],
+                    "walletIds": ["w-000001", "w-000002"],
+                    "userId": 1
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(!resp.status().is_success());
+        }
+
+        let req = test::TestRequest::get().uri("/api/wallet/w-000002/history?limit=2").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"]["total"], 3);
+        assert_eq!(body["data"]["next_offset"], 2);
+        let records = body["data"]["items"].as_array().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["action"], "buy");
+        assert_eq!(records[0]["amount"], 0.2);
+        assert_eq!(records[0]["success"], false);
+        assert!(records[0]["error"].is_string());
+
+        let req = test::TestRequest::get().uri("/api/wallet/w-000002/history?offset=2").to_request();
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body["data"]["items"].as_array().unwrap().len(), 1);
+        assert!(body["data"]["next_offset"].is_null());
+    }
+
+    #[actix_web::test]
+    async fn test_history_exports_as_csv_or_json_within_a_date_range() {
+        let state = mock_state(HashMap::new());
+        let trade = |timestamp: u64, amount: f64| {
+            let outcome = Ok(TransactionResult {
+                success: true,
+                signature: Some(format!("sig-{}", timestamp)),
+                error: None,
+                ..TransactionResult::failure("")
+            });
+            TradeRecord {
+                timestamp,
+                ..TradeRecord::from_outcome(TradeAction::Buy, "w-000001", Some("mint"), Some(amount), &outcome)
+            }
+        };
+        state.trade_log.append(&[trade(100, 0.1), trade(200, 0.2), trade(300, 0.3)]).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/wallet/{id}/history", web::get().to(wallet_history))
+                .route("/api/wallet/{id}/history.{format}", web::get().to(export_wallet_history)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/wallet/w-000001/history.csv?from=150&to=300").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get("content-type").unwrap(), "text/csv; charset=utf-8");
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert_eq!(
+            body,
+            "timestamp,wallet_id,action,token_address,amount,success,signature,bundle_id,fee_paid,error\n\
+             200,w-000001,buy,mint,0.2,true,sig-200,,,\n\
+             300,w-000001,buy,mint,0.3,true,sig-300,,,\n"
+        );
+
+        let req = test::TestRequest::get().uri("/api/wallet/w-000001/history.json?to=199").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("content-type").unwrap(), "application/json");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let records = body.as_array().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["timestamp"], 100);
+
+        // The paginated history is still served alongside the exports
+        let req = test::TestRequest::get().uri("/api/wallet/w-000001/history").to_request();
+        let body: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body["data"]["total"], 3);
+
+        let req = test::TestRequest::get().uri("/api/wallet/w-000001/history.csv?from=300&to=100").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        let req = test::TestRequest::get().uri("/api/wallet/w-000001/history.xml").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_queued_bundles_return_at_once_with_an_id_to_poll() {
+        let mut api_state = mock_api_state(HashMap::new());
+        api_state.admin_token = Some(Secret::new("admin-secret"));
+        let state = web::Data::new(api_state);
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/bundle/queue", web::post().to(queue_bundle))
+                .route("/api/bundle/queue/{id}", web::get().to(queued_bundle_status)),
+        )
+        .await;
+        let payer = Keypair::new();
+        let encode = |instructions: &[solana_sdk::instruction::Instruction]| {
+            let transaction = solana_sdk::transaction::Transaction::new_signed_with_payer(
+                instructions,
+                Some(&payer.pubkey()),
+                &[&payer],
+                solana_sdk::hash::Hash::new_unique(),
+            );
+            base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap())
+        };
+        let tipped = vec![encode(&[crate::jito_bundle::tip_transfer(&payer.pubkey(), 50_000)])];
+        assert_eq!(bundle_tip_lamports(&tipped), 50_000);
+
+        // Only admins may add to the queue the server's own trades wait in
+        let req = test::TestRequest::post()
+            .uri("/api/bundle/queue")
+            .set_json(serde_json::json!({ "transactions": tipped }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/api/bundle/queue")
+            .insert_header(("Authorization", "Bearer admin-secret"))
+            .set_json(serde_json::json!({ "transactions": tipped }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 202);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let queue_id = body["data"]["queue_id"].as_str().unwrap().to_string();
+        assert!(["queued", "submitting"].contains(&body["data"]["status"].as_str().unwrap()), "{}", body);
+
+        // The mock block engine is unreachable, so the submission fails in the background
+        let mut status = serde_json::Value::Null;
+        for _ in 0..200 {
+            let req = test::TestRequest::get().uri(&format!("/api/bundle/queue/{}", queue_id)).to_request();
+            status = test::read_body_json(test::call_service(&app, req).await).await;
+            if status["data"]["status"] == "failed" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(status["data"]["status"], "failed", "{}", status);
+        assert!(status["data"]["error"].is_string());
+
+        // Bundles Jito would refuse never enter the queue
+        let untipped = vec![encode(&[solana_sdk::system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)])];
+        let req = test::TestRequest::post()
+            .uri("/api/bundle/queue")
+            .insert_header(("Authorization", "Bearer admin-secret"))
+            .set_json(serde_json::json!({ "transactions": untipped }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get().uri("/api/bundle/queue/unknown").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        // A full queue turns bundles away until it drains
+        let mut full_state = mock_api_state(HashMap::new());
+        full_state.admin_token = Some(Secret::new("admin-secret"));
+        full_state.bundle_queue = Arc::new(BundleQueue::new(
+            Arc::new(JitoBundleClient::new("http://127.0.0.1:1".to_string())),
+            QueueLimits { capacity: 0, ..QueueLimits::default() },
+        ));
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(full_state))
+                .route("/api/bundle/queue", web::post().to(queue_bundle)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/api/bundle/queue")
+            .insert_header(("Authorization", "Bearer admin-secret"))
+            .set_json(serde_json::json!({ "transactions": tipped }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 429);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["error"].as_str().unwrap().contains("queue is full"), "{}", body);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sweep_requires_the_admin_token() {
+        let treasury = Pubkey::new_unique();
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetBalance, serde_json::json!({ "context": { "slot": 1 }, "value": 1_000_000_000u64 }));
+        mocks.insert(RpcRequest::GetMinimumBalanceForRentExemption, serde_json::json!(890_880));
+        let state = web::Data::new(ApiState {
+            admin_token: Some(Secret::new("admin-secret")),
+            treasury: Some(treasury),
+            ..mock_api_state(mocks)
+        });
+        state.wallets.write().await.add_wallet("w-000001".to_string(), Keypair::new()).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/api/admin/sweep", web::post().to(sweep_wallets)),
+        )
+        .await;
+        let sweep = |authorization: Option<&str>| {
+            let mut req = test::TestRequest::post()
+                .uri("/api/admin/sweep")
+                .set_json(serde_json::json!({ "walletIds": ["w-000001"], "dryRun": true }));
+            if let Some(authorization) = authorization {
+                req = req.insert_header(("Authorization", authorization));
+            }
+            req.to_request()
+        };
+
+        assert_eq!(test::call_service(&app, sweep(None)).await.status(), 401);
+        assert_eq!(test::call_service(&app, sweep(Some("Bearer admin-secreT"))).await.status(), 401);
+        assert_eq!(test::call_service(&app, sweep(Some("admin-secret"))).await.status(), 401);
+
+        let resp = test::call_service(&app, sweep(Some("Bearer admin-secret"))).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let kept = 890_880 + state.pump_fun_client.config.balance_buffer_lamports;
+        assert_eq!(body["data"]["treasury"], treasury.to_string());
+        assert_eq!(body["data"]["kept_lamports"], kept);
+        assert_eq!(body["data"]["wallets"][0]["swept_lamports"], 1_000_000_000 - kept);
+        assert!(body["data"]["transaction"]["serialized_tx"].is_string());
+
+        // Without a configured token, admin endpoints are closed to everyone
+        let closed = web::Data::new(ApiState { admin_token: None, ..mock_api_state(HashMap::new()) });
+        let app = test::init_service(
+            App::new()
+                .app_data(closed)
+                .route("/api/admin/sweep", web::post().to(sweep_wallets)),
+        )
+        .await;
+        assert_eq!(test::call_service(&app, sweep(Some("Bearer admin-secret"))).await.status(), 403);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_holders_are_paginated_with_a_capped_page_size() {
+        let holders: Vec<serde_json::Value> = (1..=5u64)
+            .map(|i| serde_json::json!({
+                "address": Pubkey::new_unique().to_string(),
+                "amount": (i * 1_000_000).to_string(),
+                "decimals": 6,
+                "uiAmount": i as f64,
+                "uiAmountString": i.to_string()
+            }))
+            .collect();
+        let page = |query: &'static str| {
+            let holders = holders.clone();
+            async move {
+                let mut mocks = HashMap::new();
+                mocks.insert(
+                    RpcRequest::GetTokenLargestAccounts,
+                    serde_json::json!({ "context": { "slot": 1 }, "value": holders }),
+                );
+                mocks.insert(
+                    RpcRequest::GetTokenSupply,
+                    serde_json::json!({ "context": { "slot": 1 }, "value": {
+                        "amount": "100000000", "decimals": 6, "uiAmount": 100.0, "uiAmountString": "100"
+                    } }),
+                );
+                let app = test::init_service(
+                    App::new()
+                        .app_data(mock_state(mocks))
+                        .route("/api/token/{mint}/holders", web::get().to(token_holders)),
+                )
+                .await;
+                let uri = format!("/api/token/{}/holders?{}", Pubkey::new_unique(), query);
+                let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+                assert_eq!(resp.status(), 200);
+                let body: serde_json::Value = test::read_body_json(resp).await;
+                body["data"].clone()
+            }
+        };
+
+        let first = page("limit=2").await;
+        assert_eq!(first["total"], 5);
+        assert_eq!(first["next_offset"], 2);
+        let amounts: Vec<f64> = first["items"].as_array().unwrap().iter().map(|h| h["amount"].as_f64().unwrap()).collect();
+        assert_eq!(amounts, vec![5.0, 4.0]);
+
+        let last = page("limit=2&offset=4").await;
+        assert_eq!(last["items"].as_array().unwrap().len(), 1);
+        assert!(last["next_offset"].is_null());
+
+        let capped = page("limit=100000").await;
+        assert_eq!(capped["limit"], MAX_PAGE_SIZE);
+        assert_eq!(capped["items"].as_array().unwrap().len(), 5);
+        assert!(capped["next_offset"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_state_bundles_through_the_configured_block_engine() {
+        let dir = std::env::temp_dir().join(format!("api-state-{}", Uuid::new_v4()));
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "telegram_token": "",
+            "solana_rpc_url": "http://127.0.0.1:8899",
+            "jito_bundle_url": "http://block-engine.test/api/v1/bundles",
+            "pump_fun_program_id": PumpFunConfig::default().program_id,
+            "fee_address": PumpFunConfig::default().fee_address,
+            "min_sol_amount": 0.01,
+            "jito_tip_amount": 0.0004,
+            "jito_min_tip_fraction": 0.0001,
+            "encryption_key": "test-key",
+            "wallet_sequence_path": dir.join("wallet-seq").to_str().unwrap(),
+            "wallet_store_path": dir.join("wallets.enc").to_str().unwrap(),
+            "trade_log_path": dir.join("trades.jsonl").to_str().unwrap()
+        }))
+        .unwrap();
+
+        let pump_fun_config = PumpFunConfig::default();
+        let state = ApiState::new(
+            PumpFunClient::new(pump_fun_config.program_id, pump_fun_config.fee_address),
+            RpcPool::new_mock("succeeds"),
+            JitoBundleClient::from_config(&config).await.unwrap(),
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(state.jito_client.bundle_url(), "http://block-engine.test/api/v1/bundles");
+        assert_eq!(state.jito_client.tip_amount(), 0.0004);
+        // Bundled trades report the configured tip
+        assert_eq!(submission_route(true, &state.jito_client), (SubmissionRoute::Jito, 400_000));
+        // A trade too large for that tip is refused
+        assert!(state.jito_client.check_tip_for_trade(1.0).is_ok());
+        assert!(state.jito_client.check_tip_for_trade(100.0).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// Collects every `$ref` a JSON document makes.
+    fn schema_refs<'a>(value: &'a serde_json::Value, refs: &mut Vec<&'a str>) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                refs.extend(fields.get("$ref").and_then(|r| r.as_str()));
+                fields.values().for_each(|field| schema_refs(field, refs));
+            }
+            serde_json::Value::Array(items) => items.iter().for_each(|item| schema_refs(item, refs)),
+            _ => {}
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_openapi_document_lists_the_trading_routes() {
+        let app = test::init_service(
+            App::new().route("/openapi.json", web::get().to(openapi_document)),
+        )
+        .await;
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/openapi.json").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let document: serde_json::Value = test::read_body_json(resp).await;
+
+        assert!(document["openapi"].as_str().unwrap().starts_with("3."));
+        for (path, method) in [
+            ("/api/token/create", "post"),
+            ("/api/bundle/buy", "post"),
+            ("/api/bundle/sell", "post"),
+            ("/api/bundle/status/{bundle_id}", "get"),
+        ] {
+            assert!(document["paths"][path][method]["responses"]["200"].is_object(), "{} {} missing", method, path);
+        }
+
+        // Request fields follow their serde names, and every schema referenced is defined
+        let schemas = &document["components"]["schemas"];
+        assert!(schemas["BuyRequest"]["properties"]["solAmounts"].is_object());
+        assert!(schemas["WalletTradeResult"].to_string().contains("wallet_id"));
+        let mut refs = Vec::new();
+        schema_refs(&document, &mut refs);
+        assert!(!refs.is_empty());
+        for reference in refs {
+            let name = reference.strip_prefix("#/components/schemas/").unwrap();
+            assert!(schemas[name].is_object(), "{} is not defined", name);
+        }
+    }
+
+    /// Starts a stand-in RPC websocket that pushes `curves` to the first account subscription,
+    /// one notification each, and reports when the subscription is dropped.
+    async fn start_mock_pubsub(curves: Vec<Vec<u8>>, unsubscribed: tokio::sync::oneshot::Sender<()>) -> String {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut unsubscribed = Some(unsubscribed);
+            while let Some(Ok(Message::Text(text))) = socket.next().await {
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                let reply = |result: serde_json::Value| {
+                    Message::Text(serde_json::json!({ "jsonrpc": "2.0", "result": result, "id": request["id"] }).to_string())
+                };
+                match request["method"].as_str() {
+                    Some("accountSubscribe") => {
+                        socket.send(reply(serde_json::json!(7))).await.unwrap();
+                        for curve in &curves {
+                            let notification = serde_json::json!({
+                                "jsonrpc": "2.0",
+                                "method": "accountNotification",
+                                "params": { "result": mock_account_info(curve), "subscription": 7 }
+                            });
+                            socket.send(Message::Text(notification.to_string())).await.unwrap();
+                        }
+                    }
+                    Some("accountUnsubscribe") => {
+                        socket.send(reply(serde_json::json!(true))).await.unwrap();
+                        if let Some(unsubscribed) = unsubscribed.take() {
+                            let _ = unsubscribed.send(());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+        url
+    }
+
+    #[actix_web::test]
+    async fn test_price_socket_pushes_a_price_per_curve_update() {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mint = Pubkey::new_unique();
+        let curves = vec![
+            crate::pump_fun::tests::curve_account_bytes(1000.0, 1_000_000.0),
+            crate::pump_fun::tests::curve_account_bytes(1100.0, 909_091.0),
+        ];
+        let (unsubscribed_tx, unsubscribed) = tokio::sync::oneshot::channel();
+        let ws_url = start_mock_pubsub(curves.clone(), unsubscribed_tx).await;
+
+        let state = web::Data::new(ApiState {
+            price_feed: PriceFeed { ws_url: Some(ws_url), poll_interval: Duration::from_secs(60) },
+            ..mock_api_state(HashMap::new())
+        });
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(state.clone())
+                .route("/ws/token/{mint}/price", web::get().to(token_price_socket))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/token/{}/price", addr, mint))
+            .await
+            .unwrap();
+        let mut prices = Vec::new();
+        while prices.len() < curves.len() {
+            let frame = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .expect("no price frame")
+                .unwrap()
+                .unwrap();
+            if let Message::Text(text) = frame {
+                prices.push(serde_json::from_str::<price_stream::PriceUpdate>(&text).unwrap());
+            }
+        }
+        for (update, curve) in prices.iter().zip(&curves) {
+            let expected = crate::pump_fun::parse_bonding_curve(&mint, curve).unwrap();
+            assert_eq!(update.mint, mint.to_string());
+            assert!((update.price - expected.current_price).abs() < 1e-12);
+            assert_eq!(update.slot, Some(1));
+        }
+        assert!(prices[1].price > prices[0].price);
+
+        socket.close(None).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), unsubscribed)
+            .await
+            .expect("subscription outlived the client")
+            .unwrap();
+    }
+}
@@ -0,0 +1,459 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::{info, warn};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::jito_bundle::{BundleResponse, JitoBundleClient};
+
+/// Queued bundles submitted at once when the config doesn't say
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Bundles left waiting before new ones are turned away, when the config doesn't say
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// Longest a bundle may wait its turn. A blockhash is only valid for 150 slots, about a
+/// minute, so a bundle signed before it was queued can't land after waiting any longer.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Finished bundles whose outcome is kept for polling; the oldest are forgotten first
+const MAX_FINISHED: usize = 1024;
+
+/// Sends a bundle to the block engine
+#[async_trait]
+pub trait BundleSubmitter: Send + Sync {
+    async fn submit(&self, transactions: Vec<String>) -> Result<BundleResponse>;
+}
+
+#[async_trait]
+impl BundleSubmitter for JitoBundleClient {
+    async fn submit(&self, transactions: Vec<String>) -> Result<BundleResponse> {
+        self.send_bundle(transactions).await
+    }
+}
+
+/// Error returned when a bundle arrives at a queue already holding its capacity.
+#[derive(Debug)]
+pub struct QueueFull {
+    pub capacity: usize,
+}
+
+impl fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bundle queue is full with {} bundles waiting; retry shortly", self.capacity)
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+/// How many bundles a queue submits at once, holds waiting, and lets wait
+#[derive(Debug, Clone, Copy)]
+pub struct QueueLimits {
+    pub concurrency: usize, // Bundles with the block engine at once
+    pub capacity: usize, // Bundles waiting their turn before new ones are turned away
+    pub max_age: Duration, // Longest a bundle may wait before it fails as expired
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            capacity: DEFAULT_CAPACITY,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+/// Where a queued bundle has got
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum QueuedBundle {
+    Queued { position: usize }, // Bundles that go before it; 0 when it's next
+    Submitting,
+    Submitted { bundle_id: String },
+    Failed { error: String },
+}
+
+/// A queued bundle's status under the id it's polled by
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueueTicket {
+    pub queue_id: String,
+    #[serde(flatten)]
+    pub status: QueuedBundle,
+}
+
+/// Paces bundle submissions under load: at most `concurrency` bundles are with the block
+/// engine at once, and the rest wait their turn, highest priority first.
+///
+/// Bundles of equal priority go in the order they were queued. A bundle still waiting after
+/// `max_age` fails without being sent, as its blockhash will have expired.
+pub struct BundleQueue {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    submitter: Arc<dyn BundleSubmitter>,
+    limits: QueueLimits,
+    state: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: BinaryHeap<Pending>,
+    in_flight: usize,
+    next_sequence: u64,
+    outcomes: HashMap<String, QueuedBundle>, // Bundles that have left the queue
+    finished: VecDeque<String>, // Submitted or failed bundles, oldest first
+}
+
+struct Pending {
+    priority: u64,
+    sequence: u64,
+    id: String,
+    transactions: Vec<String>,
+    queued_at: Instant,
+    waiter: Option<oneshot::Sender<Result<BundleResponse>>>, // Set when the caller awaits the outcome
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The heap pops its greatest entry: the highest priority, then the earliest queued
+        self.priority.cmp(&other.priority).then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl BundleQueue {
+    /// Creates a queue handing bundles to `submitter` within `limits`.
+    pub fn new(submitter: Arc<dyn BundleSubmitter>, limits: QueueLimits) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                submitter,
+                limits: QueueLimits { concurrency: limits.concurrency.max(1), ..limits },
+                state: Mutex::new(QueueState::default()),
+            }),
+        }
+    }
+
+    /// Queues a bundle and starts submitting it if there's room, without waiting for it.
+    ///
+    /// Must be called within a tokio runtime, which submissions are spawned on.
+    ///
+    /// # Returns
+    /// The id the bundle's progress can be polled under with `status`.
+    ///
+    /// # Errors
+    /// Returns `QueueFull` when the queue already holds its capacity of waiting bundles.
+    pub fn enqueue(&self, transactions: Vec<String>, priority: u64) -> Result<String> {
+        self.push(transactions, priority, None)
+    }
+
+    /// Queues a bundle and waits for the block engine's answer to it.
+    ///
+    /// # Errors
+    /// Returns `QueueFull` when the queue has no room, an error if the bundle waited past
+    /// `max_age`, or the submitter's own error.
+    pub async fn submit(&self, transactions: Vec<String>, priority: u64) -> Result<BundleResponse> {
+        let (sender, receiver) = oneshot::channel();
+        self.push(transactions, priority, Some(sender))?;
+        receiver.await.context("Bundle queue dropped the submission")?
+    }
+
+    fn push(
+        &self,
+        transactions: Vec<String>,
+        priority: u64,
+        waiter: Option<oneshot::Sender<Result<BundleResponse>>>,
+    ) -> Result<String> {
+        let id = Uuid::new_v4().to_string();
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.expire(self.shared.limits.max_age);
+            if state.pending.len() >= self.shared.limits.capacity {
+                return Err(QueueFull { capacity: self.shared.limits.capacity }.into());
+            }
+            let sequence = state.next_sequence;
+            state.next_sequence += 1;
+            state.pending.push(Pending {
+                priority,
+                sequence,
+                id: id.clone(),
+                transactions,
+                queued_at: Instant::now(),
+                waiter,
+            });
+        }
+        dispatch(&self.shared);
+        Ok(id)
+    }
+
+    /// Reports where a queued bundle has got, or `None` for an unknown or long-finished id.
+    pub fn status(&self, id: &str) -> Option<QueuedBundle> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.expire(self.shared.limits.max_age);
+        if let Some(pending) = state.pending.iter().find(|pending| pending.id == id) {
+            let position = state.pending.iter().filter(|other| *other > pending).count();
+            return Some(QueuedBundle::Queued { position });
+        }
+        state.outcomes.get(id).cloned()
+    }
+}
+
+/// Starts submitting the highest-priority bundles while there's room for them.
+fn dispatch(shared: &Arc<Shared>) {
+    let mut state = shared.state.lock().unwrap();
+    state.expire(shared.limits.max_age);
+    while state.in_flight < shared.limits.concurrency {
+        let Some(next) = state.pending.pop() else {
+            break;
+        };
+        state.in_flight += 1;
+        state.outcomes.insert(next.id.clone(), QueuedBundle::Submitting);
+
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            let result = shared.submitter.submit(next.transactions).await;
+            let outcome = match &result {
+                Ok(response) => match &response.error {
+                    Some(error) => QueuedBundle::Failed { error: format!("Bundle rejected: {}", error) },
+                    None => QueuedBundle::Submitted { bundle_id: response.bundle_id.clone() },
+                },
+                Err(e) => QueuedBundle::Failed { error: format!("{:#}", e) },
+            };
+            match &outcome {
+                QueuedBundle::Submitted { bundle_id } => info!("Queued bundle {} submitted as {}", next.id, bundle_id),
+                _ => warn!("Queued bundle {} failed: {:?}", next.id, outcome),
+            }
+            if let Some(waiter) = next.waiter {
+                // The caller may have given up waiting; the outcome stays pollable either way
+                let _ = waiter.send(result);
+            }
+            shared.state.lock().unwrap().finish(next.id, outcome);
+            dispatch(&shared);
+        });
+    }
+}
+
+impl QueueState {
+    /// Fails every bundle that has waited longer than `max_age`, without sending it.
+    fn expire(&mut self, max_age: Duration) {
+        let now = Instant::now();
+        let expired = |pending: &Pending| now.duration_since(pending.queued_at) > max_age;
+        if !self.pending.iter().any(expired) {
+            return;
+        }
+        let (stale, fresh): (Vec<Pending>, Vec<Pending>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(expired);
+        self.pending = fresh.into();
+        for pending in stale {
+            let error = format!("Waited over {}s in the queue; its blockhash has expired", max_age.as_secs());
+            warn!("Queued bundle {} expired", pending.id);
+            if let Some(waiter) = pending.waiter {
+                let _ = waiter.send(Err(anyhow::anyhow!(error.clone())));
+            }
+            self.record(pending.id, QueuedBundle::Failed { error });
+        }
+    }
+
+    /// Records a submission's outcome and frees its slot.
+    fn finish(&mut self, id: String, outcome: QueuedBundle) {
+        self.in_flight -= 1;
+        self.record(id, outcome);
+    }
+
+    /// Keeps a bundle's final outcome for polling, forgetting the oldest past `MAX_FINISHED`.
+    fn record(&mut self, id: String, outcome: QueuedBundle) {
+        self.outcomes.insert(id.clone(), outcome);
+        self.finished.push_back(id);
+        while self.finished.len() > MAX_FINISHED {
+            if let Some(oldest) = self.finished.pop_front() {
+                self.outcomes.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    /// Records submissions in order, holding each until the test releases it
+    struct GatedSubmitter {
+        gate: Semaphore,
+        order: Mutex<Vec<String>>,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    impl GatedSubmitter {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                gate: Semaphore::new(0),
+                order: Mutex::new(Vec::new()),
+                in_flight: AtomicUsize::new(0),
+                max_in_flight: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl BundleSubmitter for GatedSubmitter {
+        async fn submit(&self, transactions: Vec<String>) -> Result<BundleResponse> {
+            let name = transactions[0].clone();
+            self.order.lock().unwrap().push(name.clone());
+            let now = self.in_flight.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, AtomicOrdering::SeqCst);
+            self.gate.acquire().await.unwrap().forget();
+            self.in_flight.fetch_sub(1, AtomicOrdering::SeqCst);
+            Ok(BundleResponse { bundle_id: format!("bundle-{}", name), status: "success".to_string(), error: None, landed_slot: None })
+        }
+    }
+
+    fn limits(concurrency: usize, capacity: usize) -> QueueLimits {
+        QueueLimits { concurrency, capacity, ..QueueLimits::default() }
+    }
+
+    async fn wait_until(mut done: impl FnMut() -> bool) {
+        for _ in 0..500 {
+            if done() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("queue never settled");
+    }
+
+    fn settled(queue: &BundleQueue, ids: &[String]) -> bool {
+        ids.iter().all(|id| matches!(queue.status(id), Some(QueuedBundle::Submitted { .. } | QueuedBundle::Failed { .. })))
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_bundles_are_submitted_first() {
+        let submitter = GatedSubmitter::new();
+        let queue = BundleQueue::new(submitter.clone(), limits(1, 10));
+
+        // The first bundle takes the only slot, so the rest queue behind it
+        let first = queue.enqueue(vec!["first".to_string()], 0).unwrap();
+        wait_until(|| submitter.order.lock().unwrap().len() == 1).await;
+        let ids: Vec<String> = [("low", 1), ("high", 100), ("mid", 50), ("high-later", 100)]
+            .into_iter()
+            .map(|(name, priority)| queue.enqueue(vec![name.to_string()], priority).unwrap())
+            .collect();
+
+        assert_eq!(queue.status(&first), Some(QueuedBundle::Submitting));
+        assert_eq!(queue.status(&ids[0]), Some(QueuedBundle::Queued { position: 3 }));
+        assert_eq!(queue.status(&ids[1]), Some(QueuedBundle::Queued { position: 0 }));
+        assert_eq!(queue.status(&ids[3]), Some(QueuedBundle::Queued { position: 1 }));
+        assert_eq!(queue.status("unknown"), None);
+
+        submitter.gate.add_permits(ids.len() + 1);
+        wait_until(|| settled(&queue, &ids)).await;
+        assert_eq!(*submitter.order.lock().unwrap(), vec!["first", "high", "high-later", "mid", "low"]);
+        assert_eq!(queue.status(&ids[1]), Some(QueuedBundle::Submitted { bundle_id: "bundle-high".to_string() }));
+    }
+
+    #[tokio::test]
+    async fn test_submissions_never_exceed_the_concurrency() {
+        let submitter = GatedSubmitter::new();
+        let queue = BundleQueue::new(submitter.clone(), limits(2, 10));
+        let ids: Vec<String> = (0..6).map(|i| queue.enqueue(vec![format!("bundle-{}", i)], 0).unwrap()).collect();
+
+        wait_until(|| submitter.order.lock().unwrap().len() == 2).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(submitter.order.lock().unwrap().len(), 2);
+        let submitting = ids.iter().filter(|id| queue.status(id) == Some(QueuedBundle::Submitting)).count();
+        assert_eq!(submitting, 2);
+        assert_eq!(queue.status(&ids[5]), Some(QueuedBundle::Queued { position: 3 }));
+
+        submitter.gate.add_permits(ids.len());
+        wait_until(|| settled(&queue, &ids)).await;
+        assert_eq!(submitter.order.lock().unwrap().len(), 6);
+        assert_eq!(submitter.max_in_flight.load(AtomicOrdering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_a_full_queue_turns_bundles_away() {
+        let submitter = GatedSubmitter::new();
+        let queue = BundleQueue::new(submitter.clone(), limits(1, 2));
+
+        // One bundle with the block engine and two waiting fill the queue
+        let first = queue.enqueue(vec!["first".to_string()], 0).unwrap();
+        wait_until(|| submitter.order.lock().unwrap().len() == 1).await;
+        let waiting: Vec<String> = ["second", "third"]
+            .into_iter()
+            .map(|name| queue.enqueue(vec![name.to_string()], 0).unwrap())
+            .collect();
+        let err = queue.enqueue(vec!["fourth".to_string()], u64::MAX).unwrap_err();
+        assert!(err.downcast_ref::<QueueFull>().is_some(), "{}", err);
+        let err = queue.submit(vec!["fifth".to_string()], 0).await.unwrap_err();
+        assert!(err.downcast_ref::<QueueFull>().is_some(), "{}", err);
+
+        // Room opens up as the queue drains
+        submitter.gate.add_permits(4);
+        wait_until(|| settled(&queue, &waiting) && settled(&queue, std::slice::from_ref(&first))).await;
+        assert!(queue.enqueue(vec!["fourth".to_string()], 0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bundles_waiting_past_their_blockhash_fail_unsent() {
+        let submitter = GatedSubmitter::new();
+        let queue = BundleQueue::new(
+            submitter.clone(),
+            QueueLimits { max_age: Duration::from_millis(50), ..limits(1, 10) },
+        );
+
+        let first = queue.enqueue(vec!["first".to_string()], 0).unwrap();
+        wait_until(|| submitter.order.lock().unwrap().len() == 1).await;
+        let stale = queue.enqueue(vec!["stale".to_string()], 0).unwrap();
+        let waiting = tokio::spawn({
+            let queue = BundleQueue { shared: queue.shared.clone() };
+            async move { queue.submit(vec!["awaited".to_string()], 0).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        match queue.status(&stale) {
+            Some(QueuedBundle::Failed { error }) => assert!(error.contains("blockhash has expired"), "{}", error),
+            other => panic!("expected the bundle to expire, got {:?}", other),
+        }
+        let err = waiting.await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("blockhash has expired"), "{}", err);
+
+        // Only the bundle already in flight reaches the block engine
+        submitter.gate.add_permits(1);
+        wait_until(|| settled(&queue, std::slice::from_ref(&first))).await;
+        assert_eq!(*submitter.order.lock().unwrap(), vec!["first"]);
+    }
+
+    #[tokio::test]
+    async fn test_submit_waits_for_the_block_engine() {
+        let submitter = GatedSubmitter::new();
+        let queue = BundleQueue::new(submitter.clone(), limits(1, 10));
+        submitter.gate.add_permits(1);
+        let response = queue.submit(vec!["trade".to_string()], 0).await.unwrap();
+        assert_eq!(response.bundle_id, "bundle-trade");
+    }
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Source of deliberate waits, so tests can observe them without sleeping
+#[async_trait]
+pub trait Clock: Send + Sync {
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Waits on the tokio timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[async_trait]
+impl Clock for TokioClock {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::fees::{validate_fee_splits, FeeSchedule};
+use crate::jito_bundle::{JitoRegion, MAX_BUNDLE_TRANSACTIONS};
+use crate::logging::{LogFormat, Secret};
+use crate::types::ConfirmationStrategy;
+
+/// Shortest `encryption_key` accepted; the wallet store derives its AES-256 key from it
+pub const MIN_ENCRYPTION_KEY_LEN: usize = 32;
+
+/// Runtime configuration loaded from `config/config.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub telegram_token: Secret,
+    pub solana_rpc_url: String,
+    /// RPC nodes to fail over to, in order, when `solana_rpc_url` is failing
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
+    /// RPC websocket that live price streams subscribe through; they poll `solana_rpc_url` when omitted
+    #[serde(default)]
+    pub solana_ws_url: Option<String>,
+    /// How often a live price stream polls the bonding curve when there is no RPC websocket, in milliseconds
+    #[serde(default = "default_price_poll_interval_ms")]
+    pub price_poll_interval_ms: u64,
+    /// Spread reads across every RPC node instead of sending them to the first healthy one
+    #[serde(default)]
+    pub balance_rpc_reads: bool,
+    /// Longest any single RPC request may take, in seconds
+    #[serde(default = "default_rpc_timeout_secs")]
+    pub rpc_timeout_secs: u64,
+    /// Consecutive RPC failures that open the circuit breaker, failing RPC calls fast
+    #[serde(default = "default_rpc_breaker_threshold")]
+    pub rpc_breaker_threshold: u32,
+    /// How long an open circuit breaker waits before letting a trial call through, in seconds
+    #[serde(default = "default_rpc_breaker_cooldown_secs")]
+    pub rpc_breaker_cooldown_secs: u64,
+    pub jito_bundle_url: String,
+    /// Block engine region (`amsterdam`, `frankfurt`, `ny`, `tokyo`) or `auto`; overrides `jito_bundle_url`
+    #[serde(default)]
+    pub jito_region: Option<String>,
+    pub pump_fun_program_id: String,
+    pub fee_address: String,
+    /// Deprecated and ignored; trading fees come from `fee_schedule`
+    #[serde(default)]
+    pub fee_percentage: Option<f64>,
+    /// Creation fee recipients as `[address, share]` pairs whose shares sum to 1.0; the whole
+    /// fee goes to `fee_address` when empty
+    #[serde(default)]
+    pub fee_splits: Vec<(String, f64)>,
+    /// Trading fee schedule; the client's flat default applies when omitted
+    #[serde(default)]
+    pub fee_schedule: Option<FeeSchedule>,
+    /// Share (0-1) of a referred trade's fee paid to its referrer; referrals are only logged when omitted
+    #[serde(default)]
+    pub referral_fee_share: Option<f64>,
+    pub min_sol_amount: f64,
+    /// Slippage (0-100%) a trade tolerates when it sets no minimum output; the client's 5% applies when omitted
+    #[serde(default)]
+    pub default_max_slippage_pct: Option<f64>,
+    /// Most wallets per request and transactions per bundle; the client's default applies when omitted
+    #[serde(default)]
+    pub max_wallets_per_bundle: Option<usize>,
+    /// Most transactions of a multi-wallet trade sent at once; the client's default applies when omitted
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+    /// Most queued bundles with the block engine at once; 4 when omitted
+    #[serde(default)]
+    pub bundle_queue_concurrency: Option<usize>,
+    /// Most bundles left waiting in the queue before new ones get a 429; 256 when omitted
+    #[serde(default)]
+    pub bundle_queue_capacity: Option<usize>,
+    pub jito_tip_amount: f64,
+    /// Smallest tip a bundled trade may pay, as a fraction (0-1) of its volume; 0 disables the check
+    #[serde(default)]
+    pub jito_min_tip_fraction: f64,
+    /// Compute unit price in micro-lamports; transactions pay no priority fee when omitted
+    #[serde(default)]
+    pub priority_fee_micro_lamports: Option<u64>,
+    /// Share of recent slots a `priority_fee` strategy's price should have landed in
+    #[serde(default)]
+    pub priority_fee_landing_probability: Option<f64>,
+    /// Percentile (0-100) of recent prioritization fees the fee autopilot prices compute units
+    /// at, replacing `priority_fee_micro_lamports` once sampled; the autopilot is off when omitted
+    #[serde(default)]
+    pub priority_fee_percentile: Option<f64>,
+    /// How often the fee autopilot samples recent prioritization fees, in seconds
+    #[serde(default = "default_priority_fee_refresh_secs")]
+    pub priority_fee_refresh_secs: u64,
+    /// Accounts whose recent fees the autopilot samples; defaults to the fee address every trade writes
+    #[serde(default)]
+    pub priority_fee_accounts: Vec<String>,
+    /// `bundle_aware` confirms Jito trades by bundle status, `rpc` by signature status like
+    /// every other trade; bundle-aware when omitted
+    #[serde(default)]
+    pub confirmation_strategy: Option<ConfirmationStrategy>,
+    /// Whole tokens minted to each new token's bonding curve; the client's default of 1B
+    /// applies when omitted
+    #[serde(default)]
+    pub initial_supply: Option<u64>,
+    /// Longest backoff between bundle resubmissions, in seconds
+    #[serde(default = "default_jito_retry_max_delay_secs")]
+    pub jito_retry_max_delay_secs: u64,
+    pub encryption_key: Secret,
+    #[serde(default)]
+    pub expose_version_endpoint: bool,
+    #[serde(default = "default_request_deadline_ms")]
+    pub request_deadline_ms: u64,
+    /// How long a sent transaction is polled for confirmation before it is reported as
+    /// submitted, in seconds; the client's 30 applies when omitted
+    #[serde(default)]
+    pub confirm_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub ipfs_endpoint: Option<String>,
+    #[serde(default)]
+    pub ipfs_api_key: Option<Secret>,
+    /// Gateway `ipfs://` token metadata is fetched through; defaults to ipfs.io
+    #[serde(default)]
+    pub ipfs_gateway: Option<String>,
+    #[serde(default)]
+    pub include_bundle_details: bool,
+    #[serde(default)]
+    pub check_fee_wallet_on_startup: bool,
+    #[serde(default = "default_wallet_sequence_path")]
+    pub wallet_sequence_path: String,
+    /// Still accept deprecated raw private keys in creation requests
+    #[serde(default)]
+    pub allow_raw_key: bool,
+    #[serde(default = "default_wallet_store_path")]
+    pub wallet_store_path: String,
+    /// Append-only JSONL file recording every trade attempted per wallet
+    #[serde(default = "default_trade_log_path")]
+    pub trade_log_path: String,
+    /// Solana CLI keypair file the command line signs with when `--keypair` isn't given
+    #[serde(default)]
+    pub keypair_path: Option<String>,
+    /// Origins allowed to call the API from a browser; empty allows localhost only and `"*"` allows any
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Bearer token admin endpoints require; they refuse every request when omitted
+    #[serde(default)]
+    pub admin_token: Option<Secret>,
+    /// Where `/api/admin/sweep` sends SOL swept from bundle wallets
+    #[serde(default)]
+    pub treasury_address: Option<String>,
+    /// `text` for human-readable logs, or `json` for one JSON object per line
+    #[serde(default)]
+    pub log_format: LogFormat,
+}
+
+impl Config {
+    /// Checks every field the server builds from, so a bad config fails at startup
+    /// instead of partway through a trade.
+    ///
+    /// Only the form of URLs is checked; whether the RPC node answers is up to the RPC pool.
+    ///
+    /// # Errors
+    /// Names the first invalid field and what is wrong with it.
+    pub fn validate(&self) -> Result<()> {
+        parse_address("pump_fun_program_id", &self.pump_fun_program_id)?;
+        parse_address("fee_address", &self.fee_address)?;
+        let fee_splits = self.fee_splits.iter()
+            .map(|(recipient, share)| Ok((parse_address("fee_splits", recipient)?, *share)))
+            .collect::<Result<Vec<_>>>()?;
+        validate_fee_splits(&fee_splits).context("Invalid fee_splits")?;
+        if let Some(fee_schedule) = &self.fee_schedule {
+            fee_schedule.validate().context("Invalid fee_schedule")?;
+        }
+        for account in &self.priority_fee_accounts {
+            parse_address("priority_fee_accounts", account)?;
+        }
+        if let Some(treasury) = &self.treasury_address {
+            parse_address("treasury_address", treasury)?;
+        }
+        if self.admin_token.as_deref().is_some_and(str::is_empty) {
+            return Err(anyhow::anyhow!("admin_token must not be empty"));
+        }
+
+        check_range("min_sol_amount", self.min_sol_amount, 0.0..=f64::MAX)?;
+        check_range("jito_tip_amount", self.jito_tip_amount, 0.0..=f64::MAX)?;
+        check_range("jito_min_tip_fraction", self.jito_min_tip_fraction, 0.0..=1.0)?;
+        if let Some(share) = self.referral_fee_share {
+            check_range("referral_fee_share", share, 0.0..=1.0)?;
+        }
+        if let Some(slippage) = self.default_max_slippage_pct {
+            check_range("default_max_slippage_pct", slippage, 0.0..=100.0)?;
+        }
+        if let Some(probability) = self.priority_fee_landing_probability {
+            check_range("priority_fee_landing_probability", probability, 0.0..=1.0)?;
+        }
+        if let Some(percentile) = self.priority_fee_percentile {
+            check_range("priority_fee_percentile", percentile, 0.0..=100.0)?;
+        }
+        if let Some(max_wallets) = self.max_wallets_per_bundle {
+            if !(1..=MAX_BUNDLE_TRANSACTIONS).contains(&max_wallets) {
+                return Err(anyhow::anyhow!(
+                    "max_wallets_per_bundle must be between 1 and {}, got {}",
+                    MAX_BUNDLE_TRANSACTIONS,
+                    max_wallets
+                ));
+            }
+        }
+        let counts = [
+            ("rpc_timeout_secs", self.rpc_timeout_secs),
+            ("rpc_breaker_threshold", u64::from(self.rpc_breaker_threshold)),
+            ("request_deadline_ms", self.request_deadline_ms),
+            ("price_poll_interval_ms", self.price_poll_interval_ms),
+            ("priority_fee_refresh_secs", self.priority_fee_refresh_secs),
+            ("max_concurrency", self.max_concurrency.map_or(1, |n| n as u64)),
+            ("bundle_queue_concurrency", self.bundle_queue_concurrency.map_or(1, |n| n as u64)),
+            ("bundle_queue_capacity", self.bundle_queue_capacity.map_or(1, |n| n as u64)),
+            ("initial_supply", self.initial_supply.unwrap_or(1)),
+        ];
+        if let Some((name, _)) = counts.iter().find(|(_, value)| *value == 0) {
+            return Err(anyhow::anyhow!("{} must be greater than 0", name));
+        }
+
+        check_url("solana_rpc_url", &self.solana_rpc_url, &["http", "https"])?;
+        for url in &self.fallback_rpc_urls {
+            check_url("fallback_rpc_urls", url, &["http", "https"])?;
+        }
+        if let Some(url) = &self.solana_ws_url {
+            check_url("solana_ws_url", url, &["ws", "wss"])?;
+        }
+        check_url("jito_bundle_url", &self.jito_bundle_url, &["http", "https"])?;
+        if let Some(region) = self.jito_region.as_deref().filter(|region| *region != "auto") {
+            region.parse::<JitoRegion>().context("Invalid jito_region")?;
+        }
+        if let Some(url) = &self.ipfs_endpoint {
+            check_url("ipfs_endpoint", url, &["http", "https"])?;
+        }
+        if let Some(url) = &self.ipfs_gateway {
+            check_url("ipfs_gateway", url, &["http", "https"])?;
+        }
+
+        if self.encryption_key.chars().count() < MIN_ENCRYPTION_KEY_LEN {
+            return Err(anyhow::anyhow!(
+                "encryption_key must be at least {} characters",
+                MIN_ENCRYPTION_KEY_LEN
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn parse_address(field: &str, address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address).map_err(|_| anyhow::anyhow!("{} is not a valid address: {}", field, address))
+}
+
+fn check_range(field: &str, value: f64, range: RangeInclusive<f64>) -> Result<()> {
+    // NaN falls outside every range
+    if !range.contains(&value) {
+        return Err(anyhow::anyhow!(
+            "{} must be between {} and {}, got {}",
+            field,
+            range.start(),
+            range.end(),
+            value
+        ));
+    }
+    Ok(())
+}
+
+fn check_url(field: &str, url: &str, schemes: &[&str]) -> Result<()> {
+    let parsed = url::Url::parse(url).map_err(|e| anyhow::anyhow!("{} is not a valid URL ({}): {}", field, e, url))?;
+    if !schemes.contains(&parsed.scheme()) || parsed.host().is_none() {
+        return Err(anyhow::anyhow!("{} must be a {} URL: {}", field, schemes.join("/"), url));
+    }
+    Ok(())
+}
+
+fn default_rpc_timeout_secs() -> u64 {
+    30
+}
+
+fn default_rpc_breaker_threshold() -> u32 {
+    5
+}
+
+fn default_rpc_breaker_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_price_poll_interval_ms() -> u64 {
+    2_000
+}
+
+fn default_priority_fee_refresh_secs() -> u64 {
+    10
+}
+
+fn default_request_deadline_ms() -> u64 {
+    60_000
+}
+
+fn default_wallet_sequence_path() -> String {
+    "data/wallet_sequence".to_string()
+}
+
+fn default_wallet_store_path() -> String {
+    "data/wallets.enc".to_string()
+}
+
+fn default_jito_retry_max_delay_secs() -> u64 {
+    30
+}
+
+fn default_trade_log_path() -> String {
+    "data/trades.jsonl".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_config() -> serde_json::Value {
+        serde_json::json!({
+            "telegram_token": "",
+            "solana_rpc_url": "https://api.mainnet-beta.solana.com",
+            "jito_bundle_url": "https://mainnet.block-engine.jito.wtf/api/v1/bundles",
+            "pump_fun_program_id": "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P",
+            "fee_address": "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM",
+            "min_sol_amount": 0.01,
+            "jito_tip_amount": 0.0004,
+            "encryption_key": "0123456789abcdef0123456789abcdef"
+        })
+    }
+
+    /// Validates the valid config with `field` replaced by `value`.
+    fn validate_with(field: &str, value: serde_json::Value) -> Result<()> {
+        let mut config = valid_config();
+        config[field] = value;
+        serde_json::from_value::<Config>(config).unwrap().validate()
+    }
+
+    #[test]
+    fn test_valid_config_passes() {
+        let config: Config = serde_json::from_value(valid_config()).unwrap();
+        assert!(config.validate().is_ok());
+        assert!(validate_with("jito_region", serde_json::json!("auto")).is_ok());
+        assert!(validate_with("solana_ws_url", serde_json::json!("wss://api.mainnet-beta.solana.com")).is_ok());
+        // Configs written before fee_percentage was deprecated still load
+        assert!(validate_with("fee_percentage", serde_json::json!(0.5)).is_ok());
+    }
+
+    #[test]
+    fn test_each_invalid_field_is_named() {
+        let cases = [
+            ("pump_fun_program_id", serde_json::json!("not-a-program")),
+            ("fee_address", serde_json::json!("")),
+            ("fee_splits", serde_json::json!([["nowhere", 1.0]])),
+            ("fee_splits", serde_json::json!([["CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM", 0.5]])),
+            ("priority_fee_accounts", serde_json::json!(["nowhere"])),
+            ("treasury_address", serde_json::json!("nowhere")),
+            ("admin_token", serde_json::json!("")),
+            ("fee_schedule", serde_json::json!({ "mode": "flat", "rate": 1.5 })),
+            ("fee_schedule", serde_json::json!({ "mode": "tiered", "tiers": [
+                { "min_sol": 10.0, "rate": 0.003 },
+                { "min_sol": 0.0, "rate": 0.005 }
+            ] })),
+            ("min_sol_amount", serde_json::json!(-1.0)),
+            ("jito_tip_amount", serde_json::json!(-0.001)),
+            ("jito_min_tip_fraction", serde_json::json!(1.5)),
+            ("referral_fee_share", serde_json::json!(1.5)),
+            ("default_max_slippage_pct", serde_json::json!(-1.0)),
+            ("default_max_slippage_pct", serde_json::json!(120.0)),
+            ("priority_fee_landing_probability", serde_json::json!(2.0)),
+            ("priority_fee_percentile", serde_json::json!(101.0)),
+            ("max_wallets_per_bundle", serde_json::json!(0)),
+            ("max_wallets_per_bundle", serde_json::json!(MAX_BUNDLE_TRANSACTIONS + 1)),
+            ("max_concurrency", serde_json::json!(0)),
+            ("bundle_queue_concurrency", serde_json::json!(0)),
+            ("bundle_queue_capacity", serde_json::json!(0)),
+            ("initial_supply", serde_json::json!(0)),
+            ("rpc_timeout_secs", serde_json::json!(0)),
+            ("rpc_breaker_threshold", serde_json::json!(0)),
+            ("request_deadline_ms", serde_json::json!(0)),
+            ("price_poll_interval_ms", serde_json::json!(0)),
+            ("priority_fee_refresh_secs", serde_json::json!(0)),
+            ("solana_rpc_url", serde_json::json!("api.mainnet-beta.solana.com")),
+            ("solana_rpc_url", serde_json::json!("ftp://api.mainnet-beta.solana.com")),
+            ("fallback_rpc_urls", serde_json::json!(["https://ok.example", "not a url"])),
+            ("solana_ws_url", serde_json::json!("https://api.mainnet-beta.solana.com")),
+            ("jito_bundle_url", serde_json::json!("block-engine")),
+            ("jito_region", serde_json::json!("mars")),
+            ("ipfs_endpoint", serde_json::json!("localhost:5001")),
+            ("ipfs_gateway", serde_json::json!("ipfs://gateway")),
+            ("encryption_key", serde_json::json!("short-key")),
+        ];
+        for (field, value) in cases {
+            let err = validate_with(field, value.clone()).expect_err(field);
+            let message = format!("{:#}", err);
+            assert!(message.contains(field), "{} = {}: {}", field, value, message);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Header clients can use to bound how long a request may take, in milliseconds
+pub const DEADLINE_HEADER: &str = "X-Deadline-Ms";
+
+/// Error returned when an operation runs past its request deadline
+#[derive(Debug, Clone, Copy)]
+pub struct DeadlineExceeded {
+    pub budget: Duration,
+}
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Deadline of {} ms exceeded", self.budget.as_millis())
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+tokio::task_local! {
+    /// The deadline of the request whose work is running, set by `Deadline::run`
+    static CURRENT: Deadline;
+}
+
+/// A single time budget shared by every step of a request (RPC calls, confirmation
+/// waits, bundle retries), so the request as a whole can't outlive it.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    budget: Duration,
+    expires_at: Instant,
+}
+
+impl Deadline {
+    pub fn after(budget: Duration) -> Self {
+        Self {
+            budget,
+            expires_at: Instant::now() + budget,
+        }
+    }
+
+    /// Time left before the deadline passes (zero once expired).
+    pub fn remaining(&self) -> Duration {
+        self.expires_at.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// The error for work cut short by this deadline.
+    pub fn exceeded(&self) -> DeadlineExceeded {
+        DeadlineExceeded { budget: self.budget }
+    }
+
+    /// The deadline of the request being served, when called from work running under `run`.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|deadline| *deadline).ok()
+    }
+
+    /// `limit`, cut down to the time the current request has left, if any.
+    pub fn limit(limit: Duration) -> Duration {
+        Self::current().map_or(limit, |deadline| limit.min(deadline.remaining()))
+    }
+
+    /// Runs `fut`, cancelling it and returning `DeadlineExceeded` if the deadline passes first.
+    ///
+    /// The deadline is `current` while `fut` runs, so RPC calls, confirmation waits and Jito
+    /// requests inside it are bounded by what's left of it, including blocking RPC calls the
+    /// timeout here can't interrupt.
+    pub async fn run<T, F>(&self, fut: F) -> anyhow::Result<T>
+    where
+        F: Future<Output = anyhow::Result<T>>,
+    {
+        match tokio::time::timeout(self.remaining(), CURRENT.scope(*self, fut)).await {
+            // A step that gave up on the deadline fails the request as having run out of time
+            Ok(Err(e)) if self.is_expired() => Err(e.context(self.exceeded())),
+            Ok(result) => result,
+            Err(_) => Err(self.exceeded().into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_short_deadline_cancels_slow_operation() {
+        let deadline = Deadline::after(Duration::from_millis(50));
+        let started = Instant::now();
+
+        // Stands in for a chain of RPC calls and retry backoffs
+        let result: anyhow::Result<()> = deadline
+            .run(async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .await;
+
+        let err = result.unwrap_err();
+        assert!(err.downcast_ref::<DeadlineExceeded>().is_some());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(deadline.is_expired());
+    }
+
+    #[tokio::test]
+    async fn test_steps_see_the_deadline_they_run_under() {
+        assert!(Deadline::current().is_none());
+        assert_eq!(Deadline::limit(Duration::from_secs(30)), Duration::from_secs(30));
+
+        let deadline = Deadline::after(Duration::from_millis(500));
+        let limit = deadline.run(async { Ok(Deadline::limit(Duration::from_secs(30))) }).await.unwrap();
+        assert!(limit <= Duration::from_millis(500));
+
+        // A step failing on its own once the time is up reports the deadline
+        let deadline = Deadline::after(Duration::from_millis(20));
+        let result: anyhow::Result<()> = deadline
+            .run(async {
+                std::thread::sleep(Duration::from_millis(30));
+                Err(anyhow::anyhow!("RPC call timed out"))
+            })
+            .await;
+        assert!(result.unwrap_err().downcast_ref::<DeadlineExceeded>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fast_operation_completes_within_deadline() {
+        let deadline = Deadline::after(Duration::from_secs(5));
+        let result = deadline.run(async { Ok(7) }).await.unwrap();
+        assert_eq!(result, 7);
+        assert!(deadline.remaining() > Duration::ZERO);
+    }
+}
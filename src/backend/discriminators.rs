@@ -0,0 +1,51 @@
+use solana_sdk::hash::hashv;
+
+/// Length of an Anchor discriminator
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// An Anchor discriminator, prefixed to instruction data and account data
+pub type Discriminator = [u8; DISCRIMINATOR_LEN];
+
+/// Pump.Fun `create`: `sha256("global:create")[..8]`
+pub const CREATE: Discriminator = [24, 30, 200, 40, 5, 28, 7, 119];
+
+/// Pump.Fun `buy`: `sha256("global:buy")[..8]`
+pub const BUY: Discriminator = [102, 6, 61, 18, 1, 218, 235, 234];
+
+/// Pump.Fun `sell`: `sha256("global:sell")[..8]`
+pub const SELL: Discriminator = [51, 230, 133, 164, 1, 127, 131, 173];
+
+/// Pump.Fun bonding curve account: `sha256("account:BondingCurve")[..8]`
+pub const BONDING_CURVE: Discriminator = [23, 183, 248, 55, 96, 216, 172, 96];
+
+/// The discriminator Anchor gives the instruction `name` (in snake case, as declared).
+pub fn instruction(name: &str) -> Discriminator {
+    anchor_hash("global", name)
+}
+
+/// The discriminator Anchor gives the account type `name` (in Pascal case, as declared).
+pub fn account(name: &str) -> Discriminator {
+    anchor_hash("account", name)
+}
+
+fn anchor_hash(namespace: &str, name: &str) -> Discriminator {
+    let hash = hashv(&[namespace.as_bytes(), b":", name.as_bytes()]);
+    let mut discriminator = [0; DISCRIMINATOR_LEN];
+    discriminator.copy_from_slice(&hash.to_bytes()[..DISCRIMINATOR_LEN]);
+    discriminator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pump_fun_discriminators_match_their_names() {
+        assert_eq!(instruction("create"), CREATE);
+        assert_eq!(instruction("buy"), BUY);
+        assert_eq!(instruction("sell"), SELL);
+        assert_eq!(account("BondingCurve"), BONDING_CURVE);
+        // Namespaces keep an instruction and an account of the same name apart
+        assert_ne!(instruction("BondingCurve"), account("BondingCurve"));
+    }
+}
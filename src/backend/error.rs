@@ -0,0 +1,217 @@
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use std::fmt;
+
+/// Custom error code of `SystemError::ResultWithNegativeLamports`, a transfer of more SOL than the sender holds
+const SYSTEM_NEGATIVE_LAMPORTS: u32 = 1;
+
+/// Custom error code of `TokenError::InsufficientFunds`, shared by SPL Token and Token-2022
+const TOKEN_INSUFFICIENT_FUNDS: u32 = 1;
+
+/// Pump.Fun's `TooMuchSolRequired` and `TooLittleSolReceived`: a buy or sell past its slippage limit
+const PUMP_FUN_SLIPPAGE_CODES: [u32; 2] = [6002, 6003];
+
+/// Domain errors raised by the Pump.Fun client.
+///
+/// These travel inside `anyhow::Error`; callers that need to react to a specific
+/// failure can `downcast_ref::<PumpFunError>()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PumpFunError {
+    /// The bonding curve's reserves can't support the requested calculation
+    InvalidCurveMath { sol_reserve: f64, token_reserve: f64 },
+    /// The account traded as a mint isn't an initialized SPL Token or Token-2022 mint
+    NotATokenMint { mint: String, reason: String },
+    /// The bonding curve is complete and the token now trades on Raydium
+    CurveComplete { mint: String },
+    /// A graduated token has no Raydium pool against SOL to trade through
+    PoolNotFound { mint: String },
+    /// The mint has no Metaplex metadata account
+    MetadataNotFound { mint: String },
+    /// A UI token amount has no whole number of base units in the mint's decimals
+    InvalidTokenAmount { amount: f64, reason: String },
+    /// The durable nonce account a transaction would advance can't be used by its signer
+    InvalidNonceAccount { account: String, reason: String },
+    /// The paying wallet couldn't cover what the transaction spends, or its fee
+    InsufficientFunds { wallet: String, detail: String },
+    /// The transaction would leave an account below the rent-exempt minimum
+    InsufficientFundsForRent { account: String },
+    /// The curve moved past the trade's slippage limit before the transaction landed
+    SlippageExceeded { code: u32 },
+    /// A program rejected the transaction with an error code of its own
+    ProgramError { program: String, code: u32 },
+}
+
+impl PumpFunError {
+    /// Explains why the cluster rejected `transaction`, for the failures a caller can act on.
+    ///
+    /// Only custom error codes from the system program, the token programs and Pump.Fun
+    /// (`pump_program`) have a known meaning; other programs' codes are reported as they are.
+    ///
+    /// # Returns
+    /// `None` for errors with no friendlier form than the RPC's own message.
+    pub fn from_transaction_error(
+        error: &TransactionError,
+        transaction: &Transaction,
+        pump_program: &Pubkey,
+    ) -> Option<Self> {
+        let keys = &transaction.message.account_keys;
+        let wallet = || keys.first().map(Pubkey::to_string).unwrap_or_default();
+        let insufficient = |detail: &str| PumpFunError::InsufficientFunds { wallet: wallet(), detail: detail.to_string() };
+
+        match error {
+            TransactionError::InsufficientFundsForFee => Some(insufficient("not enough SOL for the transaction fee")),
+            TransactionError::InsufficientFundsForRent { account_index } => {
+                let account = keys.get(*account_index as usize)?;
+                Some(PumpFunError::InsufficientFundsForRent { account: account.to_string() })
+            }
+            TransactionError::InstructionError(_, InstructionError::InsufficientFunds) => {
+                Some(insufficient("not enough SOL"))
+            }
+            TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+                let instruction = transaction.message.instructions.get(*index as usize)?;
+                let program = keys.get(instruction.program_id_index as usize)?;
+                if *program == system_program::id() && *code == SYSTEM_NEGATIVE_LAMPORTS {
+                    Some(insufficient("not enough SOL"))
+                } else if (*program == spl_token::id() || *program == spl_token_2022::id()) && *code == TOKEN_INSUFFICIENT_FUNDS {
+                    Some(insufficient("not enough tokens"))
+                } else if program == pump_program && PUMP_FUN_SLIPPAGE_CODES.contains(code) {
+                    Some(PumpFunError::SlippageExceeded { code: *code })
+                } else {
+                    Some(PumpFunError::ProgramError { program: program.to_string(), code: *code })
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the error is the caller's to fix, such as topping up a wallet, rather than the server's.
+    pub fn is_caller_error(&self) -> bool {
+        matches!(
+            self,
+            PumpFunError::InsufficientFunds { .. }
+                | PumpFunError::InsufficientFundsForRent { .. }
+                | PumpFunError::SlippageExceeded { .. }
+                | PumpFunError::InvalidNonceAccount { .. }
+        )
+    }
+}
+
+impl fmt::Display for PumpFunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PumpFunError::InvalidCurveMath { sol_reserve, token_reserve } => write!(
+                f,
+                "Invalid bonding curve reserves (sol_reserve: {}, token_reserve: {})",
+                sol_reserve, token_reserve
+            ),
+            PumpFunError::NotATokenMint { mint, reason } => {
+                write!(f, "{} is not a token mint: {}", mint, reason)
+            }
+            PumpFunError::CurveComplete { mint } => write!(
+                f,
+                "Token {} has graduated: its bonding curve is complete and it now trades on Raydium",
+                mint
+            ),
+            PumpFunError::PoolNotFound { mint } => {
+                write!(f, "No Raydium SOL pool found for graduated token {}", mint)
+            }
+            PumpFunError::MetadataNotFound { mint } => {
+                write!(f, "Token {} has no metadata account", mint)
+            }
+            PumpFunError::InvalidTokenAmount { amount, reason } => {
+                write!(f, "Invalid token amount {}: {}", amount, reason)
+            }
+            PumpFunError::InvalidNonceAccount { account, reason } => {
+                write!(f, "Nonce account {} can't be used: {}", account, reason)
+            }
+            PumpFunError::InsufficientFunds { wallet, detail } => {
+                write!(f, "Wallet {} has insufficient funds: {}", wallet, detail)
+            }
+            PumpFunError::InsufficientFundsForRent { account } => write!(
+                f,
+                "Account {} would be left below the rent-exempt minimum; fund the wallet with more SOL",
+                account
+            ),
+            PumpFunError::SlippageExceeded { code } => write!(
+                f,
+                "The price moved past the trade's slippage limit before it landed (Pump.Fun error {})",
+                code
+            ),
+            PumpFunError::ProgramError { program, code } => {
+                write!(f, "Program {} rejected the transaction with custom error {} ({:#x})", program, code, code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PumpFunError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::system_instruction;
+
+    /// A buy-shaped transaction: a SOL transfer, a token transfer, then a Pump.Fun instruction.
+    fn trade(payer: &Pubkey, pump_program: &Pubkey) -> Transaction {
+        let token_account = Pubkey::new_unique();
+        let instructions = [
+            system_instruction::transfer(payer, &Pubkey::new_unique(), 1_000),
+            spl_token::instruction::transfer(&spl_token::id(), &token_account, &Pubkey::new_unique(), payer, &[], 1)
+                .unwrap(),
+            Instruction::new_with_bytes(*pump_program, &[0], vec![AccountMeta::new(*payer, true)]),
+        ];
+        Transaction::new_with_payer(&instructions, Some(payer))
+    }
+
+    /// Decodes a `TransactionError` as the RPC reports it, then explains it.
+    fn explain(json: serde_json::Value, transaction: &Transaction, pump_program: &Pubkey) -> Option<PumpFunError> {
+        let error: TransactionError = serde_json::from_value(json).unwrap();
+        PumpFunError::from_transaction_error(&error, transaction, pump_program)
+    }
+
+    #[test]
+    fn test_rpc_errors_map_to_friendly_variants() {
+        let payer = Pubkey::new_unique();
+        let pump_program = Pubkey::new_unique();
+        let transaction = trade(&payer, &pump_program);
+        let insufficient = |detail: &str| Some(PumpFunError::InsufficientFunds { wallet: payer.to_string(), detail: detail.to_string() });
+
+        let cases = [
+            (serde_json::json!("InsufficientFundsForFee"), insufficient("not enough SOL for the transaction fee")),
+            (
+                serde_json::json!({ "InsufficientFundsForRent": { "account_index": 1 } }),
+                Some(PumpFunError::InsufficientFundsForRent { account: transaction.message.account_keys[1].to_string() }),
+            ),
+            (serde_json::json!({ "InstructionError": [0, "InsufficientFunds"] }), insufficient("not enough SOL")),
+            (serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }), insufficient("not enough SOL")),
+            (serde_json::json!({ "InstructionError": [1, { "Custom": 1 }] }), insufficient("not enough tokens")),
+            (serde_json::json!({ "InstructionError": [2, { "Custom": 6003 }] }), Some(PumpFunError::SlippageExceeded { code: 6003 })),
+            (
+                serde_json::json!({ "InstructionError": [2, { "Custom": 6005 }] }),
+                Some(PumpFunError::ProgramError { program: pump_program.to_string(), code: 6005 }),
+            ),
+            // Codes only mean something for the program that defines them
+            (
+                serde_json::json!({ "InstructionError": [1, { "Custom": 6003 }] }),
+                Some(PumpFunError::ProgramError { program: spl_token::id().to_string(), code: 6003 }),
+            ),
+            (serde_json::json!("AccountInUse"), None),
+            (serde_json::json!({ "InstructionError": [9, { "Custom": 1 }] }), None),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(explain(json.clone(), &transaction, &pump_program), expected, "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_insufficient_funds_name_the_wallet() {
+        let payer = Pubkey::new_unique();
+        let error = PumpFunError::InsufficientFunds { wallet: payer.to_string(), detail: "not enough SOL".to_string() };
+        assert_eq!(error.to_string(), format!("Wallet {} has insufficient funds: not enough SOL", payer));
+        assert!(error.is_caller_error());
+        assert!(!PumpFunError::ProgramError { program: payer.to_string(), code: 6005 }.is_caller_error());
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use log::{info, warn};
+use solana_sdk::pubkey::Pubkey;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::pump_fun::priority_fee_for_landing;
+use crate::rpc::RpcPool;
+
+/// Keeps the compute unit price at a percentile of recent prioritization fees.
+///
+/// Each refresh samples `getRecentPrioritizationFees` for the accounts trades write and
+/// stores the chosen percentile of them, so transactions pay what recently landed instead
+/// of a fixed guess. Until the first sample lands there is no price.
+pub struct FeeAutopilot {
+    accounts: Vec<Pubkey>,
+    percentile: f64, // 0-100, e.g. 75 for p75
+    max_price: u64, // Cap in micro-lamports, whatever recent fees did
+    price: Mutex<Option<u64>>,
+}
+
+impl FeeAutopilot {
+    pub fn new(accounts: Vec<Pubkey>, percentile: f64, max_price: u64) -> Self {
+        Self {
+            accounts,
+            percentile,
+            max_price,
+            price: Mutex::new(None),
+        }
+    }
+
+    /// The compute unit price in micro-lamports from the latest sample, if any.
+    pub fn price(&self) -> Option<u64> {
+        *self.price.lock().unwrap()
+    }
+
+    /// Stores the configured percentile of `recent_fees`, capped at the maximum.
+    ///
+    /// # Returns
+    /// The new price.
+    pub fn record(&self, recent_fees: &[u64]) -> u64 {
+        let price = priority_fee_for_landing(recent_fees, self.percentile / 100.0).min(self.max_price);
+        *self.price.lock().unwrap() = Some(price);
+        price
+    }
+
+    /// Samples recent prioritization fees on the tracked accounts and records the new price.
+    ///
+    /// # Errors
+    /// Returns an error if the fees can't be fetched; the previous price is kept.
+    pub fn refresh(&self, rpc_client: &RpcPool) -> Result<u64> {
+        let recent = rpc_client
+            .get_recent_prioritization_fees(&self.accounts)
+            .context("Failed to fetch recent prioritization fees")?;
+        let fees: Vec<u64> = recent.iter().map(|fee| fee.prioritization_fee).collect();
+        Ok(self.record(&fees))
+    }
+
+    /// Refreshes the price every `interval` until the returned task is aborted.
+    pub fn spawn(self: Arc<Self>, rpc_client: RpcPool, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.refresh(&rpc_client) {
+                    Ok(price) => info!("Priority fee autopilot set {} micro-lamports per compute unit", price),
+                    Err(e) => warn!("Priority fee autopilot kept its last price: {:#}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use std::collections::HashMap;
+
+    fn rpc_with_fees(fees: &[u64]) -> RpcPool {
+        let samples: Vec<serde_json::Value> = fees
+            .iter()
+            .enumerate()
+            .map(|(slot, fee)| serde_json::json!({ "slot": slot, "prioritizationFee": fee }))
+            .collect();
+        RpcPool::new_mock_with_mocks(
+            "succeeds",
+            HashMap::from([(RpcRequest::GetRecentPrioritizationFees, serde_json::json!(samples))]),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_autopilot_prices_at_the_configured_percentile() {
+        let samples = [700, 100, 0, 400, 200, 900, 300, 600, 500, 800];
+        let accounts = vec![Pubkey::new_unique()];
+
+        let p75 = FeeAutopilot::new(accounts.clone(), 75.0, 1_000_000);
+        assert_eq!(p75.price(), None);
+        assert_eq!(p75.refresh(&rpc_with_fees(&samples)).unwrap(), 700);
+        assert_eq!(p75.price(), Some(700));
+
+        let p50 = FeeAutopilot::new(accounts.clone(), 50.0, 1_000_000);
+        assert_eq!(p50.refresh(&rpc_with_fees(&samples)).unwrap(), 400);
+        let p100 = FeeAutopilot::new(accounts.clone(), 100.0, 1_000_000);
+        assert_eq!(p100.refresh(&rpc_with_fees(&samples)).unwrap(), 900);
+
+        // Capped however high recent fees run
+        let capped = FeeAutopilot::new(accounts.clone(), 75.0, 550);
+        assert_eq!(capped.refresh(&rpc_with_fees(&samples)).unwrap(), 550);
+
+        // Each refresh replaces the price; a failed one keeps it
+        assert_eq!(p75.refresh(&rpc_with_fees(&[10, 20, 30, 40])).unwrap(), 30);
+        assert!(p75.refresh(&RpcPool::new_mock("fails")).is_err());
+        assert_eq!(p75.price(), Some(30));
+    }
+}
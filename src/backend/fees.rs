@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A tier of a tiered fee schedule, applying to trades of at least `min_sol`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub min_sol: f64,
+    pub rate: f64,
+}
+
+/// How a fee schedule picks its rate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum FeeMode {
+    Flat { rate: f64 },
+    Tiered { tiers: Vec<FeeTier> },
+}
+
+/// The trading fee charged on buys and sells.
+///
+/// Loaded from config as e.g. `{"mode": "flat", "rate": 0.005}` or
+/// `{"mode": "tiered", "tiers": [{"min_sol": 0, "rate": 0.005}, {"min_sol": 10, "rate": 0.003}]}`,
+/// optionally with `zero_fee_until` to run a promotional window with no fee.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    #[serde(flatten)]
+    pub mode: FeeMode,
+    #[serde(default)]
+    pub zero_fee_until: Option<u64>, // Unix seconds; no fee is charged before this
+}
+
+impl FeeSchedule {
+    /// A schedule charging the same rate on every trade.
+    pub fn flat(rate: f64) -> Self {
+        Self {
+            mode: FeeMode::Flat { rate },
+            zero_fee_until: None,
+        }
+    }
+
+    /// The fee rate for a trade of `amount_sol`, as a fraction.
+    pub fn rate_for(&self, amount_sol: f64) -> f64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.rate_at(now, amount_sol)
+    }
+
+    /// The fee for a trade of `amount_sol`, in SOL.
+    pub fn fee_for(&self, amount_sol: f64) -> f64 {
+        amount_sol * self.rate_for(amount_sol)
+    }
+
+    /// Checks every rate is a fraction between 0 and 1, and that tiers start at finite,
+    /// non-negative amounts listed in ascending order.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let check_rate = |rate: f64| {
+            // NaN fails the range check too
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(anyhow::anyhow!("Fee rate must be between 0 and 1, got {}", rate));
+            }
+            Ok(())
+        };
+        match &self.mode {
+            FeeMode::Flat { rate } => check_rate(*rate),
+            FeeMode::Tiered { tiers } => {
+                if tiers.is_empty() {
+                    return Err(anyhow::anyhow!("A tiered fee schedule needs at least one tier"));
+                }
+                let mut previous: Option<f64> = None;
+                for tier in tiers {
+                    check_rate(tier.rate)?;
+                    if !tier.min_sol.is_finite() || tier.min_sol < 0.0 {
+                        return Err(anyhow::anyhow!("Fee tier must start at a non-negative amount, got {}", tier.min_sol));
+                    }
+                    if let Some(previous) = previous.filter(|previous| tier.min_sol <= *previous) {
+                        return Err(anyhow::anyhow!(
+                            "Fee tiers must be listed by ascending min_sol; {} follows {}",
+                            tier.min_sol,
+                            previous
+                        ));
+                    }
+                    previous = Some(tier.min_sol);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn rate_at(&self, now_secs: u64, amount_sol: f64) -> f64 {
+        if self.zero_fee_until.is_some_and(|until| now_secs < until) {
+            return 0.0;
+        }
+        match &self.mode {
+            FeeMode::Flat { rate } => *rate,
+            // The highest tier the trade reaches; trades below every tier pay the lowest tier's rate
+            FeeMode::Tiered { tiers } => tiers
+                .iter()
+                .filter(|tier| amount_sol >= tier.min_sol)
+                .max_by(|a, b| a.min_sol.total_cmp(&b.min_sol))
+                .or_else(|| tiers.iter().min_by(|a, b| a.min_sol.total_cmp(&b.min_sol)))
+                .map_or(0.0, |tier| tier.rate),
+        }
+    }
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self::flat(0.005)
+    }
+}
+
+/// Checks each fee split is a positive share and that the shares add up to the whole fee.
+///
+/// No splits at all is valid: the fee then goes to a single recipient.
+pub fn validate_fee_splits(splits: &[(Pubkey, f64)]) -> anyhow::Result<()> {
+    if let Some((recipient, share)) = splits.iter().find(|(_, share)| !share.is_finite() || *share <= 0.0) {
+        return Err(anyhow::anyhow!("Fee split for {} must be a positive share, got {}", recipient, share));
+    }
+    let total: f64 = splits.iter().map(|(_, share)| share).sum();
+    if !splits.is_empty() && (total - 1.0).abs() > 1e-9 {
+        return Err(anyhow::anyhow!("Fee splits must sum to 100%, got {}%", total * 100.0));
+    }
+    Ok(())
+}
+
+/// Divides `lamports` between the split recipients by share.
+///
+/// Each share is rounded down and the last recipient takes what rounding leaves, so the
+/// amounts always add up to exactly `lamports`.
+pub fn split_fee(lamports: u64, splits: &[(Pubkey, f64)]) -> Vec<(Pubkey, u64)> {
+    let mut remaining = lamports;
+    splits
+        .iter()
+        .enumerate()
+        .map(|(i, (recipient, share))| {
+            let amount = if i + 1 == splits.len() {
+                remaining
+            } else {
+                ((lamports as f64 * share) as u64).min(remaining)
+            };
+            remaining -= amount;
+            (*recipient, amount)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flat_schedule() {
+        let schedule = FeeSchedule::flat(0.005);
+        assert_eq!(schedule.rate_for(0.1), 0.005);
+        assert_eq!(schedule.rate_for(1000.0), 0.005);
+        assert!((schedule.fee_for(2.0) - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_two_tier_schedule_at_boundaries() {
+        let schedule: FeeSchedule = serde_json::from_value(serde_json::json!({
+            "mode": "tiered",
+            "tiers": [
+                { "min_sol": 10.0, "rate": 0.003 },
+                { "min_sol": 0.0, "rate": 0.005 }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(schedule.rate_at(0, 0.0), 0.005);
+        assert_eq!(schedule.rate_at(0, 9.999), 0.005);
+        assert_eq!(schedule.rate_at(0, 10.0), 0.003);
+        assert_eq!(schedule.rate_at(0, 500.0), 0.003);
+    }
+
+    #[test]
+    fn test_schedules_with_bad_rates_or_tiers_are_invalid() {
+        let tiered = |tiers: &[(f64, f64)]| FeeSchedule {
+            mode: FeeMode::Tiered {
+                tiers: tiers.iter().map(|&(min_sol, rate)| FeeTier { min_sol, rate }).collect(),
+            },
+            zero_fee_until: None,
+        };
+        assert!(FeeSchedule::default().validate().is_ok());
+        assert!(tiered(&[(0.0, 0.005), (10.0, 0.003)]).validate().is_ok());
+
+        for rate in [-0.01, 1.5, f64::NAN, f64::INFINITY] {
+            assert!(FeeSchedule::flat(rate).validate().is_err(), "{}", rate);
+            assert!(tiered(&[(0.0, rate)]).validate().is_err(), "{}", rate);
+        }
+        assert!(tiered(&[]).validate().is_err());
+        assert!(tiered(&[(f64::NAN, 0.005)]).validate().is_err());
+        assert!(tiered(&[(-1.0, 0.005)]).validate().is_err());
+        let err = tiered(&[(10.0, 0.003), (0.0, 0.005)]).validate().unwrap_err();
+        assert!(err.to_string().contains("ascending min_sol"), "{}", err);
+        assert!(tiered(&[(0.0, 0.005), (0.0, 0.003)]).validate().is_err());
+    }
+
+    #[test]
+    fn test_fee_splits_must_cover_the_whole_fee() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(validate_fee_splits(&[]).is_ok());
+        assert!(validate_fee_splits(&[(a, 0.7), (b, 0.3)]).is_ok());
+
+        let err = validate_fee_splits(&[(a, 0.7), (b, 0.2)]).unwrap_err();
+        assert!(err.to_string().contains("must sum to 100%"), "{}", err);
+        assert!(validate_fee_splits(&[(a, 1.2), (b, -0.2)]).is_err());
+        assert!(validate_fee_splits(&[(a, f64::NAN)]).is_err());
+
+        // Rounding never loses or invents a lamport
+        let thirds = [(a, 1.0 / 3.0), (b, 1.0 / 3.0), (Pubkey::new_unique(), 1.0 / 3.0)];
+        let amounts: Vec<u64> = split_fee(10_000_000, &thirds).into_iter().map(|(_, amount)| amount).collect();
+        assert_eq!(amounts, vec![3_333_333, 3_333_333, 3_333_334]);
+    }
+
+    #[test]
+    fn test_zero_fee_window_overrides_the_schedule() {
+        let schedule = FeeSchedule {
+            zero_fee_until: Some(1_000),
+            ..FeeSchedule::flat(0.005)
+        };
+        assert_eq!(schedule.rate_at(999, 5.0), 0.0);
+        assert_eq!(schedule.rate_at(1_000, 5.0), 0.005);
+
+        // A window that has already closed charges the normal fee
+        assert_eq!(schedule.rate_for(5.0), 0.005);
+    }
+}
@@ -0,0 +1,147 @@
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Header clients send to make retries of a mutating request safe
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+/// A completed response, kept so a retried request gets the same answer
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+/// What to do with a request carrying an idempotency key
+#[derive(Debug)]
+pub enum Claim {
+    /// First time this key has been seen; handle the request, then `complete` the key
+    Started,
+    /// A request with this key is still being handled
+    InFlight,
+    /// The key was already handled; replay its response
+    Cached(CachedResponse),
+}
+
+enum Entry {
+    InFlight,
+    Done { response: CachedResponse, stored_at: Instant },
+}
+
+/// Recently seen idempotency keys and their responses.
+///
+/// Holds at most `capacity` keys, evicting the least recently used, and forgets a
+/// response once it is older than `ttl`.
+pub struct IdempotencyCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<String, Entry>,
+    order: VecDeque<String>, // Least recently used first
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Claims `key` for a new request, or reports why it can't be claimed.
+    pub fn claim(&mut self, key: &str) -> Claim {
+        self.claim_at(Instant::now(), key)
+    }
+
+    pub(crate) fn claim_at(&mut self, now: Instant, key: &str) -> Claim {
+        let claim = match self.entries.get(key) {
+            Some(Entry::InFlight) => Claim::InFlight,
+            Some(Entry::Done { response, stored_at }) if now.duration_since(*stored_at) < self.ttl => {
+                Claim::Cached(response.clone())
+            }
+            _ => {
+                self.entries.insert(key.to_string(), Entry::InFlight);
+                Claim::Started
+            }
+        };
+        self.touch(key);
+        claim
+    }
+
+    /// Stores the response for a key claimed with `claim`.
+    pub fn complete(&mut self, key: &str, response: CachedResponse) {
+        self.complete_at(Instant::now(), key, response)
+    }
+
+    pub(crate) fn complete_at(&mut self, now: Instant, key: &str, response: CachedResponse) {
+        self.entries.insert(key.to_string(), Entry::Done { response, stored_at: now });
+        self.touch(key);
+    }
+
+    /// Releases a claimed key without a response, so the request can be retried.
+    pub fn release(&mut self, key: &str) {
+        self.entries.remove(key);
+        self.order.retain(|k| k != key);
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.order.retain(|k| k != key);
+        self.order.push_back(key.to_string());
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            status: StatusCode::OK,
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_claims_replay_and_expire() {
+        let mut cache = IdempotencyCache::new(Duration::from_secs(60), 10);
+        let start = Instant::now();
+
+        assert!(matches!(cache.claim_at(start, "a"), Claim::Started));
+        assert!(matches!(cache.claim_at(start, "a"), Claim::InFlight));
+
+        cache.complete_at(start, "a", response("first"));
+        match cache.claim_at(start + Duration::from_secs(30), "a") {
+            Claim::Cached(cached) => assert_eq!(cached.body, "first"),
+            other => panic!("expected cached response, got {:?}", other),
+        }
+
+        // Past the TTL the key can be used again
+        assert!(matches!(cache.claim_at(start + Duration::from_secs(61), "a"), Claim::Started));
+
+        // A released key isn't remembered
+        cache.release("a");
+        assert!(matches!(cache.claim_at(start, "a"), Claim::Started));
+    }
+
+    #[test]
+    fn test_least_recently_used_key_is_evicted() {
+        let mut cache = IdempotencyCache::new(Duration::from_secs(60), 2);
+        let now = Instant::now();
+
+        cache.complete_at(now, "a", response("a"));
+        cache.complete_at(now, "b", response("b"));
+        // Using "a" again makes "b" the oldest
+        assert!(matches!(cache.claim_at(now, "a"), Claim::Cached(_)));
+        cache.complete_at(now, "c", response("c"));
+
+        assert!(matches!(cache.claim_at(now, "a"), Claim::Cached(_)));
+        assert!(matches!(cache.claim_at(now, "b"), Claim::Started));
+    }
+}
@@ -1,16 +1,169 @@
 use anyhow::{Context, Result};
+use base64::Engine;
 use log::{error, info, warn};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use solana_sdk::{
+    instruction::Instruction,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    system_instruction::{self, SystemInstruction},
+    system_program,
+    transaction::Transaction,
+};
+use crate::bundle_queue::BundleQueue;
+use crate::config::Config;
+use crate::deadline::Deadline;
+use crate::pump_fun::build_signed_transactions;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::types::*;
+/// Most transactions Jito accepts in one bundle; the default for
+/// `PumpFunConfig::max_wallets_per_bundle`
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 16;
+
+/// Most bytes a bundle's serialized transactions may add up to, well short of
+/// `MAX_BUNDLE_TRANSACTIONS` full packets
+pub const MAX_BUNDLE_BYTES: usize = 8 * PACKET_DATA_SIZE;
+
+/// Longest a block engine request may take; less when the request's deadline is nearer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Jito's mainnet tip accounts; a bundle only lands if it pays one of them
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// Picks a tip account at random to spread load across Jito's accounts.
+pub fn random_tip_account() -> Pubkey {
+    let account = JITO_TIP_ACCOUNTS
+        .choose(&mut rand::thread_rng())
+        .expect("tip account list is not empty");
+    Pubkey::from_str(account).expect("Invalid Jito tip account")
+}
+
+/// Builds a transfer of `lamports` from `payer` to a Jito tip account.
+pub fn tip_transfer(payer: &Pubkey, lamports: u64) -> Instruction {
+    system_instruction::transfer(payer, &random_tip_account(), lamports)
+}
+
+/// Whether `transaction` transfers lamports to one of Jito's tip accounts.
+pub fn pays_tip(transaction: &Transaction) -> bool {
+    tip_transfers(transaction).next().is_some()
+}
+
+/// The lamports a bundle of base64 transactions tips in total; transactions that don't
+/// decode tip nothing.
+pub fn bundle_tip_lamports(transactions: &[String]) -> u64 {
+    transactions.iter()
+        .filter_map(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded).ok())
+        .filter_map(|bytes| bincode::deserialize::<Transaction>(&bytes).ok())
+        .map(|transaction| tip_transfers(&transaction).sum::<u64>())
+        .sum()
+}
+
+/// The lamports of each transfer in `transaction` to one of Jito's tip accounts.
+fn tip_transfers(transaction: &Transaction) -> impl Iterator<Item = u64> + '_ {
+    let message = &transaction.message;
+    let key = move |index: u8| message.account_keys.get(index as usize);
+    message.instructions.iter().filter_map(move |ix| {
+        if key(ix.program_id_index) != Some(&system_program::id()) {
+            return None;
+        }
+        let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&ix.data) else {
+            return None;
+        };
+        let to = ix.accounts.get(1).and_then(|&to| key(to))?;
+        JITO_TIP_ACCOUNTS.contains(&to.to_string().as_str()).then_some(lamports)
+    })
+}
+
+/// A Jito block engine region; submitting to the nearest one lands bundles faster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JitoRegion {
+    Amsterdam,
+    Frankfurt,
+    NewYork,
+    Tokyo,
+}
+
+impl JitoRegion {
+    pub const ALL: [JitoRegion; 4] = [
+        JitoRegion::Amsterdam,
+        JitoRegion::Frankfurt,
+        JitoRegion::NewYork,
+        JitoRegion::Tokyo,
+    ];
+
+    /// The block engine's bundle submission endpoint for this region.
+    pub fn bundle_url(self) -> &'static str {
+        match self {
+            JitoRegion::Amsterdam => "https://amsterdam.mainnet.block-engine.jito.wtf/api/v1/bundles",
+            JitoRegion::Frankfurt => "https://frankfurt.mainnet.block-engine.jito.wtf/api/v1/bundles",
+            JitoRegion::NewYork => "https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles",
+            JitoRegion::Tokyo => "https://tokyo.mainnet.block-engine.jito.wtf/api/v1/bundles",
+        }
+    }
+}
+
+impl FromStr for JitoRegion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "amsterdam" => Ok(JitoRegion::Amsterdam),
+            "frankfurt" => Ok(JitoRegion::Frankfurt),
+            "ny" | "newyork" | "new_york" => Ok(JitoRegion::NewYork),
+            "tokyo" => Ok(JitoRegion::Tokyo),
+            _ => Err(anyhow::anyhow!("Unknown Jito region: {}", s)),
+        }
+    }
+}
+
+/// Pings each region and returns the one that answered fastest, if any answered.
+async fn pick_fastest<F, Fut>(regions: &[JitoRegion], ping: F) -> Option<JitoRegion>
+where
+    F: Fn(JitoRegion) -> Fut,
+    Fut: Future<Output = Result<Duration>>,
+{
+    let mut fastest: Option<(JitoRegion, Duration)> = None;
+    for &region in regions {
+        match ping(region).await {
+            Ok(latency) => {
+                info!("Jito region {:?} answered in {:?}", region, latency);
+                if fastest.is_none_or(|(_, best)| latency < best) {
+                    fastest = Some((region, latency));
+                }
+            }
+            Err(e) => warn!("Jito region {:?} unreachable: {}", region, e),
+        }
+    }
+    fastest.map(|(region, _)| region)
+}
 
 #[derive(Clone)]
 pub struct JitoBundleClient {
     client: Client,
     bundle_url: String,
     tip_amount: f64,
+    min_tip_fraction: f64, // Minimum tip as a fraction of trade volume; 0 disables the check
+    landing_polls: u32, // Status checks before an accepted bundle is considered dropped
+    landing_poll_interval: Duration,
+    max_transactions: usize,
+    retry_base_delay: Duration, // Backoff ceiling before the first resubmission; doubles each retry
+    retry_max_delay: Duration, // Longest any single backoff may be
+    queue: Option<Arc<BundleQueue>>, // Paces submissions when set; bundles go straight out otherwise
 }
 
 #[derive(Debug, Serialize)]
@@ -25,12 +178,38 @@ pub struct BundleResponse {
     pub bundle_id: String,
     pub status: String,
     pub error: Option<String>,
+    #[serde(default)]
+    pub landed_slot: Option<u64>, // Set once the bundle is confirmed on-chain
+}
+
+/// Where an accepted bundle ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleLanding {
+    Landed { slot: u64 },
+    Dropped, // Accepted by the block engine but never confirmed
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BundleStatuses {
+    value: Vec<Option<BundleStatus>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BundleStatus {
+    slot: u64,
+    confirmation_status: Option<String>,
 }
 
 impl JitoBundleClient {
     pub fn new(bundle_url: String) -> Self {
         let client = Client::builder()
-            .timeout(Duration::from_secs(30))
+            .timeout(REQUEST_TIMEOUT)
             .build()
             .expect("Failed to create HTTP client");
 
@@ -38,22 +217,197 @@ impl JitoBundleClient {
             client,
             bundle_url,
             tip_amount: 0.00001, // 0.00001 SOL tip
+            min_tip_fraction: 0.0,
+            landing_polls: 30,
+            landing_poll_interval: Duration::from_secs(1),
+            max_transactions: MAX_BUNDLE_TRANSACTIONS,
+            retry_base_delay: Duration::from_secs(1),
+            retry_max_delay: Duration::from_secs(30),
+            queue: None,
         }
     }
 
-    pub async fn submit_bundle(&self, transactions: Vec<String>) -> Result<BundleResponse> {
-        info!("Submitting bundle with {} transactions", transactions.len());
+    /// Creates a client that pays `tip_sol` with every bundle.
+    /// 
+    /// # Errors
+    /// Returns an error if the tip is negative or not a number.
+    pub fn with_tip(bundle_url: String, tip_sol: f64) -> Result<Self> {
+        let mut client = Self::new(bundle_url);
+        client.set_tip(tip_sol)?;
+        Ok(client)
+    }
 
-        if transactions.is_empty() {
-            return Err(anyhow::anyhow!("No transactions to bundle"));
+    /// Creates the client the server bundles through, from `jito_region` (or `jito_bundle_url`
+    /// when no region is set), `jito_tip_amount` and the bundle limits in `config`.
+    /// 
+    /// # Errors
+    /// Returns an error if the region is unknown or the tip is invalid.
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        let mut client = match config.jito_region.as_deref() {
+            Some("auto") => Self::auto_select().await.unwrap_or_else(|e| {
+                warn!("Jito region selection failed ({}); using {}", e, config.jito_bundle_url);
+                Self::new(config.jito_bundle_url.clone())
+            }),
+            Some(region) => Self::for_region(region.parse()?),
+            None => Self::new(config.jito_bundle_url.clone()),
+        };
+        client.set_tip(config.jito_tip_amount)?;
+        client.set_min_tip_fraction(config.jito_min_tip_fraction);
+        client.set_max_transactions(config.max_wallets_per_bundle.unwrap_or(MAX_BUNDLE_TRANSACTIONS));
+        client.set_retry_backoff(Duration::from_secs(1), Duration::from_secs(config.jito_retry_max_delay_secs));
+        Ok(client)
+    }
+
+    /// Creates a client that submits to the given region's block engine.
+    pub fn for_region(region: JitoRegion) -> Self {
+        Self::new(region.bundle_url().to_string())
+    }
+
+    /// Creates a client for whichever region answers a ping fastest.
+    /// 
+    /// # Errors
+    /// Returns an error if no region can be reached.
+    pub async fn auto_select() -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(2))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        let ping = |region: JitoRegion| {
+            let client = client.clone();
+            async move {
+                let started = Instant::now();
+                client.get(region.bundle_url()).send().await?;
+                Ok(started.elapsed())
+            }
+        };
+        let region = pick_fastest(&JitoRegion::ALL, ping)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("No Jito region is reachable"))?;
+        info!("Selected Jito region {:?}", region);
+        Ok(Self::for_region(region))
+    }
+
+    /// Checks the block engine answers at all, returning how long it took.
+    /// 
+    /// Any HTTP response counts; only a connection failure or timeout is an error.
+    pub async fn ping(&self) -> Result<Duration> {
+        let started = Instant::now();
+        self.client
+            .get(&self.bundle_url)
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .with_context(|| format!("Jito endpoint {} is unreachable", self.bundle_url))?;
+        Ok(started.elapsed())
+    }
+
+    /// The endpoint bundles are submitted to.
+    pub fn bundle_url(&self) -> &str {
+        &self.bundle_url
+    }
+
+    /// The tip paid per bundle, in SOL.
+    pub fn tip_amount(&self) -> f64 {
+        self.tip_amount
+    }
+
+    /// Builds the transfer that pays this client's tip from `payer` to a Jito tip account.
+    /// 
+    /// Belongs in the last transaction of a bundle, so the tip is only paid if every
+    /// transaction before it succeeds.
+    pub fn tip_instruction(&self, payer: &Pubkey) -> Instruction {
+        tip_transfer(payer, (self.tip_amount * 1e9) as u64)
+    }
+
+    /// Sets the tip paid per bundle, in SOL.
+    /// 
+    /// # Errors
+    /// Returns an error if the tip is negative or not a number.
+    pub fn set_tip(&mut self, tip_sol: f64) -> Result<()> {
+        if !tip_sol.is_finite() || tip_sol < 0.0 {
+            return Err(anyhow::anyhow!("Jito tip must be a non-negative amount of SOL, got {}", tip_sol));
         }
+        self.tip_amount = tip_sol;
+        Ok(())
+    }
+
+    /// Requires the tip to be at least `fraction` of the trade volume before submitting.
+    pub fn set_min_tip_fraction(&mut self, fraction: f64) {
+        self.min_tip_fraction = fraction.max(0.0);
+    }
+
+    /// Caps how many transactions a bundle may hold; set from `PumpFunConfig::max_wallets_per_bundle`.
+    pub fn set_max_transactions(&mut self, max_transactions: usize) {
+        self.max_transactions = max_transactions.max(1);
+    }
+
+    /// Sets how many times, and how often, to check an accepted bundle has landed.
+    pub fn set_landing_poll(&mut self, polls: u32, interval: Duration) {
+        self.landing_polls = polls.max(1);
+        self.landing_poll_interval = interval;
+    }
+
+    /// Sets the backoff between bundle resubmissions: `base` doubles every retry, up to `max`.
+    pub fn set_retry_backoff(&mut self, base: Duration, max: Duration) {
+        self.retry_base_delay = base;
+        self.retry_max_delay = max.max(base);
+    }
+
+    /// Sends every bundle through `queue`, so they wait their turn with queued ones.
+    /// 
+    /// The queue must submit through a client of its own without a queue, or nothing is sent.
+    pub fn set_queue(&mut self, queue: Arc<BundleQueue>) {
+        self.queue = Some(queue);
+    }
+
+    /// Checks the tip is large enough to plausibly land a trade of the given size.
+    /// 
+    /// Returns an error suggesting the minimum tip when it falls short.
+    pub fn check_tip_for_trade(&self, trade_volume_sol: f64) -> Result<()> {
+        let min_tip = trade_volume_sol * self.min_tip_fraction;
+        if self.tip_amount < min_tip {
+            return Err(anyhow::anyhow!(
+                "Tip of {} SOL is too small for a {} SOL trade; use a tip of at least {} SOL",
+                self.tip_amount,
+                trade_volume_sol,
+                min_tip
+            ));
+        }
+        Ok(())
+    }
 
-        if transactions.len() > 16 {
-            return Err(anyhow::anyhow!("Maximum 16 transactions allowed per bundle"));
+    /// Submits a bundle for a trade after checking the tip is proportionate to its volume.
+    pub async fn submit_trade_bundle(
+        &self,
+        transactions: Vec<String>,
+        trade_volume_sol: f64,
+    ) -> Result<BundleResponse> {
+        self.check_tip_for_trade(trade_volume_sol)?;
+        self.submit_bundle(transactions).await
+    }
+
+    /// Submits a bundle, waiting its turn in the pacing queue when the client has one.
+    /// 
+    /// Queued bundles go first by the lamports they tip.
+    pub async fn submit_bundle(&self, transactions: Vec<String>) -> Result<BundleResponse> {
+        match &self.queue {
+            Some(queue) => {
+                // Refused bundles never take up a place in the queue
+                self.preflight(&transactions)?;
+                let priority = bundle_tip_lamports(&transactions);
+                queue.submit(transactions, priority).await
+            }
+            None => self.send_bundle(transactions).await,
         }
+    }
 
-        // Create tip account (this would be a real account in practice)
-        let tip_account = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM".to_string();
+    /// Sends a bundle straight to the block engine.
+    pub(crate) async fn send_bundle(&self, transactions: Vec<String>) -> Result<BundleResponse> {
+        info!("Submitting bundle with {} transactions", transactions.len());
+        self.preflight(&transactions)?;
+
+        let tip_account = random_tip_account().to_string();
         let tip_amount_lamports = (self.tip_amount * 1e9) as u64;
 
         let request = BundleRequest {
@@ -66,6 +420,7 @@ impl JitoBundleClient {
             .client
             .post(&self.bundle_url)
             .json(&request)
+            .timeout(Deadline::limit(REQUEST_TIMEOUT))
             .send()
             .await
             .context("Failed to send bundle request")?;
@@ -92,6 +447,7 @@ impl JitoBundleClient {
         let response = self
             .client
             .get(&url)
+            .timeout(Deadline::limit(REQUEST_TIMEOUT))
             .send()
             .await
             .context("Failed to get bundle status")?;
@@ -110,6 +466,59 @@ impl JitoBundleClient {
         Ok(bundle_response)
     }
 
+    /// Polls `getBundleStatuses` until an accepted bundle is confirmed on-chain.
+    /// 
+    /// # Returns
+    /// `BundleLanding::Landed` with the landing slot once the bundle reaches `confirmed`,
+    /// or `BundleLanding::Dropped` if it still hasn't after the configured number of polls.
+    pub async fn confirm_landing(&self, bundle_id: &str) -> Result<BundleLanding> {
+        for poll in 0..self.landing_polls {
+            if poll > 0 {
+                tokio::time::sleep(self.landing_poll_interval).await;
+            }
+            if let Some(slot) = self.landed_slot(bundle_id).await? {
+                info!("Bundle {} landed in slot {}", bundle_id, slot);
+                return Ok(BundleLanding::Landed { slot });
+            }
+        }
+        warn!("Bundle {} was accepted but never landed", bundle_id);
+        Ok(BundleLanding::Dropped)
+    }
+
+    /// Returns the slot a bundle landed in, or `None` if it isn't confirmed yet.
+    async fn landed_slot(&self, bundle_id: &str) -> Result<Option<u64>> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getBundleStatuses",
+            "params": [[bundle_id]]
+        });
+        let response: JsonRpcResponse<BundleStatuses> = self
+            .client
+            .post(&self.bundle_url)
+            .json(&request)
+            .timeout(Deadline::limit(REQUEST_TIMEOUT))
+            .send()
+            .await
+            .context("Failed to get bundle statuses")?
+            .json()
+            .await
+            .context("Failed to parse bundle statuses")?;
+        if let Some(error) = response.error {
+            return Err(anyhow::anyhow!("getBundleStatuses failed: {}", error));
+        }
+
+        let status = response
+            .result
+            .and_then(|statuses| statuses.value.into_iter().next().flatten());
+        Ok(status
+            .filter(|status| {
+                matches!(status.confirmation_status.as_deref(), Some("confirmed") | Some("finalized"))
+            })
+            .map(|status| status.slot))
+    }
+
+    /// Submits a bundle and waits for it to land, resubmitting if it fails or is dropped.
     pub async fn submit_bundle_with_retry(
         &self,
         transactions: Vec<String>,
@@ -120,9 +529,22 @@ impl JitoBundleClient {
 
         while retries < max_retries {
             match self.submit_bundle(transactions.clone()).await {
-                Ok(response) => {
+                Ok(mut response) => {
                     if response.status == "success" {
-                        return Ok(response);
+                        // Acceptance only means the block engine has it; wait for it to land
+                        match self.confirm_landing(&response.bundle_id).await {
+                            Ok(BundleLanding::Landed { slot }) => {
+                                response.landed_slot = Some(slot);
+                                return Ok(response);
+                            }
+                            Ok(BundleLanding::Dropped) => {
+                                last_error = Some(format!("Bundle {} was accepted but dropped", response.bundle_id));
+                            }
+                            Err(e) => {
+                                warn!("Failed to confirm bundle {}: {}", response.bundle_id, e);
+                                last_error = Some(e.to_string());
+                            }
+                        }
                     } else if let Some(error) = &response.error {
                         warn!("Bundle submission failed: {}", error);
                         last_error = Some(error.clone());
@@ -136,8 +558,7 @@ impl JitoBundleClient {
 
             retries += 1;
             if retries < max_retries {
-                // Exponential backoff
-                let delay = Duration::from_secs(2u64.pow(retries));
+                let delay = backoff_delay(retries, self.retry_base_delay, self.retry_max_delay, &mut rand::thread_rng());
                 tokio::time::sleep(delay).await;
             }
         }
@@ -154,13 +575,13 @@ impl JitoBundleClient {
             return Err(anyhow::anyhow!("No transactions provided"));
         }
 
-        if transactions.len() > 16 {
-            return Err(anyhow::anyhow!("Maximum 16 transactions allowed per bundle"));
+        if transactions.len() > self.max_transactions {
+            return Err(anyhow::anyhow!("Maximum {} transactions allowed per bundle", self.max_transactions));
         }
 
         // Validate base64 encoding
         for (i, tx) in transactions.iter().enumerate() {
-            if let Err(e) = base64::decode(tx) {
+            if let Err(e) = base64::engine::general_purpose::STANDARD.decode(tx) {
                 return Err(anyhow::anyhow!("Invalid base64 transaction at index {}: {}", i, e));
             }
         }
@@ -168,6 +589,49 @@ impl JitoBundleClient {
         Ok(())
     }
 
+    /// Checks a bundle is one Jito will take before it is sent: within the transaction limit,
+    /// every transaction small enough to fit a packet, no more than `MAX_BUNDLE_BYTES` in
+    /// all, and at least one paying a Jito tip.
+    ///
+    /// # Errors
+    /// Returns an error naming the first transaction that is malformed or oversized, or
+    /// saying the bundle is too large as a whole or pays no tip.
+    pub fn preflight(&self, transactions: &[String]) -> Result<()> {
+        self.validate_transactions(transactions)?;
+
+        let mut tipped = false;
+        let mut total_bytes = 0;
+        for (i, encoded) in transactions.iter().enumerate() {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+            total_bytes += bytes.len();
+            if bytes.len() > PACKET_DATA_SIZE {
+                return Err(anyhow::anyhow!(
+                    "Transaction {} is {} bytes; Jito accepts at most {} per transaction",
+                    i,
+                    bytes.len(),
+                    PACKET_DATA_SIZE
+                ));
+            }
+            let transaction: Transaction = bincode::deserialize(&bytes)
+                .with_context(|| format!("Transaction {} isn't a serialized transaction", i))?;
+            tipped |= pays_tip(&transaction);
+        }
+        if total_bytes > MAX_BUNDLE_BYTES {
+            return Err(anyhow::anyhow!(
+                "Bundle is {} bytes; Jito accepts at most {} per bundle",
+                total_bytes,
+                MAX_BUNDLE_BYTES
+            ));
+        }
+        if !tipped {
+            return Err(anyhow::anyhow!(
+                "Bundle pays no tip; Jito only lands bundles that transfer to one of its tip accounts"
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn calculate_bundle_fee(&self, transaction_count: usize) -> f64 {
         // Base fee + per-transaction fee
         let base_fee = 0.00001; // 0.00001 SOL base fee
@@ -176,9 +640,147 @@ impl JitoBundleClient {
     }
 }
 
+/// What a bundled transaction does, which decides where in the bundle it may go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleStep {
+    Create,
+    Buy,
+    Sell,
+}
+
+/// Assembles a bundle in landing order, checking the order is one that can land.
+///
+/// A token must be created before anything trades it, and the tip must come last so it is
+/// only paid if every transaction before it succeeds. `build` rejects bundles that break
+/// either rule or don't tip at all.
+pub struct BundleBuilder {
+    transactions: Vec<(BundleStep, Transaction)>,
+    tip: Option<Transaction>,
+    max_transactions: usize,
+}
+
+impl BundleBuilder {
+    /// Creates a builder for bundles of at most `max_transactions`, tip included; set from
+    /// `PumpFunConfig::max_wallets_per_bundle`.
+    pub fn new(max_transactions: usize) -> Self {
+        Self {
+            transactions: Vec::new(),
+            tip: None,
+            max_transactions: max_transactions.max(1),
+        }
+    }
+
+    /// Appends a transaction to the bundle.
+    pub fn add_transaction(&mut self, step: BundleStep, transaction: Transaction) -> &mut Self {
+        self.transactions.push((step, transaction));
+        self
+    }
+
+    /// Sets the transaction that pays the tip, which always goes last.
+    pub fn with_tip(&mut self, transaction: Transaction) -> &mut Self {
+        self.tip = Some(transaction);
+        self
+    }
+
+    /// Checks the bundle's order and serializes it for `JitoBundleClient::submit_bundle`.
+    ///
+    /// # Errors
+    /// Returns an error if the bundle is empty or too large, has no tip, tips anywhere but
+    /// its last transaction, or trades before it creates.
+    pub fn build(&self) -> Result<Vec<String>> {
+        if self.transactions.is_empty() {
+            return Err(anyhow::anyhow!("No transactions to bundle"));
+        }
+        if self.transactions.len() + 1 > self.max_transactions {
+            return Err(anyhow::anyhow!("Maximum {} transactions allowed per bundle", self.max_transactions));
+        }
+        let tip = self.tip.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Bundle has no tip; Jito only lands bundles that pay one"))?;
+        if !pays_tip(tip) {
+            return Err(anyhow::anyhow!("The tip transaction doesn't pay a Jito tip account"));
+        }
+
+        let mut traded = false;
+        for (i, (step, transaction)) in self.transactions.iter().enumerate() {
+            if pays_tip(transaction) {
+                return Err(anyhow::anyhow!("Transaction {} pays a tip; only the last transaction may", i));
+            }
+            match step {
+                BundleStep::Create if traded => {
+                    return Err(anyhow::anyhow!("Transaction {} creates the token after a trade on it", i));
+                }
+                BundleStep::Create => {}
+                BundleStep::Buy | BundleStep::Sell => traded = true,
+            }
+        }
+
+        let ordered: Vec<Transaction> = self.transactions.iter()
+            .map(|(_, transaction)| transaction.clone())
+            .chain(std::iter::once(tip.clone()))
+            .collect();
+        build_signed_transactions(&ordered)
+    }
+}
+
+/// Exponential backoff with full jitter: a uniformly random delay between zero and
+/// `base * 2^attempt`, never more than `max`.
+/// 
+/// Randomizing the whole delay keeps clients that failed together from retrying in lockstep.
+fn backoff_delay<R: Rng + ?Sized>(attempt: u32, base: Duration, max: Duration, rng: &mut R) -> Duration {
+    let ceiling = base.saturating_mul(2u32.saturating_pow(attempt)).min(max);
+    Duration::from_millis(rng.gen_range(0..=ceiling.as_millis() as u64))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, Signer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Starts a block engine that accepts every bundle and reports it landed in slot 4242
+    /// from the `lands_after`th status check, or never when `lands_after` is `None`.
+    async fn start_mock_block_engine(lands_after: Option<usize>) -> String {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let server = HttpServer::new(move || {
+            let polls = polls.clone();
+            App::new().default_service(web::to(move |body: web::Json<serde_json::Value>| {
+                let polls = polls.clone();
+                async move {
+                    if body.get("method").is_none() {
+                        return HttpResponse::Ok().json(serde_json::json!({
+                            "bundle_id": "bundle-1",
+                            "status": "success",
+                            "error": null
+                        }));
+                    }
+                    let poll = polls.fetch_add(1, Ordering::SeqCst) + 1;
+                    let status = match lands_after {
+                        Some(n) if poll >= n => serde_json::json!({
+                            "bundle_id": "bundle-1",
+                            "slot": 4242,
+                            "confirmation_status": "confirmed"
+                        }),
+                        _ => serde_json::Value::Null,
+                    };
+                    HttpResponse::Ok().json(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "result": { "context": { "slot": 4300 }, "value": [status] }
+                    }))
+                }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        format!("http://{}", addr)
+    }
 
     #[tokio::test]
     async fn test_validate_transactions() {
@@ -208,4 +810,309 @@ mod tests {
         let fee = client.calculate_bundle_fee(5);
         assert_eq!(fee, 0.00001 + (5.0 * 0.000001));
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_tip_too_small_for_trade() {
+        let mut client = JitoBundleClient::new("https://test.api.jito.wtf".to_string());
+        assert!(client.check_tip_for_trade(100.0).is_ok());
+
+        client.set_min_tip_fraction(0.0001);
+        let err = client
+            .submit_trade_bundle(vec!["dGVzdA==".to_string()], 100.0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("at least 0.01 SOL"));
+
+        // Small trades still pass with the default tip
+        assert!(client.check_tip_for_trade(0.05).is_ok());
+    }
+
+    #[test]
+    fn test_tip_instruction_pays_a_jito_tip_account() {
+        let client = JitoBundleClient::new("https://test.api.jito.wtf".to_string());
+        let payer = Pubkey::new_unique();
+
+        let ix = client.tip_instruction(&payer);
+        assert_eq!(ix.program_id, solana_sdk::system_program::id());
+        assert_eq!(ix.accounts[0].pubkey, payer);
+        assert!(JITO_TIP_ACCOUNTS.contains(&ix.accounts[1].pubkey.to_string().as_str()));
+
+        let transfer: solana_sdk::system_instruction::SystemInstruction =
+            bincode::deserialize(&ix.data).unwrap();
+        assert_eq!(
+            transfer,
+            solana_sdk::system_instruction::SystemInstruction::Transfer { lamports: 10_000 }
+        );
+    }
+
+    #[test]
+    fn test_regions_map_to_block_engine_urls() {
+        let expected = [
+            (JitoRegion::Amsterdam, "https://amsterdam.mainnet.block-engine.jito.wtf/api/v1/bundles"),
+            (JitoRegion::Frankfurt, "https://frankfurt.mainnet.block-engine.jito.wtf/api/v1/bundles"),
+            (JitoRegion::NewYork, "https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles"),
+            (JitoRegion::Tokyo, "https://tokyo.mainnet.block-engine.jito.wtf/api/v1/bundles"),
+        ];
+        for (region, url) in expected {
+            assert_eq!(JitoBundleClient::for_region(region).bundle_url(), url);
+        }
+        assert_eq!("NY".parse::<JitoRegion>().unwrap(), JitoRegion::NewYork);
+        assert!("mars".parse::<JitoRegion>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_auto_selection_picks_lowest_latency() {
+        let ping = |region: JitoRegion| async move {
+            match region {
+                JitoRegion::Amsterdam => Ok(Duration::from_millis(80)),
+                JitoRegion::Frankfurt => Ok(Duration::from_millis(25)),
+                JitoRegion::NewYork => Err(anyhow::anyhow!("timed out")),
+                JitoRegion::Tokyo => Ok(Duration::from_millis(190)),
+            }
+        };
+        assert_eq!(pick_fastest(&JitoRegion::ALL, ping).await, Some(JitoRegion::Frankfurt));
+
+        let unreachable = |_| async { Err(anyhow::anyhow!("timed out")) };
+        assert_eq!(pick_fastest(&JitoRegion::ALL, unreachable).await, None);
+    }
+
+    #[actix_web::test]
+    async fn test_accepted_bundle_reports_landing_slot() {
+        let mut client = JitoBundleClient::new(start_mock_block_engine(Some(2)).await);
+        client.set_landing_poll(5, Duration::from_millis(10));
+
+        let response = client
+            .submit_bundle_with_retry(tipped_bundle(), 1)
+            .await
+            .unwrap();
+        assert_eq!(response.bundle_id, "bundle-1");
+        assert_eq!(response.landed_slot, Some(4242));
+    }
+
+    #[actix_web::test]
+    async fn test_accepted_but_dropped_bundle_is_not_success() {
+        let mut client = JitoBundleClient::new(start_mock_block_engine(None).await);
+        client.set_landing_poll(3, Duration::from_millis(10));
+
+        assert_eq!(client.confirm_landing("bundle-1").await.unwrap(), BundleLanding::Dropped);
+        let err = client
+            .submit_bundle_with_retry(tipped_bundle(), 1)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("accepted but dropped"));
+    }
+
+    #[test]
+    fn test_backoff_is_jittered_within_an_exponential_cap() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let base = Duration::from_secs(1);
+        let max = Duration::from_secs(30);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for attempt in 0..10 {
+            let ceiling = (base * 2u32.pow(attempt)).min(max);
+            let delays: Vec<Duration> = (0..200).map(|_| backoff_delay(attempt, base, max, &mut rng)).collect();
+            assert!(delays.iter().all(|delay| *delay <= ceiling), "attempt {} exceeded {:?}", attempt, ceiling);
+            // Jitter spreads retries out rather than pinning them to the ceiling
+            assert!(delays.iter().any(|delay| *delay < ceiling / 2));
+        }
+
+        // Far past the cap, and without overflowing
+        assert!(backoff_delay(u32::MAX, base, max, &mut rng) <= max);
+
+        // The same seed replays the same delays
+        let replay = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (1..5).map(|attempt| backoff_delay(attempt, base, max, &mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(replay(42), replay(42));
+    }
+
+    /// A bundle of one signed transaction paying a tip
+    fn tipped_bundle() -> Vec<String> {
+        let payer = Keypair::new();
+        let tip = Transaction::new_signed_with_payer(
+            &[tip_transfer(&payer.pubkey(), 10_000)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        build_signed_transactions(&[tip]).unwrap()
+    }
+
+    fn encode(transaction: &Transaction) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_preflight_requires_a_tip_within_size_limits() {
+        let client = JitoBundleClient::new("http://127.0.0.1:1".to_string());
+        let payer = Keypair::new();
+        let transfer = |count: usize| {
+            let instructions: Vec<Instruction> = (0..count)
+                .map(|_| system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1))
+                .collect();
+            Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[&payer], Hash::new_unique())
+        };
+
+        let mut bundle = tipped_bundle();
+        assert!(client.preflight(&bundle).is_ok());
+        // The tip may be anywhere in the bundle
+        bundle.insert(0, encode(&transfer(1)));
+        assert!(client.preflight(&bundle).is_ok());
+
+        // Nothing pays a tip
+        let tipless = vec![encode(&transfer(1)), encode(&transfer(2))];
+        let err = client.submit_bundle(tipless).await.unwrap_err();
+        assert!(err.to_string().contains("Bundle pays no tip"), "{}", err);
+
+        // A transaction too large for a packet, even alongside a tip
+        let oversized = transfer(40);
+        assert!(bincode::serialized_size(&oversized).unwrap() as usize > PACKET_DATA_SIZE);
+        bundle.push(encode(&oversized));
+        let err = client.submit_bundle(bundle).await.unwrap_err();
+        assert!(err.to_string().contains("Transaction 2 is"), "{}", err);
+        assert!(err.to_string().contains("at most 1232 per transaction"), "{}", err);
+
+        // Transactions that each fit a packet but together outgrow the bundle
+        let large = transfer(20);
+        let large_size = bincode::serialized_size(&large).unwrap() as usize;
+        assert!(large_size <= PACKET_DATA_SIZE);
+        let mut bundle = tipped_bundle();
+        let mut large_bytes = 0;
+        while large_bytes <= MAX_BUNDLE_BYTES {
+            bundle.push(encode(&large));
+            large_bytes += large_size;
+        }
+        assert!(bundle.len() <= MAX_BUNDLE_TRANSACTIONS);
+        let err = client.submit_bundle(bundle).await.unwrap_err();
+        assert!(err.to_string().contains("at most 9856 per bundle"), "{}", err);
+
+        // Bytes that aren't a transaction at all
+        let err = client.preflight(&["dGVzdA==".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("Transaction 0 isn't a serialized transaction"), "{}", err);
+    }
+
+    fn bundle_transaction(payer: &Pubkey, instruction: Instruction) -> Transaction {
+        Transaction::new_with_payer(&[instruction], Some(payer))
+    }
+
+    #[test]
+    fn test_bundle_builder_enforces_launch_order() {
+        let payer = Pubkey::new_unique();
+        let create = || bundle_transaction(&payer, system_instruction::transfer(&payer, &Pubkey::new_unique(), 1));
+        let buy = || bundle_transaction(&payer, system_instruction::transfer(&payer, &Pubkey::new_unique(), 2));
+        let tip = || bundle_transaction(&payer, tip_transfer(&payer, 10_000));
+
+        let mut bundle = BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS);
+        bundle.add_transaction(BundleStep::Create, create())
+            .add_transaction(BundleStep::Buy, buy())
+            .add_transaction(BundleStep::Buy, buy());
+        let err = bundle.build().unwrap_err();
+        assert!(err.to_string().contains("no tip"), "{}", err);
+
+        let encoded = bundle.with_tip(tip()).build().unwrap();
+        assert_eq!(encoded.len(), 4);
+        let last: Transaction = bincode::deserialize(
+            &base64::engine::general_purpose::STANDARD.decode(&encoded[3]).unwrap(),
+        )
+        .unwrap();
+        assert!(pays_tip(&last));
+
+        // Buying before the token exists
+        let mut bundle = BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS);
+        bundle.add_transaction(BundleStep::Buy, buy())
+            .add_transaction(BundleStep::Create, create())
+            .with_tip(tip());
+        let err = bundle.build().unwrap_err();
+        assert!(err.to_string().contains("Transaction 1 creates the token after a trade"), "{}", err);
+
+        // Tipping before the last transaction
+        let mut bundle = BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS);
+        bundle.add_transaction(BundleStep::Create, create())
+            .add_transaction(BundleStep::Buy, tip())
+            .with_tip(tip());
+        let err = bundle.build().unwrap_err();
+        assert!(err.to_string().contains("Transaction 1 pays a tip"), "{}", err);
+
+        // A "tip" that pays someone else
+        let mut bundle = BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS);
+        bundle.add_transaction(BundleStep::Create, create()).with_tip(buy());
+        let err = bundle.build().unwrap_err();
+        assert!(err.to_string().contains("doesn't pay a Jito tip account"), "{}", err);
+
+        // Nothing but a tip, or more than fits
+        assert!(BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS).with_tip(tip()).build().is_err());
+        let mut bundle = BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS);
+        for _ in 0..MAX_BUNDLE_TRANSACTIONS {
+            bundle.add_transaction(BundleStep::Buy, buy());
+        }
+        let err = bundle.with_tip(tip()).build().unwrap_err();
+        assert!(err.to_string().contains("Maximum 16 transactions"), "{}", err);
+
+        // A lower configured limit applies in its place
+        let mut bundle = BundleBuilder::new(3);
+        bundle.add_transaction(BundleStep::Create, create())
+            .add_transaction(BundleStep::Buy, buy())
+            .with_tip(tip());
+        assert!(bundle.build().is_ok());
+        let err = bundle.add_transaction(BundleStep::Buy, buy()).build().unwrap_err();
+        assert!(err.to_string().contains("Maximum 3 transactions"), "{}", err);
+    }
+
+    #[actix_web::test]
+    async fn test_bundles_wait_in_the_clients_queue() {
+        use crate::bundle_queue::{QueueFull, QueueLimits};
+
+        let engine = JitoBundleClient::new(start_mock_block_engine(None).await);
+        let queued = |capacity| {
+            let mut client = JitoBundleClient::new("http://127.0.0.1:1".to_string());
+            let limits = QueueLimits { capacity, ..QueueLimits::default() };
+            client.set_queue(Arc::new(BundleQueue::new(Arc::new(engine.clone()), limits)));
+            client
+        };
+
+        // Sent by the queue's own client, not this one's unreachable endpoint
+        let response = queued(10).submit_bundle(tipped_bundle()).await.unwrap();
+        assert_eq!(response.bundle_id, "bundle-1");
+
+        let err = queued(0).submit_bundle(tipped_bundle()).await.unwrap_err();
+        assert!(err.downcast_ref::<QueueFull>().is_some(), "{}", err);
+        // Bundles Jito would refuse are turned away before they reach the queue
+        let tipless = vec![encode(&Transaction::new_with_payer(&[], Some(&Pubkey::new_unique())))];
+        let err = queued(0).submit_bundle(tipless).await.unwrap_err();
+        assert!(err.downcast_ref::<QueueFull>().is_none(), "{}", err);
+    }
+
+    #[actix_web::test]
+    async fn test_configured_tip_is_sent_with_the_bundle() {
+        let submitted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = submitted.clone();
+        let server = HttpServer::new(move || {
+            let captured = captured.clone();
+            App::new().default_service(web::to(move |body: web::Json<serde_json::Value>| {
+                captured.lock().unwrap().push(body.into_inner());
+                async { HttpResponse::Ok().json(serde_json::json!({ "bundle_id": "bundle-1", "status": "success" })) }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+
+        let mut client = JitoBundleClient::with_tip(url, 0.0025).unwrap();
+        client.submit_bundle(tipped_bundle()).await.unwrap();
+        client.set_tip(0.01).unwrap();
+        client.submit_bundle(tipped_bundle()).await.unwrap();
+
+        let tips: Vec<u64> = submitted.lock().unwrap().iter().map(|body| body["tip_amount"].as_u64().unwrap()).collect();
+        assert_eq!(tips, vec![2_500_000, 10_000_000]);
+
+        assert!(JitoBundleClient::with_tip("http://127.0.0.1:1".to_string(), -0.1).is_err());
+        assert!(client.set_tip(f64::NAN).is_err());
+        assert_eq!(client.tip_amount(), 0.01);
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use bip39::{Language, Mnemonic, Seed};
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::signature::{keypair_from_seed, keypair_from_seed_and_derivation_path, Keypair};
+use std::fs;
+use std::path::Path;
+
+/// Loads a keypair from a Solana CLI keypair file such as `~/.config/solana/id.json`.
+///
+/// The file holds a JSON byte array: either the full 64-byte keypair written by
+/// `solana-keygen`, or just the 32-byte secret seed.
+///
+/// # Errors
+/// Returns an error if the file can't be read, isn't a JSON byte array, or has the wrong length.
+pub fn load_keypair_from_file(path: impl AsRef<Path>) -> Result<Keypair> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read keypair file: {}", path.display()))?;
+    let bytes: Vec<u8> = serde_json::from_str(&contents)
+        .with_context(|| format!("Keypair file is not a JSON byte array: {}", path.display()))?;
+    keypair_from_file_bytes(&bytes).map_err(|e| anyhow::anyhow!("{} in {}", e, path.display()))
+}
+
+/// Builds a keypair from the bytes a Solana CLI keypair file holds: the full 64-byte
+/// keypair or just the 32-byte secret seed.
+///
+/// # Errors
+/// Returns an error if there are neither 64 nor 32 bytes, or they aren't a valid key.
+pub fn keypair_from_file_bytes(bytes: &[u8]) -> Result<Keypair> {
+    match bytes.len() {
+        64 => Keypair::from_bytes(bytes).map_err(|e| anyhow::anyhow!("Invalid keypair: {}", e)),
+        32 => keypair_from_seed(bytes).map_err(|e| anyhow::anyhow!("Invalid seed: {}", e)),
+        len => Err(anyhow::anyhow!("Keypair holds {} bytes; expected 64 (keypair) or 32 (seed)", len)),
+    }
+}
+
+/// Derives the keypair for `account_index` from a BIP39 seed phrase.
+///
+/// Uses the standard Solana path `m/44'/501'/{account_index}'/0'`, so account 0 matches what
+/// Phantom and `solana-keygen recover` produce for the same phrase and passphrase.
+///
+/// # Errors
+/// Returns an error if the phrase isn't a valid English BIP39 mnemonic.
+pub fn keypair_from_mnemonic(phrase: &str, passphrase: &str, account_index: u32) -> Result<Keypair> {
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|e| anyhow::anyhow!("Invalid seed phrase: {}", e))?;
+    let seed = Seed::new(&mnemonic, passphrase);
+    let path = DerivationPath::new_bip44(Some(account_index), Some(0));
+    keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(path))
+        .map_err(|e| anyhow::anyhow!("Failed to derive account {}: {}", account_index, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    fn write_temp(contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("keypair-{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_loads_full_keypair_and_seed_files() {
+        let keypair = Keypair::new();
+        let path = write_temp(&serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap());
+        assert_eq!(load_keypair_from_file(&path).unwrap().pubkey(), keypair.pubkey());
+        fs::remove_file(&path).unwrap();
+
+        // The seed is the first half of the keypair bytes
+        let path = write_temp(&serde_json::to_string(&keypair.to_bytes()[..32].to_vec()).unwrap());
+        assert_eq!(load_keypair_from_file(&path).unwrap().pubkey(), keypair.pubkey());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_wrong_length_and_malformed_files() {
+        let path = write_temp(&serde_json::to_string(&vec![1u8; 48]).unwrap());
+        let err = load_keypair_from_file(&path).unwrap_err();
+        assert!(err.to_string().contains("48 bytes"));
+        fs::remove_file(&path).unwrap();
+
+        let path = write_temp("[1, 2, 3,");
+        let err = load_keypair_from_file(&path).unwrap_err();
+        assert!(err.to_string().contains("JSON byte array"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(load_keypair_from_file("/nonexistent/id.json").is_err());
+    }
+
+    #[test]
+    fn test_mnemonic_derives_known_accounts() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let derived: Vec<String> = (0..3)
+            .map(|index| keypair_from_mnemonic(phrase, "", index).unwrap().pubkey().to_string())
+            .collect();
+        assert_eq!(
+            derived,
+            vec![
+                "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk",
+                "Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb",
+                "7WktogJEd2wQ9eH2oWusmcoFTgeYi6rS632UviTBJ2jm",
+            ]
+        );
+
+        // The passphrase changes every derived account
+        assert_eq!(
+            keypair_from_mnemonic(phrase, "secret", 0).unwrap().pubkey().to_string(),
+            "3UarjG9p9Z4vkbRyfkmtL37HVMskFKUtte7Ajw8nwVpc"
+        );
+
+        // A bad checksum word is rejected rather than silently deriving a different wallet
+        let typo = phrase.replace("about", "abandon");
+        assert!(keypair_from_mnemonic(&typo, "", 0).is_err());
+    }
+}
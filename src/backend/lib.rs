@@ -6,13 +6,35 @@
 // - Jito bundle submission for MEV-protected trading
 // - REST API server for frontend communication
 
+// solana_client's ClientError is large by design; boxing it at every RPC call site buys nothing
+#![allow(clippy::result_large_err)]
+
 pub mod api_server;
+pub mod bundle_queue;
+pub mod clock;
+pub mod config;
+pub mod deadline;
+pub mod discriminators;
+pub mod error;
+pub mod fee_autopilot;
+pub mod fees;
+pub mod idempotency;
 pub mod pump_fun;
+pub mod raydium;
 pub mod jito_bundle;
+pub mod keys;
+pub mod logging;
+pub mod metadata;
+pub mod price_stream;
+pub mod rpc;
+pub mod sequence;
+pub mod trade_log;
 pub mod types;
+pub mod wallet_store;
 
 // Re-export main components for easy access
 pub use api_server::start_api_server;
 pub use pump_fun::PumpFunClient;
 pub use jito_bundle::JitoBundleClient;
+pub use error::PumpFunError;
 pub use types::*; 
\ No newline at end of file
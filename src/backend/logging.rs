@@ -0,0 +1,142 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// What a `Secret` shows wherever it would otherwise be printed or serialized
+pub const REDACTED: &str = "[REDACTED]";
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json, // One JSON object per line
+}
+
+/// A string that never reaches a log line or serialized output.
+///
+/// `Debug` and `Serialize` both write `[REDACTED]`, so a request or config holding one can
+/// be logged whole. The value itself is only readable through `Deref`.
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl Deref for Secret {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Builds a subscriber writing `format` lines to `writer`, filtered by `RUST_LOG` (`info`
+/// when unset).
+pub fn subscriber<W>(format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(writer);
+    match format {
+        LogFormat::Text => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.json().flatten_event(true).finish()),
+    }
+}
+
+/// Sends this process's logs, including everything written through the `log` macros, to
+/// stderr in `format`.
+pub fn init(format: LogFormat) -> Result<()> {
+    tracing_log::LogTracer::init()?;
+    tracing::subscriber::set_global_default(subscriber(format, std::io::stderr))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::LaunchRequest;
+    use std::sync::{Arc, Mutex};
+
+    /// Collects log output in memory
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_log_of_a_buy_is_valid_json_without_the_private_key() {
+        let raw_key = bs58::encode(solana_sdk::signature::Keypair::new().to_bytes()).into_string();
+        // A launch buys from the listed wallets, and is the buy whose creator may send a raw key
+        let request: LaunchRequest = serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "Test Token",
+                "symbol": "TEST",
+                "description": "A test token",
+                "image_url": "https://example.com/image.png"
+            },
+            "user_id": 1,
+            "wallet_id": "w-000001",
+            "private_key": raw_key,
+            "buy_sol_amounts": [0.5],
+            "buy_wallet_ids": ["w-000002"]
+        }))
+        .unwrap();
+        assert_eq!(request.private_key.as_deref(), Some(raw_key.as_str()));
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        // Emitted through `tracing` directly: bridging the `log` macros would claim the
+        // process-wide logger other tests capture with
+        tracing::subscriber::with_default(subscriber(LogFormat::Json, move || writer.clone()), || {
+            tracing::info!("Buying tokens: {:?}", request);
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let line = output.lines().find(|line| line.contains("Buying tokens")).expect("no log line");
+        let event: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert!(event["message"].as_str().unwrap().contains(REDACTED));
+        assert!(!output.contains(&raw_key));
+
+        // Serializing the request hides the key too
+        assert!(!serde_json::to_string(&request).unwrap().contains(&raw_key));
+    }
+}
@@ -1,38 +1,113 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use log::{error, info};
-use serde::{Deserialize, Serialize};
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use log::{error, info, warn};
+use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
 use pump_swap_bot::*;
+use pump_swap_bot::config::Config;
+use pump_swap_bot::keys::load_keypair_from_file;
+use pump_swap_bot::metadata::{MetadataFetcher, MetadataUploader};
+use pump_swap_bot::rpc::RpcPool;
+use pump_swap_bot::wallet_store::WalletStore;
 use pump_swap_bot::api_server::start_api_server;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Path to config file
-    #[arg(short, long, default_value = "config/config.json")]
+    #[arg(short, long, default_value = "config/config.json", global = true)]
     config: String,
+
+    /// What to run; starts the API server when omitted
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum Command {
+    /// Run the API server
+    Serve,
+    /// Create a token directly, without the server
+    CreateToken(CreateTokenArgs),
+    /// Buy a token from one or more wallets
+    Buy(BuyArgs),
+    /// Sell a token from one or more wallets
+    Sell(SellArgs),
+    /// Quote a trade against a token's bonding curve
+    Quote(QuoteArgs),
+}
+
+#[derive(ClapArgs, Debug, PartialEq)]
+struct CreateTokenArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    symbol: String,
+    #[arg(long, default_value = "")]
+    description: String,
+    #[arg(long)]
+    image_url: String,
+    #[arg(long)]
+    telegram: Option<String>,
+    #[arg(long)]
+    twitter: Option<String>,
+    #[arg(long)]
+    website: Option<String>,
+    #[arg(long)]
+    discord: Option<String>,
+    /// Solana CLI keypair file for the creator; defaults to the config's `keypair_path`
+    #[arg(long)]
+    keypair: Option<String>,
+    /// Build and sign, but print the transaction instead of sending it
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(ClapArgs, Debug, PartialEq)]
+struct BuyArgs {
+    #[arg(long)]
+    mint: String,
+    /// SOL to spend per wallet, in the same order as --wallet
+    #[arg(long = "sol", required = true, num_args = 1..)]
+    sol_amounts: Vec<f64>,
+    #[arg(long = "wallet", required = true, num_args = 1..)]
+    wallet_ids: Vec<String>,
+    #[arg(long)]
+    via_bundle: bool,
+    #[arg(long)]
+    dry_run: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Config {
-    pub telegram_token: String,
-    pub solana_rpc_url: String,
-    pub jito_bundle_url: String,
-    pub pump_fun_program_id: String,
-    pub fee_address: String,
-    pub fee_percentage: f64,
-    pub min_sol_amount: f64,
-    pub jito_tip_amount: f64,
-    pub encryption_key: String,
+#[derive(ClapArgs, Debug, PartialEq)]
+struct SellArgs {
+    #[arg(long)]
+    mint: String,
+    /// Tokens to sell per wallet, in the same order as --wallet
+    #[arg(long = "tokens", required = true, num_args = 1..)]
+    token_amounts: Vec<u64>,
+    #[arg(long = "wallet", required = true, num_args = 1..)]
+    wallet_ids: Vec<String>,
+    #[arg(long)]
+    via_bundle: bool,
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(ClapArgs, Debug, PartialEq)]
+struct QuoteArgs {
+    #[arg(long)]
+    mint: String,
+    /// SOL to spend
+    #[arg(long, required_unless_present = "tokens", conflicts_with = "tokens")]
+    sol: Option<f64>,
+    /// Tokens to buy
+    #[arg(long)]
+    tokens: Option<f64>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    env_logger::init();
-
     // Parse command line arguments
     let args = Args::parse();
 
@@ -40,23 +115,266 @@ async fn main() -> Result<()> {
     let config_content = std::fs::read_to_string(&args.config)
         .with_context(|| format!("Failed to read config file: {}", args.config))?;
     let config: Config = serde_json::from_str(&config_content)?;
+    config.validate().with_context(|| format!("Invalid config file: {}", args.config))?;
+
+    // Initialize logging
+    logging::init(config.log_format)?;
+    if config.fee_percentage.is_some() {
+        warn!("fee_percentage is deprecated and ignored; trading fees come from fee_schedule");
+    }
 
     // Initialize components
-    let pump_fun_client = PumpFunClient::new(
+    let mut pump_fun_client = PumpFunClient::new(
         config.pump_fun_program_id.clone(),
         config.fee_address.clone(),
     );
+    pump_fun_client.config.min_sol_amount = config.min_sol_amount;
+    pump_fun_client.config.fee_splits = config.fee_splits.iter()
+        .map(|(recipient, share)| {
+            let recipient = Pubkey::from_str(recipient)
+                .with_context(|| format!("Invalid fee split recipient: {}", recipient))?;
+            Ok((recipient, *share))
+        })
+        .collect::<Result<_>>()?;
+    if let Some(fee_schedule) = &config.fee_schedule {
+        pump_fun_client.config.fee_schedule = fee_schedule.clone();
+    }
+    if let Some(share) = config.referral_fee_share {
+        pump_fun_client.config.referral_fee_share = share;
+    }
+    if let Some(slippage) = config.default_max_slippage_pct {
+        pump_fun_client.config.default_max_slippage_pct = slippage;
+    }
+    if let Some(max_wallets) = config.max_wallets_per_bundle {
+        pump_fun_client.config.max_wallets_per_bundle = max_wallets;
+    }
+    if let Some(max_concurrency) = config.max_concurrency {
+        pump_fun_client.config.max_concurrency = max_concurrency;
+    }
+    pump_fun_client.config.priority_fee_micro_lamports = config.priority_fee_micro_lamports;
+    if let Some(confirm_timeout_secs) = config.confirm_timeout_secs {
+        pump_fun_client.config.confirm_timeout_secs = confirm_timeout_secs;
+    }
+    if let Some(strategy) = config.confirmation_strategy {
+        pump_fun_client.config.confirmation_strategy = strategy;
+    }
+    if let Some(initial_supply) = config.initial_supply {
+        pump_fun_client.config.initial_supply = initial_supply;
+    }
+    if let Some(landing_probability) = config.priority_fee_landing_probability {
+        pump_fun_client.config.priority_fee_landing_probability = landing_probability;
+    }
+    if let Some(endpoint) = &config.ipfs_endpoint {
+        pump_fun_client.uploader = Some(MetadataUploader::new(
+            endpoint.clone(),
+            config.ipfs_api_key.as_deref().unwrap_or_default().to_string(),
+        ));
+    }
+    if let Some(gateway) = &config.ipfs_gateway {
+        pump_fun_client.metadata_fetcher = MetadataFetcher::new(gateway.clone());
+    }
+
+    match args.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(pump_fun_client, config).await,
+        command => run_once(command, &pump_fun_client, &config).await,
+    }
+}
 
+async fn serve(pump_fun_client: PumpFunClient, config: Config) -> Result<()> {
     info!("Starting Pump Swap Bot API Server...");
     info!("Solana RPC URL: {}", config.solana_rpc_url);
     info!("Pump.Fun Program ID: {}", config.pump_fun_program_id);
     info!("Jito Bundle URL: {}", config.jito_bundle_url);
 
+    let jito_client = JitoBundleClient::from_config(&config).await?;
+
     // Start API server
-    if let Err(e) = start_api_server(pump_fun_client).await {
+    if let Err(e) = start_api_server(pump_fun_client, jito_client, config).await {
         error!("API server error: {}", e);
         return Err(e.into());
     }
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Runs a one-off command against the configured RPC and prints its outcome.
+async fn run_once(command: Command, client: &PumpFunClient, config: &Config) -> Result<()> {
+    let rpc_client = RpcPool::from_config(config);
+    let jito_client = JitoBundleClient::from_config(config).await?;
+
+    let result = match command {
+        Command::Serve => unreachable!("serve is handled by the caller"),
+        Command::CreateToken(args) => {
+            let keypair_path = args
+                .keypair
+                .as_ref()
+                .or(config.keypair_path.as_ref())
+                .context("No keypair given; pass --keypair or set keypair_path in the config")?;
+            let creator = load_keypair_from_file(keypair_path)?;
+            let metadata = TokenMetadata {
+                name: args.name,
+                symbol: args.symbol,
+                description: args.description,
+                image_url: args.image_url,
+                telegram_link: args.telegram,
+                twitter_link: args.twitter,
+                website: args.website,
+                discord: args.discord,
+                extra_links: Default::default(),
+                uri: None,
+                decimals: 9,
+            };
+            client.create_token(metadata, &MintOptions::default(), &creator, None, &rpc_client, args.dry_run).await?
+        }
+        Command::Buy(args) => {
+            let request = BuyRequest {
+                token_address: args.mint,
+                sol_amounts: args.sol_amounts,
+                wallet_ids: args.wallet_ids.clone(),
+                user_id: 0,
+                min_tokens_out: None,
+                max_price_impact_bps: None,
+                dry_run: args.dry_run,
+                via_bundle: args.via_bundle,
+                strategy: None,
+                referrer: None,
+                max_total_sol: None,
+            };
+            let wallets = WalletStore::load(&config.wallet_store_path, &config.encryption_key)?;
+            let results = client.buy_tokens(request, &wallets, &rpc_client, Some(&jito_client)).await?;
+            return report_wallet_results("Buy", &args.wallet_ids, &results);
+        }
+        Command::Sell(args) => {
+            let request = SellRequest {
+                token_address: args.mint,
+                token_amounts: args.token_amounts,
+                ui_token_amounts: None,
+                wallet_ids: args.wallet_ids.clone(),
+                user_id: 0,
+                min_sol_out: None,
+                max_price_impact_bps: None,
+                dry_run: args.dry_run,
+                via_bundle: args.via_bundle,
+                strategy: None,
+                close_account: false,
+                referrer: None,
+            };
+            let wallets = WalletStore::load(&config.wallet_store_path, &config.encryption_key)?;
+            let results = client.sell_tokens(request, &wallets, &rpc_client, Some(&jito_client)).await?;
+            return report_wallet_results("Sell", &args.wallet_ids, &results);
+        }
+        Command::Quote(args) => {
+            let mint = Pubkey::from_str(&args.mint).context("Invalid token mint address")?;
+            let curve = client.get_bonding_curve_data(&mint, &rpc_client).await?;
+            let input = match (args.sol, args.tokens) {
+                (Some(sol), _) => QuoteInput::Sol(sol),
+                (None, Some(tokens)) => QuoteInput::Tokens(tokens),
+                (None, None) => unreachable!("clap requires one of --sol or --tokens"),
+            };
+            let quote = client.quote(input, &curve)?;
+            println!("{}", serde_json::to_string_pretty(&quote)?);
+            return Ok(());
+        }
+    };
+
+    if !result.success {
+        return Err(anyhow::anyhow!(result.error.unwrap_or_else(|| "Command failed".to_string())));
+    }
+    if let Some(serialized_tx) = &result.serialized_tx {
+        println!("{}", serialized_tx);
+    } else if let Some(signature) = result.signature.as_ref().or(result.bundle_id.as_ref()) {
+        println!("{}", signature);
+    }
+    Ok(())
+}
+
+/// Prints each wallet's outcome of a trade, failing only if no wallet traded.
+fn report_wallet_results(action: &str, wallet_ids: &[String], results: &[TransactionResult]) -> Result<()> {
+    for (wallet_id, result) in wallet_ids.iter().zip(results) {
+        // Bundled wallets report the bundle they landed in
+        match (&result.serialized_tx, result.signature.as_ref().or(result.bundle_id.as_ref()), &result.error) {
+            (Some(serialized_tx), _, _) => println!("{}: {}", wallet_id, serialized_tx),
+            (None, Some(signature), None) => println!("{}: {}", wallet_id, signature),
+            _ => eprintln!("{}: {}", wallet_id, result.error.as_deref().unwrap_or("Trade failed")),
+        }
+    }
+    if !results.iter().any(|result| result.success) {
+        return Err(anyhow::anyhow!("{} failed for every wallet", action));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from(std::iter::once("pump-swap-bot").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_serve_is_the_default() {
+        assert_eq!(parse(&[]).unwrap().command, None);
+        assert_eq!(parse(&["serve"]).unwrap().command, Some(Command::Serve));
+        assert_eq!(parse(&["serve", "--config", "other.json"]).unwrap().config, "other.json");
+    }
+
+    #[test]
+    fn test_create_token_args() {
+        let args = parse(&[
+            "create-token", "--name", "Test", "--symbol", "TST", "--image-url", "https://example.com/a.png",
+            "--keypair", "creator.json", "--dry-run",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::CreateToken(create)) => {
+                assert_eq!(create.name, "Test");
+                assert_eq!(create.symbol, "TST");
+                assert_eq!(create.keypair.as_deref(), Some("creator.json"));
+                assert_eq!(create.description, "");
+                assert!(create.dry_run);
+            }
+            other => panic!("expected create-token, got {:?}", other),
+        }
+
+        // Name, symbol and image are required; the keypair can come from the config instead
+        assert!(parse(&["create-token", "--name", "Test", "--symbol", "TST", "--image-url", "x"]).is_ok());
+        assert!(parse(&["create-token", "--name", "Test", "--image-url", "x", "--keypair", "k.json"]).is_err());
+    }
+
+    #[test]
+    fn test_buy_and_sell_args() {
+        let args = parse(&["buy", "--mint", "M", "--sol", "0.1", "0.2", "--wallet", "w1", "w2"]).unwrap();
+        match args.command {
+            Some(Command::Buy(buy)) => {
+                assert_eq!(buy.sol_amounts, vec![0.1, 0.2]);
+                assert_eq!(buy.wallet_ids, vec!["w1", "w2"]);
+                assert!(!buy.via_bundle);
+            }
+            other => panic!("expected buy, got {:?}", other),
+        }
+        assert!(parse(&["buy", "--mint", "M", "--wallet", "w1"]).is_err());
+
+        let args = parse(&["sell", "--mint", "M", "--tokens", "1000", "--wallet", "w1", "--via-bundle"]).unwrap();
+        match args.command {
+            Some(Command::Sell(sell)) => {
+                assert_eq!(sell.token_amounts, vec![1000]);
+                assert!(sell.via_bundle);
+            }
+            other => panic!("expected sell, got {:?}", other),
+        }
+        assert!(parse(&["sell", "--tokens", "1000", "--wallet", "w1"]).is_err());
+        assert!(parse(&["sell", "--mint", "M", "--tokens", "-5", "--wallet", "w1"]).is_err());
+    }
+
+    #[test]
+    fn test_quote_takes_exactly_one_amount() {
+        let args = parse(&["quote", "--mint", "M", "--sol", "1.5"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Quote(QuoteArgs { mint: "M".to_string(), sol: Some(1.5), tokens: None }))
+        );
+        assert!(parse(&["quote", "--mint", "M"]).is_err());
+        assert!(parse(&["quote", "--mint", "M", "--sol", "1", "--tokens", "5"]).is_err());
+    }
+}
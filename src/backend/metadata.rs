@@ -0,0 +1,532 @@
+use anyhow::{Context, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use log::{error, info};
+use reqwest::Client;
+use serde::Deserialize;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use std::fmt;
+use std::time::Duration;
+
+use crate::types::TokenMetadata;
+
+/// The Metaplex Token Metadata program, which owns each mint's metadata account
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Account key Metaplex tags metadata accounts with
+const METADATA_V1_KEY: u8 = 4;
+
+/// Index of `CreateMetadataAccountV3` among the Token Metadata program's instructions
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+/// Longest name, symbol and URI a Metaplex metadata account holds, in bytes
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+
+/// Size of a Metaplex metadata account, which its payer funds the rent for
+pub const METADATA_ACCOUNT_LEN: usize = 679;
+
+/// Gateway `ipfs://` metadata URIs are fetched through unless configured otherwise
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
+
+/// Error returned when pinning an image or metadata JSON fails.
+///
+/// Kept distinct from validation failures so callers can tell a bad request
+/// from an unavailable pinning provider.
+#[derive(Debug, Clone)]
+pub struct UploadError(pub String);
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Metadata upload failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+/// Error returned when a token's off-chain metadata JSON can't be fetched or read.
+///
+/// Kept distinct from a missing metadata account so callers can tell a token without
+/// metadata from an unavailable host.
+#[derive(Debug, Clone)]
+pub struct FetchError(pub String);
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Metadata fetch failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Address of a mint's Metaplex metadata account.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    ).0
+}
+
+/// Arguments of Metaplex's `CreateMetadataAccountV3`
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct CreateMetadataAccountArgsV3 {
+    pub data: DataV2,
+    pub is_mutable: bool,
+    pub collection_details: Option<CollectionDetails>,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct DataV2 {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub collection: Option<Collection>,
+    pub uses: Option<Uses>,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct Creator {
+    pub address: [u8; 32],
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct Collection {
+    pub verified: bool,
+    pub key: [u8; 32],
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct Uses {
+    pub use_method: u8,
+    pub remaining: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub(crate) enum CollectionDetails {
+    V1 { size: u64 },
+}
+
+/// Builds Metaplex's `CreateMetadataAccountV3` for `mint`, so wallets show its name and symbol.
+///
+/// `creator` is the mint authority, payer, update authority and sole verified creator, and
+/// must sign. The account stays mutable so the creator can correct it later.
+///
+/// # Errors
+/// Returns an error if the name, symbol or URI is longer than Metaplex stores.
+pub fn create_metadata_account_v3(
+    mint: &Pubkey,
+    creator: &Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Result<Instruction> {
+    let fields = [("name", name, MAX_NAME_LENGTH), ("symbol", symbol, MAX_SYMBOL_LENGTH), ("URI", uri, MAX_URI_LENGTH)];
+    for (field, value, max) in fields {
+        if value.len() > max {
+            anyhow::bail!("Metadata {} is {} bytes; Metaplex stores at most {}", field, value.len(), max);
+        }
+    }
+
+    let args = CreateMetadataAccountArgsV3 {
+        data: DataV2 {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+            seller_fee_basis_points: 0,
+            creators: Some(vec![Creator { address: creator.to_bytes(), verified: true, share: 100 }]),
+            collection: None,
+            uses: None,
+        },
+        is_mutable: true,
+        collection_details: None,
+    };
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+    data.extend(borsh::to_vec(&args).context("Failed to serialize metadata account")?);
+
+    Ok(Instruction {
+        program_id: TOKEN_METADATA_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(metadata_address(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*creator, true), // Mint authority
+            AccountMeta::new(*creator, true),          // Payer
+            AccountMeta::new_readonly(*creator, true), // Update authority
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    })
+}
+
+/// The leading fields of a Metaplex metadata account, which is all a lookup reads
+#[derive(BorshDeserialize)]
+struct MetaplexMetadata {
+    key: u8,
+    update_authority: [u8; 32],
+    mint: [u8; 32],
+    name: String,
+    symbol: String,
+    uri: String,
+    _seller_fee_basis_points: u16,
+    creators: Option<Vec<MetaplexCreator>>,
+}
+
+#[derive(BorshDeserialize)]
+struct MetaplexCreator {
+    address: [u8; 32],
+    verified: bool,
+    _share: u8,
+}
+
+/// What a mint's Metaplex metadata account records on-chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnChainMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub creator: Pubkey, // First verified creator, else the update authority
+}
+
+impl OnChainMetadata {
+    /// Decodes `mint`'s Metaplex metadata account, stripping the padding Metaplex stores
+    /// its fixed-width strings with.
+    pub fn decode(data: &[u8], mint: &Pubkey) -> Result<Self> {
+        let metadata = MetaplexMetadata::deserialize(&mut &data[..])
+            .context("Invalid metadata account")?;
+        if metadata.key != METADATA_V1_KEY {
+            anyhow::bail!("Invalid metadata account: unexpected key {}", metadata.key);
+        }
+        if metadata.mint != mint.to_bytes() {
+            anyhow::bail!("Invalid metadata account: describes mint {}", Pubkey::new_from_array(metadata.mint));
+        }
+
+        let creator = metadata.creators.iter().flatten()
+            .find(|creator| creator.verified)
+            .map_or(metadata.update_authority, |creator| creator.address);
+        let unpad = |field: String| field.trim_end_matches('\0').to_string();
+        Ok(Self {
+            name: unpad(metadata.name),
+            symbol: unpad(metadata.symbol),
+            uri: unpad(metadata.uri),
+            creator: Pubkey::new_from_array(creator),
+        })
+    }
+}
+
+/// The fields read from a token's off-chain metadata JSON
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OffChainMetadata {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub telegram: Option<String>,
+    #[serde(default)]
+    pub twitter: Option<String>,
+}
+
+/// Fetches the off-chain metadata JSON tokens point at, resolving `ipfs://` URIs
+/// through a gateway
+#[derive(Clone)]
+pub struct MetadataFetcher {
+    client: Client,
+    ipfs_gateway: String,
+}
+
+impl MetadataFetcher {
+    pub fn new(ipfs_gateway: String) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            ipfs_gateway: format!("{}/", ipfs_gateway.trim_end_matches('/')),
+        }
+    }
+
+    /// The HTTP URL a metadata URI is fetched from.
+    pub fn resolve(&self, uri: &str) -> String {
+        match uri.strip_prefix("ipfs://") {
+            Some(path) => format!("{}{}", self.ipfs_gateway, path.trim_start_matches("ipfs/")),
+            None => uri.to_string(),
+        }
+    }
+
+    /// Fetches and parses the metadata JSON at `uri`.
+    ///
+    /// # Errors
+    /// Returns a `FetchError` if the host is unreachable, answers with an error status, or
+    /// serves something other than a metadata JSON object.
+    pub async fn fetch_json(&self, uri: &str) -> Result<OffChainMetadata> {
+        let url = self.resolve(uri);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| FetchError(format!("{}: {}", url, e)))?;
+
+        if !response.status().is_success() {
+            return Err(FetchError(format!("{} returned {}", url, response.status())).into());
+        }
+
+        let metadata = response
+            .json()
+            .await
+            .map_err(|e| FetchError(format!("Invalid metadata JSON at {}: {}", url, e)))?;
+        Ok(metadata)
+    }
+}
+
+impl Default for MetadataFetcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_IPFS_GATEWAY.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PinResponse {
+    cid: String,
+}
+
+/// Pins token images and metadata JSON to an IPFS pinning provider
+#[derive(Clone)]
+pub struct MetadataUploader {
+    client: Client,
+    endpoint: String,
+    api_key: String,
+}
+
+impl MetadataUploader {
+    pub fn new(endpoint: String, api_key: String) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Pins raw image bytes and returns the resulting `ipfs://` URI.
+    ///
+    /// # Arguments
+    /// * `bytes` - The image contents.
+    /// * `mime` - The image MIME type, e.g. `image/png`.
+    pub async fn upload_image(&self, bytes: Vec<u8>, mime: &str) -> Result<String> {
+        if !mime.starts_with("image/") {
+            return Err(UploadError(format!("Unsupported image type: {}", mime)).into());
+        }
+        info!("Uploading {} byte image ({})", bytes.len(), mime);
+
+        let request = self
+            .client
+            .post(format!("{}/upload", self.endpoint))
+            .header(reqwest::header::CONTENT_TYPE, mime)
+            .body(bytes);
+        self.pin(request).await
+    }
+
+    /// Pins the off-chain metadata JSON for a token and returns the resulting `ipfs://` URI.
+    ///
+    /// # Arguments
+    /// * `metadata` - The token metadata; `image_url` should already point at the pinned image.
+    pub async fn upload_metadata_json(&self, metadata: &TokenMetadata) -> Result<String> {
+        let body = serde_json::json!({
+            "name": metadata.name,
+            "symbol": metadata.symbol,
+            "description": metadata.description,
+            "image": metadata.image_url,
+            "telegram": metadata.telegram_link,
+            "twitter": metadata.twitter_link,
+            "website": metadata.website,
+            "discord": metadata.discord,
+            "extra_links": metadata.extra_links,
+        });
+
+        let request = self
+            .client
+            .post(format!("{}/json", self.endpoint))
+            .json(&body);
+        self.pin(request).await
+    }
+
+    async fn pin(&self, request: reqwest::RequestBuilder) -> Result<String> {
+        let response = request
+            .bearer_auth(&self.api_key)
+            .send()
+            .await
+            .map_err(|e| UploadError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            error!("Pinning failed ({}): {}", status, error_text);
+            return Err(UploadError(format!("{}: {}", status, error_text)).into());
+        }
+
+        let pinned: PinResponse = response
+            .json()
+            .await
+            .map_err(|e| UploadError(format!("Invalid pinning response: {}", e)))?;
+
+        Ok(format!("ipfs://{}", pinned.cid))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+
+    /// Starts a local pinning server; `fail` makes every upload return 500.
+    pub(crate) async fn start_mock_pinning_server(fail: bool) -> String {
+        let server = HttpServer::new(move || {
+            App::new().default_service(web::to(move |req: HttpRequest, body: web::Bytes| async move {
+                if fail {
+                    return HttpResponse::InternalServerError().body("pinning backend down");
+                }
+                let authorized = req
+                    .headers()
+                    .get("Authorization")
+                    .and_then(|value| value.to_str().ok())
+                    == Some("Bearer test-key");
+                if !authorized {
+                    return HttpResponse::Unauthorized().finish();
+                }
+                let cid = format!("cid-{}-{}", req.path().trim_start_matches('/'), body.len());
+                HttpResponse::Ok().json(serde_json::json!({ "cid": cid }))
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        format!("http://{}", addr)
+    }
+
+    /// Starts a local metadata host serving `body` at every path, or 404s when it's `None`.
+    pub(crate) fn start_mock_metadata_host(body: Option<serde_json::Value>) -> String {
+        let server = HttpServer::new(move || {
+            let body = body.clone();
+            App::new().default_service(web::to(move || {
+                let body = body.clone();
+                async move {
+                    match body {
+                        Some(body) => HttpResponse::Ok().json(body),
+                        None => HttpResponse::NotFound().finish(),
+                    }
+                }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let addr = server.addrs()[0];
+        tokio::spawn(server.run());
+        format!("http://{}", addr)
+    }
+
+    /// A Metaplex metadata account for `mint`, with its strings padded as Metaplex pads them.
+    pub(crate) fn metaplex_account_bytes(mint: &Pubkey, name: &str, symbol: &str, uri: &str, creator: &Pubkey) -> Vec<u8> {
+        let pad = |field: &str, width: usize| format!("{:\0<width$}", field, width = width);
+        let mut data = borsh::to_vec(&(
+            METADATA_V1_KEY,
+            Pubkey::new_unique().to_bytes(),
+            mint.to_bytes(),
+            pad(name, 32),
+            pad(symbol, 10),
+            pad(uri, 200),
+            500u16,
+            Some(vec![(creator.to_bytes(), true, 100u8)]),
+        ))
+        .unwrap();
+        // Fields a lookup doesn't read follow
+        data.extend_from_slice(&[1, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_decode_metaplex_metadata() {
+        let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = metaplex_account_bytes(&mint, "Test Token", "TEST", "ipfs://cid-json", &creator);
+
+        let metadata = OnChainMetadata::decode(&data, &mint).unwrap();
+        assert_eq!(metadata, OnChainMetadata {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            uri: "ipfs://cid-json".to_string(),
+            creator,
+        });
+
+        // Another mint's metadata, or a truncated account, is refused
+        assert!(OnChainMetadata::decode(&data, &Pubkey::new_unique()).is_err());
+        assert!(OnChainMetadata::decode(&data[..40], &mint).is_err());
+
+        let fetcher = MetadataFetcher::new("https://gateway.test/ipfs".to_string());
+        assert_eq!(fetcher.resolve("ipfs://cid-json"), "https://gateway.test/ipfs/cid-json");
+        assert_eq!(fetcher.resolve("ipfs://ipfs/cid-json"), "https://gateway.test/ipfs/cid-json");
+        assert_eq!(fetcher.resolve("https://host.test/m.json"), "https://host.test/m.json");
+    }
+
+    fn test_metadata() -> TokenMetadata {
+        TokenMetadata {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            description: "A test token".to_string(),
+            image_url: "ipfs://cid-upload-4".to_string(),
+            telegram_link: Some("https://t.me/test".to_string()),
+            twitter_link: Some("https://twitter.com/test".to_string()),
+            website: None,
+            discord: None,
+            extra_links: Default::default(),
+            uri: None,
+            decimals: 9,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_upload_image_and_metadata() {
+        let endpoint = start_mock_pinning_server(false).await;
+        let uploader = MetadataUploader::new(endpoint, "test-key".to_string());
+
+        let image_uri = uploader.upload_image(vec![1, 2, 3, 4], "image/png").await.unwrap();
+        assert_eq!(image_uri, "ipfs://cid-upload-4");
+
+        let metadata_uri = uploader.upload_metadata_json(&test_metadata()).await.unwrap();
+        assert!(metadata_uri.starts_with("ipfs://cid-json-"));
+    }
+
+    #[actix_web::test]
+    async fn test_upload_failures_are_upload_errors() {
+        let endpoint = start_mock_pinning_server(true).await;
+        let uploader = MetadataUploader::new(endpoint.clone(), "test-key".to_string());
+
+        let err = uploader.upload_image(vec![1, 2, 3], "image/png").await.unwrap_err();
+        assert!(err.downcast_ref::<UploadError>().is_some());
+
+        // A rejected API key is also an upload failure
+        let healthy = start_mock_pinning_server(false).await;
+        let uploader = MetadataUploader::new(healthy, "wrong-key".to_string());
+        let err = uploader.upload_metadata_json(&test_metadata()).await.unwrap_err();
+        assert!(err.downcast_ref::<UploadError>().is_some());
+
+        // Non-image payloads are refused before any network call
+        let err = uploader.upload_image(vec![0], "text/plain").await.unwrap_err();
+        assert!(err.downcast_ref::<UploadError>().is_some());
+    }
+}
@@ -0,0 +1,225 @@
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{Codec, Frame, Message};
+use actix_web::web::{self, Bytes, BytesMut};
+use actix_web::{HttpRequest, HttpResponse};
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::api_server::ApiState;
+use crate::pump_fun::parse_bonding_curve;
+use crate::types::BondingCurveData;
+
+/// Longest an unsubscribe waits for the RPC node to acknowledge it
+const UNSUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where live curve updates come from
+#[derive(Debug, Clone)]
+pub struct PriceFeed {
+    pub ws_url: Option<String>, // RPC websocket to subscribe through; the curve is polled when unset
+    pub poll_interval: Duration,
+}
+
+/// One price pushed to a `/ws/token/{mint}/price` subscriber
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceUpdate {
+    pub mint: String,
+    pub price: f64, // SOL per token
+    pub sol_reserve: f64,
+    pub token_reserve: f64,
+    pub complete: bool,
+    pub slot: Option<u64>, // Slot the curve was read at, when the source reports it
+}
+
+impl PriceUpdate {
+    fn from_curve(curve: &BondingCurveData, slot: Option<u64>) -> Self {
+        Self {
+            mint: curve.token_address.clone(),
+            price: curve.current_price,
+            sol_reserve: curve.sol_reserve,
+            token_reserve: curve.token_reserve,
+            complete: curve.complete,
+            slot,
+        }
+    }
+}
+
+/// The server end of a WebSocket: frames out through the response body, frames in from
+/// the request payload.
+struct Socket {
+    outgoing: mpsc::Sender<Bytes>,
+    incoming: web::Payload,
+    codec: Codec,
+    buffer: BytesMut,
+}
+
+impl Socket {
+    /// Sends a frame, returning false once the client has gone.
+    async fn send(&mut self, message: Message) -> bool {
+        let mut frame = BytesMut::new();
+        if self.codec.encode(message, &mut frame).is_err() {
+            return false;
+        }
+        self.outgoing.send(frame.freeze()).await.is_ok()
+    }
+
+    /// Resolves once the client closes the socket or disconnects, answering pings meanwhile.
+    async fn closed(&mut self) {
+        loop {
+            match self.codec.decode(&mut self.buffer) {
+                Ok(Some(Frame::Ping(payload))) => {
+                    if !self.send(Message::Pong(payload)).await {
+                        return;
+                    }
+                    continue;
+                }
+                Ok(Some(Frame::Close(reason))) => {
+                    self.send(Message::Close(reason)).await;
+                    return;
+                }
+                Ok(Some(_)) => continue,
+                Ok(None) => {}
+                Err(_) => return,
+            }
+            match self.incoming.next().await {
+                Some(Ok(chunk)) => self.buffer.extend_from_slice(&chunk),
+                _ => return,
+            }
+        }
+    }
+}
+
+/// Upgrades the request to a WebSocket that pushes the curve's price on every change.
+///
+/// With an RPC websocket configured the curve account is subscribed to, otherwise it is
+/// polled. The subscription is dropped as soon as the client disconnects.
+pub fn serve(
+    req: &HttpRequest,
+    payload: web::Payload,
+    token_mint: Pubkey,
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut handshake = actix_http::ws::handshake(req.head())?;
+    let (outgoing, frames) = mpsc::channel::<Bytes>(16);
+    let body = futures::stream::unfold(frames, |mut frames| async move {
+        frames.recv().await.map(|frame| (Ok::<_, actix_web::Error>(frame), frames))
+    });
+    let response = HttpResponse::from(handshake.message_body(actix_web::body::BodyStream::new(body))?);
+
+    let mut socket = Socket { outgoing, incoming: payload, codec: Codec::new(), buffer: BytesMut::new() };
+    // The request payload is tied to this worker, so the stream runs on it too
+    actix_web::rt::spawn(async move {
+        let feed = state.price_feed.clone();
+        let streamed = match &feed.ws_url {
+            Some(ws_url) => stream_subscribed(ws_url, &token_mint, &state, &mut socket).await,
+            None => stream_polled(feed.poll_interval, token_mint, &state, &mut socket).await,
+        };
+        if let Err(e) = streamed {
+            warn!("Price stream for {} ended: {:#}", token_mint, e);
+            socket.send(Message::Close(None)).await;
+        }
+    });
+
+    Ok(response.map_into_boxed_body())
+}
+
+/// Pushes a price for every change the RPC websocket reports to the curve account.
+async fn stream_subscribed(ws_url: &str, token_mint: &Pubkey, state: &ApiState, socket: &mut Socket) -> Result<()> {
+    let curve_address = state.pump_fun_client.bonding_curve_address(token_mint);
+    let pubsub = PubsubClient::new(ws_url).await.context("Failed to connect to the RPC websocket")?;
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(state.rpc_client.commitment()),
+        ..RpcAccountInfoConfig::default()
+    };
+    let (mut updates, unsubscribe) = pubsub.account_subscribe(&curve_address, Some(config))
+        .await
+        .context("Failed to subscribe to the bonding curve")?;
+    info!("Streaming prices for {} from account updates", token_mint);
+
+    let streamed = loop {
+        tokio::select! {
+            update = updates.next() => {
+                let Some(update) = update else {
+                    break Err(anyhow::anyhow!("RPC websocket closed the subscription"));
+                };
+                let account: Account = match update.value.decode() {
+                    Some(account) => account,
+                    None => continue,
+                };
+                let curve = match parse_bonding_curve(token_mint, &account.data) {
+                    Ok(curve) => curve,
+                    Err(e) => break Err(e),
+                };
+                if !send_update(socket, &PriceUpdate::from_curve(&curve, Some(update.context.slot))).await {
+                    break Ok(());
+                }
+            }
+            _ = socket.closed() => break Ok(()),
+        }
+    };
+
+    drop(updates);
+    if tokio::time::timeout(UNSUBSCRIBE_TIMEOUT, unsubscribe()).await.is_err() {
+        warn!("RPC websocket didn't acknowledge unsubscribing from {}", curve_address);
+    }
+    let _ = pubsub.shutdown().await;
+    streamed
+}
+
+/// Polls the curve, pushing a price whenever it has changed since the last one.
+async fn stream_polled(
+    interval: Duration,
+    token_mint: Pubkey,
+    state: &web::Data<ApiState>,
+    socket: &mut Socket,
+) -> Result<()> {
+    info!("Streaming prices for {} by polling every {:?}", token_mint, interval);
+    let mut ticker = tokio::time::interval(interval);
+    let mut last = None;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                // RPC calls block, so they run off the worker
+                let state = state.clone();
+                let curve = tokio::task::spawn_blocking(move || {
+                    let curve_address = state.pump_fun_client.bonding_curve_address(&token_mint);
+                    let data = state.rpc_client.get_account_data(&curve_address)?;
+                    parse_bonding_curve(&token_mint, &data)
+                })
+                .await?;
+                let update = match curve {
+                    Ok(curve) => PriceUpdate::from_curve(&curve, None),
+                    Err(e) => {
+                        warn!("Failed to poll the bonding curve of {}: {:#}", token_mint, e);
+                        continue;
+                    }
+                };
+                if last.as_ref() != Some(&update) {
+                    if !send_update(socket, &update).await {
+                        return Ok(());
+                    }
+                    last = Some(update);
+                }
+            }
+            _ = socket.closed() => return Ok(()),
+        }
+    }
+}
+
+/// Sends one price frame, returning false once the client has gone.
+async fn send_update(socket: &mut Socket, update: &PriceUpdate) -> bool {
+    match serde_json::to_string(update) {
+        Ok(text) => socket.send(Message::Text(text.into())).await,
+        Err(_) => false,
+    }
+}
@@ -1,26 +1,83 @@
 use anyhow::{Context, Result};
-use log::{info, warn, error};
-use serde::{Deserialize, Serialize};
+use base64::Engine;
+use log::{error, info, warn};
 use borsh::{BorshSerialize, BorshDeserialize};
-use solana_client::rpc_client::RpcClient;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcSimulateTransactionConfig};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::Response;
 use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
     instruction::{AccountMeta, Instruction},
+    message::Message,
+    nonce::state::{State as NonceState, Versions as NonceVersions},
+    packet::PACKET_DATA_SIZE,
+    program_pack::Pack,
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    signature::{Keypair, Signature, Signer},
     system_instruction,
-    transaction::Transaction,
-    commitment_config::CommitmentConfig,
+    transaction::{uses_durable_nonce, Transaction},
 };
-use spl_associated_token_account::get_associated_token_address;
-use spl_token;
+use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id};
+use rand::Rng;
+use std::borrow::Cow;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::clock::{Clock, TokioClock};
+use crate::deadline::Deadline;
+use crate::discriminators::{self, Discriminator};
+use crate::error::PumpFunError;
+use crate::fee_autopilot::FeeAutopilot;
+use crate::fees::{split_fee, validate_fee_splits};
+use crate::jito_bundle::{tip_transfer, BundleBuilder, BundleLanding, BundleStep, JitoBundleClient};
+use crate::metadata::{self, MetadataFetcher, MetadataUploader, OffChainMetadata, OnChainMetadata};
+use crate::raydium;
+use crate::rpc::{is_blockhash_not_found, join_bounded, retry_rpc, BlockhashCache, RentCache, RpcPool, SlotTracker};
 use crate::types::*;
+use crate::wallet_store::WalletStore;
+
+/// Highest number of decimals a token may be created with
+pub const MAX_DECIMALS: u8 = 9;
+
+/// Decimals of every token launched on Pump.Fun
+pub const PUMP_TOKEN_DECIMALS: u8 = 6;
+
+/// PDA seed of a mint's bonding curve account
+const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
+
+/// The Token-2022 program, whose mints are tradable alongside SPL Token mints
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Most compute units a transaction may request
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// How often to check whether a sent transaction has confirmed
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How many times a durable nonce transaction is resent when it hasn't confirmed in time
+const MAX_NONCE_RESENDS: usize = 2;
+
+/// Longest description a token may have
+const MAX_DESCRIPTION_LEN: usize = 200;
+
+/// Description length past which it's likely cut short in wallets and explorers
+const LONG_DESCRIPTION_LEN: usize = 180;
 
 /// Pump.Fun client for creating and trading tokens
 pub struct PumpFunClient {
     pub program_id: Pubkey,
     pub fee_address: Pubkey,
     pub config: PumpFunConfig,
+    pub blockhash_cache: BlockhashCache,
+    pub uploader: Option<MetadataUploader>,
+    pub metadata_fetcher: MetadataFetcher,
+    pub slot_tracker: SlotTracker,
+    pub rent_cache: RentCache,
+    pub fee_autopilot: Option<Arc<FeeAutopilot>>, // Prices compute units from recent fees when set
+    pub clock: Arc<dyn Clock>, // Times the delay before a launch's dev buy
 }
 
 impl PumpFunClient {
@@ -30,18 +87,26 @@ impl PumpFunClient {
         let fee_address = Pubkey::from_str(&fee_address)
             .expect("Invalid fee address");
         
+        let config = PumpFunConfig {
+            program_id: program_id.to_string(),
+            fee_address: fee_address.to_string(),
+            creation_fee: 0.01,
+            min_sol_amount: 0.02,
+            ..PumpFunConfig::default()
+        };
+        let blockhash_cache = BlockhashCache::new(Duration::from_secs(config.blockhash_cache_ttl_secs));
+
         Self {
             program_id,
             fee_address,
-            config: PumpFunConfig {
-                program_id: program_id.to_string(),
-                fee_address: fee_address.to_string(),
-                creation_fee: 0.01,
-                trading_fee: 0.005,
-                fee_percentage: 0.008,
-                min_sol_amount: 0.02,
-                max_wallets_per_bundle: 10,
-            },
+            config,
+            blockhash_cache,
+            uploader: None,
+            metadata_fetcher: MetadataFetcher::default(),
+            slot_tracker: SlotTracker::new(),
+            rent_cache: RentCache::new(),
+            fee_autopilot: None,
+            clock: Arc::new(TokioClock),
         }
     }
 
@@ -50,294 +115,1354 @@ impl PumpFunClient {
     /// # Arguments
     /// * `metadata` - The token metadata (name, symbol, description, image URL).
     /// * `creator_keypair` - The keypair of the token creator.
+    /// * `nonce_account` - A durable nonce account the creator is authority of. The transaction
+    ///   then advances the nonce instead of using a recent blockhash, so it never expires and
+    ///   can be resent until it lands.
     /// * `rpc_client` - The Solana RPC client for blockchain interaction.
+    /// * `dry_run` - Build and sign the transaction but return it instead of submitting it.
     /// 
     /// # Returns
     /// A `Result` containing a `TransactionResult` with the transaction signature and fee details.
     /// 
     /// # Errors
-    /// Returns an error if metadata validation fails, the transaction cannot be signed, or the RPC call fails.
+    /// Returns an error if metadata validation fails, the transaction cannot be signed, or the RPC call fails,
+    /// and `PumpFunError::InvalidNonceAccount` if the nonce account can't be used.
     pub async fn create_token(
         &self,
         metadata: TokenMetadata,
+        mint_options: &MintOptions,
         creator_keypair: &Keypair,
-        rpc_client: &RpcClient,
+        nonce_account: Option<&Pubkey>,
+        rpc_client: &RpcPool,
+        dry_run: bool,
     ) -> Result<TransactionResult> {
         info!("Creating token with metadata: {:?}", metadata);
 
         // Validate metadata
         let mut validation = ValidationResult::new();
         self.validate_token_metadata(&metadata, &mut validation);
+        self.validate_mint_options(mint_options, &mut validation);
         
         if !validation.is_valid {
-            return Ok(TransactionResult {
-                success: false,
-                signature: None,
-                bundle_id: None,
-                error: Some(validation.errors.join(", ")),
-                fee_paid: None,
-            });
+            return Ok(TransactionResult::failure(validation.errors.join(", ")));
         }
 
         // Check creator balance
-        let balance = rpc_client
-            .get_balance(&creator_keypair.pubkey())
-            .context("Failed to get creator balance")?;
+        let creator = creator_keypair.pubkey();
+        let balance = retry_rpc(
+            || rpc_client.nonblocking().get_balance(&creator),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get creator balance")?;
         
-        let required_balance = (self.config.creation_fee * 1e9) as u64 + 1000000; // 1 SOL buffer
+        let (_, mint_len) = mint_account_len(&metadata, mint_options)?;
+        let mint_rent = self.rent_exempt_minimum(mint_len, rpc_client).await?;
+        let ata_rent = self.rent_exempt_minimum(token_account_len(mint_options)?, rpc_client).await?;
+        let metadata_rent = if mint_options.extensions.contains(&MintExtension::Metadata) {
+            0
+        } else {
+            self.rent_exempt_minimum(metadata::METADATA_ACCOUNT_LEN, rpc_client).await?
+        };
+        // The mint, the creator and program ATAs, and any Metaplex metadata account
+        let required_balance = self.required_creation_balance(mint_rent + 2 * ata_rent + metadata_rent);
         
         if balance < required_balance {
-            return Ok(TransactionResult {
-                success: false,
-                signature: None,
-                bundle_id: None,
-                error: Some(format!(
-                    "Insufficient balance. Required: {} SOL, Available: {} SOL",
-                    required_balance as f64 / 1e9,
-                    balance as f64 / 1e9
-                )),
-                fee_paid: None,
-            });
+            return Ok(TransactionResult::failure(format!(
+                "Insufficient balance. Required: {} SOL, Available: {} SOL",
+                required_balance as f64 / 1e9,
+                balance as f64 / 1e9
+            )));
         }
 
         // Create token mint
         let token_mint = Keypair::new();
         let token_mint_pubkey = token_mint.pubkey();
 
+        // Build instructions; a nonce advance must come first for the runtime to honour it
+        let mut instructions = Vec::new();
+        if let Some(nonce_account) = nonce_account {
+            instructions.push(system_instruction::advance_nonce_account(nonce_account, &creator_keypair.pubkey()));
+        }
+        instructions.extend(self.compute_budget_instructions());
+        instructions.extend(self.build_create_instructions(
+            &metadata,
+            mint_options,
+            &creator_keypair.pubkey(),
+            &token_mint_pubkey,
+            mint_rent,
+        )?);
+
+        // Build and sign transaction
+        let recent_blockhash = match nonce_account {
+            Some(nonce_account) => self.nonce_blockhash(nonce_account, &creator_keypair.pubkey(), rpc_client).await?,
+            None => self.latest_blockhash(rpc_client).await?,
+        };
+        
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&creator_keypair.pubkey()));
+        transaction.sign(&[creator_keypair, &token_mint], recent_blockhash);
+        self.tighten_compute_limits(std::slice::from_mut(&mut transaction), &[creator_keypair, &token_mint], rpc_client)
+            .await?;
+
+        if dry_run {
+            let mut result = self.dry_run_result(std::slice::from_ref(&transaction), self.config.creation_fee)?;
+            result.mint = Some(token_mint_pubkey.to_string());
+            return Ok(result);
+        }
+
+        // Send transaction
+        let (signature, confirmation) = self.send_transaction(&transaction, &[creator_keypair, &token_mint], rpc_client)
+            .await
+            .context("Failed to send transaction")?;
+
+        info!("Token created successfully: {}", token_mint_pubkey);
+        Ok(TransactionResult {
+            success: true,
+            signature: Some(signature.to_string()),
+            bundle_id: None,
+            error: None,
+            fee_paid: Some(self.config.creation_fee),
+            serialized_tx: None,
+            confirmation: Some(confirmation),
+            mint: Some(token_mint_pubkey.to_string()),
+            signatures: Vec::new(),
+        })
+    }
+
+    /// The lamports a creator needs to create a token: the creation fee, the rent for the
+    /// accounts being created, and the configured buffer for transaction fees.
+    pub fn required_creation_balance(&self, rent_lamports: u64) -> u64 {
+        (self.config.creation_fee * 1e9) as u64 + rent_lamports + self.config.balance_buffer_lamports
+    }
+
+    /// Gets the rent-exempt minimum for an account of `size` bytes, cached after the first query.
+    async fn rent_exempt_minimum(&self, size: usize, rpc_client: &RpcPool) -> Result<u64> {
+        retry_rpc(
+            || self.rent_cache.get_or_fetch(size, || rpc_client.nonblocking().get_minimum_balance_for_rent_exemption(size)),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get rent-exempt minimum")
+    }
+
+    /// Builds the instructions that create a token: mint, ATAs, bonding curve, initial supply,
+    /// Metaplex metadata, and creation fee.
+    /// 
+    /// # Arguments
+    /// * `metadata` - The token metadata.
+    /// * `mint_options` - The token program and extensions the mint is created with.
+    /// * `creator` - The creator's public key (fee payer).
+    /// * `token_mint` - The new token mint's public key.
+    /// * `mint_rent` - The rent-exempt minimum funding the mint account, including any
+    ///   embedded metadata.
+    /// 
+    /// # Returns
+    /// A `Result` containing the ordered instructions.
+    fn build_create_instructions(
+        &self,
+        metadata: &TokenMetadata,
+        mint_options: &MintOptions,
+        creator: &Pubkey,
+        token_mint: &Pubkey,
+        mint_rent: u64,
+    ) -> Result<Vec<Instruction>> {
+        let token_program = mint_options.token_program.id();
+
         // Create associated token account for creator
-        let creator_ata = get_associated_token_address(&creator_keypair.pubkey(), &token_mint_pubkey);
+        let creator_ata = get_associated_token_address_with_program_id(creator, token_mint, &token_program);
 
         // Create associated token account for program
-        let program_ata = get_associated_token_address(&self.program_id, &token_mint_pubkey);
+        let program_ata = get_associated_token_address_with_program_id(&self.program_id, token_mint, &token_program);
 
         // Build instructions
         let mut instructions = Vec::new();
 
+        // Allocate the mint account; embedded metadata grows it later, so only fixed-size
+        // extensions are allocated up front
+        let (mint_space, _) = mint_account_len(metadata, mint_options)?;
+        instructions.push(system_instruction::create_account(
+            creator,
+            token_mint,
+            mint_rent,
+            mint_space as u64,
+            &token_program,
+        ));
+
+        // Extensions are initialized before the mint itself
+        for extension in &mint_options.extensions {
+            let extension_ix = match extension {
+                MintExtension::TransferFee { basis_points, maximum_fee } => {
+                    spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                        &token_program,
+                        token_mint,
+                        Some(creator),
+                        Some(creator),
+                        *basis_points,
+                        *maximum_fee,
+                    )
+                }
+                MintExtension::Metadata => spl_token_2022::extension::metadata_pointer::instruction::initialize(
+                    &token_program,
+                    token_mint,
+                    Some(*creator),
+                    Some(*token_mint),
+                ),
+            }
+            .context("Failed to create mint extension instruction")?;
+            instructions.push(extension_ix);
+        }
+
         // Create token mint
-        let mint_ix = spl_token::instruction::initialize_mint(
-            &spl_token::id(),
-            &token_mint_pubkey,
-            &creator_keypair.pubkey(),
-            Some(&creator_keypair.pubkey()),
-            9, // decimals
-        ).context("Failed to create mint instruction")?;
+        let mint_ix = match mint_options.token_program {
+            TokenProgram::Legacy => spl_token::instruction::initialize_mint(
+                &token_program,
+                token_mint,
+                creator,
+                Some(creator),
+                metadata.decimals,
+            ),
+            TokenProgram::Token2022 => spl_token_2022::instruction::initialize_mint(
+                &token_program,
+                token_mint,
+                creator,
+                Some(creator),
+                metadata.decimals,
+            ),
+        }
+        .context("Failed to create mint instruction")?;
         instructions.push(mint_ix);
 
+        // Embedded metadata is written once the mint exists
+        if mint_options.extensions.contains(&MintExtension::Metadata) {
+            instructions.push(spl_token_metadata_interface::instruction::initialize(
+                &token_program,
+                token_mint,
+                creator,
+                token_mint,
+                creator,
+                metadata.name.clone(),
+                metadata.symbol.clone(),
+                metadata.uri.clone().unwrap_or_default(),
+            ));
+        }
+
         // Create creator ATA
         instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
-            &creator_keypair.pubkey(),
-            &creator_keypair.pubkey(),
-            &token_mint_pubkey,
-            &spl_token::id(),
+            creator,
+            creator,
+            token_mint,
+            &token_program,
         ));
 
         // Create program ATA
         instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
-            &creator_keypair.pubkey(),
+            creator,
             &self.program_id,
-            &token_mint_pubkey,
-            &spl_token::id(),
+            token_mint,
+            &token_program,
         ));
 
         // Initialize bonding curve (Pump.Fun specific)
         let init_curve_ix = self.create_init_curve_instruction(
-            &token_mint_pubkey,
-            &creator_keypair.pubkey(),
+            token_mint,
+            &token_program,
+            creator,
             &creator_ata,
             &program_ata,
-            &metadata,
+            metadata,
         ).context("Failed to create init curve instruction")?;
         instructions.push(init_curve_ix);
 
+        // The whole supply starts in the curve's account, for buyers to draw from
+        let supply = self.initial_supply_base_units(metadata.decimals)?;
+        let mint_to_ix = match mint_options.token_program {
+            TokenProgram::Legacy => {
+                spl_token::instruction::mint_to(&token_program, token_mint, &program_ata, creator, &[], supply)
+            }
+            TokenProgram::Token2022 => {
+                spl_token_2022::instruction::mint_to(&token_program, token_mint, &program_ata, creator, &[], supply)
+            }
+        }
+        .context("Failed to create mint-to instruction")?;
+        instructions.push(mint_to_ix);
+
+        // Mints without embedded metadata are named for wallets by a Metaplex account
+        if !mint_options.extensions.contains(&MintExtension::Metadata) {
+            instructions.push(metadata::create_metadata_account_v3(
+                token_mint,
+                creator,
+                &metadata.name,
+                &metadata.symbol,
+                metadata.uri.as_deref().unwrap_or_default(),
+            )?);
+        }
+
         // Transfer creation fee
-        instructions.push(system_instruction::transfer(
-            &creator_keypair.pubkey(),
-            &self.fee_address,
-            (self.config.creation_fee * 1e9) as u64,
-        ));
+        instructions.extend(self.creation_fee_transfers(creator)?);
 
-        // Build and sign transaction
-        let recent_blockhash = rpc_client
-            .get_latest_blockhash()
-            .context("Failed to get recent blockhash")?;
-        
-        let mut transaction = Transaction::new_with_payer(&instructions, Some(&creator_keypair.pubkey()));
-        transaction.sign(&[creator_keypair, &token_mint], recent_blockhash);
+        Ok(instructions)
+    }
 
-        // Send transaction
-        let signature = rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .context("Failed to send transaction")?;
+    /// The configured initial supply in base units of a token with `decimals` decimals.
+    ///
+    /// # Errors
+    /// Returns an error if the scaled supply doesn't fit a token amount.
+    pub fn initial_supply_base_units(&self, decimals: u8) -> Result<u64> {
+        10u64.checked_pow(u32::from(decimals))
+            .and_then(|unit| self.config.initial_supply.checked_mul(unit))
+            .ok_or_else(|| anyhow::anyhow!(
+                "Initial supply of {} tokens overflows a token amount at {} decimals",
+                self.config.initial_supply,
+                decimals
+            ))
+    }
 
-        info!("Token created successfully: {}", token_mint_pubkey);
+    /// Builds the transfers paying the creation fee: one per `fee_splits` recipient, or a
+    /// single transfer to the fee address when no splits are configured.
+    /// 
+    /// # Errors
+    /// Returns an error if the fee fails its sanity caps or the splits don't sum to 100%.
+    pub fn creation_fee_transfers(&self, creator: &Pubkey) -> Result<Vec<Instruction>> {
+        self.validate_fee_transfer(self.config.creation_fee, 0.0)?;
+        let lamports = (self.config.creation_fee * 1e9) as u64;
+        if self.config.fee_splits.is_empty() {
+            return Ok(vec![system_instruction::transfer(creator, &self.fee_address, lamports)]);
+        }
+
+        validate_fee_splits(&self.config.fee_splits)?;
+        Ok(split_fee(lamports, &self.config.fee_splits)
+            .into_iter()
+            .map(|(recipient, amount)| system_instruction::transfer(creator, &recipient, amount))
+            .collect())
+    }
+
+    /// Pins a raw image and the token's metadata JSON, then creates the token.
+    /// 
+    /// # Arguments
+    /// * `metadata` - The token metadata; `image_url` and `uri` are filled in from the uploads.
+    /// * `image` - The raw image bytes.
+    /// * `mime` - The image MIME type.
+    /// * `creator_keypair` - The keypair of the token creator.
+    /// * `rpc_client` - The Solana RPC client for blockchain interaction.
+    /// * `dry_run` - Build and sign the transaction but return it instead of submitting it.
+    /// 
+    /// # Errors
+    /// Returns an `UploadError` if pinning fails, or any error from `create_token`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_token_with_image(
+        &self,
+        mut metadata: TokenMetadata,
+        mint_options: &MintOptions,
+        image: Vec<u8>,
+        mime: &str,
+        creator_keypair: &Keypair,
+        nonce_account: Option<&Pubkey>,
+        rpc_client: &RpcPool,
+        dry_run: bool,
+    ) -> Result<TransactionResult> {
+        let uploader = self.uploader.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Image upload is not configured"))?;
+
+        // Validate everything but the image before spending an upload
+        let mut validation = ValidationResult::new();
+        let pending = TokenMetadata { image_url: "ipfs://pending".to_string(), ..metadata.clone() };
+        self.validate_token_metadata(&pending, &mut validation);
+        self.validate_mint_options(mint_options, &mut validation);
+        if !validation.is_valid {
+            return Ok(TransactionResult::failure(validation.errors.join(", ")));
+        }
+
+        metadata.image_url = uploader.upload_image(image, mime).await?;
+        metadata.uri = Some(uploader.upload_metadata_json(&metadata).await?);
+
+        self.create_token(metadata, mint_options, creator_keypair, nonce_account, rpc_client, dry_run).await
+    }
+
+    /// Creates a token and snipes the first buys in a single Jito bundle.
+    /// 
+    /// The creation transaction always comes first so no one can buy ahead of the launch
+    /// wallets, and the creator's tip comes last. Bundles land atomically, so if submission
+    /// fails nothing is created or bought.
+    /// 
+    /// # Arguments
+    /// * `metadata` - The token metadata.
+    /// * `creator_keypair` - The keypair of the token creator.
+    /// * `buy_sol_amounts` - The SOL each launch wallet spends.
+    /// * `wallets` - The launch wallets as `(wallet_id, keypair)`, in the same order as the amounts.
+    /// * `jito_client` - The bundle client to submit through.
+    /// * `rpc_client` - The Solana RPC client.
+    /// 
+    /// # Returns
+    /// A `Result` containing a `TransactionResult` with the creation signature and bundle id.
+    pub async fn create_and_snipe(
+        &self,
+        metadata: TokenMetadata,
+        creator_keypair: &Keypair,
+        buy_sol_amounts: &[f64],
+        wallets: &[(&str, &Keypair)],
+        jito_client: &JitoBundleClient,
+        rpc_client: &RpcPool,
+    ) -> Result<TransactionResult> {
+        info!("Launching token with {} snipe wallets: {:?}", wallets.len(), metadata);
+
+        let mut validation = ValidationResult::new();
+        self.validate_token_metadata(&metadata, &mut validation);
+        if !validation.is_valid {
+            return Ok(TransactionResult::failure(validation.errors.join(", ")));
+        }
+        let wallet_ids: Vec<&str> = wallets.iter().map(|(wallet_id, _)| *wallet_id).collect();
+        if let Err(e) = validate_wallet_ids(buy_sol_amounts.len(), &wallet_ids, "SOL amounts") {
+            return Ok(TransactionResult::failure(e));
+        }
+
+        let token_mint = Keypair::new();
+        let mint_rent = self.rent_exempt_minimum(spl_token::state::Mint::LEN, rpc_client).await?;
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
+        let transactions = self.build_launch_transactions(
+            &metadata,
+            creator_keypair,
+            &token_mint,
+            buy_sol_amounts,
+            wallets,
+            mint_rent,
+            recent_blockhash,
+        )?;
+
+        let tip = Transaction::new_signed_with_payer(
+            &[jito_client.tip_instruction(&creator_keypair.pubkey())],
+            Some(&creator_keypair.pubkey()),
+            &[creator_keypair],
+            recent_blockhash,
+        );
+        let signatures: Vec<String> = transactions.iter().chain([&tip]).map(|tx| tx.signatures[0].to_string()).collect();
+        let create = transactions[0].clone();
+        let mut bundle = BundleBuilder::new(self.config.max_wallets_per_bundle);
+        for (i, transaction) in transactions.into_iter().enumerate() {
+            let step = if i == 0 { BundleStep::Create } else { BundleStep::Buy };
+            bundle.add_transaction(step, transaction);
+        }
+        let encoded = bundle.with_tip(tip).build()?;
+        let buy_fees: f64 = buy_sol_amounts.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+
+        match jito_client.submit_bundle(encoded).await {
+            Ok(response) => {
+                if let Some(error) = response.error {
+                    return Ok(TransactionResult::failure(format!("Launch bundle rejected: {}", error)));
+                }
+                info!("Launch bundle {} submitted for {}", response.bundle_id, token_mint.pubkey());
+                let confirmation = match self.confirm_bundle(&response.bundle_id, &create, jito_client, rpc_client).await {
+                    Ok(confirmation) => confirmation,
+                    Err(e) => return Ok(TransactionResult::failure(format!("Launch bundle failed: {:#}", e))),
+                };
+                Ok(TransactionResult {
+                    success: true,
+                    signature: Some(signatures[0].clone()),
+                    bundle_id: Some(response.bundle_id),
+                    error: None,
+                    fee_paid: Some(self.config.creation_fee + buy_fees + jito_client.tip_amount()),
+                    serialized_tx: None,
+                    confirmation: Some(confirmation),
+                    mint: Some(token_mint.pubkey().to_string()),
+                    signatures,
+                })
+            }
+            // Nothing lands unless the whole bundle does
+            Err(e) => Ok(TransactionResult::failure(format!("Launch bundle failed: {}", e))),
+        }
+    }
+
+    /// Most snipe wallets in a launch; two slots of the bundle go to the creation and tip transactions.
+    pub fn max_launch_wallets(&self) -> usize {
+        self.config.max_wallets_per_bundle.saturating_sub(2)
+    }
+
+    /// Launches a token without a bundle: creates it, waits a random `delay_ms` range once the
+    /// creation has confirmed, then buys from each wallet.
+    ///
+    /// The gap keeps the dev buy from landing in the creation slot, where anti-bot filters
+    /// look for it. The token exists even if the buys then fail; the result still carries its
+    /// mint.
+    pub async fn create_then_snipe(
+        &self,
+        metadata: TokenMetadata,
+        creator_keypair: &Keypair,
+        buy_sol_amounts: &[f64],
+        wallets: &[(&str, &Keypair)],
+        delay_ms: (u64, u64),
+        rpc_client: &RpcPool,
+    ) -> Result<TransactionResult> {
+        info!("Launching token with {} delayed snipe wallets: {:?}", wallets.len(), metadata);
+
+        let wallet_ids: Vec<&str> = wallets.iter().map(|(wallet_id, _)| *wallet_id).collect();
+        if let Err(e) = validate_wallet_ids(buy_sol_amounts.len(), &wallet_ids, "SOL amounts") {
+            return Ok(TransactionResult::failure(e));
+        }
+        if delay_ms.0 > delay_ms.1 {
+            return Ok(TransactionResult::failure(format!(
+                "Snipe delay minimum ({} ms) exceeds its maximum ({} ms)",
+                delay_ms.0, delay_ms.1
+            )));
+        }
+
+        let created = self.create_token(metadata, &MintOptions::default(), creator_keypair, None, rpc_client, false).await?;
+        let Some(mint) = created.mint.as_deref().filter(|_| created.success) else {
+            return Ok(created);
+        };
+        let mint = Pubkey::from_str(mint).context("Created mint address is invalid")?;
+        // Buying before the creation has landed would fail against a missing curve
+        if created.confirmation != Some(ConfirmationStatus::Confirmed) {
+            return Ok(TransactionResult {
+                success: false,
+                error: Some(format!("Creation of {} wasn't confirmed; no buys were sent", mint)),
+                ..created
+            });
+        }
+
+        let delay = snipe_delay(delay_ms, &mut rand::thread_rng());
+        info!("Token {} created; buying in {} ms", mint, delay.as_millis());
+        self.clock.sleep(delay).await;
+
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
+        let buys = self.build_launch_buys(
+            &mint,
+            buy_sol_amounts,
+            wallets,
+            self.initial_bonding_curve(&mint),
+            recent_blockhash,
+        )?;
+        let signers: Vec<&Keypair> = wallets.iter().map(|(_, wallet)| *wallet).collect();
+        let buy_fees: f64 = buy_sol_amounts.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        let submission = TradeSubmission {
+            fee: buy_fees,
+            trade_volume_sol: buy_sol_amounts.iter().sum(),
+            dry_run: false,
+            via_bundle: false,
+        };
+        let bought = self.submit_trade(&buys, &signers, submission, "buy", rpc_client, None).await?;
+        if !bought.success {
+            return Ok(TransactionResult {
+                success: false,
+                error: Some(format!(
+                    "Token {} was created but its buys failed: {}",
+                    mint,
+                    bought.error.unwrap_or_else(|| "Unknown error".to_string())
+                )),
+                ..created
+            });
+        }
+
+        let signatures = created.signature.iter()
+            .cloned()
+            .chain(buys.iter().map(|tx| tx.signatures[0].to_string()))
+            .collect();
         Ok(TransactionResult {
-            success: true,
-            signature: Some(signature.to_string()),
-            bundle_id: None,
-            error: None,
-            fee_paid: Some(self.config.creation_fee),
+            fee_paid: Some(self.config.creation_fee + buy_fees),
+            confirmation: bought.confirmation,
+            signatures,
+            ..created
         })
     }
 
+    /// Builds the signed launch bundle: the creation transaction followed by one buy per wallet.
+    /// 
+    /// Each buy is quoted against the curve as left by the buys before it, starting from the
+    /// configured initial reserves.
+    /// 
+    /// # Arguments
+    /// * `metadata` - The token metadata.
+    /// * `creator_keypair` - The keypair of the token creator.
+    /// * `token_mint` - The keypair of the new token mint.
+    /// * `buy_sol_amounts` - The SOL each launch wallet spends.
+    /// * `wallets` - The launch wallets as `(wallet_id, keypair)`.
+    /// * `mint_rent` - The rent-exempt minimum funding the mint account.
+    /// * `recent_blockhash` - The blockhash to sign every transaction with.
+    /// 
+    /// # Returns
+    /// A `Result` containing the signed transactions in bundle order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_launch_transactions(
+        &self,
+        metadata: &TokenMetadata,
+        creator_keypair: &Keypair,
+        token_mint: &Keypair,
+        buy_sol_amounts: &[f64],
+        wallets: &[(&str, &Keypair)],
+        mint_rent: u64,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<Transaction>> {
+        if buy_sol_amounts.len() != wallets.len() {
+            return Err(anyhow::anyhow!(
+                "Expected {} launch wallets, got {}",
+                buy_sol_amounts.len(),
+                wallets.len()
+            ));
+        }
+        if wallets.len() > self.max_launch_wallets() {
+            return Err(anyhow::anyhow!("Maximum {} launch wallets allowed per bundle", self.max_launch_wallets()));
+        }
+
+        let mint = token_mint.pubkey();
+        let creator = creator_keypair.pubkey();
+        let create_ix = self.build_create_instructions(metadata, &MintOptions::default(), &creator, &mint, mint_rent)?;
+        let mut create_tx = Transaction::new_with_payer(&create_ix, Some(&creator));
+        create_tx.sign(&[creator_keypair, token_mint], recent_blockhash);
+
+        let mut transactions = vec![create_tx];
+        transactions.extend(self.build_launch_buys(
+            &mint,
+            buy_sol_amounts,
+            wallets,
+            self.initial_bonding_curve(&mint),
+            recent_blockhash,
+        )?);
+        Ok(transactions)
+    }
+
+    /// Builds one signed buy per wallet of a token no one else has bought yet, each quoted
+    /// against the curve as left by the buys before it.
+    fn build_launch_buys(
+        &self,
+        mint: &Pubkey,
+        buy_sol_amounts: &[f64],
+        wallets: &[(&str, &Keypair)],
+        mut curve: BondingCurveData,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<Transaction>> {
+        let mut transactions = Vec::with_capacity(wallets.len());
+        for (sol_amount, (wallet_id, wallet)) in buy_sol_amounts.iter().zip(wallets) {
+            let expected_tokens = self.calculate_tokens_for_sol(*sol_amount, &curve)?;
+            let min_tokens_out = self.min_outputs(None, &[expected_tokens])?;
+            let fee = self.config.fee_schedule.fee_for(*sol_amount);
+            self.validate_fee_transfer(fee, *sol_amount)?;
+
+            let buy_ix = self.create_buy_instruction(
+                mint,
+                &[*sol_amount],
+                &[expected_tokens],
+                &min_tokens_out,
+                &[wallet_id.to_string()],
+            ).context("Failed to create buy instruction")?;
+            let mut instructions = vec![buy_ix];
+            // Launches aren't referred, so the whole fee goes to the platform
+            instructions.extend(self.fee_transfers(&wallet.pubkey(), *sol_amount, None));
+
+            let mut buy_tx = Transaction::new_with_payer(&instructions, Some(&wallet.pubkey()));
+            buy_tx.sign(&[*wallet], recent_blockhash);
+            transactions.push(buy_tx);
+
+            // Later buys fill against the curve this one leaves behind
+            curve.sol_reserve += sol_amount;
+            curve.token_reserve -= expected_tokens;
+            curve.current_price = curve.sol_reserve / curve.token_reserve;
+        }
+
+        Ok(transactions)
+    }
+
+    /// The bonding curve a freshly created token starts from.
+    pub fn initial_bonding_curve(&self, token_mint: &Pubkey) -> BondingCurveData {
+        let sol_reserve = self.config.initial_virtual_sol_reserve;
+        let token_reserve = self.config.initial_virtual_token_reserve;
+        BondingCurveData {
+            token_address: token_mint.to_string(),
+            current_price: sol_reserve / token_reserve,
+            total_supply: token_reserve as u64,
+            sol_reserve,
+            token_reserve,
+            complete: false,
+        }
+    }
+
     /// Buys tokens using SOL.
     /// 
+    /// Each wallet is resolved from the wallet store, so an unknown wallet fails on its own
+    /// while the rest buy. Wallets that resolved share transactions, so they share the
+    /// buy's outcome.
+    /// 
     /// # Arguments
     /// * `request` - The buy request containing token address, SOL amounts, and wallet IDs.
+    /// * `wallets` - The store the request's wallet IDs are resolved from.
     /// * `rpc_client` - The Solana RPC client.
+    /// * `jito_client` - The bundle client used when the request sets `via_bundle`.
     /// 
     /// # Returns
-    /// A `Result` containing a `TransactionResult` with the transaction signature.
+    /// A `Result` containing one `TransactionResult` per requested wallet, in request order.
     pub async fn buy_tokens(
         &self,
         request: BuyRequest,
-        rpc_client: &RpcClient,
-    ) -> Result<TransactionResult> {
+        wallets: &WalletStore,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<Vec<TransactionResult>> {
         info!("Buying tokens: {:?}", request);
+        let wallet_count = request.wallet_ids.len();
 
         // Validate request
-        if request.solAmounts.is_empty() {
-            return Ok(TransactionResult {
-                success: false,
-                signature: None,
-                bundle_id: None,
-                error: Some("No SOL amounts provided".to_string()),
-                fee_paid: None,
-            });
+        if request.sol_amounts.is_empty() {
+            return Ok(failed_wallets(wallet_count, "No SOL amounts provided"));
+        }
+        if let Err(e) = validate_wallet_ids(request.sol_amounts.len(), &request.wallet_ids, "SOL amounts") {
+            return Ok(failed_wallets(wallet_count, e));
+        }
+        let referrer = match parse_referrer(request.referrer.as_deref()) {
+            Ok(referrer) => referrer,
+            Err(e) => return Ok(failed_wallets(wallet_count, e)),
+        };
+        self.log_referral("Buy", &request.token_address, referrer.as_ref());
+
+        // Dust buys can't cover their fees and would only fail on-chain
+        if let Err(e) = self.check_min_sol_amounts(&request.sol_amounts) {
+            return Ok(failed_wallets(wallet_count, e.to_string()));
+        }
+
+        // A mistyped amount multiplied across the bundle shouldn't drain every wallet
+        if let Err(e) = check_max_total_sol(&request.sol_amounts, request.max_total_sol) {
+            return Ok(failed_wallets(wallet_count, e.to_string()));
+        }
+
+        let resolved: Vec<Option<&Keypair>> = request.wallet_ids.iter()
+            .map(|wallet_id| wallets.get_keypair(wallet_id))
+            .collect();
+        // One key stored under two IDs would spend from the same wallet twice
+        if let Err(e) = reject_shared_wallets(&request.wallet_ids, &resolved) {
+            return Ok(failed_wallets(wallet_count, e));
+        }
+        let signers: Vec<&Keypair> = resolved.iter().flatten().copied().collect();
+        let bought = if signers.is_empty() {
+            None
+        } else {
+            // Only the wallets that resolved buy, each with its own amount and minimum
+            let known = |i: &usize| resolved[*i].is_some();
+            let buy = BuyRequest {
+                wallet_ids: (0..wallet_count).filter(known).map(|i| request.wallet_ids[i].clone()).collect(),
+                sol_amounts: (0..wallet_count).filter(known).map(|i| request.sol_amounts[i]).collect(),
+                min_tokens_out: request.min_tokens_out.as_ref()
+                    .map(|minimums| (0..wallet_count).filter(known).filter_map(|i| minimums.get(i).copied()).collect()),
+                ..request.clone()
+            };
+            Some(self.buy_from_wallets(&buy, &signers, referrer.as_ref(), rpc_client, jito_client).await?)
+        };
+        Ok(request.wallet_ids.iter()
+            .zip(&resolved)
+            .map(|(wallet_id, wallet)| match (wallet, &bought) {
+                (Some(_), Some(result)) => result.clone(),
+                _ => TransactionResult::failure(format!("Unknown wallet ID: {}", wallet_id)),
+            })
+            .collect())
+    }
+
+    /// Buys from the wallets that resolved, which sign in request order.
+    async fn buy_from_wallets(
+        &self,
+        request: &BuyRequest,
+        wallets: &[&Keypair],
+        referrer: Option<&Pubkey>,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<TransactionResult> {
+        // Quotes from a lagging node are stale
+        if let Err(e) = self.check_slot_lag(rpc_client).await {
+            return Ok(TransactionResult::failure(e.to_string()));
         }
 
-        let token_mint = Pubkey::from_str(&request.tokenAddress)
+        let token_mint = Pubkey::from_str(&request.token_address)
             .context("Invalid token address")?;
 
+        // Refuse to trade accounts that aren't real token mints
+        if let Err(e) = self.verify_token_mint(&token_mint, rpc_client).await {
+            return match e.downcast_ref::<PumpFunError>() {
+                Some(_) => Ok(TransactionResult::failure(e.to_string())),
+                None => Err(e),
+            };
+        }
+
         // Get bonding curve data
         let bonding_curve = self.get_bonding_curve_data(&token_mint, rpc_client)
             .await
             .context("Failed to get bonding curve data")?;
 
-        // Calculate total SOL needed
+        // Graduated tokens have migrated to Raydium, so the buy goes through their pool instead
+        if bonding_curve.complete {
+            return self.buy_on_raydium(&token_mint, request, wallets, referrer, rpc_client, jito_client).await;
+        }
+
+        // Calculate total SOL needed and the quoted tokens per wallet
         let mut total_sol_needed = 0.0;
-        for sol_amount in &request.solAmounts {
-            let tokens_to_buy = self.calculate_tokens_for_sol(*sol_amount, &bonding_curve)?;
+        let mut quoted_tokens = Vec::with_capacity(request.sol_amounts.len());
+        for sol_amount in &request.sol_amounts {
+            quoted_tokens.push(self.calculate_tokens_for_sol(*sol_amount, &bonding_curve)?);
             total_sol_needed += *sol_amount;
         }
+        let min_tokens_out = self.min_outputs(request.min_tokens_out.as_deref(), &quoted_tokens)?;
 
-        // Create buy instruction
-        let buy_ix = self.create_buy_instruction(
-            &token_mint,
-            &request.solAmounts,
-            &request.walletIds,
-        ).context("Failed to create buy instruction")?;
-
-        // Build transaction
-        let mut instructions = vec![buy_ix];
-
-        // Add SOL transfers for each wallet
-        for (i, sol_amount) in request.solAmounts.iter().enumerate() {
-            let wallet_id = request.walletIds.get(i).unwrap_or(&"0".to_string());
-            // In a real implementation, you'd get the wallet keypair here
-            let wallet_keypair = Keypair::new(); // Placeholder
-            
-            instructions.push(system_instruction::transfer(
-                &wallet_keypair.pubkey(),
-                &self.fee_address,
-                (sol_amount * 1e9) as u64,
-            ));
+        // Abort if the trade would move the curve further than the caller allows
+        let price_impact = self.calculate_price_impact(total_sol_needed, &bonding_curve);
+        if let Err(e) = self.check_price_impact(price_impact, request.max_price_impact_bps) {
+            return Ok(TransactionResult::failure(e.to_string()));
         }
+        let fee: f64 = request.sol_amounts.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        self.validate_fee_transfer(fee, total_sol_needed)?;
 
-        // Sign and send transaction
-        let recent_blockhash = rpc_client
-            .get_latest_blockhash()
-            .context("Failed to get recent blockhash")?;
+        // Re-quote against the latest curve state and abort if the fill has slipped too far
+        let latest_curve = self.get_bonding_curve_data(&token_mint, rpc_client)
+            .await
+            .context("Failed to refresh bonding curve data")?;
+        let expected_tokens = request.sol_amounts.iter()
+            .map(|sol_amount| self.calculate_tokens_for_sol(*sol_amount, &latest_curve))
+            .collect::<Result<Vec<f64>>>()?;
+        if let Err(e) = self.check_slippage(&expected_tokens, &min_tokens_out) {
+            return Ok(TransactionResult::failure(e.to_string()));
+        }
 
-        let mut transaction = Transaction::new_with_payer(&instructions, Some(&Keypair::new().pubkey()));
-        // In a real implementation, you'd sign with the actual wallet keypairs
-        transaction.sign(&[&Keypair::new()], recent_blockhash);
+        // Sign and send transactions
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
 
-        let signature = rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .context("Failed to send buy transaction")?;
+        let legs: Vec<BuyLeg> = request.wallet_ids.iter()
+            .zip(wallets.iter().copied())
+            .zip(request.sol_amounts.iter().zip(&quoted_tokens).zip(&min_tokens_out))
+            .map(|((wallet_id, wallet), ((sol_amount, expected), minimum))| BuyLeg {
+                wallet_id,
+                wallet,
+                sol_amount: *sol_amount,
+                expected_tokens: *expected,
+                min_tokens_out: *minimum,
+            })
+            .collect();
+        let mut transactions = self.build_buy_transactions(
+            &token_mint,
+            &legs,
+            referrer,
+            request.submit_strategy().is_bundle(),
+            jito_client,
+            recent_blockhash,
+        )?;
+        if request.submit_strategy() == SubmitStrategy::PriorityFee {
+            self.attach_priority_fees(&mut transactions, wallets, rpc_client).await?;
+        }
+        self.tighten_compute_limits(&mut transactions, wallets, rpc_client).await?;
 
-        Ok(TransactionResult {
-            success: true,
-            signature: Some(signature.to_string()),
-            bundle_id: None,
-            error: None,
-            fee_paid: Some(total_sol_needed * self.config.trading_fee),
-        })
+        let submission = TradeSubmission { fee, trade_volume_sol: total_sol_needed, dry_run: request.dry_run, via_bundle: request.submit_strategy().is_bundle() };
+        self.submit_trade(&transactions, wallets, submission, "buy", rpc_client, jito_client).await
     }
 
-    /// Sells tokens for SOL.
+    /// Sells tokens for SOL, one transaction per wallet.
+    /// 
+    /// Each wallet is resolved from the wallet store and signs its own transaction, so an
+    /// unknown wallet or a failed send only fails that wallet. A bundled sell lands
+    /// atomically, so its wallets share the bundle's outcome.
     /// 
     /// # Arguments
     /// * `request` - The sell request containing token address, token amounts, and wallet IDs.
+    /// * `wallets` - The store the request's wallet IDs are resolved from.
     /// * `rpc_client` - The Solana RPC client.
+    /// * `jito_client` - The bundle client used when the request sets `via_bundle`.
     /// 
     /// # Returns
-    /// A `Result` containing a `TransactionResult` with the transaction signature.
+    /// A `Result` containing one `TransactionResult` per requested wallet, in request order.
     pub async fn sell_tokens(
         &self,
         request: SellRequest,
-        rpc_client: &RpcClient,
-    ) -> Result<TransactionResult> {
+        wallets: &WalletStore,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<Vec<TransactionResult>> {
         info!("Selling tokens: {:?}", request);
 
         // Validate request
-        if request.tokenAmounts.is_empty() {
-            return Ok(TransactionResult {
-                success: false,
-                signature: None,
-                bundle_id: None,
-                error: Some("No token amounts provided".to_string()),
-                fee_paid: None,
-            });
+        if request.amount_count() == 0 {
+            return Ok(failed_wallets(request.wallet_ids.len(), "No token amounts provided"));
+        }
+        if let Err(e) = request.check_amount_unit()
+            .and_then(|_| validate_wallet_ids(request.amount_count(), &request.wallet_ids, "token amounts"))
+        {
+            return Ok(failed_wallets(request.wallet_ids.len(), e));
         }
+        let referrer = match parse_referrer(request.referrer.as_deref()) {
+            Ok(referrer) => referrer,
+            Err(e) => return Ok(failed_wallets(request.wallet_ids.len(), e)),
+        };
+        self.log_referral("Sell", &request.token_address, referrer.as_ref());
+        let wallet_count = request.wallet_ids.len();
+        let request = match self.resolve_token_amounts(request, rpc_client).await {
+            Ok(request) => request,
+            Err(e) => return Self::trade_failure_or_error(e).map(|result| vec![result; wallet_count]),
+        };
 
-        let token_mint = Pubkey::from_str(&request.tokenAddress)
+        let token_mint = Pubkey::from_str(&request.token_address)
             .context("Invalid token address")?;
 
-        // Get bonding curve data
-        let bonding_curve = self.get_bonding_curve_data(&token_mint, rpc_client)
+        let resolved: Vec<Option<&Keypair>> = request.wallet_ids.iter()
+            .map(|wallet_id| wallets.get_keypair(wallet_id))
+            .collect();
+        let legs: Vec<SellLeg> = request.wallet_ids.iter()
+            .zip(&resolved)
+            .zip(&request.token_amounts)
+            .enumerate()
+            .filter_map(|(index, ((wallet_id, wallet), token_amount))| {
+                wallet.map(|wallet| SellLeg { index, wallet_id, wallet, token_amount: *token_amount })
+            })
+            .collect();
+
+        let mut sold = if legs.is_empty() {
+            Vec::new()
+        } else {
+            self.sell_from_wallets(&token_mint, &request, &legs, referrer.as_ref(), rpc_client, jito_client).await?
+        }
+        .into_iter();
+        Ok(request.wallet_ids.iter()
+            .zip(&resolved)
+            .map(|(wallet_id, wallet)| match wallet {
+                Some(_) => sold.next().unwrap_or_else(|| TransactionResult::failure("Sell produced no result for this wallet")),
+                None => TransactionResult::failure(format!("Unknown wallet ID: {}", wallet_id)),
+            })
+            .collect())
+    }
+
+    /// Sells from the wallets that resolved, returning one result per leg.
+    /// 
+    /// Each wallet pays the trading fee on its quoted proceeds, split with the referrer if any.
+    async fn sell_from_wallets(
+        &self,
+        token_mint: &Pubkey,
+        request: &SellRequest,
+        legs: &[SellLeg<'_>],
+        referrer: Option<&Pubkey>,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<Vec<TransactionResult>> {
+        // Quotes from a lagging node are stale
+        if let Err(e) = self.check_slot_lag(rpc_client).await {
+            return Ok(failed_wallets(legs.len(), e.to_string()));
+        }
+
+        // Refuse to trade accounts that aren't real token mints
+        if let Err(e) = self.verify_token_mint(token_mint, rpc_client).await {
+            return Self::trade_failure_or_error(e).map(|result| vec![result; legs.len()]);
+        }
+
+        // Get bonding curve data
+        let bonding_curve = self.get_bonding_curve_data(token_mint, rpc_client)
             .await
             .context("Failed to get bonding curve data")?;
 
-        // Calculate total SOL to receive
+        // Graduated tokens have migrated to Raydium, so the sell goes through their pool instead
+        if bonding_curve.complete {
+            return self.sell_on_raydium(token_mint, request, legs, referrer, rpc_client, jito_client).await;
+        }
+
+        // The curve works in whole tokens, the request in base units
+        let leg_tokens: Vec<f64> = legs.iter().map(|leg| leg.whole_tokens()).collect();
+
+        // Calculate total SOL to receive and the quoted SOL per wallet
         let mut total_sol_received = 0.0;
-        for token_amount in &request.tokenAmounts {
-            let sol_received = self.calculate_sol_for_tokens(*token_amount as f64, &bonding_curve)?;
+        let mut quoted_sol = Vec::with_capacity(legs.len());
+        for tokens in &leg_tokens {
+            let sol_received = self.calculate_sol_for_sell(*tokens, &bonding_curve)?;
+            quoted_sol.push(sol_received);
             total_sol_received += sol_received;
         }
+        let explicit_minimums = explicit_leg_minimums(request.min_sol_out.as_deref(), legs);
+        let min_sol_out = self.min_outputs(explicit_minimums.as_deref(), &quoted_sol)?;
 
-        // Create sell instruction
-        let sell_ix = self.create_sell_instruction(
-            &token_mint,
-            &request.tokenAmounts.iter().map(|&x| x as f64).collect::<Vec<f64>>(),
-            &request.walletIds,
-        ).context("Failed to create sell instruction")?;
+        // Abort if the trade would move the curve further than the caller allows
+        let total_tokens: f64 = leg_tokens.iter().sum();
+        let price_impact = self.calculate_sell_price_impact(total_tokens, &bonding_curve);
+        if let Err(e) = self.check_price_impact(price_impact, request.max_price_impact_bps) {
+            return Ok(failed_wallets(legs.len(), e.to_string()));
+        }
+        let fee: f64 = quoted_sol.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        self.validate_fee_transfer(fee, total_sol_received)?;
+
+        // Each wallet sells in its own instruction and pays its fee, closing its account if asked and emptied
+        let mut wallet_instructions = Vec::with_capacity(legs.len());
+        for ((leg, tokens), sol) in legs.iter().zip(&leg_tokens).zip(&quoted_sol) {
+            let owner = leg.wallet.pubkey();
+            let mut instructions = vec![self.create_sell_instruction(
+                token_mint,
+                &[*tokens],
+                &[leg.wallet_id.to_string()],
+            ).context("Failed to create sell instruction")?];
+            instructions.extend(self.fee_transfers(&owner, *sol, referrer));
+            if request.close_account {
+                let balance = self.get_wallet_info(&owner, Some(token_mint), rpc_client).await?.token_balance;
+                instructions.extend(self.close_emptied_accounts(token_mint, &[owner], &[leg.token_amount], &[balance])?);
+            }
+            wallet_instructions.push(instructions);
+        }
+
+        // Re-quote against the latest curve state and abort if the fill has slipped too far
+        let latest_curve = self.get_bonding_curve_data(token_mint, rpc_client)
+            .await
+            .context("Failed to refresh bonding curve data")?;
+        let expected_sol = leg_tokens.iter()
+            .map(|tokens| self.calculate_sol_for_sell(*tokens, &latest_curve))
+            .collect::<Result<Vec<f64>>>()?;
+        if let Err(e) = self.check_slippage(&expected_sol, &min_sol_out) {
+            return Ok(failed_wallets(legs.len(), e.to_string()));
+        }
+
+        // Sign and send transactions
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
+        let last = legs.len() - 1;
+        let wallet_transactions = legs.iter()
+            .zip(wallet_instructions)
+            .zip(&quoted_sol)
+            .enumerate()
+            .map(|(i, ((leg, instructions), sol))| {
+                Ok(WalletTransaction {
+                    wallet: leg.wallet,
+                    transaction: self.sign_trade_transaction(
+                        instructions,
+                        leg.wallet,
+                        request.submit_strategy().is_bundle() && i == last,
+                        jito_client,
+                        recent_blockhash,
+                    )?,
+                    trade_sol: *sol,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.submit_wallet_transactions(wallet_transactions, request.dry_run, request.submit_strategy(), "sell", rpc_client, jito_client).await
+    }
+
+    /// Buys a graduated token through its Raydium pool, one transaction per wallet.
+    /// 
+    /// Each wallet wraps its SOL, swaps it for the token, and unwraps what is left.
+    /// Minimums and the price impact cap apply to the pool's quote as they do on the curve.
+    async fn buy_on_raydium(
+        &self,
+        token_mint: &Pubkey,
+        request: &BuyRequest,
+        wallets: &[&Keypair],
+        referrer: Option<&Pubkey>,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<TransactionResult> {
+        info!("Token {} has graduated; buying through Raydium", token_mint);
+        let pool = match raydium::fetch_pool(token_mint, rpc_client) {
+            Ok(pool) => pool,
+            Err(e) => return Self::trade_failure_or_error(e),
+        };
+
+        let token_unit = 10f64.powi(PUMP_TOKEN_DECIMALS as i32);
+        let lamports_in: Vec<u64> = request.sol_amounts.iter().map(|sol| (sol * 1e9) as u64).collect();
+        let quoted_tokens: Vec<f64> = lamports_in.iter()
+            .map(|lamports| pool.quote_buy(*lamports) as f64 / token_unit)
+            .collect();
+        let min_tokens_out = self.min_outputs(request.min_tokens_out.as_deref(), &quoted_tokens)?;
+        if let Err(e) = self.check_slippage(&quoted_tokens, &min_tokens_out) {
+            return Ok(TransactionResult::failure(e.to_string()));
+        }
+
+        let total_sol: f64 = request.sol_amounts.iter().sum();
+        let price_impact = pool.buy_price_impact_pct(lamports_in.iter().sum());
+        if let Err(e) = self.check_price_impact(price_impact, request.max_price_impact_bps) {
+            return Ok(TransactionResult::failure(e.to_string()));
+        }
+        let fee: f64 = request.sol_amounts.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        self.validate_fee_transfer(fee, total_sol)?;
+        self.check_raydium_bundle_size(request.wallet_ids.len(), request.submit_strategy().is_bundle())?;
+
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
+
+        let last = wallets.len().saturating_sub(1);
+        let mut transactions = wallets.iter()
+            .zip(request.sol_amounts.iter().zip(&lamports_in).zip(&min_tokens_out))
+            .enumerate()
+            .map(|(i, (wallet, ((sol_amount, lamports), minimum)))| {
+                let owner = wallet.pubkey();
+                let mut instructions = pool.buy_instructions(&owner, *lamports, (minimum * token_unit) as u64);
+                instructions.extend(self.fee_transfers(&owner, *sol_amount, referrer));
+                self.sign_trade_transaction(instructions, wallet, request.submit_strategy().is_bundle() && i == last, jito_client, recent_blockhash)
+            })
+            .collect::<Result<Vec<Transaction>>>()?;
+        if request.submit_strategy() == SubmitStrategy::PriorityFee {
+            self.attach_priority_fees(&mut transactions, wallets, rpc_client).await?;
+        }
+        self.tighten_compute_limits(&mut transactions, wallets, rpc_client).await?;
+
+        let submission = TradeSubmission { fee, trade_volume_sol: total_sol, dry_run: request.dry_run, via_bundle: request.submit_strategy().is_bundle() };
+        self.submit_trade(&transactions, wallets, submission, "buy", rpc_client, jito_client).await
+    }
+
+    /// Sells a graduated token through its Raydium pool, one transaction per wallet.
+    /// 
+    /// Proceeds arrive as wrapped SOL and are unwrapped in the same transaction.
+    async fn sell_on_raydium(
+        &self,
+        token_mint: &Pubkey,
+        request: &SellRequest,
+        legs: &[SellLeg<'_>],
+        referrer: Option<&Pubkey>,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<Vec<TransactionResult>> {
+        info!("Token {} has graduated; selling through Raydium", token_mint);
+        let pool = match raydium::fetch_pool(token_mint, rpc_client) {
+            Ok(pool) => pool,
+            Err(e) => return Self::trade_failure_or_error(e).map(|result| vec![result; legs.len()]),
+        };
+
+        let raw_amounts: Vec<u64> = legs.iter().map(|leg| leg.token_amount).collect();
+        let quoted_sol: Vec<f64> = raw_amounts.iter().map(|raw| pool.quote_sell(*raw) as f64 / 1e9).collect();
+        let explicit_minimums = explicit_leg_minimums(request.min_sol_out.as_deref(), legs);
+        let min_sol_out = self.min_outputs(explicit_minimums.as_deref(), &quoted_sol)?;
+        if let Err(e) = self.check_slippage(&quoted_sol, &min_sol_out) {
+            return Ok(failed_wallets(legs.len(), e.to_string()));
+        }
+
+        let price_impact = pool.sell_price_impact_pct(raw_amounts.iter().sum());
+        if let Err(e) = self.check_price_impact(price_impact, request.max_price_impact_bps) {
+            return Ok(failed_wallets(legs.len(), e.to_string()));
+        }
+        let total_sol: f64 = quoted_sol.iter().sum();
+        let fee: f64 = quoted_sol.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        self.validate_fee_transfer(fee, total_sol)?;
+        self.check_raydium_bundle_size(legs.len(), request.submit_strategy().is_bundle())?;
+
+        let mut wallet_instructions = Vec::with_capacity(legs.len());
+        for (leg, (raw, (sol, minimum))) in legs.iter()
+            .zip(raw_amounts.iter().zip(quoted_sol.iter().zip(&min_sol_out)))
+        {
+            let owner = leg.wallet.pubkey();
+            let mut instructions = pool.sell_instructions(&owner, *raw, (minimum * 1e9) as u64);
+            instructions.extend(self.fee_transfers(&owner, *sol, referrer));
+            if request.close_account {
+                let balance = self.get_wallet_info(&owner, Some(token_mint), rpc_client).await?.token_balance;
+                instructions.extend(self.close_emptied_accounts(token_mint, &[owner], &[leg.token_amount], &[balance])?);
+            }
+            wallet_instructions.push(instructions);
+        }
+
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
+        let last = legs.len() - 1;
+        let wallet_transactions = legs.iter()
+            .zip(wallet_instructions)
+            .zip(&quoted_sol)
+            .enumerate()
+            .map(|(i, ((leg, instructions), sol))| {
+                Ok(WalletTransaction {
+                    wallet: leg.wallet,
+                    transaction: self.sign_trade_transaction(
+                        instructions,
+                        leg.wallet,
+                        request.submit_strategy().is_bundle() && i == last,
+                        jito_client,
+                        recent_blockhash,
+                    )?,
+                    trade_sol: *sol,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.submit_wallet_transactions(wallet_transactions, request.dry_run, request.submit_strategy(), "sell", rpc_client, jito_client).await
+    }
+
+    /// Checks a one-transaction-per-wallet Raydium trade fits in a single bundle.
+    fn check_raydium_bundle_size(&self, wallet_count: usize, via_bundle: bool) -> Result<()> {
+        if via_bundle && wallet_count > self.config.max_wallets_per_bundle {
+            return Err(anyhow::anyhow!(
+                "Raydium trade needs {} transactions but a bundle holds at most {}; use fewer wallets",
+                wallet_count,
+                self.config.max_wallets_per_bundle
+            ));
+        }
+        Ok(())
+    }
+
+    /// Turns a client-facing `PumpFunError` into a failed trade, passing other errors through.
+    fn trade_failure_or_error(e: anyhow::Error) -> Result<TransactionResult> {
+        match e.downcast_ref::<PumpFunError>() {
+            Some(_) => Ok(TransactionResult::failure(e.to_string())),
+            None => Err(e),
+        }
+    }
+
+    /// Sells every listed wallet's full balance of a token in a coordinated Jito exit.
+    /// 
+    /// Wallets without a balance are skipped. More sells than fit in one bundle are split
+    /// across several, each tipping at `exit_tip_multiplier` times the usual tip.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token to exit.
+    /// * `wallets` - The selling wallets as `(wallet_id, keypair)`.
+    /// * `jito_client` - The bundle client to submit through.
+    /// * `rpc_client` - The Solana RPC client.
+    /// 
+    /// # Returns
+    /// A `Result` containing a `TransactionResult` whose `bundle_id` lists every submitted bundle.
+    /// A failed exit still lists the bundles accepted before the failure.
+    pub async fn sell_all(
+        &self,
+        token_mint: &Pubkey,
+        wallets: &[(&str, &Keypair)],
+        jito_client: &JitoBundleClient,
+        rpc_client: &RpcPool,
+    ) -> Result<TransactionResult> {
+        info!("Selling all {} for {} wallets", token_mint, wallets.len());
+
+        if let Err(e) = self.check_slot_lag(rpc_client).await {
+            return Ok(TransactionResult::failure(e.to_string()));
+        }
+
+        // Refuse to trade accounts that aren't real token mints
+        if let Err(e) = self.verify_token_mint(token_mint, rpc_client).await {
+            return match e.downcast_ref::<PumpFunError>() {
+                Some(_) => Ok(TransactionResult::failure(e.to_string())),
+                None => Err(e),
+            };
+        }
+
+        let bonding_curve = self.get_bonding_curve_data(token_mint, rpc_client)
+            .await
+            .context("Failed to get bonding curve data")?;
+
+        // Graduated tokens trade on Raydium; anything sent to the curve would fail
+        if let Err(e) = self.check_not_graduated(&bonding_curve) {
+            return Ok(TransactionResult::failure(e.to_string()));
+        }
 
-        // Build transaction
-        let mut instructions = vec![sell_ix];
+        let mut holdings = Vec::with_capacity(wallets.len());
+        for (wallet_id, wallet) in wallets {
+            let info = self.get_wallet_info(&wallet.pubkey(), Some(token_mint), rpc_client).await?;
+            match info.token_balance {
+                Some(balance) if balance > 0 => holdings.push((*wallet_id, *wallet, balance)),
+                _ => info!("Wallet {} holds no {}, skipping", wallet_id, token_mint),
+            }
+        }
+        if holdings.is_empty() {
+            return Ok(TransactionResult::failure("No wallet holds this token"));
+        }
 
-        // Sign and send transaction
-        let recent_blockhash = rpc_client
-            .get_latest_blockhash()
-            .context("Failed to get recent blockhash")?;
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
+        let tip_lamports = (jito_client.tip_amount() * self.config.exit_tip_multiplier * 1e9) as u64;
+        let (bundles, quoted_sol) = self.build_sell_all_bundles(
+            token_mint,
+            &holdings,
+            &bonding_curve,
+            tip_lamports,
+            recent_blockhash,
+        )?;
+        let total_sol: f64 = quoted_sol.iter().sum();
+        let fee: f64 = quoted_sol.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        self.validate_fee_transfer(fee, total_sol)?;
+
+        // Re-quote against the latest curve state and abort if the exit has slipped too far
+        let min_sol_out = self.min_outputs(None, &quoted_sol)?;
+        let latest_curve = self.get_bonding_curve_data(token_mint, rpc_client)
+            .await
+            .context("Failed to refresh bonding curve data")?;
+        let expected_sol = self.sequential_sell_quotes(&whole_token_balances(&holdings), &latest_curve)?;
+        if let Err(e) = self.check_slippage(&expected_sol, &min_sol_out) {
+            return Ok(TransactionResult::failure(e.to_string()));
+        }
 
-        let mut transaction = Transaction::new_with_payer(&instructions, Some(&Keypair::new().pubkey()));
-        // In a real implementation, you'd sign with the actual wallet keypairs
-        transaction.sign(&[&Keypair::new()], recent_blockhash);
+        // A later bundle failing leaves the earlier ones in flight, so their ids go back too
+        let (bundle_ids, error) = submit_exit_bundles(&bundles, jito_client).await?;
+        let partial_failure = |error: String| {
+            let accepted = &bundles[..bundle_ids.len()];
+            TransactionResult {
+                bundle_id: (!bundle_ids.is_empty()).then(|| bundle_ids.join(",")),
+                signatures: accepted.iter().flatten().map(|tx| tx.signatures[0].to_string()).collect(),
+                ..TransactionResult::failure(error)
+            }
+        };
+        if let Some(error) = error {
+            return Ok(partial_failure(error));
+        }
 
-        let signature = rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .context("Failed to send sell transaction")?;
+        // Every bundle was accepted; the exit is only confirmed once all of them are
+        let mut confirmation = ConfirmationStatus::Confirmed;
+        for (bundle_id, bundle) in bundle_ids.iter().zip(&bundles) {
+            match self.confirm_bundle(bundle_id, &bundle[0], jito_client, rpc_client).await {
+                Ok(ConfirmationStatus::Confirmed) => {}
+                Ok(ConfirmationStatus::Submitted) => confirmation = ConfirmationStatus::Submitted,
+                Err(e) => return Ok(partial_failure(format!("Exit bundle {} failed: {:#}", bundle_id, e))),
+            }
+        }
 
         Ok(TransactionResult {
             success: true,
-            signature: Some(signature.to_string()),
-            bundle_id: None,
+            signature: Some(bundles[0][0].signatures[0].to_string()),
+            bundle_id: Some(bundle_ids.join(",")),
             error: None,
-            fee_paid: Some(total_sol_received * self.config.trading_fee),
+            fee_paid: Some(fee),
+            serialized_tx: None,
+            confirmation: Some(confirmation),
+            mint: None,
+            signatures: bundles.iter().flatten().map(|tx| tx.signatures[0].to_string()).collect(),
         })
     }
 
+    /// Builds the signed sell bundles for a coordinated exit.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token being sold.
+    /// * `holdings` - Each wallet's id, keypair and full token balance.
+    /// * `bonding_curve` - The curve to quote the sells against.
+    /// * `tip_lamports` - The tip paid from the last transaction of each bundle.
+    /// * `recent_blockhash` - The blockhash to sign every transaction with.
+    /// 
+    /// # Returns
+    /// A `Result` containing the bundles in submission order and the SOL each wallet is quoted.
+    pub fn build_sell_all_bundles(
+        &self,
+        token_mint: &Pubkey,
+        holdings: &[(&str, &Keypair, u64)],
+        bonding_curve: &BondingCurveData,
+        tip_lamports: u64,
+        recent_blockhash: Hash,
+    ) -> Result<(Vec<Vec<Transaction>>, Vec<f64>)> {
+        let tokens = whole_token_balances(holdings);
+        let quoted_sol = self.sequential_sell_quotes(&tokens, bonding_curve)?;
+        let mut sells = tokens.iter().zip(&quoted_sol);
+        let mut bundles = Vec::new();
+
+        for chunk in holdings.chunks(self.config.max_wallets_per_bundle.max(1)) {
+            let mut bundle = Vec::with_capacity(chunk.len());
+            for (i, ((wallet_id, wallet, _), (tokens, sol_out))) in chunk.iter().zip(sells.by_ref()).enumerate() {
+                let mut instructions = vec![self.create_sell_instruction(
+                    token_mint,
+                    &[*tokens],
+                    &[wallet_id.to_string()],
+                ).context("Failed to create sell instruction")?];
+                instructions.extend(self.fee_transfers(&wallet.pubkey(), *sol_out, None));
+                // Each bundle tips from its last transaction
+                if i == chunk.len() - 1 {
+                    instructions.push(tip_transfer(&wallet.pubkey(), tip_lamports));
+                }
+
+                let mut transaction = Transaction::new_with_payer(&instructions, Some(&wallet.pubkey()));
+                transaction.sign(&[*wallet], recent_blockhash);
+                bundle.push(transaction);
+            }
+            bundles.push(bundle);
+        }
+
+        Ok((bundles, quoted_sol))
+    }
+
+    /// Quotes sells filled one after another, each against the curve the previous one leaves behind.
+    /// 
+    /// # Returns
+    /// A `Result` containing the SOL each sell receives, net of the trading fee.
+    fn sequential_sell_quotes(&self, token_amounts: &[f64], bonding_curve: &BondingCurveData) -> Result<Vec<f64>> {
+        let mut curve = bonding_curve.clone();
+        token_amounts.iter()
+            .map(|tokens| {
+                let quote = self.sell_quote(*tokens, &curve)?;
+                // The curve pays out the fee too; the seller forwards it to the fee address
+                curve.sol_reserve -= quote.sol_out + quote.fee;
+                curve.token_reserve += tokens;
+                curve.current_price = quote.price_after;
+                Ok(quote.sol_out)
+            })
+            .collect()
+    }
+
     /// Validates token metadata according to Pump.Fun requirements.
     /// 
     /// # Arguments
@@ -350,279 +1475,5635 @@ impl PumpFunClient {
         if metadata.symbol.is_empty() || metadata.symbol.len() > 8 {
             validation.add_error("Token symbol must be 1-8 characters".to_string());
         }
-        if metadata.description.is_empty() || metadata.description.len() > 200 {
-            validation.add_error("Description must be 1-200 characters".to_string());
+        // Hidden characters let a name or symbol pass for another token's
+        if metadata.name.chars().any(is_hidden_char) {
+            validation.add_error("Token name must not contain control or zero-width characters".to_string());
+        }
+        if !metadata.symbol.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+            validation.add_error("Token symbol must be uppercase letters and digits only".to_string());
+        }
+        // Well formed, but easily mistaken for an amount or lost among other tickers
+        if !metadata.symbol.is_empty() && metadata.symbol.chars().all(|c| c.is_ascii_digit()) {
+            validation.add_warning("Token symbol has no letters and may be mistaken for an amount".to_string());
+        } else if metadata.symbol.len() == 1 {
+            validation.add_warning("Token symbol is a single character and may be hard to search for".to_string());
+        }
+        if !metadata.name.is_ascii() && !metadata.name.chars().any(is_hidden_char) {
+            validation.add_warning(
+                "Token name contains non-ASCII characters that may look like another token's name".to_string(),
+            );
+        }
+        if metadata.decimals > MAX_DECIMALS {
+            validation.add_error(format!("Decimals must be at most {}", MAX_DECIMALS));
+        }
+        if metadata.description.is_empty() || metadata.description.len() > MAX_DESCRIPTION_LEN {
+            validation.add_error(format!("Description must be 1-{} characters", MAX_DESCRIPTION_LEN));
+        } else if metadata.description.len() > LONG_DESCRIPTION_LEN {
+            validation.add_warning(format!(
+                "Description is {} of {} characters and may be cut short in wallets",
+                metadata.description.len(),
+                MAX_DESCRIPTION_LEN
+            ));
+        }
+        if metadata.uri.as_ref().is_some_and(|uri| uri.len() > metadata::MAX_URI_LENGTH) {
+            validation.add_error(format!("Metadata URI must be at most {} characters", metadata::MAX_URI_LENGTH));
         }
-        if let Err(_) = url::Url::parse(&metadata.image_url) {
+        if url::Url::parse(&metadata.image_url).is_err() {
             validation.add_error("Invalid image URL".to_string());
         }
-        if metadata.telegram_link.is_none() || metadata.telegram_link.as_ref().unwrap().is_empty() {
-            validation.add_error("Telegram link is required".to_string());
+        // Socials are optional, but must be well formed when given
+        if metadata.telegram_link.as_deref().is_some_and(|link| !is_web_url(link)) {
+            validation.add_error("Telegram link must be an http(s) URL".to_string());
+        }
+        if metadata.twitter_link.as_deref().is_some_and(|link| !is_twitter_link(link)) {
+            validation.add_error("Twitter link must be a profile URL or @handle".to_string());
+        }
+        match metadata.website.as_deref() {
+            Some(link) if !is_web_url(link) => validation.add_error("Website must be an http(s) URL".to_string()),
+            Some(_) => {}
+            None => validation.add_warning("No website given; buyers often look for one before trading".to_string()),
+        }
+        if metadata.discord.as_deref().is_some_and(|link| !is_web_url(link)) {
+            validation.add_error("Discord link must be an http(s) URL".to_string());
         }
-        if metadata.twitter_link.is_none() || metadata.twitter_link.as_ref().unwrap().is_empty() {
-            validation.add_error("Twitter link is required".to_string());
+        for (label, link) in &metadata.extra_links {
+            if label.trim().is_empty() || !is_web_url(link) {
+                validation.add_error(format!("Link \"{}\" must have a label and an http(s) URL", label));
+            }
         }
     }
 
-    /// Creates the initialization curve instruction for Pump.Fun.
+    /// Validates the token program and extensions a mint is created with.
     /// 
-    /// # Arguments
-    /// * `token_mint` - The token mint public key.
-    /// * `creator` - The creator's public key.
-    /// * `creator_ata` - The creator's associated token account.
-    /// * `program_ata` - The program's associated token account.
-    /// * `metadata` - The token metadata.
+    /// Extensions are Token-2022 only, each may be listed once, and a transfer fee can't
+    /// exceed the whole transfer.
     /// 
-    /// # Returns
-    /// A `Result` containing the instruction.
-    fn create_init_curve_instruction(
-        &self,
-        token_mint: &Pubkey,
-        creator: &Pubkey,
-        creator_ata: &Pubkey,
-        program_ata: &Pubkey,
-        metadata: &TokenMetadata,
-    ) -> Result<Instruction> {
-        // Serialize metadata using Borsh
-        let metadata_bytes = borsh::to_vec(metadata)
-            .context("Failed to serialize metadata")?;
-
-        // Create instruction data with discriminator
-        let mut data = vec![0]; // Discriminator for init curve
-        data.extend_from_slice(&metadata_bytes);
+    /// # Arguments
+    /// * `mint_options` - The mint setup to validate.
+    /// * `validation` - The validation result to populate with errors.
+    pub fn validate_mint_options(&self, mint_options: &MintOptions, validation: &mut ValidationResult) {
+        if mint_options.token_program == TokenProgram::Legacy && !mint_options.extensions.is_empty() {
+            validation.add_error("Mint extensions need the token2022 token program".to_string());
+        }
+        for (i, extension) in mint_options.extensions.iter().enumerate() {
+            let kind = std::mem::discriminant(extension);
+            if mint_options.extensions[..i].iter().any(|earlier| std::mem::discriminant(earlier) == kind) {
+                validation.add_error(format!("Mint extension {:?} is listed more than once", extension));
+            }
+            if let MintExtension::TransferFee { basis_points, .. } = extension {
+                if *basis_points > spl_token_2022::extension::transfer_fee::MAX_FEE_BASIS_POINTS {
+                    validation.add_error("Transfer fee must be at most 10000 basis points".to_string());
+                }
+            }
+        }
+    }
 
-        Ok(Instruction {
-            program_id: self.program_id,
-            accounts: vec![
-                AccountMeta::new(*token_mint, false),
-                AccountMeta::new(*creator, true),
-                AccountMeta::new(*creator_ata, false),
-                AccountMeta::new(*program_ata, false),
-                AccountMeta::new_readonly(self.fee_address, false),
-                AccountMeta::new_readonly(spl_token::id(), false),
-                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
-                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
-            ],
-            data,
-        })
+    /// Gets a recent blockhash, reusing the cached one while it is still fresh.
+    async fn latest_blockhash(&self, rpc_client: &RpcPool) -> Result<Hash> {
+        retry_rpc(
+            || self.blockhash_cache.get_or_fetch(|| rpc_client.nonblocking().get_latest_blockhash()),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get recent blockhash")
     }
 
-    /// Creates a buy instruction for Pump.Fun.
+    /// Reads the blockhash stored in a durable nonce account, for signing a transaction that
+    /// advances it.
     /// 
-    /// # Arguments
-    /// * `token_mint` - The token mint public key.
-    /// * `sol_amounts` - The SOL amounts to spend.
-    /// * `wallet_ids` - The wallet IDs.
+    /// # Errors
+    /// Returns `PumpFunError::InvalidNonceAccount` if the account is missing, isn't an
+    /// initialized nonce account, or has another authority, or an RPC error if it can't be read.
+    pub async fn nonce_blockhash(&self, nonce_account: &Pubkey, authority: &Pubkey, rpc_client: &RpcPool) -> Result<Hash> {
+        let account = retry_rpc(
+            || rpc_client.nonblocking().get_account_with_commitment(nonce_account, rpc_client.commitment()),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to fetch nonce account")?
+        .value;
+
+        let invalid = |reason: String| PumpFunError::InvalidNonceAccount {
+            account: nonce_account.to_string(),
+            reason,
+        };
+        let account = account.ok_or_else(|| invalid("account does not exist".to_string()))?;
+        if account.owner != solana_sdk::system_program::id() {
+            return Err(invalid(format!("owned by {}", account.owner)).into());
+        }
+        let versions: NonceVersions = bincode::deserialize(&account.data)
+            .map_err(|_| invalid("not a nonce account".to_string()))?;
+        match versions.state() {
+            NonceState::Initialized(data) if data.authority == *authority => Ok(data.blockhash()),
+            NonceState::Initialized(data) => Err(invalid(format!("its authority is {}, not {}", data.authority, authority)).into()),
+            NonceState::Uninitialized => Err(invalid("nonce is not initialized".to_string()).into()),
+        }
+    }
+
+    /// Checks a mint is an initialized SPL Token or Token-2022 mint, when `verify_mint_owner` is set.
     /// 
-    /// # Returns
-    /// A `Result` containing the instruction.
-    fn create_buy_instruction(
-        &self,
-        token_mint: &Pubkey,
-        sol_amounts: &[f64],
-        wallet_ids: &[String],
-    ) -> Result<Instruction> {
-        // Serialize buy data
-        let buy_data = BuyInstructionData {
-            discriminator: 1, // Buy instruction discriminator
-            sol_amounts: sol_amounts.to_vec(),
-            wallet_ids: wallet_ids.to_vec(),
+    /// # Errors
+    /// Returns `PumpFunError::NotATokenMint` if the account is missing, owned by another
+    /// program, or uninitialized, or an RPC error if the account can't be fetched.
+    pub async fn verify_token_mint(&self, token_mint: &Pubkey, rpc_client: &RpcPool) -> Result<()> {
+        if !self.config.verify_mint_owner {
+            return Ok(());
+        }
+        self.fetch_mint(token_mint, rpc_client).await.map(|_| ())
+    }
+
+    /// Reads how many decimals a mint's amounts carry from its mint account.
+    ///
+    /// # Errors
+    /// Returns `PumpFunError::NotATokenMint` if the account isn't an initialized mint, or an
+    /// RPC error if it can't be read.
+    pub async fn mint_decimals(&self, token_mint: &Pubkey, rpc_client: &RpcPool) -> Result<u8> {
+        self.fetch_mint(token_mint, rpc_client).await.map(|mint| mint.decimals)
+    }
+
+    /// Converts a sell given in UI amounts to raw base units with the mint's decimals. A
+    /// sell already in base units is returned unchanged.
+    ///
+    /// # Errors
+    /// Returns `PumpFunError::InvalidTokenAmount` for an amount with no base unit equivalent,
+    /// or the errors of `mint_decimals`.
+    pub async fn resolve_token_amounts(&self, mut request: SellRequest, rpc_client: &RpcPool) -> Result<SellRequest> {
+        let Some(ui_amounts) = request.ui_token_amounts.take() else {
+            return Ok(request);
         };
+        let token_mint = Pubkey::from_str(&request.token_address).context("Invalid token address")?;
+        let decimals = self.mint_decimals(&token_mint, rpc_client).await?;
+        request.token_amounts = ui_amounts.iter()
+            .map(|amount| ui_to_raw_amount(*amount, decimals))
+            .collect::<Result<_>>()?;
+        Ok(request)
+    }
 
-        let data = borsh::to_vec(&buy_data)
-            .context("Failed to serialize buy instruction data")?;
+    /// Fetches an account that must be an initialized SPL Token or Token-2022 mint.
+    async fn fetch_mint(&self, token_mint: &Pubkey, rpc_client: &RpcPool) -> Result<spl_token::state::Mint> {
+        let account = retry_rpc(
+            || rpc_client.nonblocking().get_account_with_commitment(token_mint, rpc_client.commitment()),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to fetch mint account")?
+        .value;
 
-        Ok(Instruction {
-            program_id: self.program_id,
-            accounts: vec![
-                AccountMeta::new(*token_mint, false),
-                AccountMeta::new_readonly(self.fee_address, false),
-                AccountMeta::new_readonly(spl_token::id(), false),
-            ],
-            data,
+        let not_a_mint = |reason: String| PumpFunError::NotATokenMint {
+            mint: token_mint.to_string(),
+            reason,
+        };
+        let account = account.ok_or_else(|| not_a_mint("account does not exist".to_string()))?;
+        if account.owner != spl_token::id() && account.owner != TOKEN_2022_PROGRAM_ID {
+            return Err(not_a_mint(format!("owned by {}", account.owner)).into());
+        }
+
+        // Token-2022 mints may carry extensions after the base mint layout
+        account.data
+            .get(..spl_token::state::Mint::LEN)
+            .and_then(|data| spl_token::state::Mint::unpack_from_slice(data).ok())
+            .filter(|mint| mint.is_initialized)
+            .ok_or_else(|| not_a_mint("mint is not initialized".to_string()).into())
+    }
+
+    /// Looks up a token's details from its Metaplex metadata account and the off-chain
+    /// JSON that account points at.
+    ///
+    /// # Errors
+    /// Returns `PumpFunError::MetadataNotFound` if the mint has no metadata account, a
+    /// `FetchError` if its off-chain JSON can't be fetched, or an RPC error if the account
+    /// can't be read.
+    pub async fn token_metadata(&self, token_mint: &Pubkey, rpc_client: &RpcPool) -> Result<PumpFunToken> {
+        let address = metadata::metadata_address(token_mint);
+        let account = retry_rpc(
+            || rpc_client.nonblocking().get_account_with_commitment(&address, rpc_client.commitment()),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to fetch metadata account")?
+        .value
+        .filter(|account| account.owner == metadata::TOKEN_METADATA_PROGRAM_ID)
+        .ok_or_else(|| PumpFunError::MetadataNotFound { mint: token_mint.to_string() })?;
+
+        let on_chain = OnChainMetadata::decode(&account.data, token_mint)?;
+        let off_chain = if on_chain.uri.is_empty() {
+            OffChainMetadata::default()
+        } else {
+            self.metadata_fetcher.fetch_json(&on_chain.uri).await?
+        };
+
+        Ok(PumpFunToken {
+            address: token_mint.to_string(),
+            name: on_chain.name,
+            symbol: on_chain.symbol,
+            description: off_chain.description.unwrap_or_default(),
+            image_url: off_chain.image.unwrap_or_default(),
+            telegram_link: off_chain.telegram,
+            twitter_link: off_chain.twitter,
+            creator: on_chain.creator.to_string(),
+            creation_time: None,
         })
     }
 
-    /// Creates a sell instruction for Pump.Fun.
+    /// Checks the RPC node isn't further behind the chain than `max_slot_lag` allows.
     /// 
-    /// # Arguments
-    /// * `token_mint` - The token mint public key.
-    /// * `token_amounts` - The token amounts to sell.
-    /// * `wallet_ids` - The wallet IDs.
-    /// 
-    /// # Returns
-    /// A `Result` containing the instruction.
-    fn create_sell_instruction(
-        &self,
-        token_mint: &Pubkey,
-        token_amounts: &[f64],
-        wallet_ids: &[String],
-    ) -> Result<Instruction> {
-        // Serialize sell data
-        let sell_data = SellInstructionData {
-            discriminator: 2, // Sell instruction discriminator
-            token_amounts: token_amounts.to_vec(),
-            wallet_ids: wallet_ids.to_vec(),
+    /// # Errors
+    /// Returns an error if the node is lagging or its slot can't be fetched.
+    pub async fn check_slot_lag(&self, rpc_client: &RpcPool) -> Result<()> {
+        let Some(max_slot_lag) = self.config.max_slot_lag else {
+            return Ok(());
         };
 
-        let data = borsh::to_vec(&sell_data)
-            .context("Failed to serialize sell instruction data")?;
+        let slot = retry_rpc(
+            || rpc_client.nonblocking().get_slot(),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get current slot")?;
 
-        Ok(Instruction {
-            program_id: self.program_id,
-            accounts: vec![
-                AccountMeta::new(*token_mint, false),
-                AccountMeta::new_readonly(self.fee_address, false),
-                AccountMeta::new_readonly(spl_token::id(), false),
-            ],
-            data,
-        })
+        let lag = self.slot_tracker.observe(slot);
+        if lag > max_slot_lag {
+            return Err(anyhow::anyhow!(
+                "RPC node is {} slots behind (max {}); try again shortly",
+                lag,
+                max_slot_lag
+            ));
+        }
+        Ok(())
     }
 
-    /// Gets bonding curve data from the blockchain.
+    /// Splits a multi-wallet buy into transactions that each fit in a packet.
+    /// 
+    /// Wallets are packed greedily in order, each transaction paid for by its first wallet.
+    /// Space for the Jito tip is reserved in every transaction when bundling, though only the
+    /// last one pays it.
     /// 
     /// # Arguments
-    /// * `token_mint` - The token mint public key.
-    /// * `rpc_client` - The Solana RPC client.
+    /// * `token_mint` - The token being bought.
+    /// * `legs` - One entry per buying wallet.
+    /// * `referrer` - The wallet paid a share of each trading fee, if the buy was referred.
+    /// * `via_bundle` - Whether the transactions will be submitted as a bundle.
+    /// * `jito_client` - The bundle client whose tip is paid.
+    /// * `recent_blockhash` - The blockhash to sign with.
     /// 
-    /// # Returns
-    /// A `Result` containing the bonding curve data.
-    async fn get_bonding_curve_data(
+    /// # Errors
+    /// Returns an error if a single wallet's buy doesn't fit in a packet or the buy needs
+    /// more transactions than a bundle can hold.
+    fn build_buy_transactions(
         &self,
         token_mint: &Pubkey,
-        rpc_client: &RpcClient,
-    ) -> Result<BondingCurveData> {
-        let account_data = rpc_client
-            .get_account_data(token_mint)
-            .context("Failed to fetch bonding curve account")?;
+        legs: &[BuyLeg],
+        referrer: Option<&Pubkey>,
+        via_bundle: bool,
+        jito_client: Option<&JitoBundleClient>,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<Transaction>> {
+        let mut groups: Vec<&[BuyLeg]> = Vec::new();
+        let mut start = 0;
+        for end in 1..=legs.len() {
+            let candidate = self.sign_buy_group(token_mint, &legs[start..end], referrer, via_bundle, jito_client, recent_blockhash)?;
+            if transaction_size(&candidate)? <= PACKET_DATA_SIZE {
+                continue;
+            }
+            if end - start == 1 {
+                return Err(anyhow::anyhow!(
+                    "Buy for wallet {} does not fit in a single transaction",
+                    legs[start].wallet_id
+                ));
+            }
+            // Close the group before the wallet that overflowed it
+            groups.push(&legs[start..end - 1]);
+            start = end - 1;
+        }
+        if start < legs.len() {
+            groups.push(&legs[start..]);
+        }
 
-        // Deserialize account data according to Pump.Fun's bonding curve structure
-        let bonding_curve = BondingCurveData::try_from_slice(&account_data)
-            .context("Failed to deserialize bonding curve data")?;
+        if via_bundle && groups.len() > self.config.max_wallets_per_bundle {
+            return Err(anyhow::anyhow!(
+                "Buy needs {} transactions but a bundle holds at most {}; use fewer wallets",
+                groups.len(),
+                self.config.max_wallets_per_bundle
+            ));
+        }
 
-        Ok(bonding_curve)
+        let last = groups.len().saturating_sub(1);
+        groups.iter()
+            .enumerate()
+            .map(|(i, group)| self.sign_buy_group(token_mint, group, referrer, via_bundle && i == last, jito_client, recent_blockhash))
+            .collect()
     }
 
-    /// Calculates SOL needed for a given token amount using the bonding curve.
-    /// 
-    /// # Arguments
-    /// * `token_amount` - The token amount to buy.
-    /// * `bonding_curve` - The bonding curve data.
+    /// Signs one buy transaction covering a group of wallets.
+    fn sign_buy_group(
+        &self,
+        token_mint: &Pubkey,
+        legs: &[BuyLeg],
+        referrer: Option<&Pubkey>,
+        with_tip: bool,
+        jito_client: Option<&JitoBundleClient>,
+        recent_blockhash: Hash,
+    ) -> Result<Transaction> {
+        let sol_amounts: Vec<f64> = legs.iter().map(|leg| leg.sol_amount).collect();
+        let expected_tokens: Vec<f64> = legs.iter().map(|leg| leg.expected_tokens).collect();
+        let min_tokens_out: Vec<f64> = legs.iter().map(|leg| leg.min_tokens_out).collect();
+        let wallet_ids: Vec<String> = legs.iter().map(|leg| leg.wallet_id.to_string()).collect();
+
+        let mut instructions = self.compute_budget_instructions();
+        instructions.push(self.create_buy_instruction(
+            token_mint,
+            &sol_amounts,
+            &expected_tokens,
+            &min_tokens_out,
+            &wallet_ids,
+        ).context("Failed to create buy instruction")?);
+
+        // Each wallet pays its own trading fee
+        for leg in legs {
+            instructions.extend(self.fee_transfers(&leg.wallet.pubkey(), leg.sol_amount, referrer));
+        }
+
+        let payer = legs[0].wallet;
+        if with_tip {
+            let jito_client = jito_client
+                .ok_or_else(|| anyhow::anyhow!("Bundle submission is not configured"))?;
+            instructions.push(jito_client.tip_instruction(&payer.pubkey()));
+        }
+
+        let signers: Vec<&Keypair> = legs.iter().map(|leg| leg.wallet).collect();
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&signers, recent_blockhash);
+        Ok(transaction)
+    }
+
+    /// Transfers the trading fee on `trade_sol` from the wallet to the fee address, less the
+    /// `referral_fee_share` paid to the trade's referrer when it has one.
+    fn fee_transfers(&self, wallet: &Pubkey, trade_sol: f64, referrer: Option<&Pubkey>) -> Vec<Instruction> {
+        let fee = (self.config.fee_schedule.fee_for(trade_sol) * 1e9) as u64;
+        let referral = referrer.map_or(0, |_| (fee as f64 * self.config.referral_fee_share) as u64);
+        let mut transfers = vec![system_instruction::transfer(wallet, &self.fee_address, fee - referral)];
+        if let (Some(referrer), true) = (referrer, referral > 0) {
+            transfers.push(system_instruction::transfer(wallet, referrer, referral));
+        }
+        transfers
+    }
+
+    /// Logs a referred trade's attribution, for revenue sharing.
+    fn log_referral(&self, kind: &str, token_address: &str, referrer: Option<&Pubkey>) {
+        if let Some(referrer) = referrer {
+            info!(
+                "{} of {} referred by {}; {}% of its fee goes to the referrer",
+                kind,
+                token_address,
+                referrer,
+                self.config.referral_fee_share * 100.0
+            );
+        }
+    }
+
+    /// Signs a trade transaction, adding the Jito tip when it is headed for a bundle.
+    /// 
+    /// # Arguments
+    /// * `instructions` - The trade instructions.
+    /// * `payer` - The fee payer, who also pays any tip.
+    /// * `via_bundle` - Whether the transaction will be submitted as a bundle.
+    /// * `jito_client` - The bundle client whose tip is paid.
+    /// * `recent_blockhash` - The blockhash to sign with.
+    /// 
+    /// # Errors
+    /// Returns an error if a bundle is requested without a bundle client.
+    fn sign_trade_transaction(
+        &self,
+        trade_instructions: Vec<Instruction>,
+        payer: &Keypair,
+        via_bundle: bool,
+        jito_client: Option<&JitoBundleClient>,
+        recent_blockhash: Hash,
+    ) -> Result<Transaction> {
+        let mut instructions = self.compute_budget_instructions();
+        instructions.extend(trade_instructions);
+        if via_bundle {
+            let jito_client = jito_client
+                .ok_or_else(|| anyhow::anyhow!("Bundle submission is not configured"))?;
+            // Tip last, so it is only paid if the trade itself succeeds
+            instructions.push(jito_client.tip_instruction(&payer.pubkey()));
+        }
+
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[payer], recent_blockhash);
+        Ok(transaction)
+    }
+
+    /// Compute budget instructions leading a transaction when priority fees are enabled.
+    /// 
+    /// The limit starts at the maximum so simulation never runs out of units;
+    /// `tighten_compute_limits` lowers it once the real usage is known.
+    fn compute_budget_instructions(&self) -> Vec<Instruction> {
+        match self.compute_unit_price() {
+            Some(price) => vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+                ComputeBudgetInstruction::set_compute_unit_price(price),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    /// The compute unit price transactions carry, in micro-lamports: the fee autopilot's
+    /// latest sample once it has one, else the fixed `priority_fee_micro_lamports`.
+    pub fn compute_unit_price(&self) -> Option<u64> {
+        self.fee_autopilot.as_ref()
+            .and_then(|autopilot| autopilot.price())
+            .or(self.config.priority_fee_micro_lamports)
+    }
+
+    /// Estimates the compute limit a transaction needs by simulating it.
+    /// 
+    /// The transaction should request a high limit, as those built with priority fees do,
+    /// so the simulation isn't cut short. Signatures aren't checked and the blockhash is
+    /// replaced, so it needn't be signed.
     /// 
     /// # Returns
-    /// A `Result` containing the SOL amount needed.
-    fn calculate_sol_for_tokens(&self, token_amount: f64, bonding_curve: &BondingCurveData) -> Result<f64> {
-        // Constant product formula (simplified)
-        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
-        let new_token_reserve = bonding_curve.token_reserve - token_amount;
-        let new_sol_reserve = k / new_token_reserve;
-        let sol_needed = new_sol_reserve - bonding_curve.sol_reserve;
-        
-        // Add Pump.Fun fees
-        let fee = sol_needed * self.config.trading_fee;
-        Ok(sol_needed + fee)
+    /// The units the simulation consumed plus `compute_unit_margin_pct`, capped at the maximum.
+    /// 
+    /// # Errors
+    /// Returns an error if the simulation fails or doesn't report the units it consumed.
+    pub async fn estimate_compute_units(&self, transaction: &Transaction, rpc_client: &RpcPool) -> Result<u32> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let simulation = rpc_client
+            .simulate_transaction_with_config(transaction, config)
+            .context("Failed to simulate transaction")?
+            .value;
+        if let Some(err) = simulation.err {
+            return Err(anyhow::anyhow!("Transaction simulation failed: {}", err));
+        }
+        let consumed = simulation.units_consumed
+            .ok_or_else(|| anyhow::anyhow!("Simulation did not report compute units consumed"))?;
+
+        let limit = (consumed as f64 * (1.0 + self.config.compute_unit_margin_pct / 100.0)).ceil();
+        Ok(limit.min(MAX_COMPUTE_UNIT_LIMIT as f64) as u32)
     }
 
-    /// Calculates tokens received for a given SOL amount using the bonding curve.
+    /// Lowers each transaction's compute limit to its estimate and signs it again.
+    /// 
+    /// Only transactions built with priority fees carry a limit to lower; the rest are
+    /// left alone.
     /// 
     /// # Arguments
-    /// * `sol_amount` - The SOL amount to spend.
-    /// * `bonding_curve` - The bonding curve data.
+    /// * `transactions` - The signed transactions.
+    /// * `signers` - Every keypair that signed any of them.
+    /// * `rpc_client` - The Solana RPC client to simulate against.
+    async fn tighten_compute_limits(
+        &self,
+        transactions: &mut [Transaction],
+        signers: &[&Keypair],
+        rpc_client: &RpcPool,
+    ) -> Result<()> {
+        for transaction in transactions {
+            if compute_unit_limit_index(transaction).is_none() {
+                continue;
+            }
+            let units = self.estimate_compute_units(transaction, rpc_client).await?;
+            set_compute_unit_limit(transaction, units)?;
+            resign(transaction, signers).context("Failed to re-sign transaction with its compute limit")?;
+        }
+        Ok(())
+    }
+
+    /// Sizes a compute unit price for a transaction from recent prioritization fees on the
+    /// accounts it writes, aiming to land with `priority_fee_landing_probability`.
     /// 
     /// # Returns
-    /// A `Result` containing the token amount received.
-    fn calculate_tokens_for_sol(&self, sol_amount: f64, bonding_curve: &BondingCurveData) -> Result<f64> {
-        // Constant product formula (simplified)
-        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
-        let new_sol_reserve = bonding_curve.sol_reserve + sol_amount;
-        let new_token_reserve = k / new_sol_reserve;
-        let tokens_received = bonding_curve.token_reserve - new_token_reserve;
-        
-        // Subtract Pump.Fun fees
-        let fee = tokens_received * self.config.trading_fee;
-        Ok(tokens_received - fee)
+    /// The price in micro-lamports, capped at `max_priority_fee_micro_lamports`.
+    pub async fn recommended_priority_fee(&self, transaction: &Transaction, rpc_client: &RpcPool) -> Result<u64> {
+        let message = &transaction.message;
+        let writable: Vec<Pubkey> = message.account_keys.iter()
+            .enumerate()
+            .filter(|(i, _)| message.is_writable(*i))
+            .map(|(_, key)| *key)
+            .collect();
+        let recent = retry_rpc(
+            || rpc_client.nonblocking().get_recent_prioritization_fees(&writable),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to fetch recent prioritization fees")?;
+
+        let fees: Vec<u64> = recent.iter().map(|fee| fee.prioritization_fee).collect();
+        let price = priority_fee_for_landing(&fees, self.config.priority_fee_landing_probability);
+        Ok(price.min(self.config.max_priority_fee_micro_lamports))
     }
 
-    /// Decodes a base58-encoded private key.
+    /// Replaces each transaction's compute budget with one priced by
+    /// `recommended_priority_fee`, for landing without a Jito bundle, and signs it again.
+    /// 
+    /// The limit starts at the maximum; `tighten_compute_limits` lowers it afterwards.
     /// 
     /// # Arguments
-    /// * `private_key` - The base58-encoded private key.
+    /// * `transactions` - The signed transactions.
+    /// * `signers` - Every keypair that signed any of them.
+    /// * `rpc_client` - The Solana RPC client to read recent fees from.
+    pub async fn attach_priority_fees(
+        &self,
+        transactions: &mut [Transaction],
+        signers: &[&Keypair],
+        rpc_client: &RpcPool,
+    ) -> Result<()> {
+        for transaction in transactions {
+            let price = self.recommended_priority_fee(transaction, rpc_client).await?;
+            let mut instructions = vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+                ComputeBudgetInstruction::set_compute_unit_price(price),
+            ];
+            instructions.extend(
+                decompile_instructions(&transaction.message)
+                    .into_iter()
+                    .filter(|ix| ix.program_id != compute_budget::id()),
+            );
+
+            let payer = transaction.message.account_keys[0];
+            let recent_blockhash = transaction.message.recent_blockhash;
+            *transaction = Transaction::new_with_payer(&instructions, Some(&payer));
+            transaction.message.recent_blockhash = recent_blockhash;
+            resign(transaction, signers).context("Failed to re-sign transaction with its priority fee")?;
+        }
+        Ok(())
+    }
+
+    /// Dry-runs, bundles or sends a trade's signed transactions, as the request asks.
+    /// 
+    /// Sent transactions go out concurrently; the first signature identifies the trade, and
+    /// it only counts as confirmed once every transaction is. If any transaction fails the
+    /// trade fails, naming each failed transaction by its position.
+    async fn submit_trade(
+        &self,
+        transactions: &[Transaction],
+        signers: &[&Keypair],
+        submission: TradeSubmission,
+        kind: &str,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<TransactionResult> {
+        if submission.dry_run {
+            return self.dry_run_result(transactions, submission.fee);
+        }
+
+        if submission.via_bundle {
+            return self.submit_via_bundle(transactions, submission.fee, submission.trade_volume_sol, rpc_client, jito_client).await;
+        }
+
+        let results = self.send_transactions(transactions, signers, kind, rpc_client).await;
+        let signature = results.iter().find_map(|result| result.signature.clone());
+        let failures: Vec<String> = results.iter()
+            .enumerate()
+            .filter_map(|(i, result)| result.error.as_ref().map(|error| format!("#{}: {}", i, error)))
+            .collect();
+        if !failures.is_empty() {
+            let mut result = TransactionResult::failure(format!(
+                "{} of {} {} transactions failed: {}",
+                failures.len(),
+                results.len(),
+                kind,
+                failures.join("; ")
+            ));
+            // Any transactions that did land stay traceable
+            result.signature = signature;
+            return Ok(result);
+        }
+
+        let confirmation = if results.iter().any(|result| result.confirmation == Some(ConfirmationStatus::Submitted)) {
+            ConfirmationStatus::Submitted
+        } else {
+            ConfirmationStatus::Confirmed
+        };
+        Ok(TransactionResult {
+            success: true,
+            signature: Some(signature.context("Trade built no transactions")?),
+            bundle_id: None,
+            error: None,
+            fee_paid: Some(submission.fee),
+            serialized_tx: None,
+            confirmation: Some(confirmation),
+            mint: None,
+            signatures: Vec::new(),
+        })
+    }
+
+    /// Dry-runs, bundles or sends one transaction per wallet, reporting each wallet's outcome.
+    /// 
+    /// Sent transactions succeed or fail independently, and a wallet whose transaction fails
+    /// pricing or simulation is failed alone rather than holding back the rest. A bundle
+    /// lands atomically, so every wallet in it shares the bundle's outcome.
     /// 
     /// # Returns
-    /// A `Result` containing the decoded keypair.
+    /// One result per wallet transaction, in the order given.
+    async fn submit_wallet_transactions(
+        &self,
+        mut wallet_transactions: Vec<WalletTransaction<'_>>,
+        dry_run: bool,
+        strategy: SubmitStrategy,
+        kind: &str,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<Vec<TransactionResult>> {
+        let fee_for = |wallet_transaction: &WalletTransaction| self.config.fee_schedule.fee_for(wallet_transaction.trade_sol);
+
+        let mut outcomes: Vec<Option<TransactionResult>> = Vec::with_capacity(wallet_transactions.len());
+        for wallet_transaction in &mut wallet_transactions {
+            let transaction = std::slice::from_mut(&mut wallet_transaction.transaction);
+            let signers = [wallet_transaction.wallet];
+            let prepared = async {
+                if strategy == SubmitStrategy::PriorityFee {
+                    self.attach_priority_fees(transaction, &signers, rpc_client).await?;
+                }
+                self.tighten_compute_limits(transaction, &signers, rpc_client).await
+            }.await;
+            outcomes.push(prepared.err().map(|e| TransactionResult::failure(format!("{:#}", e))));
+        }
+
+        if strategy.is_bundle() && !dry_run {
+            if let Some(failed) = outcomes.iter().flatten().next() {
+                let error = format!("Bundle not submitted: {}", failed.error.as_deref().unwrap_or_default());
+                return Ok(failed_wallets(wallet_transactions.len(), error));
+            }
+            let transactions: Vec<Transaction> = wallet_transactions.iter().map(|wt| wt.transaction.clone()).collect();
+            let fee: f64 = wallet_transactions.iter().map(fee_for).sum();
+            let volume: f64 = wallet_transactions.iter().map(|wt| wt.trade_sol).sum();
+            let bundled = self.submit_via_bundle(&transactions, fee, volume, rpc_client, jito_client).await?;
+            return Ok(wallet_transactions.iter()
+                .map(|wt| TransactionResult {
+                    signature: bundled.success.then(|| wt.transaction.signatures[0].to_string()),
+                    fee_paid: bundled.success.then(|| fee_for(wt)),
+                    ..bundled.clone()
+                })
+                .collect());
+        }
+
+        let pending: Vec<usize> = (0..outcomes.len()).filter(|i| outcomes[*i].is_none()).collect();
+        if dry_run {
+            for i in pending {
+                let wallet_transaction = &wallet_transactions[i];
+                outcomes[i] = Some(self.dry_run_result(std::slice::from_ref(&wallet_transaction.transaction), fee_for(wallet_transaction))?);
+            }
+        } else {
+            let transactions: Vec<Transaction> = pending.iter().map(|i| wallet_transactions[*i].transaction.clone()).collect();
+            let signers: Vec<&Keypair> = wallet_transactions.iter().map(|wt| wt.wallet).collect();
+            let sent = self.send_transactions(&transactions, &signers, kind, rpc_client).await;
+            for (i, mut result) in pending.into_iter().zip(sent) {
+                if result.success {
+                    result.fee_paid = Some(fee_for(&wallet_transactions[i]));
+                }
+                outcomes[i] = Some(result);
+            }
+        }
+
+        // Every wallet has an outcome by now
+        Ok(outcomes.into_iter().flatten().collect())
+    }
+
+    /// Sends and confirms independent transactions concurrently, up to `max_concurrency` at once.
     /// 
-    /// # Security Note
-    /// This method should only be used for development. In production, use a secure wallet manager.
-    pub fn decode_keypair(&self, private_key: &str) -> Result<Keypair> {
-        let decoded = bs58::decode(private_key)
-            .into_vec()
-            .context("Failed to decode base58 private key")?;
-        
-        if decoded.len() != 64 {
-            return Err(anyhow::anyhow!("Invalid private key length"));
+    /// `signers` re-sign any transaction whose blockhash expires before it is sent.
+    /// 
+    /// # Returns
+    /// One result per transaction, in the order given.
+    async fn send_transactions(
+        &self,
+        transactions: &[Transaction],
+        signers: &[&Keypair],
+        kind: &str,
+        rpc_client: &RpcPool,
+    ) -> Vec<TransactionResult> {
+        join_bounded(transactions, self.config.max_concurrency, |transaction| async move {
+            match self.send_transaction(transaction, signers, rpc_client).await {
+                Ok((signature, confirmation)) => TransactionResult {
+                    success: true,
+                    signature: Some(signature.to_string()),
+                    bundle_id: None,
+                    error: None,
+                    fee_paid: None,
+                    serialized_tx: None,
+                    confirmation: Some(confirmation),
+                    mint: None,
+                    signatures: Vec::new(),
+                },
+                Err(e) => TransactionResult::failure(format!("Failed to send {} transaction: {:#}", kind, e)),
+            }
+        })
+        .await
+    }
+
+    /// Submits signed transactions as a Jito bundle instead of sending them over RPC.
+    /// 
+    /// # Arguments
+    /// * `transactions` - The signed transactions, in bundle order.
+    /// * `fee` - The protocol fee charged on the trade, in SOL.
+    /// * `trade_volume_sol` - The SOL value of the trade, used to check the tip.
+    /// * `rpc_client` - The Solana RPC client, for confirming under `ConfirmationStrategy::Rpc`.
+    /// * `jito_client` - The bundle client to submit through.
+    /// 
+    /// # Errors
+    /// Returns an error if no bundle client is configured or submission fails.
+    async fn submit_via_bundle(
+        &self,
+        transactions: &[Transaction],
+        fee: f64,
+        trade_volume_sol: f64,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<TransactionResult> {
+        let jito_client = jito_client
+            .ok_or_else(|| anyhow::anyhow!("Bundle submission is not configured"))?;
+        let encoded = build_signed_transactions(transactions)?;
+
+        let response = jito_client.submit_trade_bundle(encoded, trade_volume_sol)
+            .await
+            .context("Failed to submit bundle")?;
+        if let Some(error) = response.error {
+            return Ok(TransactionResult::failure(format!("Bundle rejected: {}", error)));
         }
-        
-        Ok(Keypair::from_bytes(&decoded)
-            .context("Failed to create keypair from bytes")?)
+        let confirmation = match self.confirm_bundle(&response.bundle_id, &transactions[0], jito_client, rpc_client).await {
+            Ok(confirmation) => confirmation,
+            Err(e) => return Ok(TransactionResult::failure(format!("Bundle {} failed: {:#}", response.bundle_id, e))),
+        };
+
+        Ok(TransactionResult {
+            success: true,
+            signature: Some(transactions[0].signatures[0].to_string()),
+            bundle_id: Some(response.bundle_id),
+            error: None,
+            fee_paid: Some(fee),
+            serialized_tx: None,
+            confirmation: Some(confirmation),
+            mint: None,
+            signatures: transactions.iter().map(|tx| tx.signatures[0].to_string()).collect(),
+        })
     }
-}
 
-/// Buy instruction data structure for Pump.Fun
-#[derive(BorshSerialize, BorshDeserialize)]
-struct BuyInstructionData {
-    discriminator: u8,
-    sol_amounts: Vec<f64>,
-    wallet_ids: Vec<String>,
-}
+    /// Waits for an accepted bundle to land, confirming it the way `confirmation_strategy` says.
+    /// 
+    /// A bundle that doesn't land within the block engine's polls, or whose status can't be
+    /// read, was still accepted, so it is returned as submitted for the caller to poll.
+    /// 
+    /// # Arguments
+    /// * `bundle_id` - The id the block engine accepted the bundle under.
+    /// * `first` - The bundle's first transaction; a bundle lands whole, so it stands for the rest.
+    /// 
+    /// # Errors
+    /// Under `ConfirmationStrategy::Rpc`, returns an error if the transaction failed on-chain.
+    async fn confirm_bundle(
+        &self,
+        bundle_id: &str,
+        first: &Transaction,
+        jito_client: &JitoBundleClient,
+        rpc_client: &RpcPool,
+    ) -> Result<ConfirmationStatus> {
+        match self.config.confirmation_strategy {
+            ConfirmationStrategy::BundleAware => match jito_client.confirm_landing(bundle_id).await {
+                Ok(BundleLanding::Landed { .. }) => Ok(ConfirmationStatus::Confirmed),
+                Ok(BundleLanding::Dropped) => Ok(ConfirmationStatus::Submitted),
+                Err(e) => {
+                    warn!("Failed to check the status of bundle {}; returning it as submitted: {:#}", bundle_id, e);
+                    Ok(ConfirmationStatus::Submitted)
+                }
+            },
+            ConfirmationStrategy::Rpc => self.await_confirmation(&first.signatures[0], first, rpc_client).await,
+        }
+    }
 
-/// Sell instruction data structure for Pump.Fun
-#[derive(BorshSerialize, BorshDeserialize)]
-struct SellInstructionData {
-    discriminator: u8,
-    token_amounts: Vec<f64>,
-    wallet_ids: Vec<String>,
-}
+    /// Returns a signed transaction without submitting it, for dry runs.
+    /// 
+    /// Trades split across several transactions return them comma-separated, in order.
+    fn dry_run_result(&self, transactions: &[Transaction], fee: f64) -> Result<TransactionResult> {
+        info!("Dry run: skipping submission of {} transaction(s)", transactions.len());
+        Ok(TransactionResult {
+            success: true,
+            signature: Some(transactions[0].signatures[0].to_string()),
+            bundle_id: None,
+            error: None,
+            fee_paid: Some(fee),
+            serialized_tx: Some(build_signed_transactions(transactions)?.join(",")),
+            confirmation: None,
+            mint: None,
+            signatures: Vec::new(),
+        })
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Sends a signed transaction, retrying transient RPC failures, then waits for it to confirm.
+    /// 
+    /// If the node no longer knows the transaction's blockhash, the cache is invalidated and
+    /// the transaction is re-signed by `signers` with a fresh blockhash and sent once more.
+    /// 
+    /// # Returns
+    /// The signature, with `ConfirmationStatus::Submitted` if the transaction hadn't confirmed
+    /// within `confirm_timeout_secs`; it may still land, so the caller can poll later.
+    /// 
+    /// # Errors
+    /// Returns an error if the send fails or the transaction lands with an error.
+    async fn send_transaction(
+        &self,
+        transaction: &Transaction,
+        signers: &[&Keypair],
+        rpc_client: &RpcPool,
+    ) -> Result<(Signature, ConfirmationStatus)> {
+        validate_transaction_size(transaction)?;
 
-    #[test]
-    fn test_validate_token_metadata() {
-        let client = PumpFunClient::new(
-            "pumpfun_program_id".to_string(),
-            "fee_address".to_string(),
-        );
-        let mut validation = ValidationResult::new();
-        let metadata = TokenMetadata {
-            name: "".to_string(),
-            symbol: "TOOLONG".to_string(),
-            description: "".to_string(),
-            image_url: "invalid_url".to_string(),
-            telegram_link: "".to_string(),
-            twitter_link: "".to_string(),
+        // A durable transaction's blockhash is its nonce: it can't be swapped, but never expires
+        let durable = uses_durable_nonce(transaction).is_some();
+        let mut transaction = Cow::Borrowed(transaction);
+        let mut refreshed = false;
+        let result = loop {
+            let result = retry_rpc(
+                || rpc_client.nonblocking().send_transaction(&*transaction),
+                self.config.rpc_max_retries,
+                self.rpc_retry_delay(),
+            )
+            .await;
+            match &result {
+                // The blockhash expired on the way out; one retry with a fresh one usually lands
+                Err(e) if is_blockhash_not_found(e) && !refreshed && !durable => {
+                    warn!("Blockhash {} expired before sending; re-signing with a fresh one", transaction.message.recent_blockhash);
+                    self.blockhash_cache.invalidate();
+                    let mut fresh = transaction.into_owned();
+                    fresh.message.recent_blockhash = self.latest_blockhash(rpc_client).await?;
+                    fresh.signatures.fill(Signature::default());
+                    resign(&mut fresh, signers).context("Failed to re-sign transaction with a fresh blockhash")?;
+                    transaction = Cow::Owned(fresh);
+                    refreshed = true;
+                }
+                _ => break result,
+            }
         };
 
-        client.validate_token_metadata(&metadata, &mut validation);
-        assert!(!validation.is_valid);
-        assert_eq!(validation.errors.len(), 6);
+        if let Err(e) = &result {
+            if is_blockhash_not_found(e) {
+                self.blockhash_cache.invalidate();
+            }
+            if self.config.log_failed_transactions {
+                error!("Send failed ({}); transaction for replay: {}", e, describe_transaction(&transaction));
+            }
+        }
+        // Preflight catches a wallet drained since the balance check; say so plainly
+        let signature = result.map_err(|e| {
+            match e.get_transaction_error().and_then(|err| PumpFunError::from_transaction_error(&err, &transaction, &self.program_id)) {
+                Some(explained) => anyhow::Error::new(explained),
+                None => e.into(),
+            }
+        })?;
+
+        let mut confirmation = self.await_confirmation(&signature, &transaction, rpc_client).await?;
+        // Resending the same durable transaction can't land it twice, so it's safe to retry
+        let mut resends = 0;
+        while durable && confirmation == ConfirmationStatus::Submitted && resends < MAX_NONCE_RESENDS {
+            resends += 1;
+            warn!("Resending durable transaction {} ({}/{})", signature, resends, MAX_NONCE_RESENDS);
+            if let Err(e) = rpc_client.nonblocking().send_transaction(&*transaction).await {
+                // Usually "already processed": the first send is still on its way
+                warn!("Resend of {} failed: {}", signature, e);
+            }
+            confirmation = self.await_confirmation(&signature, &transaction, rpc_client).await?;
+        }
+        Ok((signature, confirmation))
     }
 
-    #[test]
-    fn test_calculate_sol_for_tokens() {
-        let client = PumpFunClient::new(
-            "pumpfun_program_id".to_string(),
-            "fee_address".to_string(),
-        );
-        let bonding_curve = BondingCurveData {
-            token_address: "test_token".to_string(),
-            current_price: 0.001,
-            total_supply: 1000000,
-            sol_reserve: 1000.0,
-            token_reserve: 1000000.0,
-        };
+    /// Polls a sent transaction's status until it confirms or `confirm_timeout_secs` passes,
+    /// or sooner if the request's deadline leaves less time than that.
+    async fn await_confirmation(
+        &self,
+        signature: &Signature,
+        transaction: &Transaction,
+        rpc_client: &RpcPool,
+    ) -> Result<ConfirmationStatus> {
+        let timeout = Deadline::limit(Duration::from_secs(self.config.confirm_timeout_secs));
+        let deadline = Instant::now() + timeout;
+        loop {
+            let statuses = retry_rpc(
+                || rpc_client.nonblocking().get_signature_statuses(std::slice::from_ref(signature)),
+                self.config.rpc_max_retries,
+                self.rpc_retry_delay(),
+            )
+            .await
+            .context("Failed to get signature status")?;
 
-        let result = client.calculate_sol_for_tokens(1000.0, &bonding_curve).unwrap();
-        assert!(result > 0.0);
+            if let Some(Some(status)) = statuses.value.first() {
+                if let Some(err) = &status.err {
+                    return Err(match PumpFunError::from_transaction_error(err, transaction, &self.program_id) {
+                        Some(explained) => anyhow::Error::new(explained).context(format!("Transaction {} failed", signature)),
+                        None => anyhow::anyhow!("Transaction {} failed: {}", signature, err),
+                    });
+                }
+                if status.satisfies_commitment(rpc_client.commitment()) {
+                    return Ok(ConfirmationStatus::Confirmed);
+                }
+            }
+
+            if Instant::now() >= deadline {
+                warn!("Transaction {} not confirmed after {:?}; returning it as submitted", signature, timeout);
+                return Ok(ConfirmationStatus::Submitted);
+            }
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Returns the base delay between retries of transient RPC failures.
+    fn rpc_retry_delay(&self) -> Duration {
+        Duration::from_millis(self.config.rpc_retry_base_delay_ms)
+    }
+
+    /// Resolves the per-wallet minimum outputs for a trade.
+    /// 
+    /// Explicit minimums from the request win; otherwise each quote is discounted by
+    /// `default_max_slippage_pct` so a trade is never left without protection.
+    /// 
+    /// # Arguments
+    /// * `explicit` - Minimums supplied by the client, if any.
+    /// * `quotes` - The quoted output per wallet.
+    pub fn min_outputs(&self, explicit: Option<&[f64]>, quotes: &[f64]) -> Result<Vec<f64>> {
+        match explicit {
+            Some(minimums) if minimums.len() != quotes.len() => Err(anyhow::anyhow!(
+                "Expected {} minimum outputs, got {}",
+                quotes.len(),
+                minimums.len()
+            )),
+            Some(minimums) => Ok(minimums.to_vec()),
+            None => {
+                let tolerance = 1.0 - self.config.default_max_slippage_pct / 100.0;
+                Ok(quotes.iter().map(|quote| quote * tolerance).collect())
+            }
+        }
+    }
+
+    /// Checks that each expected fill meets its minimum output.
+    /// 
+    /// # Errors
+    /// Returns an error naming the first wallet index whose fill falls below its minimum.
+    pub fn check_slippage(&self, expected: &[f64], minimums: &[f64]) -> Result<()> {
+        for (i, (fill, minimum)) in expected.iter().zip(minimums).enumerate() {
+            if fill < minimum {
+                return Err(anyhow::anyhow!(
+                    "Slippage exceeded for wallet {}: expected {}, minimum {}",
+                    i,
+                    fill,
+                    minimum
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a fee transfer stays within the configured sanity caps.
+    /// 
+    /// # Arguments
+    /// * `fee_sol` - The fee about to be transferred, in SOL.
+    /// * `trade_sol` - The trade size the fee is charged on, in SOL (zero for flat fees).
+    /// 
+    /// # Errors
+    /// Returns an error if the fee exceeds `max_fee_sol`, or `max_fee_fraction` of the trade.
+    pub fn validate_fee_transfer(&self, fee_sol: f64, trade_sol: f64) -> Result<()> {
+        if !fee_sol.is_finite() || fee_sol < 0.0 {
+            return Err(anyhow::anyhow!("Invalid fee amount: {} SOL", fee_sol));
+        }
+        if fee_sol > self.config.max_fee_sol {
+            return Err(anyhow::anyhow!(
+                "Fee of {} SOL exceeds the sanity cap of {} SOL; check fee configuration",
+                fee_sol,
+                self.config.max_fee_sol
+            ));
+        }
+        if trade_sol > 0.0 && fee_sol > trade_sol * self.config.max_fee_fraction {
+            return Err(anyhow::anyhow!(
+                "Fee of {} SOL exceeds {}% of the {} SOL trade; check fee configuration",
+                fee_sol,
+                self.config.max_fee_fraction * 100.0,
+                trade_sol
+            ));
+        }
+        Ok(())
     }
-} 
\ No newline at end of file
+
+    /// Creates the initialization curve instruction for Pump.Fun.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token mint public key.
+    /// * `creator` - The creator's public key.
+    /// * `creator_ata` - The creator's associated token account.
+    /// * `program_ata` - The program's associated token account.
+    /// * `metadata` - The token metadata.
+    /// 
+    /// # Returns
+    /// A `Result` containing the instruction.
+    fn create_init_curve_instruction(
+        &self,
+        token_mint: &Pubkey,
+        token_program: &Pubkey,
+        creator: &Pubkey,
+        creator_ata: &Pubkey,
+        program_ata: &Pubkey,
+        metadata: &TokenMetadata,
+    ) -> Result<Instruction> {
+        // Serialize metadata using Borsh, in the layout the program expects
+        let metadata_bytes = borsh::to_vec(&TokenMetadataV1::from(metadata))
+            .context("Failed to serialize metadata")?;
+
+        let mut data = discriminators::CREATE.to_vec();
+        data.extend_from_slice(&metadata_bytes);
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*token_mint, false),
+                AccountMeta::new(*creator, true),
+                AccountMeta::new(*creator_ata, false),
+                AccountMeta::new(*program_ata, false),
+                AccountMeta::new_readonly(self.fee_address, false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            ],
+            data,
+        })
+    }
+
+    /// Creates a buy instruction for Pump.Fun.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token mint public key.
+    /// * `sol_amounts` - The SOL amounts to spend.
+    /// * `expected_tokens` - The quoted tokens per wallet.
+    /// * `min_tokens_out` - The minimum tokens per wallet before the buy should fail.
+    /// * `wallet_ids` - The wallet IDs.
+    /// 
+    /// # Returns
+    /// A `Result` containing the instruction.
+    fn create_buy_instruction(
+        &self,
+        token_mint: &Pubkey,
+        sol_amounts: &[f64],
+        expected_tokens: &[f64],
+        min_tokens_out: &[f64],
+        wallet_ids: &[String],
+    ) -> Result<Instruction> {
+        // Serialize buy data
+        let buy_data = BuyInstructionData {
+            discriminator: discriminators::BUY,
+            sol_amounts: sol_amounts.to_vec(),
+            expected_tokens: expected_tokens.to_vec(),
+            min_tokens_out: min_tokens_out.to_vec(),
+            wallet_ids: wallet_ids.to_vec(),
+        };
+
+        let data = borsh::to_vec(&buy_data)
+            .context("Failed to serialize buy instruction data")?;
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*token_mint, false),
+                AccountMeta::new_readonly(self.fee_address, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data,
+        })
+    }
+
+    /// Creates a sell instruction for Pump.Fun.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token mint public key.
+    /// * `token_amounts` - The token amounts to sell.
+    /// * `wallet_ids` - The wallet IDs.
+    /// 
+    /// # Returns
+    /// A `Result` containing the instruction.
+    fn create_sell_instruction(
+        &self,
+        token_mint: &Pubkey,
+        token_amounts: &[f64],
+        wallet_ids: &[String],
+    ) -> Result<Instruction> {
+        // Serialize sell data
+        let sell_data = SellInstructionData {
+            discriminator: discriminators::SELL,
+            token_amounts: token_amounts.to_vec(),
+            wallet_ids: wallet_ids.to_vec(),
+        };
+
+        let data = borsh::to_vec(&sell_data)
+            .context("Failed to serialize sell instruction data")?;
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*token_mint, false),
+                AccountMeta::new_readonly(self.fee_address, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data,
+        })
+    }
+
+    /// Builds instructions closing the token accounts a sell leaves empty.
+    /// 
+    /// An account is only closed when the sell amount equals its whole balance, so a
+    /// partial sell never burns the tokens left behind. The reclaimed rent goes to the owner.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token being sold.
+    /// * `owners` - The selling wallets.
+    /// * `token_amounts` - The raw amount each wallet sells.
+    /// * `balances` - Each wallet's balance before the sell, `None` if it has no account.
+    fn close_emptied_accounts(
+        &self,
+        token_mint: &Pubkey,
+        owners: &[Pubkey],
+        token_amounts: &[u64],
+        balances: &[Option<u64>],
+    ) -> Result<Vec<Instruction>> {
+        owners.iter()
+            .zip(token_amounts)
+            .zip(balances)
+            .filter(|((_, amount), balance)| **balance == Some(**amount))
+            .map(|((owner, _), _)| {
+                let ata = get_associated_token_address(owner, token_mint);
+                spl_token::instruction::close_account(&spl_token::id(), &ata, owner, owner, &[])
+                    .context("Failed to create close account instruction")
+            })
+            .collect()
+    }
+
+    /// Reports whether the fee wallet can receive fees and whether it needs sweeping.
+    /// 
+    /// A system account below the rent-exempt minimum rejects transfers that would leave
+    /// it non-exempt, so fee transfers smaller than the minimum fail until it is funded.
+    /// 
+    /// # Arguments
+    /// * `rpc_client` - The Solana RPC client.
+    /// 
+    /// # Returns
+    /// A `Result` containing the fee wallet's balance, readiness and any issues found.
+    pub async fn fee_wallet_status(&self, rpc_client: &RpcPool) -> Result<FeeWalletStatus> {
+        let lamports = retry_rpc(
+            || rpc_client.nonblocking().get_balance(&self.fee_address),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get fee wallet balance")?;
+        let rent_exempt_minimum = self.rent_exempt_minimum(0, rpc_client).await?;
+
+        let balance = lamports as f64 / 1e9;
+        let ready = lamports >= rent_exempt_minimum;
+        let sweep_recommended = balance >= self.config.fee_sweep_alert_sol;
+
+        let mut issues = Vec::new();
+        if !ready {
+            issues.push(format!(
+                "Fee wallet holds {} SOL, below the rent-exempt minimum of {} SOL",
+                balance,
+                rent_exempt_minimum as f64 / 1e9
+            ));
+        }
+        if sweep_recommended {
+            issues.push(format!(
+                "Fee wallet holds {} SOL of unswept fees (alert threshold {} SOL)",
+                balance, self.config.fee_sweep_alert_sol
+            ));
+        }
+
+        Ok(FeeWalletStatus {
+            address: self.fee_address.to_string(),
+            balance,
+            ready,
+            sweep_recommended,
+            issues,
+        })
+    }
+
+    /// Sweeps SOL from wallets back to a treasury, leaving each its rent-exempt minimum plus
+    /// `balance_buffer_lamports` for fees.
+    /// 
+    /// The transfers are packed into as few transactions as fit. A bundled sweep tips from the
+    /// first wallet of its last transaction, so that wallet's sweep is smaller by the tip.
+    /// Wallets holding no more than they keep are reported but not swept.
+    /// 
+    /// # Arguments
+    /// * `request` - The wallet ids, and how the sweep is submitted.
+    /// * `wallets` - The keypairs of `request.wallet_ids`, in the same order.
+    /// * `treasury` - Where the SOL goes.
+    /// * `rpc_client` - The Solana RPC client to read balances from and send through.
+    /// * `jito_client` - The bundle client used when the request sets `via_bundle`.
+    /// 
+    /// # Errors
+    /// Returns an error if a balance can't be read or the sweep can't be built.
+    pub async fn sweep_wallets(
+        &self,
+        request: &SweepRequest,
+        wallets: &[&Keypair],
+        treasury: &Pubkey,
+        rpc_client: &RpcPool,
+        jito_client: Option<&JitoBundleClient>,
+    ) -> Result<SweepResult> {
+        let kept_lamports = self.rent_exempt_minimum(0, rpc_client).await? + self.config.balance_buffer_lamports;
+        let mut result = SweepResult {
+            treasury: treasury.to_string(),
+            kept_lamports,
+            wallets: Vec::with_capacity(wallets.len()),
+            total_swept_lamports: 0,
+            transaction: TransactionResult::failure("No wallets to sweep"),
+        };
+        if wallets.is_empty() {
+            return Ok(result);
+        }
+        if let Err(e) = validate_wallet_ids(wallets.len(), &request.wallet_ids, "wallets") {
+            result.transaction = TransactionResult::failure(e);
+            return Ok(result);
+        }
+        // One key stored under two IDs would have its whole balance swept twice
+        let resolved: Vec<Option<&Keypair>> = wallets.iter().copied().map(Some).collect();
+        if let Err(e) = reject_shared_wallets(&request.wallet_ids, &resolved) {
+            result.transaction = TransactionResult::failure(e);
+            return Ok(result);
+        }
+
+        let mut legs = Vec::with_capacity(wallets.len());
+        for (wallet_id, wallet) in request.wallet_ids.iter().zip(wallets) {
+            let address = wallet.pubkey();
+            let balance = retry_rpc(
+                || rpc_client.nonblocking().get_balance(&address),
+                self.config.rpc_max_retries,
+                self.rpc_retry_delay(),
+            )
+            .await
+            .with_context(|| format!("Failed to get balance of wallet {}", wallet_id))?;
+            let lamports = balance.saturating_sub(kept_lamports);
+            if lamports > 0 {
+                legs.push(SweepLeg { wallet_id, wallet, lamports });
+            }
+            result.wallets.push(WalletSweep {
+                wallet_id: wallet_id.clone(),
+                address: wallet.pubkey().to_string(),
+                balance_lamports: balance,
+                swept_lamports: lamports,
+            });
+        }
+        if legs.is_empty() {
+            result.transaction = TransactionResult::failure(format!(
+                "No wallet holds more than the {} lamports it keeps",
+                kept_lamports
+            ));
+            return Ok(result);
+        }
+
+        let tipper = if request.via_bundle {
+            Some(jito_client.ok_or_else(|| anyhow::anyhow!("Bundle submission is not configured"))?)
+        } else {
+            None
+        };
+        let recent_blockhash = self.latest_blockhash(rpc_client).await?;
+        let groups = self.group_sweep_legs(&legs, treasury, tipper, recent_blockhash)?;
+        if let Some(jito_client) = tipper {
+            let tip_lamports = (jito_client.tip_amount() * 1e9) as u64;
+            let payer = groups.last().map_or(0, |group| group.start);
+            let Some(lamports) = legs[payer].lamports.checked_sub(tip_lamports).filter(|lamports| *lamports > 0) else {
+                result.transaction = TransactionResult::failure(format!(
+                    "Wallet {} holds too little to pay the {} lamport bundle tip",
+                    legs[payer].wallet_id, tip_lamports
+                ));
+                return Ok(result);
+            };
+            legs[payer].lamports = lamports;
+            if let Some(sweep) = result.wallets.iter_mut().find(|sweep| sweep.wallet_id == legs[payer].wallet_id) {
+                sweep.swept_lamports = lamports;
+            }
+        }
+
+        let last = groups.len() - 1;
+        let transactions = groups.iter()
+            .enumerate()
+            .map(|(i, group)| {
+                let tip = tipper.filter(|_| i == last);
+                self.sign_sweep_group(&legs[group.clone()], treasury, tip, recent_blockhash)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let signers: Vec<&Keypair> = legs.iter().map(|leg| leg.wallet).collect();
+
+        result.total_swept_lamports = legs.iter().map(|leg| leg.lamports).sum();
+        // A sweep moves the operator's own SOL, so it carries no trading fee or trade volume
+        let submission = TradeSubmission { fee: 0.0, trade_volume_sol: 0.0, dry_run: request.dry_run, via_bundle: request.via_bundle };
+        result.transaction = self.submit_trade(&transactions, &signers, submission, "sweep", rpc_client, jito_client).await?;
+        Ok(result)
+    }
+
+    /// Splits a sweep's transfers into runs that each fit one transaction, sized with the tip
+    /// so whichever run comes last can carry it.
+    fn group_sweep_legs(
+        &self,
+        legs: &[SweepLeg],
+        treasury: &Pubkey,
+        tip: Option<&JitoBundleClient>,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<std::ops::Range<usize>>> {
+        let mut groups = Vec::new();
+        let mut start = 0;
+        for end in 1..=legs.len() {
+            let candidate = self.sign_sweep_group(&legs[start..end], treasury, tip, recent_blockhash)?;
+            if transaction_size(&candidate)? <= PACKET_DATA_SIZE {
+                continue;
+            }
+            // Close the group before the wallet that overflowed it
+            groups.push(start..end - 1);
+            start = end - 1;
+        }
+        groups.push(start..legs.len());
+
+        if tip.is_some() && groups.len() > self.config.max_wallets_per_bundle {
+            return Err(anyhow::anyhow!(
+                "Sweep needs {} transactions but a bundle holds at most {}; sweep fewer wallets",
+                groups.len(),
+                self.config.max_wallets_per_bundle
+            ));
+        }
+        Ok(groups)
+    }
+
+    /// Signs one transaction moving a group of wallets' SOL to the treasury, paid for by the
+    /// group's first wallet.
+    fn sign_sweep_group(
+        &self,
+        legs: &[SweepLeg],
+        treasury: &Pubkey,
+        tip: Option<&JitoBundleClient>,
+        recent_blockhash: Hash,
+    ) -> Result<Transaction> {
+        let payer = legs[0].wallet.pubkey();
+        let mut instructions: Vec<Instruction> = legs.iter()
+            .map(|leg| system_instruction::transfer(&leg.wallet.pubkey(), treasury, leg.lamports))
+            .collect();
+        if let Some(jito_client) = tip {
+            instructions.push(jito_client.tip_instruction(&payer));
+        }
+        let signers: Vec<&Keypair> = legs.iter().map(|leg| leg.wallet).collect();
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer));
+        transaction.try_sign(&signers, recent_blockhash).context("Failed to sign sweep transaction")?;
+        Ok(transaction)
+    }
+
+    /// Gets a wallet's SOL balance and, optionally, its balance of one token.
+    /// 
+    /// # Arguments
+    /// * `wallet` - The wallet public key.
+    /// * `token_mint` - The token to report a balance for, if any.
+    /// * `rpc_client` - The Solana RPC client.
+    /// 
+    /// # Returns
+    /// A `Result` containing the wallet info. `token_balance` is `None` when no mint is
+    /// given or the wallet has no associated token account for it.
+    pub async fn get_wallet_info(
+        &self,
+        wallet: &Pubkey,
+        token_mint: Option<&Pubkey>,
+        rpc_client: &RpcPool,
+    ) -> Result<WalletInfo> {
+        let lamports = retry_rpc(
+            || rpc_client.nonblocking().get_balance(wallet),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get wallet balance")?;
+
+        let token_balance = match token_mint {
+            Some(token_mint) => {
+                let ata = get_associated_token_address(wallet, token_mint);
+                let account = retry_rpc(
+                    || rpc_client.nonblocking().get_account_with_commitment(&ata, rpc_client.commitment()),
+                    self.config.rpc_max_retries,
+                    self.rpc_retry_delay(),
+                )
+                .await
+                .context("Failed to get token account")?
+                .value;
+
+                match account {
+                    Some(account) => {
+                        let token_account = spl_token::state::Account::unpack(&account.data)
+                            .context("Failed to decode token account")?;
+                        Some(token_account.amount)
+                    }
+                    None => None,
+                }
+            }
+            None => None,
+        };
+
+        Ok(WalletInfo {
+            address: wallet.to_string(),
+            balance: lamports as f64 / 1e9,
+            token_balance,
+        })
+    }
+
+    /// Gets a token's largest holders and their share of total supply.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token mint public key.
+    /// * `rpc_client` - The Solana RPC client.
+    /// 
+    /// # Returns
+    /// A `Result` containing the holders the RPC reports (at most 20), largest first.
+    pub async fn get_top_holders(
+        &self,
+        token_mint: &Pubkey,
+        rpc_client: &RpcPool,
+    ) -> Result<Vec<HolderInfo>> {
+        let accounts = retry_rpc(
+            || rpc_client.nonblocking().get_token_largest_accounts(token_mint),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get largest token accounts")?;
+        let supply = retry_rpc(
+            || rpc_client.nonblocking().get_token_supply(token_mint),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to get token supply")?;
+        let total_supply: u64 = supply.amount.parse().context("Invalid token supply")?;
+
+        let mut holders = accounts
+            .into_iter()
+            .map(|account| {
+                let raw: u64 = account.amount.amount.parse().context("Invalid token account amount")?;
+                Ok(HolderInfo {
+                    address: account.address,
+                    amount: raw_to_ui_amount(raw, account.amount.decimals),
+                    percentage: if total_supply == 0 { 0.0 } else { raw as f64 / total_supply as f64 * 100.0 },
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        holders.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+        Ok(holders)
+    }
+
+    /// Gets bonding curve data from the blockchain.
+    /// 
+    /// # Arguments
+    /// * `token_mint` - The token mint public key.
+    /// * `rpc_client` - The Solana RPC client.
+    /// 
+    /// # Returns
+    /// A `Result` containing the bonding curve data.
+    pub async fn get_bonding_curve_data(
+        &self,
+        token_mint: &Pubkey,
+        rpc_client: &RpcPool,
+    ) -> Result<BondingCurveData> {
+        self.get_bonding_curve_data_with_commitment(token_mint, rpc_client.commitment(), rpc_client).await
+    }
+
+    /// Gets bonding curve data as of `commitment` rather than the client's default, e.g.
+    /// `processed` for the freshest quote or `finalized` for one that can't be rolled back.
+    /// 
+    /// Transient RPC failures are retried up to `rpc_max_retries` times.
+    pub async fn get_bonding_curve_data_with_commitment(
+        &self,
+        token_mint: &Pubkey,
+        commitment: CommitmentConfig,
+        rpc_client: &RpcPool,
+    ) -> Result<BondingCurveData> {
+        let address = self.bonding_curve_address(token_mint);
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(commitment),
+            ..RpcAccountInfoConfig::default()
+        };
+        // Sent as a raw request: the client's own account getters report every failure as a
+        // missing account, hiding the transient ones worth retrying
+        let account = retry_rpc(
+            || rpc_client.nonblocking().send::<Response<Option<UiAccount>>>(
+                RpcRequest::GetAccountInfo,
+                serde_json::json!([address.to_string(), config]),
+            ),
+            self.config.rpc_max_retries,
+            self.rpc_retry_delay(),
+        )
+        .await
+        .context("Failed to fetch bonding curve account")?
+            .value
+            .ok_or_else(|| anyhow::anyhow!("Bonding curve account {} not found", address))?;
+        let data = account.data
+            .decode()
+            .ok_or_else(|| anyhow::anyhow!("Bonding curve account {} has undecodable data", address))?;
+
+        parse_bonding_curve(token_mint, &data)
+    }
+
+    /// Derives the bonding curve account address for a mint.
+    pub fn bonding_curve_address(&self, token_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[BONDING_CURVE_SEED, token_mint.as_ref()], &self.program_id).0
+    }
+
+    /// Calculates SOL needed for a given token amount using the bonding curve.
+    /// 
+    /// # Arguments
+    /// * `token_amount` - The token amount to buy.
+    /// * `bonding_curve` - The bonding curve data.
+    /// 
+    /// # Returns
+    /// A `Result` containing the SOL amount needed.
+    pub fn calculate_sol_for_tokens(&self, token_amount: f64, bonding_curve: &BondingCurveData) -> Result<f64> {
+        self.check_not_graduated(bonding_curve)?;
+        self.check_curve_reserves(bonding_curve)?;
+        self.check_within_token_reserve(token_amount, bonding_curve)?;
+
+        // Constant product formula (simplified)
+        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
+        let new_token_reserve = bonding_curve.token_reserve - token_amount;
+        let new_sol_reserve = k / new_token_reserve;
+        let sol_needed = new_sol_reserve - bonding_curve.sol_reserve;
+        
+        // Add Pump.Fun fees
+        let fee = self.config.fee_schedule.fee_for(sol_needed);
+        Ok(sol_needed + fee)
+    }
+
+    /// Calculates tokens received for a given SOL amount using the bonding curve.
+    /// 
+    /// # Arguments
+    /// * `sol_amount` - The SOL amount to spend.
+    /// * `bonding_curve` - The bonding curve data.
+    /// 
+    /// # Returns
+    /// A `Result` containing the token amount received.
+    pub fn calculate_tokens_for_sol(&self, sol_amount: f64, bonding_curve: &BondingCurveData) -> Result<f64> {
+        self.check_not_graduated(bonding_curve)?;
+        self.check_curve_reserves(bonding_curve)?;
+        self.check_sol_amount(sol_amount)?;
+
+        // Constant product formula (simplified)
+        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
+        let new_sol_reserve = bonding_curve.sol_reserve + sol_amount;
+        let new_token_reserve = k / new_sol_reserve;
+        let tokens_received = bonding_curve.token_reserve - new_token_reserve;
+        
+        // Subtract Pump.Fun fees
+        let fee = tokens_received * self.config.fee_schedule.rate_for(sol_amount);
+        Ok(tokens_received - fee)
+    }
+
+    /// Calculates SOL received for selling a token amount into the bonding curve.
+    /// 
+    /// # Arguments
+    /// * `token_amount` - The token amount to sell.
+    /// * `bonding_curve` - The bonding curve data.
+    /// 
+    /// # Returns
+    /// A `Result` containing the SOL received, net of the trading fee.
+    pub fn calculate_sol_for_sell(&self, token_amount: f64, bonding_curve: &BondingCurveData) -> Result<f64> {
+        self.check_not_graduated(bonding_curve)?;
+        self.check_curve_reserves(bonding_curve)?;
+        self.check_token_amount(token_amount)?;
+
+        // Constant product formula (simplified)
+        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
+        let new_token_reserve = bonding_curve.token_reserve + token_amount;
+        let new_sol_reserve = k / new_token_reserve;
+        let sol_received = bonding_curve.sol_reserve - new_sol_reserve;
+
+        // Subtract Pump.Fun fees
+        let fee = self.config.fee_schedule.fee_for(sol_received);
+        Ok(sol_received - fee)
+    }
+
+    /// Quotes a trade against the bonding curve without submitting anything.
+    /// 
+    /// # Arguments
+    /// * `input` - Either the SOL to spend or the tokens to buy.
+    /// * `bonding_curve` - The bonding curve data.
+    /// 
+    /// # Returns
+    /// A `Result` containing the expected output, fee, and price impact.
+    /// 
+    /// # Errors
+    /// Returns an error if the amount is not positive or would drain the token reserve.
+    pub fn quote(&self, input: QuoteInput, bonding_curve: &BondingCurveData) -> Result<TradeQuote> {
+        let amount = match input {
+            QuoteInput::Sol(amount) | QuoteInput::Tokens(amount) => amount,
+        };
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(anyhow::anyhow!("Quote amount must be greater than zero"));
+        }
+        self.check_not_graduated(bonding_curve)?;
+        self.check_curve_reserves(bonding_curve)?;
+
+        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
+        match input {
+            QuoteInput::Sol(sol_amount) => {
+                self.check_sol_amount(sol_amount)?;
+                let new_sol_reserve = bonding_curve.sol_reserve + sol_amount;
+                let tokens_before_fee = bonding_curve.token_reserve - k / new_sol_reserve;
+                let fee = tokens_before_fee * self.config.fee_schedule.rate_for(sol_amount);
+                Ok(TradeQuote {
+                    sol_amount,
+                    token_amount: tokens_before_fee - fee,
+                    fee,
+                    price_impact_pct: Self::price_impact_pct(new_sol_reserve, k / new_sol_reserve, bonding_curve),
+                })
+            }
+            QuoteInput::Tokens(token_amount) => {
+                self.check_within_token_reserve(token_amount, bonding_curve)?;
+                let new_token_reserve = bonding_curve.token_reserve - token_amount;
+                let sol_before_fee = k / new_token_reserve - bonding_curve.sol_reserve;
+                let fee = self.config.fee_schedule.fee_for(sol_before_fee);
+                Ok(TradeQuote {
+                    sol_amount: sol_before_fee + fee,
+                    token_amount,
+                    fee,
+                    price_impact_pct: Self::price_impact_pct(k / new_token_reserve, new_token_reserve, bonding_curve),
+                })
+            }
+        }
+    }
+
+    /// Quotes the SOL a seller receives for a token amount, net of the trading fee.
+    /// 
+    /// # Arguments
+    /// * `token_amount` - The tokens to sell.
+    /// * `bonding_curve` - The bonding curve data.
+    /// 
+    /// # Returns
+    /// A `Result` containing the SOL out, the fee taken and the spot price after the trade.
+    /// 
+    /// # Errors
+    /// Returns an error if the amount isn't positive.
+    pub fn sell_quote(&self, token_amount: f64, bonding_curve: &BondingCurveData) -> Result<SellQuote> {
+        if !token_amount.is_finite() || token_amount <= 0.0 {
+            return Err(anyhow::anyhow!("Quote amount must be greater than zero"));
+        }
+        self.check_not_graduated(bonding_curve)?;
+        self.check_curve_reserves(bonding_curve)?;
+
+        // Sold tokens flow into the curve, which pays SOL out of its reserve
+        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
+        let new_token_reserve = bonding_curve.token_reserve + token_amount;
+        let new_sol_reserve = k / new_token_reserve;
+        let sol_before_fee = bonding_curve.sol_reserve - new_sol_reserve;
+        let fee = self.config.fee_schedule.fee_for(sol_before_fee);
+        Ok(SellQuote {
+            token_amount,
+            sol_out: sol_before_fee - fee,
+            fee,
+            price_after: new_sol_reserve / new_token_reserve,
+        })
+    }
+
+    /// Estimates the full cost of a buy without signing or sending anything.
+    /// 
+    /// The transactions are packed exactly as `buy_tokens` would pack them, so the network
+    /// fee reflects how many the wallets actually need.
+    /// 
+    /// # Arguments
+    /// * `request` - The buy to price.
+    /// * `jito_client` - The bundle client whose tip and fees apply.
+    /// 
+    /// # Returns
+    /// A `Result` containing the trading fee, tip, network fee and totals.
+    pub fn estimate_buy_cost(&self, request: &BuyRequest, jito_client: &JitoBundleClient) -> Result<CostEstimate> {
+        if request.sol_amounts.is_empty() {
+            return Err(anyhow::anyhow!("No SOL amounts provided"));
+        }
+        for sol_amount in &request.sol_amounts {
+            self.check_sol_amount(*sol_amount)?;
+        }
+        let token_mint = Pubkey::from_str(&request.token_address).context("Invalid token address")?;
+        let referrer = parse_referrer(request.referrer.as_deref()).map_err(anyhow::Error::msg)?;
+
+        // Only the size of the transactions matters here, so throwaway signers will do
+        let wallets: Vec<Keypair> = request.wallet_ids.iter().map(|_| Keypair::new()).collect();
+        let legs: Vec<BuyLeg> = request.wallet_ids.iter()
+            .zip(&wallets)
+            .zip(&request.sol_amounts)
+            .map(|((wallet_id, wallet), sol_amount)| BuyLeg {
+                wallet_id,
+                wallet,
+                sol_amount: *sol_amount,
+                expected_tokens: 0.0,
+                min_tokens_out: 0.0,
+            })
+            .collect();
+        let transactions = self.build_buy_transactions(
+            &token_mint,
+            &legs,
+            referrer.as_ref(),
+            request.submit_strategy().is_bundle(),
+            Some(jito_client),
+            Hash::default(),
+        )?;
+
+        let base_amount: f64 = request.sol_amounts.iter().sum();
+        let trading_fee: f64 = request.sol_amounts.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        Ok(cost_estimate(base_amount, trading_fee, transactions.len(), request.submit_strategy().is_bundle(), jito_client, true))
+    }
+
+    /// Simulates a buy against a curve: the fill each wallet expects, the worst fill its
+    /// slippage accepts, the price impact and every fee. Nothing is signed or sent.
+    /// 
+    /// The buy's own limits are checked as submitting it would, and the first it breaks is
+    /// reported as the simulation's `rejection` rather than as an error.
+    /// 
+    /// # Arguments
+    /// * `request` - The buy to simulate.
+    /// * `bonding_curve` - The curve the buy would fill against.
+    /// * `jito_client` - The bundle client whose tip and fees apply.
+    /// 
+    /// # Errors
+    /// Returns an error if the buy can't be priced at all: no or non-positive amounts, a
+    /// graduated or empty curve, or minimums that don't line up with the wallets.
+    pub fn simulate_trade(
+        &self,
+        request: &BuyRequest,
+        bonding_curve: &BondingCurveData,
+        jito_client: &JitoBundleClient,
+    ) -> Result<TradeSimulation> {
+        let costs = self.estimate_buy_cost(request, jito_client)?;
+        let expected_tokens = request.sol_amounts.iter()
+            .map(|sol_amount| self.calculate_tokens_for_sol(*sol_amount, bonding_curve))
+            .collect::<Result<Vec<f64>>>()?;
+        let worst_case_tokens = self.min_outputs(request.min_tokens_out.as_deref(), &expected_tokens)?;
+
+        let total_sol: f64 = request.sol_amounts.iter().sum();
+        let price_impact_pct = self.calculate_price_impact(total_sol, bonding_curve);
+        let price_before = bonding_curve.sol_reserve / bonding_curve.token_reserve;
+        let rejection = self.check_min_sol_amounts(&request.sol_amounts)
+            .and_then(|_| check_max_total_sol(&request.sol_amounts, request.max_total_sol))
+            .and_then(|_| self.check_price_impact(price_impact_pct, request.max_price_impact_bps))
+            .and_then(|_| self.check_slippage(&expected_tokens, &worst_case_tokens))
+            .err()
+            .map(|e| e.to_string());
+
+        Ok(TradeSimulation {
+            total_expected_tokens: expected_tokens.iter().sum(),
+            total_worst_case_tokens: worst_case_tokens.iter().sum(),
+            expected_tokens,
+            worst_case_tokens,
+            price_before,
+            price_after: price_before * (1.0 + price_impact_pct / 100.0),
+            price_impact_pct,
+            costs,
+            rejection,
+        })
+    }
+
+    /// Estimates the full cost of a sell against a curve without signing or sending anything.
+    /// 
+    /// # Arguments
+    /// * `request` - The sell to price.
+    /// * `bonding_curve` - The curve the sell would trade against.
+    /// * `jito_client` - The bundle client whose tip and fees apply.
+    /// 
+    /// # Returns
+    /// A `Result` containing the trading fee, tip, network fee and net proceeds.
+    pub fn estimate_sell_cost(
+        &self,
+        request: &SellRequest,
+        bonding_curve: &BondingCurveData,
+        jito_client: &JitoBundleClient,
+    ) -> Result<CostEstimate> {
+        if request.token_amounts.is_empty() {
+            return Err(anyhow::anyhow!("No token amounts provided"));
+        }
+        self.check_not_graduated(bonding_curve)?;
+        let quoted_sol = request.token_amounts.iter()
+            .map(|token_amount| self.calculate_sol_for_sell(raw_to_ui_amount(*token_amount, PUMP_TOKEN_DECIMALS), bonding_curve))
+            .collect::<Result<Vec<f64>>>()?;
+
+        let base_amount: f64 = quoted_sol.iter().sum();
+        let trading_fee: f64 = quoted_sol.iter().map(|sol| self.config.fee_schedule.fee_for(*sol)).sum();
+        // Each wallet sells in a transaction of its own
+        Ok(cost_estimate(
+            base_amount,
+            trading_fee,
+            request.wallet_ids.len(),
+            request.submit_strategy().is_bundle(),
+            jito_client,
+            false,
+        ))
+    }
+
+    /// Checks a token amount is non-negative and below the curve's token reserve, where
+    /// the curve math would otherwise divide by zero or go negative.
+    fn check_within_token_reserve(&self, token_amount: f64, bonding_curve: &BondingCurveData) -> Result<()> {
+        if !token_amount.is_finite() || token_amount < 0.0 {
+            return Err(anyhow::anyhow!("Token amount {} must be a non-negative number", token_amount));
+        }
+        if token_amount >= bonding_curve.token_reserve {
+            return Err(anyhow::anyhow!(
+                "Token amount {} exceeds the curve's token reserve of {}",
+                token_amount,
+                bonding_curve.token_reserve
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks a token amount to sell is non-negative; selling only adds to the token reserve.
+    fn check_token_amount(&self, token_amount: f64) -> Result<()> {
+        if !token_amount.is_finite() || token_amount < 0.0 {
+            return Err(anyhow::anyhow!("Token amount {} must be a non-negative number", token_amount));
+        }
+        Ok(())
+    }
+
+    /// Checks a SOL amount is non-negative, so it can only add to the curve's SOL reserve.
+    /// 
+    /// A negative amount would take SOL out of the reserve, and at or past the reserve
+    /// the curve math divides by zero or goes negative.
+    fn check_sol_amount(&self, sol_amount: f64) -> Result<()> {
+        if !sol_amount.is_finite() || sol_amount < 0.0 {
+            return Err(anyhow::anyhow!("SOL amount {} must be a non-negative number", sol_amount));
+        }
+        Ok(())
+    }
+
+    /// Checks the curve is still live rather than graduated to Raydium.
+    /// 
+    /// # Errors
+    /// Returns `PumpFunError::CurveComplete` once the curve has completed.
+    pub fn check_not_graduated(&self, bonding_curve: &BondingCurveData) -> Result<()> {
+        if bonding_curve.complete {
+            return Err(PumpFunError::CurveComplete {
+                mint: bonding_curve.token_address.clone(),
+            }.into());
+        }
+        Ok(())
+    }
+
+    /// Checks both curve reserves are strictly positive, when `validate_curve_reserves` is on.
+    /// 
+    /// # Errors
+    /// Returns `PumpFunError::InvalidCurveMath` carrying the offending reserves.
+    pub fn check_curve_reserves(&self, bonding_curve: &BondingCurveData) -> Result<()> {
+        if !self.config.validate_curve_reserves {
+            return Ok(());
+        }
+        let valid = |reserve: f64| reserve.is_finite() && reserve > 0.0;
+        if !valid(bonding_curve.sol_reserve) || !valid(bonding_curve.token_reserve) {
+            return Err(PumpFunError::InvalidCurveMath {
+                sol_reserve: bonding_curve.sol_reserve,
+                token_reserve: bonding_curve.token_reserve,
+            }.into());
+        }
+        Ok(())
+    }
+
+    /// Calculates how far a buy moves the curve's spot price.
+    /// 
+    /// # Arguments
+    /// * `sol_amount` - The SOL amount to spend.
+    /// * `bonding_curve` - The bonding curve data.
+    /// 
+    /// # Returns
+    /// The percentage increase in spot price caused by the buy.
+    pub fn calculate_price_impact(&self, sol_amount: f64, bonding_curve: &BondingCurveData) -> f64 {
+        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
+        let new_sol_reserve = bonding_curve.sol_reserve + sol_amount;
+        Self::price_impact_pct(new_sol_reserve, k / new_sol_reserve, bonding_curve)
+    }
+
+    /// Calculates how far a sell moves the curve's spot price.
+    /// 
+    /// # Arguments
+    /// * `token_amount` - The token amount to sell.
+    /// * `bonding_curve` - The bonding curve data.
+    /// 
+    /// # Returns
+    /// The percentage decrease in spot price caused by the sell.
+    pub fn calculate_sell_price_impact(&self, token_amount: f64, bonding_curve: &BondingCurveData) -> f64 {
+        let k = bonding_curve.sol_reserve * bonding_curve.token_reserve;
+        let new_token_reserve = bonding_curve.token_reserve + token_amount;
+        Self::price_impact_pct(k / new_token_reserve, new_token_reserve, bonding_curve).abs()
+    }
+
+    /// Checks every per-wallet SOL amount against `min_sol_amount`.
+    /// 
+    /// # Errors
+    /// Names the indices of the wallets whose amounts fall below the minimum.
+    pub fn check_min_sol_amounts(&self, sol_amounts: &[f64]) -> Result<()> {
+        let below: Vec<String> = sol_amounts
+            .iter()
+            .enumerate()
+            .filter(|(_, sol)| sol.is_nan() || **sol < self.config.min_sol_amount)
+            .map(|(index, _)| index.to_string())
+            .collect();
+        if !below.is_empty() {
+            return Err(anyhow::anyhow!(
+                "SOL amounts at wallet indices [{}] are below the minimum of {} SOL",
+                below.join(", "),
+                self.config.min_sol_amount
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks a trade's price impact against an optional cap in basis points.
+    pub fn check_price_impact(&self, price_impact_pct: f64, max_price_impact_bps: Option<u32>) -> Result<()> {
+        if let Some(max_bps) = max_price_impact_bps {
+            let max_pct = max_bps as f64 / 100.0;
+            if price_impact_pct > max_pct {
+                return Err(anyhow::anyhow!(
+                    "Price impact of {:.2}% exceeds the maximum of {:.2}%",
+                    price_impact_pct,
+                    max_pct
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Percentage change in spot price when the curve moves to the given reserves.
+    fn price_impact_pct(new_sol_reserve: f64, new_token_reserve: f64, bonding_curve: &BondingCurveData) -> f64 {
+        let price_before = bonding_curve.sol_reserve / bonding_curve.token_reserve;
+        let price_after = new_sol_reserve / new_token_reserve;
+        (price_after - price_before) / price_before * 100.0
+    }
+
+    /// Decodes a base58-encoded private key.
+    /// 
+    /// # Arguments
+    /// * `private_key` - The base58-encoded private key.
+    /// 
+    /// # Returns
+    /// A `Result` containing the decoded keypair.
+    /// 
+    /// # Security Note
+    /// This method should only be used for development. In production, use a secure wallet manager.
+    pub fn decode_keypair(&self, private_key: &str) -> Result<Keypair> {
+        let decoded = bs58::decode(private_key)
+            .into_vec()
+            .context("Failed to decode base58 private key")?;
+        
+        if decoded.len() != 64 {
+            return Err(anyhow::anyhow!("Invalid private key length"));
+        }
+        
+        Keypair::from_bytes(&decoded)
+            .context("Failed to create keypair from bytes")
+    }
+}
+
+/// Totals a trade's fees; a buy pays them on top of the base amount, a sell out of it.
+fn cost_estimate(
+    base_amount: f64,
+    trading_fee: f64,
+    transaction_count: usize,
+    via_bundle: bool,
+    jito_client: &JitoBundleClient,
+    is_buy: bool,
+) -> CostEstimate {
+    let jito_tip = if via_bundle { jito_client.tip_amount() } else { 0.0 };
+    let network_fee = jito_client.calculate_bundle_fee(transaction_count);
+    let total_fees = trading_fee + jito_tip + network_fee;
+    CostEstimate {
+        base_amount,
+        trading_fee,
+        jito_tip,
+        network_fee,
+        total_fees,
+        total_amount: if is_buy { base_amount + total_fees } else { base_amount - total_fees },
+        fee_percentage: if base_amount > 0.0 { total_fees / base_amount * 100.0 } else { 0.0 },
+        transaction_count,
+    }
+}
+
+/// Whether a link is an absolute http or https URL.
+fn is_web_url(link: &str) -> bool {
+    url::Url::parse(link).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+}
+
+/// Whether a link is an X/Twitter profile URL or a bare `@handle`.
+fn is_twitter_link(link: &str) -> bool {
+    let is_handle = |handle: &str| {
+        (1..=15).contains(&handle.len()) && handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if let Some(handle) = link.strip_prefix('@') {
+        return is_handle(handle);
+    }
+    let Ok(url) = url::Url::parse(link) else {
+        return false;
+    };
+    let on_twitter = matches!(
+        url.host_str(),
+        Some("twitter.com" | "www.twitter.com" | "x.com" | "www.x.com" | "mobile.twitter.com")
+    );
+    let mut segments = url.path_segments().into_iter().flatten().filter(|s| !s.is_empty());
+    matches!(url.scheme(), "http" | "https")
+        && on_twitter
+        && segments.next().is_some_and(is_handle)
+        && segments.next().is_none()
+}
+
+/// Whether a character is invisible when rendered: control characters, zero-width spaces and
+/// joiners, soft hyphens, byte order marks, and bidirectional overrides.
+fn is_hidden_char(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{2069}' | '\u{FEFF}'
+        )
+}
+
+/// Renders a transaction as base64 plus a per-instruction summary, for manual inspection or replay.
+pub fn describe_transaction(transaction: &Transaction) -> String {
+    let encoded = encode_transaction(transaction)
+        .unwrap_or_else(|e| format!("<unserializable: {}>", e));
+
+    let message = &transaction.message;
+    let instructions: Vec<String> = message
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(i, ix)| {
+            let program_id = message
+                .account_keys
+                .get(ix.program_id_index as usize)
+                .map(|key| key.to_string())
+                .unwrap_or_else(|| "?".to_string());
+            format!("#{} program={} accounts={} data_len={}", i, program_id, ix.accounts.len(), ix.data.len())
+        })
+        .collect();
+
+    format!("base64={} instructions=[{}]", encoded, instructions.join("; "))
+}
+
+/// Checks a trade lists one amount per wallet, and no wallet more than once.
+///
+/// A wallet listed twice would sign two transactions of one bundle, spending the same
+/// balance twice.
+///
+/// # Errors
+/// Returns a message naming the first duplicated wallet ID, or the mismatched counts.
+pub fn validate_wallet_ids(amounts: usize, wallet_ids: &[impl AsRef<str>], amount_kind: &str) -> Result<(), String> {
+    if amounts != wallet_ids.len() {
+        return Err(format!(
+            "Number of {} ({}) must match number of wallet IDs ({})",
+            amount_kind,
+            amounts,
+            wallet_ids.len()
+        ));
+    }
+    let mut seen = std::collections::HashSet::with_capacity(wallet_ids.len());
+    match wallet_ids.iter().map(AsRef::as_ref).find(|wallet_id| !seen.insert(*wallet_id)) {
+        Some(duplicate) => Err(format!("Wallet ID {} is listed more than once", duplicate)),
+        None => Ok(()),
+    }
+}
+
+/// Parses a trade's referrer, if it names one.
+///
+/// # Errors
+/// Returns a message naming the referrer if it isn't a valid wallet address.
+pub fn parse_referrer(referrer: Option<&str>) -> Result<Option<Pubkey>, String> {
+    referrer
+        .map(|address| Pubkey::from_str(address).map_err(|_| format!("Invalid referrer address: {}", address)))
+        .transpose()
+}
+
+/// Checks that a buy's SOL amounts don't sum past an optional cap, compared in lamports so
+/// amounts adding up to exactly the cap pass.
+///
+/// # Errors
+/// Names the total and the cap when the buy would spend more.
+pub fn check_max_total_sol(sol_amounts: &[f64], max_total_sol: Option<f64>) -> Result<()> {
+    let Some(max_total_sol) = max_total_sol else {
+        return Ok(());
+    };
+    let total_lamports: u64 = sol_amounts.iter().map(|sol| (sol * 1e9).round() as u64).sum();
+    if max_total_sol.is_nan() || total_lamports > (max_total_sol * 1e9).round() as u64 {
+        return Err(anyhow::anyhow!(
+            "SOL amounts total {} SOL, more than the maximum of {} SOL",
+            total_lamports as f64 / 1e9,
+            max_total_sol
+        ));
+    }
+    Ok(())
+}
+
+/// Position of the instruction setting a transaction's compute unit limit, if it sets one.
+fn compute_unit_limit_index(transaction: &Transaction) -> Option<usize> {
+    let limit_tag = ComputeBudgetInstruction::set_compute_unit_limit(0).data[0];
+    let message = &transaction.message;
+    message.instructions.iter().position(|ix| {
+        message.account_keys[ix.program_id_index as usize] == compute_budget::id()
+            && ix.data.first() == Some(&limit_tag)
+    })
+}
+
+/// Rewrites the compute unit limit a transaction requests.
+///
+/// # Errors
+/// Returns an error if the transaction doesn't request a limit.
+fn set_compute_unit_limit(transaction: &mut Transaction, units: u32) -> Result<()> {
+    let index = compute_unit_limit_index(transaction)
+        .ok_or_else(|| anyhow::anyhow!("Transaction does not set a compute unit limit"))?;
+    transaction.message.instructions[index].data = ComputeBudgetInstruction::set_compute_unit_limit(units).data;
+    Ok(())
+}
+
+/// Signs a transaction again after its message changed, with those of `signers` it requires.
+fn resign(transaction: &mut Transaction, signers: &[&Keypair]) -> Result<()> {
+    let required = &transaction.message.account_keys[..transaction.message.header.num_required_signatures as usize];
+    let transaction_signers: Vec<&Keypair> = signers.iter()
+        .copied()
+        .filter(|signer| required.contains(&signer.pubkey()))
+        .collect();
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction.try_sign(&transaction_signers, recent_blockhash)?;
+    Ok(())
+}
+
+/// A message's instructions, with their accounts resolved back from the account table.
+fn decompile_instructions(message: &Message) -> Vec<Instruction> {
+    message.instructions.iter()
+        .map(|ix| Instruction {
+            program_id: message.account_keys[ix.program_id_index as usize],
+            accounts: ix.accounts.iter()
+                .map(|&i| AccountMeta {
+                    pubkey: message.account_keys[i as usize],
+                    is_signer: message.is_signer(i as usize),
+                    is_writable: message.is_writable(i as usize),
+                })
+                .collect(),
+            data: ix.data.clone(),
+        })
+        .collect()
+}
+
+/// The compute unit price that would have landed in `landing_probability` of recent slots.
+///
+/// Each recent fee is the lowest price that landed in its slot, so this is that percentile
+/// of them, by nearest rank. With no recent fees there is nothing to outbid.
+pub fn priority_fee_for_landing(recent_fees: &[u64], landing_probability: f64) -> u64 {
+    if recent_fees.is_empty() {
+        return 0;
+    }
+    let mut fees = recent_fees.to_vec();
+    fees.sort_unstable();
+    let rank = (landing_probability.clamp(0.0, 1.0) * fees.len() as f64).ceil() as usize;
+    fees[rank.clamp(1, fees.len()) - 1]
+}
+
+/// Sizes a new mint's account: the bytes allocated when it is created, and the length it
+/// grows to once embedded metadata is written, which its rent must already cover.
+pub fn mint_account_len(metadata: &TokenMetadata, mint_options: &MintOptions) -> Result<(usize, usize)> {
+    use spl_token_2022::extension::ExtensionType;
+
+    if mint_options.token_program == TokenProgram::Legacy {
+        return Ok((spl_token::state::Mint::LEN, spl_token::state::Mint::LEN));
+    }
+    let extension_types: Vec<ExtensionType> = mint_options.extensions.iter()
+        .map(|extension| match extension {
+            MintExtension::TransferFee { .. } => ExtensionType::TransferFeeConfig,
+            MintExtension::Metadata => ExtensionType::MetadataPointer,
+        })
+        .collect();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extension_types)
+        .context("Failed to size the mint account")?;
+    if !mint_options.extensions.contains(&MintExtension::Metadata) {
+        return Ok((space, space));
+    }
+    let embedded = spl_token_metadata_interface::state::TokenMetadata {
+        name: metadata.name.clone(),
+        symbol: metadata.symbol.clone(),
+        uri: metadata.uri.clone().unwrap_or_default(),
+        ..Default::default()
+    };
+    let metadata_len = embedded.tlv_size_of().context("Failed to size the embedded metadata")?;
+    Ok((space, space + metadata_len))
+}
+
+/// The length of a token account holding a mint created with `mint_options`, as the
+/// associated token account program allocates it.
+pub fn token_account_len(mint_options: &MintOptions) -> Result<usize> {
+    use spl_token_2022::extension::ExtensionType;
+
+    if mint_options.token_program == TokenProgram::Legacy {
+        return Ok(spl_token::state::Account::LEN);
+    }
+    let mint_types: Vec<ExtensionType> = mint_options.extensions.iter()
+        .filter(|extension| matches!(extension, MintExtension::TransferFee { .. }))
+        .map(|_| ExtensionType::TransferFeeConfig)
+        .collect();
+    let mut account_types = ExtensionType::get_required_init_account_extensions(&mint_types);
+    account_types.push(ExtensionType::ImmutableOwner);
+    ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&account_types)
+        .context("Failed to size the token account")
+}
+
+/// Converts a whole-token amount to the mint's base units.
+///
+/// Rounds to the nearest base unit, so float error like `1.001 * 1e6` landing just below
+/// a whole unit doesn't lose one.
+///
+/// # Errors
+/// Returns `PumpFunError::InvalidTokenAmount` for a negative or non-finite amount, one too
+/// large for a `u64`, or a positive amount that rounds to nothing.
+pub fn ui_to_raw_amount(ui_amount: f64, decimals: u8) -> Result<u64> {
+    let invalid = |reason: &str| PumpFunError::InvalidTokenAmount { amount: ui_amount, reason: reason.to_string() };
+    if !ui_amount.is_finite() || ui_amount < 0.0 {
+        return Err(invalid("must be a non-negative number").into());
+    }
+    let raw = (ui_amount * 10f64.powi(decimals as i32)).round();
+    if raw >= u64::MAX as f64 {
+        return Err(invalid("too large for the mint's base units").into());
+    }
+    if raw == 0.0 && ui_amount > 0.0 {
+        return Err(invalid(&format!("smaller than one base unit at {} decimals", decimals)).into());
+    }
+    Ok(raw as u64)
+}
+
+/// Converts an amount in a mint's base units to whole tokens.
+pub fn raw_to_ui_amount(raw_amount: u64, decimals: u8) -> f64 {
+    raw_amount as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Each holding's raw balance in whole tokens, the unit the bonding curve is priced in.
+fn whole_token_balances(holdings: &[(&str, &Keypair, u64)]) -> Vec<f64> {
+    holdings.iter().map(|(_, _, balance)| raw_to_ui_amount(*balance, PUMP_TOKEN_DECIMALS)).collect()
+}
+
+/// How a trade's signed transactions are submitted, and the fee they carry
+#[derive(Clone, Copy)]
+struct TradeSubmission {
+    fee: f64,
+    trade_volume_sol: f64, // Checked against the Jito tip
+    dry_run: bool,
+    via_bundle: bool,
+}
+
+/// Checks that no two wallet IDs resolved to the same key, whose wallet would otherwise buy
+/// and pay its fees twice, whatever the amounts and however the buy is split into transactions,
+/// or be swept twice.
+///
+/// # Errors
+/// Names both wallet IDs and the address they share.
+fn reject_shared_wallets(wallet_ids: &[String], wallets: &[Option<&Keypair>]) -> std::result::Result<(), String> {
+    let mut seen = std::collections::HashMap::new();
+    for (wallet_id, wallet) in wallet_ids.iter().zip(wallets) {
+        let Some(wallet) = wallet else {
+            continue;
+        };
+        if let Some(first) = seen.insert(wallet.pubkey(), wallet_id) {
+            return Err(format!(
+                "Wallet IDs {} and {} are the same wallet {}; list each wallet once",
+                first,
+                wallet_id,
+                wallet.pubkey()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A uniformly random wait within `[min, max]` milliseconds.
+fn snipe_delay<R: Rng + ?Sized>((min, max): (u64, u64), rng: &mut R) -> Duration {
+    Duration::from_millis(rng.gen_range(min..=max))
+}
+
+/// One wallet's share of a multi-wallet buy
+struct BuyLeg<'a> {
+    wallet_id: &'a str,
+    wallet: &'a Keypair,
+    sol_amount: f64,
+    expected_tokens: f64,
+    min_tokens_out: f64,
+}
+
+/// One wallet's transfer in a sweep to the treasury
+struct SweepLeg<'a> {
+    wallet_id: &'a str,
+    wallet: &'a Keypair,
+    lamports: u64,
+}
+
+/// One resolved wallet's share of a multi-wallet sell
+struct SellLeg<'a> {
+    index: usize, // Position of the wallet in the request
+    wallet_id: &'a str,
+    wallet: &'a Keypair,
+    token_amount: u64, // Raw base units
+}
+
+impl SellLeg<'_> {
+    /// The leg's amount in whole tokens, the unit the bonding curve is priced in.
+    fn whole_tokens(&self) -> f64 {
+        raw_to_ui_amount(self.token_amount, PUMP_TOKEN_DECIMALS)
+    }
+}
+
+/// A wallet's own signed transaction within a multi-wallet trade
+struct WalletTransaction<'a> {
+    wallet: &'a Keypair,
+    transaction: Transaction,
+    trade_sol: f64, // The SOL its fee is charged on
+}
+
+/// The same failure for each of `count` wallets.
+fn failed_wallets(count: usize, error: impl Into<String>) -> Vec<TransactionResult> {
+    vec![TransactionResult::failure(error); count]
+}
+
+/// Picks the caller's minimum outputs for the wallets that are trading, if any were given.
+fn explicit_leg_minimums(minimums: Option<&[f64]>, legs: &[SellLeg]) -> Option<Vec<f64>> {
+    minimums.map(|minimums| legs.iter().filter_map(|leg| minimums.get(leg.index).copied()).collect())
+}
+
+/// Size of a transaction on the wire, in bytes.
+fn transaction_size(transaction: &Transaction) -> Result<usize> {
+    Ok(bincode::serialized_size(transaction).context("Failed to size transaction")? as usize)
+}
+
+/// Checks a transaction fits in a single packet before it's sent.
+///
+/// # Errors
+/// Returns an error giving the transaction's size when it exceeds `PACKET_DATA_SIZE`.
+pub fn validate_transaction_size(transaction: &Transaction) -> Result<()> {
+    let size = transaction_size(transaction)?;
+    if size > PACKET_DATA_SIZE {
+        return Err(anyhow::anyhow!(
+            "Transaction is {} bytes but the limit is {}; use fewer wallets per transaction",
+            size,
+            PACKET_DATA_SIZE
+        ));
+    }
+    Ok(())
+}
+
+/// Serializes a transaction to base64-encoded wire format.
+pub fn encode_transaction(transaction: &Transaction) -> Result<String> {
+    let bytes = bincode::serialize(transaction).context("Failed to serialize transaction")?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// Serializes signed transactions to the base64 encoding `JitoBundleClient::submit_bundle` expects.
+///
+/// # Errors
+/// Returns an error if any transaction is too large to send.
+pub fn build_signed_transactions(transactions: &[Transaction]) -> Result<Vec<String>> {
+    transactions
+        .iter()
+        .map(|transaction| {
+            validate_transaction_size(transaction)?;
+            encode_transaction(transaction)
+        })
+        .collect()
+}
+
+/// Submits exit bundles in order, stopping at the first the block engine refuses.
+///
+/// # Returns
+/// A `Result` containing the ids of the bundles accepted and, if submission stopped early,
+/// why. It is only an error when not even the first bundle could be sent.
+async fn submit_exit_bundles(
+    bundles: &[Vec<Transaction>],
+    jito_client: &JitoBundleClient,
+) -> Result<(Vec<String>, Option<String>)> {
+    let mut bundle_ids = Vec::with_capacity(bundles.len());
+    for bundle in bundles {
+        match jito_client.submit_bundle(build_signed_transactions(bundle)?).await {
+            Ok(response) => match response.error {
+                Some(error) => return Ok((bundle_ids, Some(format!("Exit bundle rejected: {}", error)))),
+                None => bundle_ids.push(response.bundle_id),
+            },
+            Err(e) if bundle_ids.is_empty() => return Err(e.context("Failed to submit exit bundle")),
+            Err(e) => return Ok((bundle_ids, Some(format!("Failed to submit exit bundle: {:#}", e)))),
+        }
+    }
+    Ok((bundle_ids, None))
+}
+
+/// On-chain layout of a Pump.Fun bonding curve account, after the discriminator
+#[derive(BorshSerialize, BorshDeserialize)]
+struct BondingCurveAccount {
+    virtual_token_reserves: u64,
+    virtual_sol_reserves: u64,
+    real_token_reserves: u64,
+    real_sol_reserves: u64,
+    token_total_supply: u64,
+    complete: bool,
+}
+
+/// Decodes a bonding curve account into SOL and whole-token reserves.
+///
+/// Prices follow the virtual reserves. Newer accounts append fields after `complete`,
+/// which are ignored.
+///
+/// # Errors
+/// Returns an error if the discriminator doesn't match or the account is too short.
+pub fn parse_bonding_curve(token_mint: &Pubkey, data: &[u8]) -> Result<BondingCurveData> {
+    let (discriminator, mut body) = data
+        .split_first_chunk::<{ discriminators::DISCRIMINATOR_LEN }>()
+        .ok_or_else(|| anyhow::anyhow!("Bonding curve account is too short"))?;
+    if *discriminator != discriminators::BONDING_CURVE {
+        return Err(anyhow::anyhow!("Account is not a bonding curve"));
+    }
+
+    let account = BondingCurveAccount::deserialize(&mut body)
+        .context("Failed to deserialize bonding curve data")?;
+
+    let token_unit = 10f64.powi(PUMP_TOKEN_DECIMALS as i32);
+    let sol_reserve = account.virtual_sol_reserves as f64 / 1e9;
+    let token_reserve = account.virtual_token_reserves as f64 / token_unit;
+    Ok(BondingCurveData {
+        token_address: token_mint.to_string(),
+        current_price: if token_reserve > 0.0 { sol_reserve / token_reserve } else { 0.0 },
+        total_supply: account.token_total_supply,
+        sol_reserve,
+        token_reserve,
+        complete: account.complete,
+    })
+}
+
+/// Buy instruction data structure for Pump.Fun
+#[derive(BorshSerialize, BorshDeserialize)]
+struct BuyInstructionData {
+    discriminator: Discriminator,
+    sol_amounts: Vec<f64>,
+    expected_tokens: Vec<f64>,
+    min_tokens_out: Vec<f64>,
+    wallet_ids: Vec<String>,
+}
+
+/// Sell instruction data structure for Pump.Fun
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SellInstructionData {
+    discriminator: Discriminator,
+    token_amounts: Vec<f64>,
+    wallet_ids: Vec<String>,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::fees::FeeSchedule;
+
+    /// Encodes a bonding curve account with the given SOL and whole-token virtual reserves.
+    pub(crate) fn curve_account_bytes(sol_reserve: f64, token_reserve: f64) -> Vec<u8> {
+        let account = BondingCurveAccount {
+            virtual_token_reserves: (token_reserve * 1e6) as u64,
+            virtual_sol_reserves: (sol_reserve * 1e9) as u64,
+            real_token_reserves: (token_reserve * 1e6) as u64,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+        let mut data = discriminators::BONDING_CURVE.to_vec();
+        data.extend(borsh::to_vec(&account).unwrap());
+        data
+    }
+
+    fn test_client() -> PumpFunClient {
+        let config = PumpFunConfig::default();
+        PumpFunClient::new(config.program_id, config.fee_address)
+    }
+
+    #[test]
+    fn test_validate_token_metadata() {
+        let client = test_client();
+        let mut validation = ValidationResult::new();
+        let metadata = TokenMetadata {
+            name: "".to_string(),
+            symbol: "TOOLONGSYMBOL".to_string(),
+            description: "".to_string(),
+            image_url: "invalid_url".to_string(),
+            telegram_link: Some("".to_string()),
+            twitter_link: None,
+            website: None,
+            discord: None,
+            extra_links: Default::default(),
+            uri: None,
+            decimals: 12,
+        };
+
+        client.validate_token_metadata(&metadata, &mut validation);
+        assert!(!validation.is_valid);
+        // Name, symbol, decimals, description, image, and the empty telegram link
+        assert_eq!(validation.errors.len(), 6);
+    }
+
+    #[test]
+    fn test_social_links_are_optional_but_checked_when_present() {
+        let client = test_client();
+        let validate = |metadata: TokenMetadata| {
+            let mut validation = ValidationResult::new();
+            client.validate_token_metadata(&metadata, &mut validation);
+            validation
+        };
+
+        let bare = TokenMetadata { telegram_link: None, twitter_link: None, ..valid_metadata() };
+        assert!(validate(bare).is_valid);
+
+        let mut full = valid_metadata();
+        full.twitter_link = Some("@test_token".to_string());
+        full.website = Some("https://test.example".to_string());
+        full.discord = Some("https://discord.gg/test".to_string());
+        full.extra_links.insert("medium".to_string(), "https://medium.com/@test".to_string());
+        let validation = validate(full);
+        assert!(validation.is_valid, "{:?}", validation.errors);
+
+        for twitter in ["https://x.com/test", "https://twitter.com/test/"] {
+            let metadata = TokenMetadata { twitter_link: Some(twitter.to_string()), ..valid_metadata() };
+            assert!(validate(metadata).is_valid, "{}", twitter);
+        }
+        for twitter in ["@has space", "@waytoolonghandle_x", "https://example.com/test", "https://x.com/a/status/1"] {
+            let metadata = TokenMetadata { twitter_link: Some(twitter.to_string()), ..valid_metadata() };
+            assert!(!validate(metadata).is_valid, "{}", twitter);
+        }
+
+        let metadata = TokenMetadata { website: Some("not a url".to_string()), ..valid_metadata() };
+        assert!(validate(metadata).errors.iter().any(|e| e.contains("Website")));
+        let metadata = TokenMetadata { discord: Some("javascript:alert(1)".to_string()), ..valid_metadata() };
+        assert!(!validate(metadata).is_valid);
+        let mut metadata = valid_metadata();
+        metadata.extra_links.insert("blog".to_string(), "ftp://example.com".to_string());
+        assert!(validate(metadata).errors.iter().any(|e| e.contains("\"blog\"")));
+    }
+
+    #[test]
+    fn test_new_links_stay_out_of_the_on_chain_layout() {
+        let mut metadata = valid_metadata();
+        let before = borsh::to_vec(&TokenMetadataV1::from(&metadata)).unwrap();
+        metadata.website = Some("https://test.example".to_string());
+        metadata.extra_links.insert("medium".to_string(), "https://medium.com/@test".to_string());
+        let after = borsh::to_vec(&TokenMetadataV1::from(&metadata)).unwrap();
+        assert_eq!(before, after);
+        assert_eq!(TokenMetadataV1::try_from_slice(&after).unwrap(), TokenMetadataV1::from(&metadata));
+    }
+
+    #[test]
+    fn test_metadata_rejects_hidden_characters_and_lowercase_symbols() {
+        let client = test_client();
+        let validate = |metadata: TokenMetadata| {
+            let mut validation = ValidationResult::new();
+            client.validate_token_metadata(&metadata, &mut validation);
+            validation
+        };
+
+        let clean = validate(clean_metadata());
+        assert!(clean.is_valid, "{:?}", clean.errors);
+        assert!(clean.warnings.is_empty());
+
+        let newline = validate(TokenMetadata { name: "Test\nToken".to_string(), ..valid_metadata() });
+        assert!(newline.errors.iter().any(|e| e.contains("control or zero-width")));
+
+        // "TEST" with a zero-width joiner renders exactly like the real thing
+        let zero_width = validate(TokenMetadata { name: "Te\u{200D}st".to_string(), ..valid_metadata() });
+        assert!(!zero_width.is_valid);
+
+        let symbol = validate(TokenMetadata { symbol: "tEST".to_string(), ..valid_metadata() });
+        assert!(symbol.errors.iter().any(|e| e.contains("uppercase")));
+        let symbol = validate(TokenMetadata { symbol: "TE\u{200B}ST".to_string(), ..valid_metadata() });
+        assert!(!symbol.is_valid);
+
+        // A Cyrillic "е" is allowed, but flagged
+        let homoglyph = validate(TokenMetadata { name: "T\u{0435}st Token".to_string(), ..clean_metadata() });
+        assert!(homoglyph.is_valid);
+        assert_eq!(homoglyph.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_metadata_warnings_do_not_fail_validation() {
+        let client = test_client();
+        let validate = |metadata: TokenMetadata| {
+            let mut validation = ValidationResult::new();
+            client.validate_token_metadata(&metadata, &mut validation);
+            validation
+        };
+
+        let no_website = validate(valid_metadata());
+        assert!(no_website.is_valid, "{:?}", no_website.errors);
+        assert_eq!(no_website.warnings.len(), 1);
+        assert!(no_website.warnings[0].contains("website"));
+
+        let warned = validate(TokenMetadata {
+            symbol: "420".to_string(),
+            description: "x".repeat(190),
+            ..valid_metadata()
+        });
+        assert!(warned.is_valid, "{:?}", warned.errors);
+        assert_eq!(warned.warnings.len(), 3);
+        assert!(warned.warnings.iter().any(|w| w.contains("190 of 200")));
+        assert!(warned.warnings.iter().any(|w| w.contains("no letters")));
+
+        let single = validate(TokenMetadata { symbol: "X".to_string(), ..clean_metadata() });
+        assert!(single.is_valid);
+        assert!(single.warnings[0].contains("single character"));
+
+        // Past the limit it's an error, not a warning
+        let too_long = validate(TokenMetadata { description: "x".repeat(201), ..clean_metadata() });
+        assert!(!too_long.is_valid);
+        assert!(too_long.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_sol_for_tokens() {
+        let client = test_client();
+        let bonding_curve = BondingCurveData {
+            token_address: "test_token".to_string(),
+            current_price: 0.001,
+            total_supply: 1000000,
+            sol_reserve: 1000.0,
+            token_reserve: 1000000.0,
+            complete: false,
+        };
+
+        let result = client.calculate_sol_for_tokens(1000.0, &bonding_curve).unwrap();
+        assert!(result > 0.0);
+    }
+
+    #[test]
+    fn test_sells_are_priced_against_a_growing_token_reserve() {
+        let client = test_client();
+        let curve = client.initial_bonding_curve(&Pubkey::new_unique());
+        let tokens = 10_000_000.0;
+
+        let quote = client.sell_quote(tokens, &curve).unwrap();
+        let k = curve.sol_reserve * curve.token_reserve;
+        let sol_before_fee = curve.sol_reserve - k / (curve.token_reserve + tokens);
+        assert!((quote.sol_out + quote.fee - sol_before_fee).abs() < 1e-9);
+        assert!(quote.price_after < curve.current_price);
+        // A round trip loses to the curve's spread as well as the fees
+        assert!(quote.sol_out < client.calculate_sol_for_tokens(tokens, &curve).unwrap());
+        assert_eq!(client.calculate_sol_for_sell(tokens, &curve).unwrap(), quote.sol_out);
+
+        // Selling more than the token reserve only drives the price further down
+        let dump = client.sell_quote(curve.token_reserve * 2.0, &curve).unwrap();
+        assert!(dump.sol_out < curve.sol_reserve);
+        assert!(client.calculate_sol_for_sell(-1.0, &curve).is_err());
+    }
+
+    #[test]
+    fn test_validate_fee_transfer_rejects_misconfigured_fee() {
+        let mut client = test_client();
+        assert!(client.validate_fee_transfer(0.005, 1.0).is_ok());
+
+        // A fat-fingered trading fee of 50% must not slip through
+        client.config.fee_schedule = FeeSchedule::flat(0.5);
+        let fee = client.config.fee_schedule.fee_for(1.0);
+        let err = client.validate_fee_transfer(fee, 1.0).unwrap_err();
+        assert!(err.to_string().contains("exceeds"));
+
+        // Flat fees are held to the absolute cap
+        client.config.creation_fee = 25.0;
+        assert!(client.validate_fee_transfer(client.config.creation_fee, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_default_slippage_applies_without_minimums() {
+        let client = test_client();
+        let quoted_curve = BondingCurveData {
+            token_address: "test_token".to_string(),
+            current_price: 0.001,
+            total_supply: 1000000,
+            sol_reserve: 1000.0,
+            token_reserve: 1000000.0,
+            complete: false,
+        };
+        // Someone bought ahead of us and moved the price ~20%
+        let moved_curve = BondingCurveData {
+            sol_reserve: 1100.0,
+            token_reserve: 909090.0,
+            complete: false,
+            ..quoted_curve.clone()
+        };
+
+        let quotes = vec![client.calculate_tokens_for_sol(1.0, &quoted_curve).unwrap()];
+        let minimums = client.min_outputs(None, &quotes).unwrap();
+        assert!(minimums[0] < quotes[0]);
+
+        // A fill at the quote is fine
+        assert!(client.check_slippage(&quotes, &minimums).is_ok());
+
+        // The slipped fill is rejected even though the request carried no minimums
+        let fills = vec![client.calculate_tokens_for_sol(1.0, &moved_curve).unwrap()];
+        assert!(client.check_slippage(&fills, &minimums).is_err());
+    }
+
+    fn test_curve() -> BondingCurveData {
+        BondingCurveData {
+            token_address: "test_token".to_string(),
+            current_price: 0.001,
+            total_supply: 1000000,
+            sol_reserve: 1000.0,
+            token_reserve: 1000000.0,
+            complete: false,
+        }
+    }
+
+    #[test]
+    fn test_quote_sol_in() {
+        let client = test_client();
+        let curve = test_curve();
+
+        let quote = client.quote(QuoteInput::Sol(10.0), &curve).unwrap();
+        assert_eq!(quote.sol_amount, 10.0);
+        assert!(quote.token_amount > 0.0 && quote.token_amount < 10000.0);
+        assert!(quote.fee > 0.0);
+        assert!(quote.price_impact_pct > 0.0);
+        assert!((quote.token_amount - client.calculate_tokens_for_sol(10.0, &curve).unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_quote_tokens_in() {
+        let client = test_client();
+        let curve = test_curve();
+
+        let quote = client.quote(QuoteInput::Tokens(10000.0), &curve).unwrap();
+        assert_eq!(quote.token_amount, 10000.0);
+        assert!(quote.sol_amount > 10.0);
+        assert!(quote.fee > 0.0);
+        assert!(quote.price_impact_pct > 0.0);
+        assert!((quote.sol_amount - client.calculate_sol_for_tokens(10000.0, &curve).unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_simulated_buy_reports_worst_case_impact_and_fees() {
+        let client = test_client();
+        let curve = test_curve();
+        let jito_client = JitoBundleClient::new("http://127.0.0.1:1".to_string());
+        let mut request = BuyRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            sol_amounts: vec![1.0, 2.0],
+            wallet_ids: vec!["w-000001".to_string(), "w-000002".to_string()],
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: true,
+            strategy: None,
+            referrer: None,
+            max_total_sol: None,
+        };
+
+        let simulation = client.simulate_trade(&request, &curve, &jito_client).unwrap();
+        assert_eq!(simulation.rejection, None);
+        for (i, (expected, worst)) in simulation.expected_tokens.iter().zip(&simulation.worst_case_tokens).enumerate() {
+            assert!((expected - client.calculate_tokens_for_sol(request.sol_amounts[i], &curve).unwrap()).abs() < 1e-9);
+            assert!(worst <= expected);
+            // The default 5% slippage
+            assert!((worst - expected * 0.95).abs() < 1e-9);
+        }
+        assert!(simulation.total_worst_case_tokens <= simulation.total_expected_tokens);
+        assert!(simulation.price_impact_pct > 0.0);
+        assert!(simulation.price_after > simulation.price_before);
+        assert!(simulation.costs.trading_fee > 0.0);
+        assert!(simulation.costs.jito_tip > 0.0);
+        assert!(simulation.costs.network_fee > 0.0);
+        assert!((simulation.costs.base_amount - 3.0).abs() < 1e-9);
+
+        // Explicit minimums are the worst case, and one above the fill would fail
+        request.min_tokens_out = Some(vec![1.0, simulation.expected_tokens[1] * 2.0]);
+        let simulation = client.simulate_trade(&request, &curve, &jito_client).unwrap();
+        assert_eq!(simulation.worst_case_tokens[0], 1.0);
+        assert!(simulation.rejection.unwrap().contains("Slippage exceeded for wallet 1"));
+
+        // So would one moving the price further than allowed
+        request.min_tokens_out = None;
+        request.max_price_impact_bps = Some(1);
+        let simulation = client.simulate_trade(&request, &curve, &jito_client).unwrap();
+        assert!(simulation.rejection.unwrap().contains("Price impact"));
+
+        // Buys that can't be priced at all are errors
+        request.min_tokens_out = Some(vec![1.0]);
+        assert!(client.simulate_trade(&request, &curve, &jito_client).is_err());
+    }
+
+    #[test]
+    fn test_quote_rejects_invalid_amounts() {
+        let client = test_client();
+        let curve = test_curve();
+
+        assert!(client.quote(QuoteInput::Sol(0.0), &curve).is_err());
+        assert!(client.quote(QuoteInput::Sol(-1.0), &curve).is_err());
+        assert!(client.quote(QuoteInput::Tokens(-5.0), &curve).is_err());
+
+        // Buying out the whole reserve (or more) is impossible on a constant-product curve
+        assert!(client.quote(QuoteInput::Tokens(1000000.0), &curve).is_err());
+        assert!(client.quote(QuoteInput::Tokens(2000000.0), &curve).is_err());
+    }
+
+    fn mint_decimals(instructions: &[Instruction]) -> u8 {
+        match spl_token::instruction::TokenInstruction::unpack(&instructions[1].data).unwrap() {
+            spl_token::instruction::TokenInstruction::InitializeMint { decimals, .. } => decimals,
+            other => panic!("Expected InitializeMint, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_token_decimals_default_to_nine() {
+        let metadata: TokenMetadata = serde_json::from_value(serde_json::json!({
+            "name": "Test Token",
+            "symbol": "TEST",
+            "description": "A test token",
+            "image_url": "https://example.com/image.png",
+            "telegram_link": "https://t.me/test",
+            "twitter_link": "https://twitter.com/test"
+        }))
+        .unwrap();
+        assert_eq!(metadata.decimals, 9);
+
+        let client = test_client();
+        let instructions = client
+            .build_create_instructions(&metadata, &MintOptions::default(), &Pubkey::new_unique(), &Pubkey::new_unique(), 1_461_600)
+            .unwrap();
+        assert_eq!(mint_decimals(&instructions), 9);
+    }
+
+    #[test]
+    fn test_create_with_six_decimals() {
+        let client = test_client();
+        let metadata = TokenMetadata {
+            name: "Stable-ish".to_string(),
+            symbol: "STBL".to_string(),
+            description: "Six decimal token".to_string(),
+            image_url: "https://example.com/image.png".to_string(),
+            telegram_link: Some("https://t.me/test".to_string()),
+            twitter_link: Some("https://twitter.com/test".to_string()),
+            website: None,
+            discord: None,
+            extra_links: Default::default(),
+            uri: None,
+            decimals: 6,
+        };
+
+        let mut validation = ValidationResult::new();
+        client.validate_token_metadata(&metadata, &mut validation);
+        assert!(validation.is_valid);
+
+        let instructions = client
+            .build_create_instructions(&metadata, &MintOptions::default(), &Pubkey::new_unique(), &Pubkey::new_unique(), 1_461_600)
+            .unwrap();
+        assert_eq!(mint_decimals(&instructions), 6);
+    }
+
+    #[test]
+    fn test_create_instructions_use_the_chosen_token_program() {
+        use solana_sdk::system_instruction::SystemInstruction;
+
+        let client = test_client();
+        let metadata = valid_metadata();
+        let creator = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        for (token_program, other_program) in [
+            (TokenProgram::Legacy, spl_token_2022::id()),
+            (TokenProgram::Token2022, spl_token::id()),
+        ] {
+            let options = MintOptions { token_program, extensions: Vec::new() };
+            let program = token_program.id();
+            let instructions = client.build_create_instructions(&metadata, &options, &creator, &mint, 1_461_600).unwrap();
+
+            // The mint is owned by, and initialized through, the chosen program
+            match bincode::deserialize(&instructions[0].data).unwrap() {
+                SystemInstruction::CreateAccount { owner, space, .. } => {
+                    assert_eq!((owner, space), (program, spl_token::state::Mint::LEN as u64));
+                }
+                other => panic!("Expected CreateAccount, got {:?}", other),
+            }
+            assert_eq!(instructions[1].program_id, program);
+            assert_eq!(mint_decimals(&instructions), 9);
+
+            // Both ATAs are derived for it and created under it
+            for (ata_ix, owner) in instructions[2..4].iter().zip([creator, client.program_id]) {
+                assert_eq!(ata_ix.program_id, spl_associated_token_account::id());
+                assert_eq!(ata_ix.accounts[1].pubkey, get_associated_token_address_with_program_id(&owner, &mint, &program));
+                assert_eq!(ata_ix.accounts[5].pubkey, program);
+            }
+            assert!(instructions[4].accounts.iter().any(|account| account.pubkey == program));
+            assert!(instructions.iter()
+                .all(|ix| ix.program_id != other_program && ix.accounts.iter().all(|account| account.pubkey != other_program)));
+        }
+    }
+
+    #[test]
+    fn test_create_instructions_name_the_token_through_metaplex() {
+        use crate::metadata::{metadata_address, CreateMetadataAccountArgsV3, TOKEN_METADATA_PROGRAM_ID};
+
+        let client = test_client();
+        let metadata = TokenMetadata { uri: Some("ipfs://cid-json".to_string()), ..valid_metadata() };
+        let creator = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let instructions = client
+            .build_create_instructions(&metadata, &MintOptions::default(), &creator, &mint, 1_461_600)
+            .unwrap();
+
+        let metaplex: Vec<&Instruction> = instructions.iter()
+            .filter(|ix| ix.program_id == TOKEN_METADATA_PROGRAM_ID)
+            .collect();
+        assert_eq!(metaplex.len(), 1);
+        let ix = metaplex[0];
+        let (expected_pda, _) = Pubkey::find_program_address(
+            &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+            &TOKEN_METADATA_PROGRAM_ID,
+        );
+        assert_eq!(metadata_address(&mint), expected_pda);
+        let accounts: Vec<(Pubkey, bool, bool)> = ix.accounts.iter()
+            .map(|account| (account.pubkey, account.is_signer, account.is_writable))
+            .collect();
+        assert_eq!(accounts, vec![
+            (expected_pda, false, true),
+            (mint, false, false),
+            (creator, true, false),
+            (creator, true, true),
+            (creator, true, false),
+            (solana_sdk::system_program::id(), false, false),
+        ]);
+
+        // CreateMetadataAccountV3, naming the creator as its only, verified creator
+        assert_eq!(ix.data[0], 33);
+        let args = CreateMetadataAccountArgsV3::try_from_slice(&ix.data[1..]).unwrap();
+        assert_eq!(
+            (args.data.name.as_str(), args.data.symbol.as_str(), args.data.uri.as_str()),
+            ("Test Token", "TEST", "ipfs://cid-json")
+        );
+        let creators = args.data.creators.unwrap();
+        assert_eq!(creators.len(), 1);
+        assert_eq!((creators[0].address, creators[0].verified, creators[0].share), (creator.to_bytes(), true, 100));
+        assert!(args.is_mutable);
+        assert!(args.collection_details.is_none());
+
+        // Mints that embed their metadata don't need an account for it
+        let embedded = MintOptions { token_program: TokenProgram::Token2022, extensions: vec![MintExtension::Metadata] };
+        let instructions = client.build_create_instructions(&metadata, &embedded, &creator, &mint, 5_000_000).unwrap();
+        assert!(instructions.iter().all(|ix| ix.program_id != TOKEN_METADATA_PROGRAM_ID));
+
+        // Metaplex caps the URI
+        let long_uri = TokenMetadata { uri: Some(format!("https://example.com/{}", "a".repeat(200))), ..valid_metadata() };
+        assert!(client.build_create_instructions(&long_uri, &MintOptions::default(), &creator, &mint, 1_461_600).is_err());
+        let mut validation = ValidationResult::new();
+        client.validate_token_metadata(&long_uri, &mut validation);
+        assert!(validation.errors.iter().any(|error| error.contains("URI must be at most 200")));
+    }
+
+    #[test]
+    fn test_create_instructions_mint_the_initial_supply_to_the_curve() {
+        let mut client = test_client();
+        let creator = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let minted = |client: &PumpFunClient, token_program: TokenProgram, decimals: u8| -> Result<Vec<(Pubkey, u64)>> {
+            let metadata = TokenMetadata { decimals, ..valid_metadata() };
+            let options = MintOptions { token_program, extensions: Vec::new() };
+            let instructions = client.build_create_instructions(&metadata, &options, &creator, &mint, 1_461_600)?;
+            Ok(instructions.iter()
+                .filter(|ix| ix.program_id == token_program.id())
+                .filter_map(|ix| match spl_token_2022::instruction::TokenInstruction::unpack(&ix.data) {
+                    Ok(spl_token_2022::instruction::TokenInstruction::MintTo { amount }) => {
+                        assert_eq!((ix.accounts[0].pubkey, ix.accounts[2].pubkey), (mint, creator));
+                        Some((ix.accounts[1].pubkey, amount))
+                    }
+                    _ => None,
+                })
+                .collect())
+        };
+
+        // 1B tokens by default, scaled to base units and minted once into the program's ATA
+        for token_program in [TokenProgram::Legacy, TokenProgram::Token2022] {
+            let program_ata = get_associated_token_address_with_program_id(&client.program_id, &mint, &token_program.id());
+            assert_eq!(minted(&client, token_program, 6).unwrap(), vec![(program_ata, 1_000_000_000_000_000)]);
+            assert_eq!(minted(&client, token_program, 9).unwrap(), vec![(program_ata, 1_000_000_000_000_000_000)]);
+        }
+
+        client.config.initial_supply = 21_000_000;
+        let program_ata = get_associated_token_address_with_program_id(&client.program_id, &mint, &spl_token::id());
+        assert_eq!(minted(&client, TokenProgram::Legacy, 0).unwrap(), vec![(program_ata, 21_000_000)]);
+        assert_eq!(minted(&client, TokenProgram::Legacy, 2).unwrap(), vec![(program_ata, 2_100_000_000)]);
+
+        // A supply that doesn't fit in base units is refused rather than wrapped
+        client.config.initial_supply = 100_000_000_000;
+        let err = minted(&client, TokenProgram::Legacy, 9).unwrap_err();
+        assert!(err.to_string().contains("overflows a token amount at 9 decimals"), "{}", err);
+    }
+
+    #[test]
+    fn test_token_2022_mints_initialize_their_extensions_first() {
+        use spl_token_2022::instruction::TokenInstruction;
+
+        let client = test_client();
+        let metadata = TokenMetadata { uri: Some("ipfs://metadata".to_string()), ..valid_metadata() };
+        let options = MintOptions {
+            token_program: TokenProgram::Token2022,
+            extensions: vec![
+                MintExtension::TransferFee { basis_points: 100, maximum_fee: 5_000_000 },
+                MintExtension::Metadata,
+            ],
+        };
+        let mut validation = ValidationResult::new();
+        client.validate_mint_options(&options, &mut validation);
+        assert!(validation.is_valid, "{:?}", validation.errors);
+
+        let mint = Pubkey::new_unique();
+        let instructions = client
+            .build_create_instructions(&metadata, &options, &Pubkey::new_unique(), &mint, 5_000_000)
+            .unwrap();
+        assert!(instructions[1..5].iter().all(|ix| ix.program_id == spl_token_2022::id()));
+        assert!(matches!(
+            TokenInstruction::unpack(&instructions[1].data).unwrap(),
+            TokenInstruction::TransferFeeExtension(..)
+        ));
+        assert!(matches!(
+            TokenInstruction::unpack(&instructions[2].data).unwrap(),
+            TokenInstruction::MetadataPointerExtension
+        ));
+        assert!(matches!(
+            TokenInstruction::unpack(&instructions[3].data).unwrap(),
+            TokenInstruction::InitializeMint { decimals: 9, .. }
+        ));
+        // Embedded metadata is written to the mint itself once it exists
+        assert_eq!(instructions[4].accounts[0].pubkey, mint);
+
+        // Rent covers the metadata the mint grows by, and token accounts carry the fee extension
+        let (space, len) = mint_account_len(&metadata, &options).unwrap();
+        assert!(space > spl_token::state::Mint::LEN && len > space);
+        assert!(token_account_len(&options).unwrap() > spl_token::state::Account::LEN);
+        assert_eq!(token_account_len(&MintOptions::default()).unwrap(), spl_token::state::Account::LEN);
+    }
+
+    #[test]
+    fn test_creation_fee_is_split_between_recipients() {
+        use solana_sdk::system_instruction::SystemInstruction;
+
+        let mut client = test_client();
+        let creator = Pubkey::new_unique();
+        let fee_transfers = |client: &PumpFunClient| -> Result<Vec<(Pubkey, u64)>> {
+            let instructions = client.build_create_instructions(
+                &valid_metadata(),
+                &MintOptions::default(),
+                &creator,
+                &Pubkey::new_unique(),
+                1_461_600,
+            )?;
+            Ok(instructions.iter()
+                .filter(|ix| ix.program_id == solana_sdk::system_program::id())
+                .filter_map(|ix| match bincode::deserialize(&ix.data) {
+                    Ok(SystemInstruction::Transfer { lamports }) => Some((ix.accounts[1].pubkey, lamports)),
+                    _ => None,
+                })
+                .collect())
+        };
+
+        // Unsplit, the whole 0.01 SOL fee goes to the fee address
+        assert_eq!(fee_transfers(&client).unwrap(), vec![(client.fee_address, 10_000_000)]);
+
+        let (platform, referrer, treasury) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        client.config.fee_splits = vec![(platform, 0.6), (referrer, 0.3), (treasury, 0.1)];
+        assert_eq!(
+            fee_transfers(&client).unwrap(),
+            vec![(platform, 6_000_000), (referrer, 3_000_000), (treasury, 1_000_000)]
+        );
+
+        client.config.fee_splits = vec![(platform, 0.6), (referrer, 0.3)];
+        let err = fee_transfers(&client).unwrap_err();
+        assert!(err.to_string().contains("must sum to 100%"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_mint_extensions_are_rejected() {
+        let client = test_client();
+        let errors = |options: MintOptions| {
+            let mut validation = ValidationResult::new();
+            client.validate_mint_options(&options, &mut validation);
+            validation.errors
+        };
+        let transfer_fee = |basis_points| MintExtension::TransferFee { basis_points, maximum_fee: 1 };
+
+        assert_eq!(
+            errors(MintOptions { token_program: TokenProgram::Legacy, extensions: vec![MintExtension::Metadata] }),
+            vec!["Mint extensions need the token2022 token program"]
+        );
+        let duplicated = errors(MintOptions {
+            token_program: TokenProgram::Token2022,
+            extensions: vec![transfer_fee(100), MintExtension::Metadata, transfer_fee(200)],
+        });
+        assert_eq!(duplicated.len(), 1);
+        assert!(duplicated[0].contains("listed more than once"), "{:?}", duplicated);
+        assert_eq!(
+            errors(MintOptions { token_program: TokenProgram::Token2022, extensions: vec![transfer_fee(10_001)] }),
+            vec!["Transfer fee must be at most 10000 basis points"]
+        );
+
+        // Requests name the program and extensions in snake case, and are rejected before any RPC call
+        let request: CreateTokenRequest = serde_json::from_value(serde_json::json!({
+            "metadata": valid_metadata(),
+            "user_id": 1,
+            "wallet_id": "w-000001",
+            "extensions": [{ "type": "transfer_fee", "basis_points": 100, "maximum_fee": 5 }]
+        }))
+        .unwrap();
+        assert_eq!(request.mint_options().token_program, TokenProgram::Legacy);
+        let result = client
+            .create_token(valid_metadata(), &request.mint_options(), &Keypair::new(), None, &RpcPool::new_mock("fails"), true)
+            .await
+            .unwrap();
+        assert_eq!(result.error.as_deref(), Some("Mint extensions need the token2022 token program"));
+    }
+
+    #[test]
+    fn test_zero_reserve_curve_is_rejected() {
+        let client = test_client();
+        let curve = BondingCurveData {
+            token_reserve: 0.0,
+            complete: false,
+            ..test_curve()
+        };
+
+        let err = client.calculate_tokens_for_sol(1.0, &curve).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PumpFunError>(),
+            Some(&PumpFunError::InvalidCurveMath { sol_reserve: 1000.0, token_reserve: 0.0 })
+        );
+        assert!(client.quote(QuoteInput::Sol(1.0), &curve).is_err());
+        assert!(client.calculate_sol_for_tokens(1.0, &curve).is_err());
+    }
+
+    #[test]
+    fn test_curve_math_rejects_amounts_that_drain_the_reserves() {
+        let client = test_client();
+        let curve = test_curve();
+
+        // Just inside the token reserve is fine; the reserve itself or beyond is not
+        let near_drain = client.calculate_sol_for_tokens(curve.token_reserve - 1.0, &curve).unwrap();
+        assert!(near_drain.is_finite() && near_drain > 0.0);
+        for token_amount in [curve.token_reserve, curve.token_reserve + 1.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(
+                client.calculate_sol_for_tokens(token_amount, &curve).is_err(),
+                "{} tokens should be rejected",
+                token_amount
+            );
+            assert!(client.quote(QuoteInput::Tokens(token_amount), &curve).is_err());
+        }
+
+        // SOL can only be added to the curve
+        let tokens = client.calculate_tokens_for_sol(0.0, &curve).unwrap();
+        assert_eq!(tokens, 0.0);
+        for sol_amount in [-1.0, -curve.sol_reserve, -curve.sol_reserve - 1.0, f64::NAN, f64::INFINITY] {
+            assert!(
+                client.calculate_tokens_for_sol(sol_amount, &curve).is_err(),
+                "{} SOL should be rejected",
+                sol_amount
+            );
+            assert!(client.quote(QuoteInput::Sol(sol_amount), &curve).is_err());
+        }
+    }
+
+    #[test]
+    fn test_price_impact_grows_with_trade_size() {
+        let client = test_client();
+        let curve = test_curve();
+
+        let small = client.calculate_price_impact(1.0, &curve);
+        let large = client.calculate_price_impact(100.0, &curve);
+        assert!(small > 0.0);
+        assert!(large > small);
+
+        let small_sell = client.calculate_sell_price_impact(1000.0, &curve);
+        let large_sell = client.calculate_sell_price_impact(100000.0, &curve);
+        assert!(small_sell > 0.0);
+        assert!(large_sell > small_sell);
+    }
+
+    #[test]
+    fn test_max_price_impact_guard() {
+        let client = test_client();
+        let curve = test_curve();
+        let impact = client.calculate_price_impact(100.0, &curve);
+
+        assert!(client.check_price_impact(impact, Some(10)).is_err()); // 0.1%
+        assert!(client.check_price_impact(impact, Some(5000)).is_ok()); // 50%
+        assert!(client.check_price_impact(impact, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_buys_below_min_sol_amount_are_rejected() {
+        let mut client = test_client();
+        client.config.min_sol_amount = 0.05;
+        assert!(client.check_min_sol_amounts(&[0.05, 1.0]).is_ok());
+
+        let err = client.check_min_sol_amounts(&[0.1, 0.01, 0.5, 0.0, f64::NAN]).unwrap_err();
+        assert!(err.to_string().contains("indices [1, 3, 4]"), "{}", err);
+
+        // Rejected before any RPC call is made
+        let request = BuyRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            sol_amounts: vec![1.0, 0.001],
+            wallet_ids: vec!["w-000001".to_string(), "w-000002".to_string()],
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: false,
+            strategy: None,
+            referrer: None,
+            max_total_sol: None,
+        };
+        let results = client.buy_tokens(request, &wallet_store_with(&[]), &RpcPool::new_mock("fails"), None).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.error.as_ref().unwrap().contains("indices [1]")));
+    }
+
+    #[tokio::test]
+    async fn test_buys_over_max_total_sol_are_rejected() {
+        // Amounts summing to exactly the cap pass even where the floats don't add up exactly
+        assert!(check_max_total_sol(&[0.1, 0.2], Some(0.5)).is_ok());
+        assert!(check_max_total_sol(&[0.1, 0.2], Some(0.3)).is_ok());
+        assert!(check_max_total_sol(&[0.1, 0.2], None).is_ok());
+        let err = check_max_total_sol(&[0.1, 0.2], Some(0.29)).unwrap_err();
+        assert!(err.to_string().contains("total 0.3 SOL"), "{}", err);
+
+        // Rejected before any RPC call is made
+        let request = BuyRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            sol_amounts: vec![10.0; 16],
+            wallet_ids: (1..=16).map(|i| format!("w-{:06}", i)).collect(),
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: false,
+            strategy: None,
+            referrer: None,
+            max_total_sol: Some(16.0),
+        };
+        let results = test_client().buy_tokens(request, &wallet_store_with(&[]), &RpcPool::new_mock("fails"), None).await.unwrap();
+        assert!(!results[0].success);
+        assert!(results[0].error.as_ref().unwrap().contains("more than the maximum of 16 SOL"));
+    }
+
+    #[test]
+    fn test_buy_instruction_carries_token_amounts() {
+        let client = test_client();
+        let curve = test_curve();
+        let sol_amounts = vec![0.5, 1.0];
+        let wallet_ids = vec!["wallet_1".to_string(), "wallet_2".to_string()];
+
+        let expected: Vec<f64> = sol_amounts
+            .iter()
+            .map(|sol| client.calculate_tokens_for_sol(*sol, &curve).unwrap())
+            .collect();
+        let minimums = client.min_outputs(None, &expected).unwrap();
+
+        let ix = client
+            .create_buy_instruction(&Pubkey::new_unique(), &sol_amounts, &expected, &minimums, &wallet_ids)
+            .unwrap();
+        let data = BuyInstructionData::try_from_slice(&ix.data).unwrap();
+
+        assert_eq!(data.discriminator, discriminators::BUY);
+        assert_eq!(data.sol_amounts, sol_amounts);
+        assert_eq!(data.expected_tokens, expected);
+        assert_eq!(data.min_tokens_out, minimums);
+        assert_eq!(data.wallet_ids, wallet_ids);
+        assert!(data.expected_tokens.iter().all(|tokens| *tokens > 0.0));
+    }
+
+    #[actix_web::test]
+    async fn test_create_token_with_image_reports_upload_failure() {
+        let mut client = test_client();
+        let endpoint = crate::metadata::tests::start_mock_pinning_server(true).await;
+        client.uploader = Some(MetadataUploader::new(endpoint, "test-key".to_string()));
+
+        let metadata = TokenMetadata {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            description: "A test token".to_string(),
+            image_url: String::new(),
+            telegram_link: Some("https://t.me/test".to_string()),
+            twitter_link: Some("https://twitter.com/test".to_string()),
+            website: None,
+            discord: None,
+            extra_links: Default::default(),
+            uri: None,
+            decimals: 9,
+        };
+        // The upload fails before any RPC call is made
+        let rpc_client = RpcPool::new_mock("fails");
+        let err = client
+            .create_token_with_image(metadata, &MintOptions::default(), vec![1, 2, 3], "image/png", &Keypair::new(), None, &rpc_client, false)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<crate::metadata::UploadError>().is_some());
+    }
+
+    struct CaptureLogger;
+
+    static CAPTURED_LOGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED_LOGS.lock().unwrap().push(format!("{} {}", record.level(), record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_send_logs_transaction_base64() {
+        let _ = log::set_logger(&CaptureLogger);
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut client = test_client();
+        client.config.log_failed_transactions = true;
+        client.config.rpc_max_retries = 0;
+
+        let payer = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], solana_sdk::hash::Hash::new_unique());
+        let encoded = base64::engine::general_purpose::STANDARD
+            .encode(bincode::serialize(&transaction).unwrap());
+
+        // The "fails" mock returns null for every call, so the send errors out
+        let rpc_client = RpcPool::new_mock("fails");
+        assert!(client.send_transaction(&transaction, &[&payer], &rpc_client).await.is_err());
+
+        let logs = CAPTURED_LOGS.lock().unwrap();
+        let entry = logs.iter().find(|line| line.contains(&encoded)).expect("base64 not logged");
+        assert!(entry.starts_with("ERROR"));
+        assert!(entry.contains(&format!("program={}", solana_sdk::system_program::id())));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dry_run_create_returns_signed_transaction_without_sending() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let creator = Keypair::new();
+        let metadata = valid_metadata();
+
+        // A null sendTransaction response fails any submission, so success proves none happened
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetBalance,
+            serde_json::json!({ "context": { "slot": 1 }, "value": 10_000_000_000u64 }),
+        );
+        mocks.insert(RpcRequest::SendTransaction, serde_json::Value::Null);
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+
+        let result = client.create_token(metadata, &MintOptions::default(), &creator, None, &rpc_client, true).await.unwrap();
+        assert!(result.success);
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(result.serialized_tx.expect("dry run returns the transaction"))
+            .unwrap();
+        let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+        assert!(transaction.verify().is_ok());
+        assert_eq!(transaction.message.account_keys[0], creator.pubkey());
+        assert_eq!(result.signature, Some(transaction.signatures[0].to_string()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_multi_transaction_trades_send_every_transaction_in_order() {
+        let mut client = test_client();
+        client.config.max_concurrency = 2;
+        let payers: Vec<Keypair> = (0..5).map(|_| Keypair::new()).collect();
+        let transactions: Vec<Transaction> = payers.iter()
+            .map(|payer| {
+                let mut tx = Transaction::new_with_payer(
+                    &[system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1)],
+                    Some(&payer.pubkey()),
+                );
+                tx.sign(&[payer], solana_sdk::hash::Hash::new_unique());
+                tx
+            })
+            .collect();
+
+        let signers: Vec<&Keypair> = payers.iter().collect();
+        let results = client.send_transactions(&transactions, &signers, "buy", &RpcPool::new_mock("succeeds")).await;
+        assert_eq!(results.len(), 5);
+        for (result, transaction) in results.iter().zip(&transactions) {
+            assert!(result.success);
+            assert_eq!(result.signature, Some(transaction.signatures[0].to_string()));
+        }
+
+        let submission = TradeSubmission { fee: 0.01, trade_volume_sol: 1.0, dry_run: false, via_bundle: false };
+        let rpc_client = RpcPool::new_mock("succeeds");
+        let result = client.submit_trade(&transactions, &signers, submission, "buy", &rpc_client, None).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.signature, Some(transactions[0].signatures[0].to_string()));
+
+        // Each failed transaction is reported by position
+        let rpc_client = RpcPool::new_mock("instruction_error");
+        let result = client.submit_trade(&transactions, &signers, submission, "buy", &rpc_client, None).await.unwrap();
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert!(error.starts_with("5 of 5 buy transactions failed"), "{}", error);
+        assert!(error.contains("#4: "));
+    }
+
+    /// A mock node that takes `delay` to answer each send, counting how many it holds at once
+    struct SlowNode {
+        inner: solana_rpc_client::mock_sender::MockSender,
+        delay: Duration,
+        in_flight: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl solana_client::rpc_sender::RpcSender for SlowNode {
+        async fn send(
+            &self,
+            request: solana_client::rpc_request::RpcRequest,
+            params: serde_json::Value,
+        ) -> Result<serde_json::Value, solana_client::client_error::ClientError> {
+            use std::sync::atomic::Ordering;
+
+            if request == solana_client::rpc_request::RpcRequest::SendTransaction {
+                let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(self.delay).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transactions_are_sent_concurrently() {
+        let mut client = test_client();
+        client.config.max_concurrency = 2;
+        let payers: Vec<Keypair> = (0..4).map(|_| Keypair::new()).collect();
+        let transactions: Vec<Transaction> = payers.iter()
+            .map(|payer| {
+                let mut tx = Transaction::new_with_payer(
+                    &[system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1)],
+                    Some(&payer.pubkey()),
+                );
+                tx.sign(&[payer], solana_sdk::hash::Hash::new_unique());
+                tx
+            })
+            .collect();
+
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let node = SlowNode {
+            inner: solana_rpc_client::mock_sender::MockSender::new("succeeds"),
+            delay: Duration::from_millis(200),
+            in_flight: Arc::default(),
+            peak: peak.clone(),
+        };
+        let rpc_client = RpcPool::new(vec![node], 5, Duration::from_secs(30), false);
+
+        // Even on a single thread, sends waiting on the node mustn't hold up the others
+        let signers: Vec<&Keypair> = payers.iter().collect();
+        let started = Instant::now();
+        let results = client.send_transactions(&transactions, &signers, "buy", &rpc_client).await;
+        assert!(results.iter().all(|result| result.success), "{:?}", results);
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(started.elapsed() < Duration::from_millis(700), "took {:?}", started.elapsed());
+    }
+
+    fn simulation_consuming(units: u64) -> serde_json::Value {
+        serde_json::json!({
+            "context": { "slot": 1 },
+            "value": {
+                "err": null,
+                "logs": null,
+                "accounts": null,
+                "unitsConsumed": units,
+                "returnData": null,
+                "innerInstructions": null
+            }
+        })
+    }
+
+    #[test]
+    fn test_sell_estimate_counts_a_transaction_per_wallet() {
+        let client = test_client();
+        let jito_client = JitoBundleClient::new("https://test.api.jito.wtf".to_string());
+        let curve = client.initial_bonding_curve(&Pubkey::new_unique());
+        let sell = |wallet_count: usize| SellRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            token_amounts: vec![1_000_000; wallet_count],
+            ui_token_amounts: None,
+            wallet_ids: (1..=wallet_count).map(|n| format!("w-{:06}", n)).collect(),
+            user_id: 1,
+            min_sol_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: true,
+            strategy: None,
+            close_account: false,
+            referrer: None,
+        };
+
+        let one = client.estimate_sell_cost(&sell(1), &curve, &jito_client).unwrap();
+        let three = client.estimate_sell_cost(&sell(3), &curve, &jito_client).unwrap();
+        assert_eq!(one.transaction_count, 1);
+        assert_eq!(three.transaction_count, 3);
+        assert_eq!(three.network_fee, jito_client.calculate_bundle_fee(3));
+        assert!(three.network_fee > one.network_fee);
+        assert!((three.total_amount - (three.base_amount - three.total_fees)).abs() < 1e-12);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_estimate_compute_units_adds_margin() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let mut client = test_client();
+        client.config.compute_unit_margin_pct = 10.0;
+        let payer = Keypair::new();
+        let transaction = Transaction::new_with_payer(
+            &[ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)],
+            Some(&payer.pubkey()),
+        );
+        let rpc_with = |simulation: serde_json::Value| {
+            let mut mocks = HashMap::new();
+            mocks.insert(RpcRequest::SimulateTransaction, simulation);
+            RpcPool::new_mock_with_mocks("succeeds", mocks)
+        };
+
+        let units = client.estimate_compute_units(&transaction, &rpc_with(simulation_consuming(54_321))).await.unwrap();
+        assert_eq!(units, 59_754);
+
+        // Never more than a transaction may request
+        let units = client.estimate_compute_units(&transaction, &rpc_with(simulation_consuming(1_390_000))).await.unwrap();
+        assert_eq!(units, MAX_COMPUTE_UNIT_LIMIT);
+
+        let mut failed = simulation_consuming(2_000);
+        failed["value"]["err"] = serde_json::json!("AccountNotFound");
+        assert!(client.estimate_compute_units(&transaction, &rpc_with(failed)).await.is_err());
+    }
+
+    #[test]
+    fn test_priority_fee_sized_from_landing_probability() {
+        let recent = [0, 100, 5_000, 200, 1_000, 300, 0, 400, 700, 2_000];
+        assert_eq!(priority_fee_for_landing(&recent, 0.5), 300);
+        assert_eq!(priority_fee_for_landing(&recent, 0.75), 1_000);
+        assert_eq!(priority_fee_for_landing(&recent, 0.9), 2_000);
+        assert_eq!(priority_fee_for_landing(&recent, 1.0), 5_000);
+        // Landing in any slot at all only needs the cheapest
+        assert_eq!(priority_fee_for_landing(&recent, 0.0), 0);
+        assert_eq!(priority_fee_for_landing(&[], 0.75), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_attach_priority_fees_prices_from_recent_fees() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let mut client = test_client();
+        client.config.priority_fee_landing_probability = 0.75;
+        let recent_fees: Vec<serde_json::Value> = [50, 10, 0, 40, 20, 30, 80, 60]
+            .iter()
+            .enumerate()
+            .map(|(slot, fee)| serde_json::json!({ "slot": slot, "prioritizationFee": fee }))
+            .collect();
+        let rpc_client = || RpcPool::new_mock_with_mocks("succeeds", HashMap::from([
+            (RpcRequest::GetRecentPrioritizationFees, serde_json::json!(recent_fees)),
+        ]));
+
+        // Built with the fixed price it carries without a strategy
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1);
+        let mut transactions = vec![Transaction::new_signed_with_payer(
+            &[ComputeBudgetInstruction::set_compute_unit_price(5), transfer.clone()],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        )];
+        client.attach_priority_fees(&mut transactions, &[&payer], &rpc_client()).await.unwrap();
+
+        let transaction = &transactions[0];
+        let message = &transaction.message;
+        let instructions: Vec<(Pubkey, &[u8])> = message.instructions.iter()
+            .map(|ix| (message.account_keys[ix.program_id_index as usize], ix.data.as_slice()))
+            .collect();
+        assert_eq!(instructions, vec![
+            (compute_budget::id(), ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT).data.as_slice()),
+            (compute_budget::id(), ComputeBudgetInstruction::set_compute_unit_price(50).data.as_slice()),
+            (solana_sdk::system_program::id(), transfer.data.as_slice()),
+        ]);
+        assert!(transaction.verify().is_ok());
+
+        // Never above the configured cap
+        client.config.max_priority_fee_micro_lamports = 25;
+        assert_eq!(client.recommended_priority_fee(transaction, &rpc_client()).await.unwrap(), 25);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_create_with_priority_fee_sets_simulated_compute_limit() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let mut client = test_client();
+        client.config.priority_fee_micro_lamports = Some(5_000);
+        client.config.compute_unit_margin_pct = 20.0;
+        let creator = Keypair::new();
+
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetBalance,
+            serde_json::json!({ "context": { "slot": 1 }, "value": 10_000_000_000u64 }),
+        );
+        mocks.insert(RpcRequest::SimulateTransaction, simulation_consuming(50_000));
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+
+        let result = client.create_token(valid_metadata(), &MintOptions::default(), &creator, None, &rpc_client, true).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(result.serialized_tx.expect("dry run returns the transaction"))
+            .unwrap();
+        let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+        let budget: Vec<&[u8]> = transaction.message.instructions.iter()
+            .filter(|ix| transaction.message.account_keys[ix.program_id_index as usize] == compute_budget::id())
+            .map(|ix| ix.data.as_slice())
+            .collect();
+        assert_eq!(budget, vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(60_000).data.as_slice(),
+            ComputeBudgetInstruction::set_compute_unit_price(5_000).data.as_slice(),
+        ]);
+        // Signed again after the limit changed
+        assert!(transaction.verify().is_ok());
+
+        // Without a priority fee no budget instructions are added
+        client.config.priority_fee_micro_lamports = None;
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", HashMap::from([(
+            RpcRequest::GetBalance,
+            serde_json::json!({ "context": { "slot": 1 }, "value": 10_000_000_000u64 }),
+        )]));
+        let result = client.create_token(valid_metadata(), &MintOptions::default(), &creator, None, &rpc_client, true).await.unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD.decode(result.serialized_tx.unwrap()).unwrap();
+        let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+        assert!(!transaction.message.account_keys.contains(&compute_budget::id()));
+    }
+
+    /// Valid metadata that draws no warnings either
+    fn clean_metadata() -> TokenMetadata {
+        TokenMetadata { website: Some("https://example.com".to_string()), ..valid_metadata() }
+    }
+
+    pub(crate) fn valid_metadata() -> TokenMetadata {
+        TokenMetadata {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            description: "A test token".to_string(),
+            image_url: "https://example.com/image.png".to_string(),
+            telegram_link: Some("https://t.me/test".to_string()),
+            twitter_link: Some("https://twitter.com/test".to_string()),
+            website: None,
+            discord: None,
+            extra_links: Default::default(),
+            uri: None,
+            decimals: 9,
+        }
+    }
+
+    #[test]
+    fn test_launch_bundle_orders_create_before_buys() {
+        let client = test_client();
+        let creator = Keypair::new();
+        let token_mint = Keypair::new();
+        let wallets = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let wallet_refs: Vec<(&str, &Keypair)> = ["w-000001", "w-000002", "w-000003"]
+            .into_iter()
+            .zip(wallets.iter())
+            .collect();
+        let amounts = [1.0, 1.0, 1.0];
+
+        let transactions = client
+            .build_launch_transactions(
+                &valid_metadata(),
+                &creator,
+                &token_mint,
+                &amounts,
+                &wallet_refs,
+                1_461_600,
+                solana_sdk::hash::Hash::new_unique(),
+            )
+            .unwrap();
+        assert_eq!(transactions.len(), 4);
+        assert!(transactions.iter().all(|tx| tx.verify().is_ok()));
+
+        // The creation transaction comes first and initializes the curve
+        let create = &transactions[0];
+        assert_eq!(create.message.account_keys[0], creator.pubkey());
+        assert!(create.message.instructions.iter().any(|ix| {
+            create.message.account_keys[ix.program_id_index as usize] == client.program_id && ix.data.starts_with(&discriminators::CREATE)
+        }));
+
+        // Then one buy per wallet, in order, each filling against a higher price than the last
+        let mut previous_tokens = f64::MAX;
+        for (i, buy) in transactions[1..].iter().enumerate() {
+            assert_eq!(buy.message.account_keys[0], wallets[i].pubkey());
+            let data = BuyInstructionData::try_from_slice(&buy.message.instructions[0].data).unwrap();
+            assert_eq!(data.sol_amounts, vec![amounts[i]]);
+            assert_eq!(data.wallet_ids, vec![wallet_refs[i].0.to_string()]);
+            assert!(data.expected_tokens[0] < previous_tokens);
+            previous_tokens = data.expected_tokens[0];
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_launch_fills_the_configured_bundle_size() {
+        let mut client = test_client();
+        client.config.max_wallets_per_bundle = 5;
+        let RecordingBlockEngine { url, bundles, .. } = start_recording_block_engine(true);
+        let mut jito_client = JitoBundleClient::new(url);
+        jito_client.set_max_transactions(client.config.max_wallets_per_bundle);
+        let rpc_client = RpcPool::new_mock("succeeds");
+        let wallets: Vec<Keypair> = (0..4).map(|_| Keypair::new()).collect();
+        let ids = ["w-000001", "w-000002", "w-000003", "w-000004"];
+        let wallet_refs: Vec<(&str, &Keypair)> = ids.into_iter().zip(wallets.iter()).collect();
+
+        // The create and tip take two of the five slots
+        assert_eq!(client.max_launch_wallets(), 3);
+        let result = client
+            .create_and_snipe(valid_metadata(), &Keypair::new(), &[0.5; 3], &wallet_refs[..3], &jito_client, &rpc_client)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(bundles.lock().unwrap()[0].len(), 5);
+
+        let err = client
+            .create_and_snipe(valid_metadata(), &Keypair::new(), &[0.5; 4], &wallet_refs, &jito_client, &rpc_client)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Maximum 3 launch wallets"), "{}", err);
+        assert_eq!(bundles.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_launch_bundle_failure_creates_nothing() {
+        let client = test_client();
+        let wallet = Keypair::new();
+        // Nothing listens here, so submission fails
+        let jito_client = JitoBundleClient::new("http://127.0.0.1:1".to_string());
+        let rpc_client = RpcPool::new_mock("succeeds");
+
+        let result = client
+            .create_and_snipe(
+                valid_metadata(),
+                &Keypair::new(),
+                &[0.5],
+                &[("w-000001", &wallet)],
+                &jito_client,
+                &rpc_client,
+            )
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.bundle_id.is_none());
+        assert!(result.error.unwrap().contains("Launch bundle failed"));
+    }
+
+    /// A stand-in block engine that accepts every bundle
+    struct RecordingBlockEngine {
+        url: String,
+        bundles: Arc<std::sync::Mutex<Vec<Vec<String>>>>, // Transactions of each bundle submitted
+        status_checks: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    /// Starts a block engine whose status checks report every bundle landed when `lands` is
+    /// set, and never otherwise.
+    fn start_recording_block_engine(lands: bool) -> RecordingBlockEngine {
+        use actix_web::{web, App, HttpResponse, HttpServer};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let bundles = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let status_checks = Arc::new(AtomicUsize::new(0));
+        let (recorded, checks) = (bundles.clone(), status_checks.clone());
+        let server = HttpServer::new(move || {
+            let (recorded, checks) = (recorded.clone(), checks.clone());
+            App::new().default_service(web::to(move |body: web::Json<serde_json::Value>| {
+                let response = if body["method"] == "getBundleStatuses" {
+                    checks.fetch_add(1, Ordering::SeqCst);
+                    let status = lands.then(|| serde_json::json!({ "slot": 4242, "confirmation_status": "confirmed" }));
+                    serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": { "value": [status] } })
+                } else {
+                    recorded.lock().unwrap().push(serde_json::from_value(body["transactions"].clone()).unwrap());
+                    serde_json::json!({ "bundle_id": "bundle-1", "status": "success" })
+                };
+                async move { HttpResponse::Ok().json(response) }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", server.addrs()[0]);
+        tokio::spawn(server.run());
+        RecordingBlockEngine { url, bundles, status_checks }
+    }
+
+    /// Tipping transfers signed by each payer, standing in for a bundled trade's transactions.
+    fn signed_transfers(payers: &[Keypair], to: &Pubkey) -> Vec<Transaction> {
+        payers
+            .iter()
+            .map(|payer| {
+                Transaction::new_signed_with_payer(
+                    &[system_instruction::transfer(&payer.pubkey(), to, 1_000), tip_transfer(&payer.pubkey(), 1_000)],
+                    Some(&payer.pubkey()),
+                    &[payer],
+                    solana_sdk::hash::Hash::new_unique(),
+                )
+            })
+            .collect()
+    }
+
+    /// First signature of each base64 wire-format transaction.
+    fn signatures_of(encoded: &[String]) -> Vec<String> {
+        encoded
+            .iter()
+            .map(|tx| {
+                let bytes = base64::engine::general_purpose::STANDARD.decode(tx).unwrap();
+                bincode::deserialize::<Transaction>(&bytes).unwrap().signatures[0].to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bundled_results_carry_every_signature() {
+        let client = test_client();
+        let RecordingBlockEngine { url, bundles, .. } = start_recording_block_engine(true);
+        let jito_client = JitoBundleClient::new(url);
+        let rpc_client = RpcPool::new_mock("succeeds");
+        let wallets = [Keypair::new(), Keypair::new()];
+
+        let result = client
+            .create_and_snipe(
+                valid_metadata(),
+                &Keypair::new(),
+                &[0.5, 0.5],
+                &[("w-000001", &wallets[0]), ("w-000002", &wallets[1])],
+                &jito_client,
+                &rpc_client,
+            )
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        // The create, both buys and the tip
+        assert_eq!(result.signatures.len(), 4);
+        assert_eq!(result.signatures, signatures_of(&bundles.lock().unwrap()[0]));
+        assert_eq!(result.signature.as_ref(), result.signatures.first());
+
+        let transactions = signed_transfers(&wallets, &client.fee_address);
+        let result = client.submit_via_bundle(&transactions, 0.0, 0.0, &rpc_client, Some(&jito_client)).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.signatures, signatures_of(&bundles.lock().unwrap()[1]));
+        assert_eq!(result.signatures[1], transactions[1].signatures[0].to_string());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_trades_confirm_the_way_they_were_submitted() {
+        use std::sync::atomic::Ordering;
+
+        let mut client = test_client();
+        let rpc_client = RpcPool::new_mock("succeeds");
+        let payers = [Keypair::new(), Keypair::new()];
+        let transactions = signed_transfers(&payers, &client.fee_address);
+        let signers: Vec<&Keypair> = payers.iter().collect();
+        let submission = |via_bundle| TradeSubmission { fee: 0.0, trade_volume_sol: 0.0, dry_run: false, via_bundle };
+
+        // RPC sends confirm by signature status, never asking the block engine
+        let engine = start_recording_block_engine(true);
+        let jito_client = JitoBundleClient::new(engine.url.clone());
+        let sent = client
+            .submit_trade(&transactions, &signers, submission(false), "buy", &rpc_client, Some(&jito_client))
+            .await
+            .unwrap();
+        assert_eq!(sent.confirmation, Some(ConfirmationStatus::Confirmed));
+        assert!(engine.bundles.lock().unwrap().is_empty());
+        assert_eq!(engine.status_checks.load(Ordering::SeqCst), 0);
+
+        // Bundles confirm by bundle status
+        let bundled = client
+            .submit_trade(&transactions, &signers, submission(true), "buy", &rpc_client, Some(&jito_client))
+            .await
+            .unwrap();
+        assert!(bundled.success, "{:?}", bundled.error);
+        assert_eq!(bundled.confirmation, Some(ConfirmationStatus::Confirmed));
+        assert_eq!(engine.status_checks.load(Ordering::SeqCst), 1);
+
+        // A bundle that never lands was still accepted, so it comes back for the caller to poll
+        let stalled = start_recording_block_engine(false);
+        let mut stalled_client = JitoBundleClient::new(stalled.url.clone());
+        stalled_client.set_landing_poll(2, Duration::from_millis(10));
+        let pending = client
+            .submit_trade(&transactions, &signers, submission(true), "buy", &rpc_client, Some(&stalled_client))
+            .await
+            .unwrap();
+        assert!(pending.success);
+        assert_eq!(pending.confirmation, Some(ConfirmationStatus::Submitted));
+        assert_eq!(stalled.status_checks.load(Ordering::SeqCst), 2);
+
+        // The RPC strategy confirms bundles by signature status instead
+        client.config.confirmation_strategy = ConfirmationStrategy::Rpc;
+        let bundled = client
+            .submit_trade(&transactions, &signers, submission(true), "buy", &rpc_client, Some(&stalled_client))
+            .await
+            .unwrap();
+        assert_eq!(bundled.confirmation, Some(ConfirmationStatus::Confirmed));
+        assert_eq!(stalled.bundles.lock().unwrap().len(), 2);
+        assert_eq!(stalled.status_checks.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_trades_rejected_while_rpc_slot_lags() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let mut client = test_client();
+        client.config.max_slot_lag = Some(10);
+        // Slot 1000 was seen 10 seconds ago, so the chain should be near slot 1025
+        client.slot_tracker.observe_at(Instant::now() - Duration::from_secs(10), 1000);
+
+        let rpc_at = |slot: u64| {
+            let mut mocks = HashMap::new();
+            mocks.insert(RpcRequest::GetSlot, serde_json::json!(slot));
+            RpcPool::new_mock_with_mocks("succeeds", mocks)
+        };
+
+        let err = client.check_slot_lag(&rpc_at(1005)).await.unwrap_err();
+        assert!(err.to_string().contains("slots behind"));
+
+        let request = BuyRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            sol_amounts: vec![1.0],
+            wallet_ids: vec!["w-000001".to_string()],
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: false,
+            strategy: None,
+            referrer: None,
+            max_total_sol: None,
+        };
+        let results = client.buy_tokens(request, &wallet_store_with(&["w-000001"]), &rpc_at(1005), None).await.unwrap();
+        assert!(!results[0].success);
+        assert!(results[0].error.as_ref().unwrap().contains("slots behind"));
+
+        // A node that has kept up passes
+        assert!(client.check_slot_lag(&rpc_at(1030)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_signed_transactions_round_trip_for_bundles() {
+        let client = test_client();
+        let payers = [Keypair::new(), Keypair::new()];
+        let transactions: Vec<Transaction> = payers
+            .iter()
+            .map(|payer| {
+                let mut tx = Transaction::new_with_payer(
+                    &[system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1_000)],
+                    Some(&payer.pubkey()),
+                );
+                tx.sign(&[payer], solana_sdk::hash::Hash::new_unique());
+                tx
+            })
+            .collect();
+
+        let encoded = build_signed_transactions(&transactions).unwrap();
+        assert_eq!(encoded.len(), 2);
+        for (encoded, original) in encoded.iter().zip(&transactions) {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+            let decoded: Transaction = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(&decoded, original);
+            assert!(decoded.verify().is_ok());
+        }
+
+        // Bundled trades need a bundle client to go through
+        let err = client.submit_via_bundle(&transactions, 0.0, 1.0, &RpcPool::new_mock("succeeds"), None).await.unwrap_err();
+        assert!(err.to_string().contains("not configured"));
+    }
+
+    #[test]
+    fn test_bundled_sell_tips_a_jito_tip_account() {
+        let client = test_client();
+        let jito_client = JitoBundleClient::new("https://test.api.jito.wtf".to_string());
+        let payer = Keypair::new();
+        let sell_ix = client
+            .create_sell_instruction(&Pubkey::new_unique(), &[1000.0], &["w-000001".to_string()])
+            .unwrap();
+        let blockhash = solana_sdk::hash::Hash::new_unique();
+
+        let transaction = client
+            .sign_trade_transaction(vec![sell_ix.clone()], &payer, true, Some(&jito_client), blockhash)
+            .unwrap();
+        assert!(transaction.verify().is_ok());
+
+        let message = &transaction.message;
+        let tip = message.instructions.last().unwrap();
+        assert_eq!(message.account_keys[tip.program_id_index as usize], solana_sdk::system_program::id());
+        let tip_account = message.account_keys[tip.accounts[1] as usize].to_string();
+        assert!(crate::jito_bundle::JITO_TIP_ACCOUNTS.contains(&tip_account.as_str()));
+
+        // RPC submissions carry no tip
+        let transaction = client
+            .sign_trade_transaction(vec![sell_ix], &payer, false, None, blockhash)
+            .unwrap();
+        assert_eq!(transaction.message.instructions.len(), 1);
+    }
+
+    #[test]
+    fn test_sell_all_bundles_full_balances() {
+        let client = test_client();
+        let mint = Pubkey::new_unique();
+        let wallets = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let balances = [5_000_000_000u64, 12_000_000_000, 700_000_000];
+        let holdings: Vec<(&str, &Keypair, u64)> = ["w-000001", "w-000002", "w-000003"]
+            .into_iter()
+            .zip(wallets.iter())
+            .zip(balances)
+            .map(|((id, wallet), balance)| (id, wallet, balance))
+            .collect();
+
+        let (bundles, quoted_sol) = client
+            .build_sell_all_bundles(&mint, &holdings, &test_curve(), 50_000, solana_sdk::hash::Hash::new_unique())
+            .unwrap();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].len(), 3);
+        // Each sell fills against the curve the ones before it leave, in whole tokens
+        let first = client.calculate_sol_for_sell(5_000.0, &test_curve()).unwrap();
+        assert_eq!(quoted_sol[0], first);
+        let alone = client.calculate_sol_for_sell(12_000.0, &test_curve()).unwrap();
+        assert!(quoted_sol[1] > 0.0 && quoted_sol[1] < alone);
+
+        for (i, transaction) in bundles[0].iter().enumerate() {
+            assert!(transaction.verify().is_ok());
+            let message = &transaction.message;
+            assert_eq!(message.account_keys[0], wallets[i].pubkey());
+
+            let data = SellInstructionData::try_from_slice(&message.instructions[0].data).unwrap();
+            assert_eq!(data.discriminator, discriminators::SELL);
+            assert_eq!(data.token_amounts, vec![raw_to_ui_amount(balances[i], PUMP_TOKEN_DECIMALS)]);
+            assert_eq!(data.wallet_ids, vec![holdings[i].0.to_string()]);
+
+            // Every seller pays the fee on its proceeds
+            let fee = (client.config.fee_schedule.fee_for(quoted_sol[i]) * 1e9) as u64;
+            let transfer = system_instruction::transfer(&wallets[i].pubkey(), &client.fee_address, fee);
+            assert_eq!(message.instructions[1].data, transfer.data);
+            assert_eq!(message.account_keys[message.instructions[1].accounts[1] as usize], client.fee_address);
+
+            // Only the last transaction pays the tip
+            let is_last = i == bundles[0].len() - 1;
+            assert_eq!(message.instructions.len(), if is_last { 3 } else { 2 });
+        }
+
+        let last = &bundles[0][2].message;
+        let tip = &last.instructions[2];
+        let tip_account = last.account_keys[tip.accounts[1] as usize].to_string();
+        assert!(crate::jito_bundle::JITO_TIP_ACCOUNTS.contains(&tip_account.as_str()));
+    }
+
+    #[test]
+    fn test_sell_all_chunks_into_bundle_sized_groups() {
+        let client = test_client();
+        let wallets: Vec<Keypair> = (0..20).map(|_| Keypair::new()).collect();
+        let ids: Vec<String> = (1..=20).map(|n| format!("w-{:06}", n)).collect();
+        let holdings: Vec<(&str, &Keypair, u64)> = ids
+            .iter()
+            .zip(wallets.iter())
+            .map(|(id, wallet)| (id.as_str(), wallet, 1_000))
+            .collect();
+
+        let (bundles, _) = client
+            .build_sell_all_bundles(&Pubkey::new_unique(), &holdings, &test_curve(), 50_000, solana_sdk::hash::Hash::new_unique())
+            .unwrap();
+        assert_eq!(bundles.iter().map(Vec::len).collect::<Vec<_>>(), vec![16, 4]);
+        for bundle in &bundles {
+            assert_eq!(bundle.last().unwrap().message.instructions.len(), 3);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exit_keeps_the_ids_of_bundles_accepted_before_a_rejection() {
+        use actix_web::{web, App, HttpResponse, HttpServer};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Accepts the first bundle and rejects the rest
+        let submissions = Arc::new(AtomicUsize::new(0));
+        let server = HttpServer::new(move || {
+            let submissions = submissions.clone();
+            App::new().default_service(web::to(move || {
+                let response = match submissions.fetch_add(1, Ordering::SeqCst) {
+                    0 => serde_json::json!({ "bundle_id": "bundle-1", "status": "success" }),
+                    _ => serde_json::json!({ "bundle_id": "", "status": "failed", "error": "tip too low" }),
+                };
+                async move { HttpResponse::Ok().json(response) }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let jito_client = JitoBundleClient::new(format!("http://{}", server.addrs()[0]));
+        tokio::spawn(server.run());
+
+        let client = test_client();
+        let wallets: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+        let holdings: Vec<(&str, &Keypair, u64)> = wallets.iter().map(|wallet| ("w", wallet, 1_000_000)).collect();
+        let bundles: Vec<Vec<Transaction>> = holdings
+            .chunks(1)
+            .map(|chunk| client.build_sell_all_bundles(&Pubkey::new_unique(), chunk, &test_curve(), 50_000, Hash::new_unique()).unwrap().0.remove(0))
+            .collect();
+
+        let (bundle_ids, error) = submit_exit_bundles(&bundles, &jito_client).await.unwrap();
+        assert_eq!(bundle_ids, vec!["bundle-1".to_string()]);
+        assert_eq!(error.as_deref(), Some("Exit bundle rejected: tip too low"));
+    }
+
+    fn mock_mint_account(owner: &Pubkey, initialized: bool) -> serde_json::Value {
+        let mint = spl_token::state::Mint {
+            decimals: 6,
+            is_initialized: initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        serde_json::json!({
+            "context": { "slot": 1 },
+            "value": {
+                "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+                "executable": false,
+                "lamports": 1_461_600,
+                "owner": owner.to_string(),
+                "rentEpoch": 0,
+                "space": spl_token::state::Mint::LEN
+            }
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_token_metadata_combines_on_chain_and_off_chain_metadata() {
+        use crate::metadata::tests::{metaplex_account_bytes, start_mock_metadata_host};
+        use crate::metadata::FetchError;
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc_with_metadata_at = |uri: &str| {
+            let data = metaplex_account_bytes(&mint, "Test Token", "TEST", uri, &creator);
+            RpcPool::new_mock_with_mocks("succeeds", HashMap::from([(
+                RpcRequest::GetAccountInfo,
+                serde_json::json!({
+                    "context": { "slot": 1 },
+                    "value": ui_account(&data, &metadata::TOKEN_METADATA_PROGRAM_ID)
+                }),
+            )]))
+        };
+
+        let host = start_mock_metadata_host(Some(serde_json::json!({
+            "name": "Test Token",
+            "symbol": "TEST",
+            "description": "A test token",
+            "image": "ipfs://cid-image",
+            "twitter": "https://twitter.com/test",
+            "showName": true
+        })));
+        let token = client
+            .token_metadata(&mint, &rpc_with_metadata_at(&format!("{}/token.json", host)))
+            .await
+            .unwrap();
+        assert_eq!((token.name.as_str(), token.symbol.as_str()), ("Test Token", "TEST"));
+        assert_eq!(token.description, "A test token");
+        assert_eq!(token.image_url, "ipfs://cid-image");
+        assert_eq!(token.twitter_link.as_deref(), Some("https://twitter.com/test"));
+        assert_eq!(token.telegram_link, None);
+        assert_eq!(token.creator, creator.to_string());
+
+        // No metadata account: the default mock has no account
+        let err = client.token_metadata(&mint, &RpcPool::new_mock("succeeds")).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(PumpFunError::MetadataNotFound { .. })));
+
+        // The account exists but its JSON can't be fetched
+        let missing = start_mock_metadata_host(None);
+        let err = client
+            .token_metadata(&mint, &rpc_with_metadata_at(&format!("{}/token.json", missing)))
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<FetchError>().is_some(), "{}", err);
+        assert!(err.to_string().contains("404"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_token_mint_rejects_non_token_accounts() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let mint = Pubkey::new_unique();
+        let rpc_with = |account: serde_json::Value| {
+            let mut mocks = HashMap::new();
+            mocks.insert(RpcRequest::GetAccountInfo, account);
+            RpcPool::new_mock_with_mocks("succeeds", mocks)
+        };
+
+        for account in [
+            mock_mint_account(&solana_sdk::system_program::id(), true),
+            mock_mint_account(&spl_token::id(), false),
+        ] {
+            let err = client.verify_token_mint(&mint, &rpc_with(account)).await.unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<PumpFunError>(),
+                Some(PumpFunError::NotATokenMint { .. })
+            ));
+            assert!(err.to_string().contains("is not a token mint"));
+        }
+
+        // A missing account is rejected too; the default mock has no account
+        let err = client
+            .verify_token_mint(&mint, &RpcPool::new_mock("succeeds"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+
+        // Trades against a non-mint fail cleanly instead of erroring
+        let request = SellRequest {
+            token_address: mint.to_string(),
+            token_amounts: vec![1000],
+            ui_token_amounts: None,
+            wallet_ids: vec!["w-000001".to_string()],
+            user_id: 1,
+            min_sol_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: false,
+            strategy: None,
+            close_account: false,
+            referrer: None,
+        };
+        let rpc_client = rpc_with(mock_mint_account(&solana_sdk::system_program::id(), true));
+        let results = client.sell_tokens(request, &wallet_store_with(&["w-000001"]), &rpc_client, None).await.unwrap();
+        assert!(!results[0].success);
+        assert!(results[0].error.as_ref().unwrap().contains("is not a token mint"));
+
+        // SPL Token and Token-2022 mints pass
+        for owner in [spl_token::id(), TOKEN_2022_PROGRAM_ID] {
+            assert!(client.verify_token_mint(&mint, &rpc_with(mock_mint_account(&owner, true))).await.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_ui_amounts_convert_to_base_units() {
+        // 1000 tokens at 6 and 9 decimals, and back
+        assert_eq!(ui_to_raw_amount(1000.0, 6).unwrap(), 1_000_000_000);
+        assert_eq!(ui_to_raw_amount(1000.0, 9).unwrap(), 1_000_000_000_000);
+        assert_eq!(raw_to_ui_amount(1_000_000_000, 6), 1000.0);
+        assert_eq!(raw_to_ui_amount(1_000_000_000_000, 9), 1000.0);
+        assert_eq!(raw_to_ui_amount(1, 6), 0.000001);
+
+        // 1.001 * 1e6 is 1000999.9999999999 in floats; the unit below isn't lost
+        assert_eq!(ui_to_raw_amount(1.001, 6).unwrap(), 1_001_000);
+        assert_eq!(ui_to_raw_amount(1.001, 9).unwrap(), 1_001_000_000);
+
+        // Precision beyond the decimals rounds to the nearest unit
+        assert_eq!(ui_to_raw_amount(0.0000014, 6).unwrap(), 1);
+        assert_eq!(ui_to_raw_amount(0.0000016, 6).unwrap(), 2);
+        assert_eq!(ui_to_raw_amount(0.0000000016, 9).unwrap(), 2);
+        assert_eq!(ui_to_raw_amount(0.0, 9).unwrap(), 0);
+
+        for (amount, decimals) in [(0.0000004, 6), (-1.0, 6), (f64::NAN, 9), (1e30, 9)] {
+            let err = ui_to_raw_amount(amount, decimals).unwrap_err();
+            assert!(matches!(err.downcast_ref(), Some(PumpFunError::InvalidTokenAmount { .. })), "{}", err);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ui_sell_amounts_use_the_mint_decimals() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let mint = Pubkey::new_unique();
+        // The mock mint has 6 decimals
+        let rpc_with_mint = || {
+            let mut mocks = HashMap::new();
+            mocks.insert(RpcRequest::GetAccountInfo, mock_mint_account(&spl_token::id(), true));
+            RpcPool::new_mock_with_mocks("succeeds", mocks)
+        };
+        assert_eq!(client.mint_decimals(&mint, &rpc_with_mint()).await.unwrap(), 6);
+
+        let request = SellRequest {
+            token_address: mint.to_string(),
+            token_amounts: Vec::new(),
+            ui_token_amounts: Some(vec![1000.0, 0.5]),
+            wallet_ids: vec!["w-000001".to_string(), "w-000002".to_string()],
+            user_id: 1,
+            min_sol_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: false,
+            strategy: None,
+            close_account: false,
+            referrer: None,
+        };
+        assert_eq!(request.amount_count(), 2);
+        let resolved = client.resolve_token_amounts(request.clone(), &rpc_with_mint()).await.unwrap();
+        assert_eq!(resolved.token_amounts, vec![1_000_000_000, 500_000]);
+        assert!(resolved.ui_token_amounts.is_none());
+
+        // Raw amounts pass through without a mint lookup
+        let raw = SellRequest { token_amounts: vec![42, 7], ui_token_amounts: None, ..request.clone() };
+        let resolved = client.resolve_token_amounts(raw, &RpcPool::new_mock("fails")).await.unwrap();
+        assert_eq!(resolved.token_amounts, vec![42, 7]);
+
+        // An amount finer than the mint's decimals fails each wallet
+        let wallets = wallet_store_with(&["w-000001", "w-000002"]);
+        let dust = SellRequest { ui_token_amounts: Some(vec![1.0, 0.0000001]), ..request.clone() };
+        let results = client.sell_tokens(dust, &wallets, &rpc_with_mint(), None).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].error.as_ref().unwrap().contains("smaller than one base unit at 6 decimals"));
+
+        // Giving both units is ambiguous
+        let both = SellRequest { token_amounts: vec![1, 2], ..request };
+        let results = client.sell_tokens(both, &wallets, &RpcPool::new_mock("fails"), None).await.unwrap();
+        assert!(results[1].error.as_ref().unwrap().contains("not both"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sell_quotes_match_in_either_unit() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let jito_client = JitoBundleClient::new("https://test.api.jito.wtf".to_string());
+        let curve = client.initial_bonding_curve(&Pubkey::new_unique());
+        // The mock mint has 6 decimals, like every Pump.Fun token
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, mock_mint_account(&spl_token::id(), true));
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+
+        let raw = SellRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            token_amounts: vec![1_000_000_000],
+            ui_token_amounts: None,
+            wallet_ids: vec!["w-000001".to_string()],
+            user_id: 1,
+            min_sol_out: None,
+            max_price_impact_bps: None,
+            dry_run: true,
+            via_bundle: false,
+            strategy: None,
+            close_account: false,
+            referrer: None,
+        };
+        let ui = SellRequest { token_amounts: Vec::new(), ui_token_amounts: Some(vec![1000.0]), ..raw.clone() };
+        let ui = client.resolve_token_amounts(ui, &rpc_client).await.unwrap();
+
+        let raw_quote = client.estimate_sell_cost(&raw, &curve, &jito_client).unwrap();
+        let ui_quote = client.estimate_sell_cost(&ui, &curve, &jito_client).unwrap();
+        assert_eq!(raw_quote.base_amount, ui_quote.base_amount);
+        // Both price 1000 whole tokens on the curve
+        assert_eq!(raw_quote.base_amount, client.calculate_sol_for_sell(1000.0, &curve).unwrap());
+
+        let wallet = Keypair::new();
+        let leg = SellLeg { index: 0, wallet_id: "w-000001", wallet: &wallet, token_amount: ui.token_amounts[0] };
+        assert_eq!(leg.whole_tokens(), 1000.0);
+    }
+
+    #[test]
+    fn test_required_creation_balance_includes_rent_and_buffer() {
+        let mut client = test_client();
+        client.config.balance_buffer_lamports = 2_000_000;
+
+        // 1_461_600 for the mint plus 2_039_280 for each ATA
+        let rent = 1_461_600 + 2 * 2_039_280;
+        assert_eq!(
+            client.required_creation_balance(rent),
+            (client.config.creation_fee * 1e9) as u64 + 2_000_000 + rent
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_create_rejects_balance_just_below_rent_threshold() {
+        use futures::future::ready;
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let creator = Keypair::new();
+        // Rent is cached per account size, so seed the ATA and metadata sizes and mock the mint size
+        client.rent_cache.get_or_fetch(spl_token::state::Account::LEN, || ready(Ok(2_039_280))).await.unwrap();
+        client.rent_cache.get_or_fetch(metadata::METADATA_ACCOUNT_LEN, || ready(Ok(5_616_720))).await.unwrap();
+        let threshold = client.required_creation_balance(1_461_600 + 2 * 2_039_280 + 5_616_720);
+
+        let rpc_with_balance = |lamports: u64| {
+            let mut mocks = HashMap::new();
+            mocks.insert(
+                RpcRequest::GetBalance,
+                serde_json::json!({ "context": { "slot": 1 }, "value": lamports }),
+            );
+            mocks.insert(RpcRequest::GetMinimumBalanceForRentExemption, serde_json::json!(1_461_600));
+            RpcPool::new_mock_with_mocks("succeeds", mocks)
+        };
+
+        let result = client
+            .create_token(valid_metadata(), &MintOptions::default(), &creator, None, &rpc_with_balance(threshold - 1), true)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Insufficient balance"));
+
+        // Exactly enough passes, now served from the rent cache
+        let result = client
+            .create_token(valid_metadata(), &MintOptions::default(), &creator, None, &rpc_with_balance(threshold), true)
+            .await
+            .unwrap();
+        assert!(result.success);
+    }
+
+    /// A freshly created curve: 30 virtual SOL against 1.073B virtual tokens
+    const FRESH_CURVE_FIXTURE: [u8; 49] = [
+        23, 183, 248, 55, 96, 216, 172, 96, 0, 16, 216, 71, 227, 207, 3, 0, 0, 172, 35, 252, 6, 0,
+        0, 0, 0, 120, 197, 251, 81, 209, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 198, 164, 126, 141,
+        3, 0, 0,
+    ];
+
+    #[test]
+    fn test_parse_bonding_curve_fixture() {
+        let mint = Pubkey::new_unique();
+        let curve = parse_bonding_curve(&mint, &FRESH_CURVE_FIXTURE).unwrap();
+        assert_eq!(curve.token_address, mint.to_string());
+        assert_eq!(curve.sol_reserve, 30.0);
+        assert_eq!(curve.token_reserve, 1_073_000_000.0);
+        assert_eq!(curve.total_supply, 1_000_000_000_000_000);
+        assert!((curve.current_price - 30.0 / 1_073_000_000.0).abs() < 1e-18);
+
+        // Fields appended by newer program versions are ignored
+        let mut extended = FRESH_CURVE_FIXTURE.to_vec();
+        extended.extend_from_slice(Pubkey::new_unique().as_ref());
+        assert_eq!(parse_bonding_curve(&mint, &extended).unwrap().sol_reserve, 30.0);
+
+        // Anything else is rejected
+        let mut wrong_discriminator = FRESH_CURVE_FIXTURE;
+        wrong_discriminator[0] ^= 0xff;
+        assert!(parse_bonding_curve(&mint, &wrong_discriminator).is_err());
+        assert!(parse_bonding_curve(&mint, &FRESH_CURVE_FIXTURE[..20]).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_graduated_curve_routes_to_raydium() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let mint = Pubkey::new_unique();
+        // The `complete` flag is the last byte of the layout
+        let mut completed = FRESH_CURVE_FIXTURE;
+        completed[48] = 1;
+        let curve = parse_bonding_curve(&mint, &completed).unwrap();
+        assert!(curve.complete);
+
+        let mut client = test_client();
+        let err = client.quote(QuoteInput::Sol(1.0), &curve).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PumpFunError>(),
+            Some(&PumpFunError::CurveComplete { mint: mint.to_string() })
+        );
+
+        // Buys go to the token's Raydium pool instead of the curve
+        client.config.verify_mint_owner = false;
+        let (mocks, pool) = graduated_pool_mocks(&client, &mint);
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+        let request = BuyRequest {
+            token_address: mint.to_string(),
+            sol_amounts: vec![1.0],
+            wallet_ids: vec!["w-000001".to_string()],
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: true,
+            via_bundle: false,
+            strategy: None,
+            referrer: None,
+            max_total_sol: None,
+        };
+        let wallets = wallet_store_with(&["w-000001"]);
+        let result = client.buy_tokens(request.clone(), &wallets, &rpc_client, None).await.unwrap().remove(0);
+        assert!(result.success, "{:?}", result.error);
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(result.serialized_tx.expect("dry run returns the transaction"))
+            .unwrap();
+        let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+        let keys = &transaction.message.account_keys;
+        let swap = transaction.message.instructions.iter()
+            .find(|ix| keys[ix.program_id_index as usize] == raydium::RAYDIUM_AMM_PROGRAM_ID)
+            .expect("buy swaps through Raydium");
+        assert!(transaction.message.instructions.iter().all(|ix| keys[ix.program_id_index as usize] != client.program_id));
+        assert_eq!(swap.accounts.len(), 18);
+        assert_eq!(keys[swap.accounts[1] as usize], pool.amm_id);
+        assert_eq!(keys[swap.accounts[17] as usize], keys[0]);
+        // The stored wallet pays for and signs its own swap
+        assert_eq!(keys[0], wallets.get_keypair("w-000001").unwrap().pubkey());
+        assert!(transaction.verify().is_ok());
+        assert_eq!(swap.data[0], 9);
+        assert_eq!(swap.data[1..9], 1_000_000_000u64.to_le_bytes());
+        let minimum_out = u64::from_le_bytes(swap.data[9..17].try_into().unwrap());
+        assert!(minimum_out > 0 && minimum_out < 1_980_000_000_000);
+
+        // Without a pool the trade fails cleanly
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, serde_json::json!({
+            "context": { "slot": 1 },
+            "value": ui_account(&completed, &client.program_id)
+        }));
+        mocks.insert(RpcRequest::GetProgramAccounts, serde_json::json!([]));
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+        let result = client.buy_tokens(request, &wallets, &rpc_client, None).await.unwrap().remove(0);
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("No Raydium SOL pool"));
+    }
+
+    fn ui_account(data: &[u8], owner: &Pubkey) -> serde_json::Value {
+        serde_json::json!({
+            "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+            "executable": false,
+            "lamports": 1_000_000,
+            "owner": owner.to_string(),
+            "rentEpoch": 0,
+            "space": data.len()
+        })
+    }
+
+    /// RPC mocks for a graduated token whose trades route through a Raydium pool.
+    /// 
+    /// Each mock answers once, so they serve a single trade.
+    pub(crate) fn graduated_pool_mocks(
+        client: &PumpFunClient,
+        mint: &Pubkey,
+    ) -> (std::collections::HashMap<solana_client::rpc_request::RpcRequest, serde_json::Value>, raydium::RaydiumPool) {
+        use solana_client::rpc_request::RpcRequest;
+
+        // The `complete` flag is the last byte of the layout
+        let mut completed = FRESH_CURVE_FIXTURE;
+        completed[48] = 1;
+        let (amm_id, market, market_program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let amm = raydium::tests::amm_account_bytes(mint, &market, &market_program);
+        let pool = raydium::RaydiumPool::from_accounts(
+            amm_id,
+            &amm,
+            &raydium::tests::market_account_bytes(&market, &market_program),
+        )
+        .unwrap();
+
+        let mut mocks = std::collections::HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, serde_json::json!({
+            "context": { "slot": 1 },
+            "value": ui_account(&completed, &client.program_id)
+        }));
+        mocks.insert(RpcRequest::GetProgramAccounts, serde_json::json!([{
+            "pubkey": amm_id.to_string(),
+            "account": ui_account(&amm, &raydium::RAYDIUM_AMM_PROGRAM_ID)
+        }]));
+        mocks.insert(RpcRequest::GetMultipleAccounts, serde_json::json!({
+            "context": { "slot": 1 },
+            "value": [
+                ui_account(&raydium::tests::market_account_bytes(&market, &market_program), &market_program),
+                ui_account(&raydium::tests::vault_account_bytes(mint, 200_000_000_000_000), &spl_token::id()),
+                ui_account(&raydium::tests::vault_account_bytes(&spl_token::native_mint::id(), 100_000_000_000), &spl_token::id())
+            ]
+        }));
+        (mocks, pool)
+    }
+
+    pub(crate) fn wallet_store_with(wallet_ids: &[&str]) -> WalletStore {
+        let path = std::env::temp_dir().join(format!("wallets-{}.enc", Pubkey::new_unique()));
+        let mut store = WalletStore::load(path, "test-key").unwrap();
+        for wallet_id in wallet_ids {
+            store.add_wallet(wallet_id.to_string(), Keypair::new()).unwrap();
+        }
+        store
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unknown_wallets_fail_alone_in_a_sell() {
+        let mut client = test_client();
+        client.config.verify_mint_owner = false;
+        let mint = Pubkey::new_unique();
+        let wallets = wallet_store_with(&["w-000001", "w-000003"]);
+        let request = SellRequest {
+            token_address: mint.to_string(),
+            token_amounts: vec![1_000_000_000, 2_000_000_000, 3_000_000_000],
+            ui_token_amounts: None,
+            wallet_ids: vec!["w-000001".to_string(), "w-missing".to_string(), "w-000003".to_string()],
+            user_id: 1,
+            min_sol_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: false,
+            strategy: None,
+            close_account: false,
+            referrer: None,
+        };
+
+        let (mocks, _) = graduated_pool_mocks(&client, &mint);
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+        let results = client.sell_tokens(request.clone(), &wallets, &rpc_client, None).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].success && results[2].success, "{:?}", results);
+        assert_ne!(results[0].signature, results[2].signature);
+        assert!(results[0].fee_paid.unwrap() > 0.0);
+        assert!(!results[1].success);
+        assert_eq!(results[1].error.as_deref(), Some("Unknown wallet ID: w-missing"));
+
+        // Each wallet signs its own transaction
+        let (mocks, _) = graduated_pool_mocks(&client, &mint);
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+        let dry_run = SellRequest { dry_run: true, ..request.clone() };
+        let results = client.sell_tokens(dry_run, &wallets, &rpc_client, None).await.unwrap();
+        for (result, wallet_id) in [(&results[0], "w-000001"), (&results[2], "w-000003")] {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(result.serialized_tx.as_ref().expect("dry run returns the transaction"))
+                .unwrap();
+            let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(transaction.message.account_keys[0], wallets.get_keypair(wallet_id).unwrap().pubkey());
+        }
+
+        // Failed sends are reported against their own wallet
+        let (mocks, _) = graduated_pool_mocks(&client, &mint);
+        let rpc_client = RpcPool::new_mock_with_mocks("instruction_error", mocks);
+        let results = client.sell_tokens(request.clone(), &wallets, &rpc_client, None).await.unwrap();
+        assert!(results.iter().all(|result| !result.success));
+        assert!(results[0].error.as_ref().unwrap().starts_with("Failed to send sell transaction"));
+        assert_eq!(results[1].error.as_deref(), Some("Unknown wallet ID: w-missing"));
+
+        // With no wallet to sell from, nothing reaches the RPC
+        let nobody = SellRequest { wallet_ids: vec!["w-x".to_string(), "w-y".to_string(), "w-z".to_string()], ..request };
+        let results = client.sell_tokens(nobody, &wallets, &RpcPool::new_mock("fails"), None).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2].error.as_deref(), Some("Unknown wallet ID: w-z"));
+    }
+
+    #[tokio::test]
+    async fn test_trades_reject_duplicate_and_mismatched_wallet_ids() {
+        let client = test_client();
+        let wallets = wallet_store_with(&["w-000001", "w-000002"]);
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        let buy = BuyRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            sol_amounts: vec![0.5, 0.5, 0.5],
+            wallet_ids: ids(&["w-000001", "w-000002", "w-000001"]),
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: true,
+            strategy: None,
+            referrer: None,
+            max_total_sol: None,
+        };
+        let sell = SellRequest {
+            token_address: buy.token_address.clone(),
+            token_amounts: vec![1_000, 2_000, 3_000],
+            ui_token_amounts: None,
+            wallet_ids: buy.wallet_ids.clone(),
+            user_id: 1,
+            min_sol_out: None,
+            max_price_impact_bps: None,
+            dry_run: false,
+            via_bundle: true,
+            strategy: None,
+            close_account: false,
+            referrer: None,
+        };
+
+        // Rejected before any RPC call is made
+        let rpc_client = RpcPool::new_mock("fails");
+        let results = client.buy_tokens(buy.clone(), &wallets, &rpc_client, None).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| {
+            result.error.as_deref() == Some("Wallet ID w-000001 is listed more than once")
+        }));
+        let results = client.sell_tokens(sell.clone(), &wallets, &rpc_client, None).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| {
+            result.error.as_deref() == Some("Wallet ID w-000001 is listed more than once")
+        }));
+
+        let short = BuyRequest { sol_amounts: vec![0.5], wallet_ids: ids(&["w-000001", "w-000002"]), ..buy };
+        let results = client.buy_tokens(short, &wallets, &rpc_client, None).await.unwrap();
+        assert_eq!(
+            results[1].error.as_deref(),
+            Some("Number of SOL amounts (1) must match number of wallet IDs (2)")
+        );
+        let short = SellRequest { wallet_ids: ids(&["w-000001", "w-000002"]), ..sell };
+        let results = client.sell_tokens(short, &wallets, &rpc_client, None).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[1].error.as_deref(),
+            Some("Number of token amounts (3) must match number of wallet IDs (2)")
+        );
+    }
+
+    fn buy_legs<'a>(ids: &'a [String], wallets: &'a [Keypair]) -> Vec<BuyLeg<'a>> {
+        ids.iter()
+            .zip(wallets)
+            .map(|(wallet_id, wallet)| BuyLeg {
+                wallet_id,
+                wallet,
+                sol_amount: 0.5,
+                expected_tokens: 480.0,
+                min_tokens_out: 456.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_multi_wallet_buy_splits_into_packet_sized_transactions() {
+        let client = test_client();
+        let jito_client = JitoBundleClient::new("https://test.api.jito.wtf".to_string());
+        let mint = Pubkey::new_unique();
+
+        for wallet_count in [1, 4, 16] {
+            let ids: Vec<String> = (1..=wallet_count).map(|n| format!("w-{:06}", n)).collect();
+            let wallets: Vec<Keypair> = ids.iter().map(|_| Keypair::new()).collect();
+            let legs = buy_legs(&ids, &wallets);
+
+            let transactions = client
+                .build_buy_transactions(&mint, &legs, None, true, Some(&jito_client), solana_sdk::hash::Hash::new_unique())
+                .unwrap();
+            assert!(transactions.len() <= client.config.max_wallets_per_bundle);
+
+            let mut covered = Vec::new();
+            for transaction in &transactions {
+                assert!(transaction.verify().is_ok());
+                assert!(transaction_size(transaction).unwrap() <= PACKET_DATA_SIZE);
+                let data = BuyInstructionData::try_from_slice(&transaction.message.instructions[0].data).unwrap();
+                covered.extend(data.wallet_ids);
+            }
+            // Every wallet is bought for exactly once, in order
+            assert_eq!(covered, ids);
+
+            // Only the last transaction tips
+            let tipping = transactions
+                .iter()
+                .filter(|tx| tx.message.instructions.len() > tx.message.header.num_required_signatures as usize + 1)
+                .count();
+            assert_eq!(tipping, 1);
+        }
+
+        // Sixteen signers can't share one packet, so the buy is split
+        let ids: Vec<String> = (1..=16).map(|n| format!("w-{:06}", n)).collect();
+        let wallets: Vec<Keypair> = ids.iter().map(|_| Keypair::new()).collect();
+        let transactions = client
+            .build_buy_transactions(&mint, &buy_legs(&ids, &wallets), None, false, None, solana_sdk::hash::Hash::new_unique())
+            .unwrap();
+        assert!(transactions.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_buys_reject_one_wallet_stored_under_two_ids() {
+        let client = test_client();
+        let wallet = Keypair::new();
+        let mut wallets = wallet_store_with(&["w-000002"]);
+        wallets.add_wallet("w-000001".to_string(), wallet.insecure_clone()).unwrap();
+        wallets.add_wallet("w-000003".to_string(), wallet.insecure_clone()).unwrap();
+        let request = BuyRequest {
+            token_address: Pubkey::new_unique().to_string(),
+            sol_amounts: vec![0.1, 0.2, 0.3],
+            wallet_ids: vec!["w-000001".to_string(), "w-000002".to_string(), "w-000003".to_string()],
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: true,
+            via_bundle: false,
+            strategy: None,
+            referrer: None,
+            max_total_sol: None,
+        };
+
+        // Different amounts, and apart in the list, yet still the one wallet; nothing reaches the RPC
+        let results = client.buy_tokens(request, &wallets, &RpcPool::new_mock("fails"), None).await.unwrap();
+        assert_eq!(results.len(), 3);
+        let expected = format!(
+            "Wallet IDs w-000001 and w-000003 are the same wallet {}; list each wallet once",
+            wallet.pubkey()
+        );
+        assert!(results.iter().all(|result| result.error.as_deref() == Some(expected.as_str())), "{:?}", results);
+
+        // Unknown wallets don't count as shared
+        let unknown = [None, None];
+        assert!(reject_shared_wallets(&["w-x".to_string(), "w-y".to_string()], &unknown).is_ok());
+    }
+
+    /// A mock node serving the same bonding curve to every account read once its first
+    /// `failures` reads have failed
+    struct CurveNode {
+        inner: solana_rpc_client::mock_sender::MockSender,
+        failures: std::sync::atomic::AtomicUsize,
+    }
+
+    impl CurveNode {
+        fn failing(failures: usize) -> Self {
+            Self {
+                inner: solana_rpc_client::mock_sender::MockSender::new("succeeds"),
+                failures: failures.into(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl solana_client::rpc_sender::RpcSender for CurveNode {
+        async fn send(
+            &self,
+            request: solana_client::rpc_request::RpcRequest,
+            params: serde_json::Value,
+        ) -> Result<serde_json::Value, solana_client::client_error::ClientError> {
+            if request != solana_client::rpc_request::RpcRequest::GetAccountInfo {
+                return self.inner.send(request, params).await;
+            }
+            let failing = self.failures
+                .fetch_update(std::sync::atomic::Ordering::SeqCst, std::sync::atomic::Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                return Err(solana_client::rpc_request::RpcError::RpcRequestError("connection reset".to_string()).into());
+            }
+            let data = curve_account_bytes(30.0, 1_000_000_000.0);
+            Ok(serde_json::json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "data": [base64::engine::general_purpose::STANDARD.encode(&data), "base64"],
+                    "executable": false,
+                    "lamports": 1_000_000,
+                    "owner": PumpFunConfig::default().program_id,
+                    "rentEpoch": 0,
+                    "space": data.len()
+                }
+            }))
+        }
+
+        fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_curve_reads_retry_transient_failures() {
+        let mut client = test_client();
+        client.config.rpc_max_retries = 2;
+        client.config.rpc_retry_base_delay_ms = 1;
+        let mint = Pubkey::new_unique();
+        let rpc_failing = |failures| RpcPool::new(vec![CurveNode::failing(failures)], 5, Duration::from_secs(30), false);
+
+        // Both the default and an explicit commitment retry
+        let curve = client.get_bonding_curve_data(&mint, &rpc_failing(2)).await.unwrap();
+        assert_eq!(curve.token_address, mint.to_string());
+        let finalized = CommitmentConfig::finalized();
+        assert!(client.get_bonding_curve_data_with_commitment(&mint, finalized, &rpc_failing(2)).await.is_ok());
+
+        // Until the retries run out
+        let err = client.get_bonding_curve_data(&mint, &rpc_failing(3)).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("connection reset"), "{:#}", err);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_referred_trades_pay_the_referrer_a_share_of_the_fee() {
+        use solana_sdk::system_instruction::SystemInstruction;
+
+        let mut client = test_client();
+        client.config.referral_fee_share = 0.25;
+        client.config.verify_mint_owner = false;
+        let mint = Pubkey::new_unique();
+        let referrer = Pubkey::new_unique();
+        let ids: Vec<String> = vec!["w-000001".to_string(), "w-000002".to_string()];
+        let wallets: Vec<Keypair> = ids.iter().map(|_| Keypair::new()).collect();
+        let fee_transfers = |referrer: Option<&Pubkey>| -> Vec<(Pubkey, Pubkey, u64)> {
+            let transactions = client
+                .build_buy_transactions(&mint, &buy_legs(&ids, &wallets), referrer, false, None, solana_sdk::hash::Hash::new_unique())
+                .unwrap();
+            transactions.iter()
+                .flat_map(|tx| {
+                    let keys = &tx.message.account_keys;
+                    tx.message.instructions.iter().filter_map(move |ix| {
+                        match (keys[ix.program_id_index as usize] == solana_sdk::system_program::id(), bincode::deserialize(&ix.data)) {
+                            (true, Ok(SystemInstruction::Transfer { lamports })) => {
+                                Some((keys[ix.accounts[0] as usize], keys[ix.accounts[1] as usize], lamports))
+                            }
+                            _ => None,
+                        }
+                    })
+                })
+                .collect()
+        };
+
+        // Each 0.5 SOL buy pays a quarter of its fee to the referrer and the rest to the platform
+        let fee = (client.config.fee_schedule.fee_for(0.5) * 1e9) as u64;
+        let referral = fee / 4;
+        let expected: Vec<(Pubkey, Pubkey, u64)> = wallets.iter()
+            .flat_map(|wallet| [
+                (wallet.pubkey(), client.fee_address, fee - referral),
+                (wallet.pubkey(), referrer, referral),
+            ])
+            .collect();
+        assert_eq!(fee_transfers(Some(&referrer)), expected);
+
+        // Unreferred, the whole fee goes to the platform
+        let unreferred: Vec<(Pubkey, Pubkey, u64)> = wallets.iter()
+            .map(|wallet| (wallet.pubkey(), client.fee_address, fee))
+            .collect();
+        assert_eq!(fee_transfers(None), unreferred);
+
+        // Graduated sells split the fee the same way
+        let seller = Pubkey::new_unique();
+        let sell_fee = (client.config.fee_schedule.fee_for(2.0) * 1e9) as u64;
+        let transfers = client.fee_transfers(&seller, 2.0, Some(&referrer));
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[1], system_instruction::transfer(&seller, &referrer, sell_fee / 4));
+
+        // So do sells on the curve
+        let sellers = wallet_store_with(&["w-000001"]);
+        let seller = sellers.get_keypair("w-000001").unwrap().pubkey();
+        let sell = SellRequest {
+            token_address: mint.to_string(),
+            token_amounts: vec![10_000_000_000_000],
+            ui_token_amounts: None,
+            wallet_ids: vec!["w-000001".to_string()],
+            user_id: 1,
+            min_sol_out: None,
+            max_price_impact_bps: None,
+            dry_run: true,
+            via_bundle: false,
+            strategy: None,
+            close_account: false,
+            referrer: Some(referrer.to_string()),
+        };
+        let rpc_client = RpcPool::new(vec![CurveNode::failing(0)], 5, Duration::from_secs(30), false);
+        let results = client.sell_tokens(sell, &sellers, &rpc_client, None).await.unwrap();
+        assert!(results[0].success, "{:?}", results[0].error);
+        let sell_fee = (results[0].fee_paid.unwrap() * 1e9) as u64;
+        let referral = (sell_fee as f64 * 0.25) as u64;
+        assert!(referral > 0);
+        assert_eq!(transfers_to(&results[0], &referrer), vec![(seller, referral)]);
+        assert_eq!(transfers_to(&results[0], &client.fee_address), vec![(seller, sell_fee - referral)]);
+
+        // A referrer that isn't a wallet address fails the trade up front
+        let request = BuyRequest {
+            token_address: mint.to_string(),
+            sol_amounts: vec![0.5],
+            wallet_ids: vec!["w-000001".to_string()],
+            user_id: 1,
+            min_tokens_out: None,
+            max_price_impact_bps: None,
+            dry_run: true,
+            via_bundle: false,
+            strategy: None,
+            referrer: Some("not-a-wallet".to_string()),
+            max_total_sol: None,
+        };
+        let results = client.buy_tokens(request, &wallet_store_with(&[]), &RpcPool::new_mock("succeeds"), None).await.unwrap();
+        assert_eq!(results[0].error.as_deref(), Some("Invalid referrer address: not-a-wallet"));
+    }
+
+    #[test]
+    fn test_validate_transaction_size() {
+        let payer = Keypair::new();
+        let blockhash = solana_sdk::hash::Hash::new_unique();
+
+        let normal = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        assert!(validate_transaction_size(&normal).is_ok());
+
+        // Forty transfers to distinct recipients need far more than one packet of account keys
+        let transfers: Vec<Instruction> = (0..40)
+            .map(|_| system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1))
+            .collect();
+        let oversized = Transaction::new_signed_with_payer(&transfers, Some(&payer.pubkey()), &[&payer], blockhash);
+        let err = validate_transaction_size(&oversized).unwrap_err();
+        assert!(err.to_string().contains("fewer wallets"));
+        assert!(build_signed_transactions(&[oversized]).is_err());
+    }
+
+    fn token_amount(raw: u64) -> serde_json::Value {
+        serde_json::json!({
+            "amount": raw.to_string(),
+            "decimals": 6,
+            "uiAmount": raw as f64 / 1e6,
+            "uiAmountString": (raw as f64 / 1e6).to_string()
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_top_holders_are_sorted_with_supply_share() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let mint = Pubkey::new_unique();
+        let holder = |raw: u64| {
+            let mut account = token_amount(raw);
+            account["address"] = serde_json::json!(Pubkey::new_unique().to_string());
+            account
+        };
+        let rpc_client = |accounts: Vec<serde_json::Value>| {
+            let mut mocks = HashMap::new();
+            mocks.insert(
+                RpcRequest::GetTokenLargestAccounts,
+                serde_json::json!({ "context": { "slot": 1 }, "value": accounts }),
+            );
+            mocks.insert(
+                RpcRequest::GetTokenSupply,
+                serde_json::json!({ "context": { "slot": 1 }, "value": token_amount(1_000_000_000_000) }),
+            );
+            RpcPool::new_mock_with_mocks("succeeds", mocks)
+        };
+
+        let accounts = vec![holder(50_000_000_000), holder(200_000_000_000), holder(100_000_000_000)];
+        let holders = client.get_top_holders(&mint, &rpc_client(accounts.clone())).await.unwrap();
+
+        // Every holder is returned, largest first
+        assert_eq!(holders.len(), 3);
+        assert_eq!(holders[0].address, accounts[1]["address"]);
+        assert_eq!(holders[0].amount, 200_000.0);
+        assert!((holders[0].percentage - 20.0).abs() < 1e-9);
+        assert!((holders[2].percentage - 5.0).abs() < 1e-9);
+        assert_eq!(holders[1].amount, 100_000.0);
+    }
+
+    #[test]
+    fn test_close_account_only_for_full_sells() {
+        let client = test_client();
+        let mint = Pubkey::new_unique();
+        let owners = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+
+        // Full sell, partial sell, and a wallet with no token account
+        let instructions = client
+            .close_emptied_accounts(&mint, &owners, &[1_000, 500, 100], &[Some(1_000), Some(800), None])
+            .unwrap();
+        assert_eq!(instructions.len(), 1);
+
+        let close = &instructions[0];
+        assert_eq!(close.program_id, spl_token::id());
+        assert!(matches!(
+            spl_token::instruction::TokenInstruction::unpack(&close.data).unwrap(),
+            spl_token::instruction::TokenInstruction::CloseAccount
+        ));
+        assert_eq!(close.accounts[0].pubkey, get_associated_token_address(&owners[0], &mint));
+        // Rent goes back to the owner
+        assert_eq!(close.accounts[1].pubkey, owners[0]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_send_reports_confirmation_or_times_out_as_submitted() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let mut client = test_client();
+        client.config.confirm_timeout_secs = 1;
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            solana_sdk::hash::Hash::new_unique(),
+        );
+
+        // Confirms on the first status check
+        let rpc_client = RpcPool::new_mock("succeeds");
+        let (signature, status) = client.send_transaction(&transaction, &[&payer], &rpc_client).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+        assert_eq!(status, ConfirmationStatus::Confirmed);
+
+        // Unknown on the first check, confirmed on the next
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetSignatureStatuses,
+            serde_json::json!({ "context": { "slot": 1 }, "value": [null] }),
+        );
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", mocks);
+        let (_, status) = client.send_transaction(&transaction, &[&payer], &rpc_client).await.unwrap();
+        assert_eq!(status, ConfirmationStatus::Confirmed);
+
+        // Never seen: the send still succeeds, reported as submitted
+        let rpc_client = RpcPool::new_mock("sig_not_found");
+        let (signature, status) = client.send_transaction(&transaction, &[&payer], &rpc_client).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+        assert_eq!(status, ConfirmationStatus::Submitted);
+
+        // A request's deadline cuts the wait short of the configured timeout
+        client.config.confirm_timeout_secs = 30;
+        let start = Instant::now();
+        let outcome = crate::deadline::Deadline::after(Duration::from_millis(500))
+            .run(client.send_transaction(&transaction, &[&payer], &rpc_client))
+            .await;
+        assert!(start.elapsed() < Duration::from_secs(5), "took {:?}", start.elapsed());
+        if let Ok((_, status)) = outcome {
+            assert_eq!(status, ConfirmationStatus::Submitted);
+        }
+
+        // Landing with an error is a failure
+        let rpc_client = RpcPool::new_mock("instruction_error");
+        assert!(client.send_transaction(&transaction, &[&payer], &rpc_client).await.is_err());
+    }
+
+    /// A mock node that rejects the first transaction sent as having an expired blockhash
+    struct ExpiringBlockhashNode {
+        inner: solana_rpc_client::mock_sender::MockSender,
+        sent_blockhashes: Arc<std::sync::Mutex<Vec<Hash>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl solana_client::rpc_sender::RpcSender for ExpiringBlockhashNode {
+        async fn send(
+            &self,
+            request: solana_client::rpc_request::RpcRequest,
+            params: serde_json::Value,
+        ) -> Result<serde_json::Value, solana_client::client_error::ClientError> {
+            if request == solana_client::rpc_request::RpcRequest::SendTransaction {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(params[0].as_str().unwrap())
+                    .unwrap();
+                let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+                let mut sent = self.sent_blockhashes.lock().unwrap();
+                sent.push(transaction.message.recent_blockhash);
+                if sent.len() == 1 {
+                    return Err(solana_sdk::transaction::TransactionError::BlockhashNotFound.into());
+                }
+            }
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    /// An RPC node whose preflight rejects every transaction with `error`.
+    struct RejectingNode {
+        inner: solana_rpc_client::mock_sender::MockSender,
+        error: solana_sdk::transaction::TransactionError,
+    }
+
+    #[async_trait::async_trait]
+    impl solana_client::rpc_sender::RpcSender for RejectingNode {
+        async fn send(
+            &self,
+            request: solana_client::rpc_request::RpcRequest,
+            params: serde_json::Value,
+        ) -> Result<serde_json::Value, solana_client::client_error::ClientError> {
+            if request == solana_client::rpc_request::RpcRequest::SendTransaction {
+                return Err(self.error.clone().into());
+            }
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_send_explains_a_wallet_drained_since_the_balance_check() {
+        use solana_sdk::instruction::InstructionError;
+        use solana_sdk::transaction::TransactionError;
+
+        let client = test_client();
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            solana_sdk::hash::Hash::new_unique(),
+        );
+        let node = RejectingNode {
+            inner: solana_rpc_client::mock_sender::MockSender::new("succeeds"),
+            error: TransactionError::InstructionError(0, InstructionError::Custom(1)),
+        };
+        let rpc_client = RpcPool::new(vec![node], 5, Duration::from_secs(30), false);
+
+        let err = client.send_transaction(&transaction, &[&payer], &rpc_client).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PumpFunError>(),
+            Some(&PumpFunError::InsufficientFunds { wallet: payer.pubkey().to_string(), detail: "not enough SOL".to_string() })
+        );
+
+        // Trades report it per wallet in plain words
+        let results = client.send_transactions(&[transaction], &[&payer], "buy", &rpc_client).await;
+        let error = results[0].error.as_deref().unwrap();
+        assert!(error.contains("has insufficient funds: not enough SOL"), "{}", error);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_send_resigns_once_when_the_blockhash_expires() {
+        let client = test_client();
+        let payer = Keypair::new();
+        let stale = solana_sdk::hash::Hash::new_unique();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &client.fee_address, 1)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            stale,
+        );
+        let sent_blockhashes = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let node = ExpiringBlockhashNode {
+            inner: solana_rpc_client::mock_sender::MockSender::new("succeeds"),
+            sent_blockhashes: sent_blockhashes.clone(),
+        };
+        let rpc_client = RpcPool::new(vec![node], 5, Duration::from_secs(30), false);
+        let fresh = rpc_client.get_latest_blockhash().unwrap();
+
+        let (signature, status) = client.send_transaction(&transaction, &[&payer], &rpc_client).await.unwrap();
+        assert_eq!(status, ConfirmationStatus::Confirmed);
+        assert_eq!(*sent_blockhashes.lock().unwrap(), vec![stale, fresh]);
+        // Re-signed over the fresh blockhash, so the signature changed with it
+        assert_ne!(signature, transaction.signatures[0]);
+
+        // Without its signer the transaction can't be re-signed, so the expiry is reported
+        sent_blockhashes.lock().unwrap().clear();
+        let err = client.send_transaction(&transaction, &[], &rpc_client).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("Failed to re-sign"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_snipe_delays_fall_within_the_range() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let delays: Vec<Duration> = (0..500).map(|_| snipe_delay((200, 300), &mut rng)).collect();
+        assert!(delays.iter().all(|delay| (200..=300).contains(&delay.as_millis())));
+        // Both ends of the range are reachable
+        assert!(delays.contains(&Duration::from_millis(200)));
+        assert!(delays.contains(&Duration::from_millis(300)));
+        assert_eq!(snipe_delay((450, 450), &mut rng), Duration::from_millis(450));
+    }
+
+    /// A mock node that counts the transactions sent to it
+    struct CountingNode {
+        inner: solana_rpc_client::mock_sender::MockSender,
+        sends: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl solana_client::rpc_sender::RpcSender for CountingNode {
+        async fn send(
+            &self,
+            request: solana_client::rpc_request::RpcRequest,
+            params: serde_json::Value,
+        ) -> Result<serde_json::Value, solana_client::client_error::ClientError> {
+            if request == solana_client::rpc_request::RpcRequest::SendTransaction {
+                self.sends.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    /// Records each wait, and how many transactions had been sent by then, without waiting
+    struct RecordingClock {
+        sends: Arc<std::sync::atomic::AtomicUsize>,
+        sleeps: std::sync::Mutex<Vec<(Duration, usize)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Clock for RecordingClock {
+        async fn sleep(&self, duration: Duration) {
+            let sent = self.sends.load(std::sync::atomic::Ordering::SeqCst);
+            self.sleeps.lock().unwrap().push((duration, sent));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_delayed_launch_waits_between_the_create_and_the_buys() {
+        let sends = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let clock = Arc::new(RecordingClock { sends: sends.clone(), sleeps: std::sync::Mutex::new(Vec::new()) });
+        let mut client = test_client();
+        client.clock = clock.clone();
+        let mut mocks = solana_rpc_client::mock_sender::Mocks::new();
+        mocks.insert(
+            solana_client::rpc_request::RpcRequest::GetBalance,
+            serde_json::json!({ "context": { "slot": 1 }, "value": 10_000_000_000u64 }),
+        );
+        let node = CountingNode {
+            inner: solana_rpc_client::mock_sender::MockSender::new_with_mocks("succeeds", mocks),
+            sends: sends.clone(),
+        };
+        let rpc_client = RpcPool::new(vec![node], 5, Duration::from_secs(30), false);
+        let creator = Keypair::new();
+        let (first, second) = (Keypair::new(), Keypair::new());
+        let wallets = [("w-000001", &first), ("w-000002", &second)];
+
+        let result = client
+            .create_then_snipe(valid_metadata(), &creator, &[0.1, 0.2], &wallets, (250, 750), &rpc_client)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.bundle_id.is_none());
+        assert!(result.mint.is_some());
+        assert_eq!(result.signatures.len(), 3);
+
+        // One wait, in range, after the create and before either buy
+        let sleeps = clock.sleeps.lock().unwrap().clone();
+        assert_eq!(sleeps.len(), 1);
+        let (delay, sent_before) = sleeps[0];
+        assert!((250..=750).contains(&delay.as_millis()), "{:?}", delay);
+        assert_eq!(sent_before, 1);
+        assert_eq!(sends.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // An inverted range is refused before anything is sent
+        let result = client
+            .create_then_snipe(valid_metadata(), &creator, &[0.1, 0.2], &wallets, (750, 250), &rpc_client)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(sends.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    fn nonce_account(authority: &Pubkey, blockhash: &Hash) -> (Vec<u8>, Hash) {
+        use solana_sdk::nonce::state::{Data, DurableNonce};
+        let durable_nonce = DurableNonce::from_blockhash(blockhash);
+        let versions = NonceVersions::new(NonceState::Initialized(Data::new(*authority, durable_nonce, 5_000)));
+        (bincode::serialize(&versions).unwrap(), *durable_nonce.as_hash())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_nonce_creation_advances_the_nonce_first() {
+        use solana_client::rpc_request::RpcRequest;
+        use solana_sdk::system_instruction::SystemInstruction;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let creator = Keypair::new();
+        let nonce = Pubkey::new_unique();
+        let (data, stored) = nonce_account(&creator.pubkey(), &Hash::new_unique());
+        let rpc_client = RpcPool::new_mock_with_mocks("succeeds", HashMap::from([
+            (RpcRequest::GetBalance, serde_json::json!({ "context": { "slot": 1 }, "value": 10_000_000_000u64 })),
+            (RpcRequest::GetAccountInfo, serde_json::json!({
+                "context": { "slot": 1 },
+                "value": ui_account(&data, &solana_sdk::system_program::id())
+            })),
+        ]));
+
+        let result = client
+            .create_token(valid_metadata(), &MintOptions::default(), &creator, Some(&nonce), &rpc_client, true)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let bytes = base64::engine::general_purpose::STANDARD.decode(result.serialized_tx.unwrap()).unwrap();
+        let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+
+        // The advance comes first and the nonce stands in for the blockhash
+        let message = &transaction.message;
+        let first = &message.instructions[0];
+        assert_eq!(message.account_keys[first.program_id_index as usize], solana_sdk::system_program::id());
+        assert_eq!(bincode::deserialize::<SystemInstruction>(&first.data).unwrap(), SystemInstruction::AdvanceNonceAccount);
+        assert_eq!(message.account_keys[first.accounts[0] as usize], nonce);
+        assert_eq!(message.recent_blockhash, stored);
+        assert!(uses_durable_nonce(&transaction).is_some());
+        assert!(transaction.verify().is_ok());
+
+        // A nonce someone else controls is refused
+        let err = client
+            .nonce_blockhash(&nonce, &Pubkey::new_unique(), &rpc_client)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(PumpFunError::InvalidNonceAccount { .. })), "{}", err);
+    }
+
+    /// A mock node that reports every signature as unknown until the second send
+    struct LateConfirmingNode {
+        inner: solana_rpc_client::mock_sender::MockSender,
+        sends: Arc<std::sync::Mutex<Vec<serde_json::Value>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl solana_client::rpc_sender::RpcSender for LateConfirmingNode {
+        async fn send(
+            &self,
+            request: solana_client::rpc_request::RpcRequest,
+            params: serde_json::Value,
+        ) -> Result<serde_json::Value, solana_client::client_error::ClientError> {
+            use solana_client::rpc_request::RpcRequest;
+            match request {
+                RpcRequest::SendTransaction => self.sends.lock().unwrap().push(params.clone()),
+                RpcRequest::GetSignatureStatuses if self.sends.lock().unwrap().len() < 2 => {
+                    return Ok(serde_json::json!({ "context": { "slot": 1 }, "value": [null] }));
+                }
+                _ => {}
+            }
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unconfirmed_durable_transactions_are_resent_unchanged() {
+        let mut client = test_client();
+        client.config.confirm_timeout_secs = 0;
+        let payer = Keypair::new();
+        let nonce = Pubkey::new_unique();
+        let (_, stored) = nonce_account(&payer.pubkey(), &Hash::new_unique());
+        let rpc_with_node = |sends: &Arc<std::sync::Mutex<Vec<serde_json::Value>>>| {
+            let node = LateConfirmingNode {
+                inner: solana_rpc_client::mock_sender::MockSender::new("succeeds"),
+                sends: sends.clone(),
+            };
+            RpcPool::new(vec![node], 5, Duration::from_secs(30), false)
+        };
+
+        let durable = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::advance_nonce_account(&nonce, &payer.pubkey()),
+                system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000),
+            ],
+            Some(&payer.pubkey()),
+            &[&payer],
+            stored,
+        );
+        let sends = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (signature, confirmation) = client.send_transaction(&durable, &[&payer], &rpc_with_node(&sends)).await.unwrap();
+        assert_eq!(signature, durable.signatures[0]);
+        assert_eq!(confirmation, ConfirmationStatus::Confirmed);
+        let sends = sends.lock().unwrap().clone();
+        assert_eq!(sends.len(), 2);
+        assert_eq!(sends[0], sends[1]);
+
+        // A blockhash transaction isn't resent; it's left for the caller to poll
+        let plain = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let sends = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (_, confirmation) = client.send_transaction(&plain, &[&payer], &rpc_with_node(&sends)).await.unwrap();
+        assert_eq!(confirmation, ConfirmationStatus::Submitted);
+        assert_eq!(sends.lock().unwrap().len(), 1);
+    }
+
+    /// The lamports each transfer to `to` in a dry run's transactions moves, by sender.
+    fn transfers_to(result: &TransactionResult, to: &Pubkey) -> Vec<(Pubkey, u64)> {
+        use solana_sdk::system_instruction::SystemInstruction;
+        let mut transfers = Vec::new();
+        for encoded in result.serialized_tx.as_deref().expect("dry run returns the transactions").split(',') {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+            let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+            let keys = &transaction.message.account_keys;
+            for ix in &transaction.message.instructions {
+                if let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&ix.data) {
+                    if keys[ix.accounts[1] as usize] == *to {
+                        transfers.push((keys[ix.accounts[0] as usize], lamports));
+                    }
+                }
+            }
+        }
+        transfers
+    }
+
+    /// A mock node where every wallet holds the same balance
+    struct FundedNode {
+        inner: solana_rpc_client::mock_sender::MockSender,
+        lamports: u64,
+    }
+
+    #[async_trait::async_trait]
+    impl solana_client::rpc_sender::RpcSender for FundedNode {
+        async fn send(
+            &self,
+            request: solana_client::rpc_request::RpcRequest,
+            params: serde_json::Value,
+        ) -> Result<serde_json::Value, solana_client::client_error::ClientError> {
+            if request == solana_client::rpc_request::RpcRequest::GetBalance {
+                return Ok(serde_json::json!({ "context": { "slot": 1 }, "value": self.lamports }));
+            }
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sweeps_leave_each_wallet_its_rent_exempt_minimum() {
+        use solana_client::rpc_request::RpcRequest;
+        use std::collections::HashMap;
+
+        let client = test_client();
+        let treasury = Pubkey::new_unique();
+        let rent_exempt_minimum = 890_880;
+        let rpc_with_balance = |lamports: u64| {
+            let mocks = HashMap::from([(RpcRequest::GetMinimumBalanceForRentExemption, serde_json::json!(rent_exempt_minimum))]);
+            let node = FundedNode { inner: solana_rpc_client::mock_sender::MockSender::new_with_mocks("succeeds", mocks), lamports };
+            RpcPool::new(vec![node], 5, Duration::from_secs(30), false)
+        };
+        let keypairs: Vec<Keypair> = (0..12).map(|_| Keypair::new()).collect();
+        let wallets: Vec<&Keypair> = keypairs.iter().collect();
+        let request = SweepRequest {
+            wallet_ids: (1..=12).map(|n| format!("w-{:06}", n)).collect(),
+            dry_run: true,
+            via_bundle: false,
+        };
+
+        let balance = 2_000_000_000;
+        let sweep = client
+            .sweep_wallets(&request, &wallets, &treasury, &rpc_with_balance(balance), None)
+            .await
+            .unwrap();
+        assert!(sweep.transaction.success, "{:?}", sweep.transaction.error);
+        let kept = rent_exempt_minimum + client.config.balance_buffer_lamports;
+        assert_eq!(sweep.kept_lamports, kept);
+        assert!(sweep.wallets.iter().all(|wallet| wallet.swept_lamports == balance - kept));
+        assert_eq!(sweep.total_swept_lamports, 12 * (balance - kept));
+
+        // Twelve signers don't fit one packet, yet each wallet is swept exactly once
+        let transfers = transfers_to(&sweep.transaction, &treasury);
+        assert!(sweep.transaction.serialized_tx.as_deref().unwrap().contains(','));
+        assert_eq!(transfers.len(), 12);
+        for (wallet, (from, lamports)) in keypairs.iter().zip(&transfers) {
+            assert_eq!(*from, wallet.pubkey());
+            assert_eq!(*lamports, balance - kept);
+            assert!(balance - lamports >= rent_exempt_minimum);
+        }
+
+        // A bundled sweep's tip comes out of the wallet paying it
+        let jito_client = JitoBundleClient::new("https://test.api.jito.wtf".to_string());
+        let tip = (jito_client.tip_amount() * 1e9) as u64;
+        let bundled = SweepRequest { wallet_ids: request.wallet_ids[..2].to_vec(), dry_run: true, via_bundle: true };
+        let sweep = client
+            .sweep_wallets(&bundled, &wallets[..2], &treasury, &rpc_with_balance(balance), Some(&jito_client))
+            .await
+            .unwrap();
+        assert!(sweep.transaction.success, "{:?}", sweep.transaction.error);
+        let swept: Vec<u64> = transfers_to(&sweep.transaction, &treasury).iter().map(|(_, lamports)| *lamports).collect();
+        assert_eq!(swept, vec![balance - kept - tip, balance - kept]);
+        assert_eq!(sweep.wallets[0].swept_lamports, balance - kept - tip);
+
+        // Wallets already down to what they keep are left alone
+        let sweep = client
+            .sweep_wallets(&request, &wallets, &treasury, &rpc_with_balance(kept), None)
+            .await
+            .unwrap();
+        assert!(!sweep.transaction.success);
+        assert!(sweep.wallets.iter().all(|wallet| wallet.swept_lamports == 0));
+        assert_eq!(sweep.total_swept_lamports, 0);
+
+        // A wallet listed under two IDs is refused before anything is swept
+        let shared = SweepRequest { wallet_ids: vec!["w-000001".to_string(), "w-000002".to_string()], dry_run: true, via_bundle: false };
+        let sweep = client
+            .sweep_wallets(&shared, &[wallets[0], wallets[0]], &treasury, &rpc_with_balance(balance), None)
+            .await
+            .unwrap();
+        assert!(!sweep.transaction.success);
+        assert!(sweep.transaction.error.as_deref().unwrap().contains("are the same wallet"));
+        assert!(sweep.wallets.is_empty());
+        assert_eq!(sweep.total_swept_lamports, 0);
+    }
+}
@@ -0,0 +1,425 @@
+use anyhow::{Context, Result};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::error::PumpFunError;
+
+/// Raydium's AMM v4 program, where Pump.Fun migrates graduated tokens
+pub const RAYDIUM_AMM_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+
+/// Seed of the AMM's authority PDA, combined with the pool's nonce
+const AMM_AUTHORITY_SEED: &[u8] = b"amm authority";
+
+/// Instruction tag of `swap_base_in`: exact input, minimum output
+const SWAP_BASE_IN: u8 = 9;
+
+/// Size of an AMM v4 pool account
+const AMM_INFO_LEN: usize = 752;
+
+// Offsets into the AMM v4 pool account
+const AMM_NONCE: usize = 8;
+const AMM_SWAP_FEE_NUMERATOR: usize = 176;
+const AMM_SWAP_FEE_DENOMINATOR: usize = 184;
+const AMM_BASE_VAULT: usize = 336;
+const AMM_QUOTE_VAULT: usize = 368;
+const AMM_BASE_MINT: usize = 400;
+const AMM_QUOTE_MINT: usize = 432;
+const AMM_OPEN_ORDERS: usize = 496;
+const AMM_MARKET: usize = 528;
+const AMM_MARKET_PROGRAM: usize = 560;
+const AMM_TARGET_ORDERS: usize = 592;
+
+// Offsets into the OpenBook market account the pool is paired with
+const MARKET_LEN: usize = 388;
+const MARKET_VAULT_SIGNER_NONCE: usize = 45;
+const MARKET_BASE_VAULT: usize = 117;
+const MARKET_QUOTE_VAULT: usize = 165;
+const MARKET_EVENT_QUEUE: usize = 253;
+const MARKET_BIDS: usize = 285;
+const MARKET_ASKS: usize = 317;
+
+/// A graduated token's Raydium pool against wrapped SOL, with its reserves when fetched.
+///
+/// Pump.Fun migrates tokens with the token as the pool's base and wrapped SOL as its quote.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumPool {
+    pub amm_id: Pubkey,
+    pub authority: Pubkey,
+    pub open_orders: Pubkey,
+    pub target_orders: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub sol_vault: Pubkey,
+    pub market_program: Pubkey,
+    pub market: Pubkey,
+    pub market_bids: Pubkey,
+    pub market_asks: Pubkey,
+    pub market_event_queue: Pubkey,
+    pub market_base_vault: Pubkey,
+    pub market_quote_vault: Pubkey,
+    pub market_vault_signer: Pubkey,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+    pub token_reserve: u64, // Raw token units
+    pub sol_reserve: u64,   // Lamports
+}
+
+impl RaydiumPool {
+    /// Decodes a pool from its AMM and market accounts.
+    ///
+    /// # Errors
+    /// Returns an error if either account is too short, the pool isn't paired against
+    /// wrapped SOL, or the market's vault signer can't be derived.
+    pub fn from_accounts(amm_id: Pubkey, amm_data: &[u8], market_data: &[u8]) -> Result<Self> {
+        if amm_data.len() < AMM_INFO_LEN {
+            return Err(anyhow::anyhow!("Raydium pool account is too short"));
+        }
+        if market_data.len() < MARKET_LEN {
+            return Err(anyhow::anyhow!("Raydium market account is too short"));
+        }
+        if pubkey_at(amm_data, AMM_QUOTE_MINT) != spl_token::native_mint::id() {
+            return Err(anyhow::anyhow!("Raydium pool {} is not paired against SOL", amm_id));
+        }
+
+        let nonce = u64_at(amm_data, AMM_NONCE) as u8;
+        let authority = Pubkey::create_program_address(&[AMM_AUTHORITY_SEED, &[nonce]], &RAYDIUM_AMM_PROGRAM_ID)
+            .context("Invalid Raydium authority nonce")?;
+        let market = pubkey_at(amm_data, AMM_MARKET);
+        let market_program = pubkey_at(amm_data, AMM_MARKET_PROGRAM);
+        let signer_nonce = u64_at(market_data, MARKET_VAULT_SIGNER_NONCE);
+        let market_vault_signer = Pubkey::create_program_address(
+            &[market.as_ref(), &signer_nonce.to_le_bytes()],
+            &market_program,
+        )
+        .context("Invalid market vault signer nonce")?;
+
+        Ok(Self {
+            amm_id,
+            authority,
+            open_orders: pubkey_at(amm_data, AMM_OPEN_ORDERS),
+            target_orders: pubkey_at(amm_data, AMM_TARGET_ORDERS),
+            token_mint: pubkey_at(amm_data, AMM_BASE_MINT),
+            token_vault: pubkey_at(amm_data, AMM_BASE_VAULT),
+            sol_vault: pubkey_at(amm_data, AMM_QUOTE_VAULT),
+            market_program,
+            market,
+            market_bids: pubkey_at(market_data, MARKET_BIDS),
+            market_asks: pubkey_at(market_data, MARKET_ASKS),
+            market_event_queue: pubkey_at(market_data, MARKET_EVENT_QUEUE),
+            market_base_vault: pubkey_at(market_data, MARKET_BASE_VAULT),
+            market_quote_vault: pubkey_at(market_data, MARKET_QUOTE_VAULT),
+            market_vault_signer,
+            swap_fee_numerator: u64_at(amm_data, AMM_SWAP_FEE_NUMERATOR),
+            swap_fee_denominator: u64_at(amm_data, AMM_SWAP_FEE_DENOMINATOR),
+            token_reserve: 0,
+            sol_reserve: 0,
+        })
+    }
+
+    /// Tokens, in raw units, that `lamports_in` buys at the current reserves.
+    pub fn quote_buy(&self, lamports_in: u64) -> u64 {
+        self.swap_output(lamports_in, self.sol_reserve, self.token_reserve)
+    }
+
+    /// Lamports that selling `tokens_in` raw units returns at the current reserves.
+    pub fn quote_sell(&self, tokens_in: u64) -> u64 {
+        self.swap_output(tokens_in, self.token_reserve, self.sol_reserve)
+    }
+
+    /// Percentage the pool's spot price moves when `lamports_in` of SOL is swapped in.
+    pub fn buy_price_impact_pct(&self, lamports_in: u64) -> f64 {
+        let tokens_out = self.quote_buy(lamports_in);
+        let price_before = self.sol_reserve as f64 / self.token_reserve as f64;
+        let price_after = (self.sol_reserve + lamports_in) as f64 / (self.token_reserve - tokens_out) as f64;
+        (price_after - price_before) / price_before * 100.0
+    }
+
+    /// Percentage the pool's spot price drops when `tokens_in` raw units are swapped in.
+    pub fn sell_price_impact_pct(&self, tokens_in: u64) -> f64 {
+        let lamports_out = self.quote_sell(tokens_in);
+        let price_before = self.sol_reserve as f64 / self.token_reserve as f64;
+        let price_after = (self.sol_reserve - lamports_out) as f64 / (self.token_reserve + tokens_in) as f64;
+        ((price_after - price_before) / price_before * 100.0).abs()
+    }
+
+    /// Constant-product output of `swap_base_in`, after the pool's swap fee.
+    fn swap_output(&self, amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+        if self.swap_fee_denominator == 0 {
+            return 0;
+        }
+        let amount_in = amount_in as u128;
+        let fee = (amount_in * self.swap_fee_numerator as u128).div_ceil(self.swap_fee_denominator as u128);
+        let amount_in = amount_in.saturating_sub(fee);
+        let denominator = reserve_in as u128 + amount_in;
+        if denominator == 0 {
+            return 0;
+        }
+        (reserve_out as u128 * amount_in / denominator) as u64
+    }
+
+    /// Builds a `swap_base_in` instruction between two of the owner's token accounts.
+    ///
+    /// # Arguments
+    /// * `user_source` - The account the input is taken from.
+    /// * `user_destination` - The account the output is paid into.
+    /// * `owner` - The signer owning both accounts.
+    /// * `amount_in` - The exact input, in raw units.
+    /// * `minimum_amount_out` - The swap fails on-chain if it would pay out less.
+    pub fn swap_instruction(
+        &self,
+        user_source: &Pubkey,
+        user_destination: &Pubkey,
+        owner: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Instruction {
+        let mut data = Vec::with_capacity(17);
+        data.push(SWAP_BASE_IN);
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+        Instruction {
+            program_id: RAYDIUM_AMM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new(self.amm_id, false),
+                AccountMeta::new_readonly(self.authority, false),
+                AccountMeta::new(self.open_orders, false),
+                AccountMeta::new(self.target_orders, false),
+                AccountMeta::new(self.token_vault, false),
+                AccountMeta::new(self.sol_vault, false),
+                AccountMeta::new_readonly(self.market_program, false),
+                AccountMeta::new(self.market, false),
+                AccountMeta::new(self.market_bids, false),
+                AccountMeta::new(self.market_asks, false),
+                AccountMeta::new(self.market_event_queue, false),
+                AccountMeta::new(self.market_base_vault, false),
+                AccountMeta::new(self.market_quote_vault, false),
+                AccountMeta::new_readonly(self.market_vault_signer, false),
+                AccountMeta::new(*user_source, false),
+                AccountMeta::new(*user_destination, false),
+                AccountMeta::new_readonly(*owner, true),
+            ],
+            data,
+        }
+    }
+
+    /// Instructions buying tokens with `lamports_in` of the owner's SOL.
+    ///
+    /// The SOL is wrapped into the owner's wrapped SOL account for the swap, which is
+    /// closed again afterwards so nothing is left wrapped.
+    pub fn buy_instructions(&self, owner: &Pubkey, lamports_in: u64, min_tokens_out: u64) -> Vec<Instruction> {
+        let wsol_account = get_associated_token_address(owner, &spl_token::native_mint::id());
+        let token_account = get_associated_token_address(owner, &self.token_mint);
+        let mut instructions = vec![
+            create_associated_token_account_idempotent(owner, owner, &spl_token::native_mint::id(), &spl_token::id()),
+            system_instruction::transfer(owner, &wsol_account, lamports_in),
+            sync_native(&wsol_account),
+            create_associated_token_account_idempotent(owner, owner, &self.token_mint, &spl_token::id()),
+        ];
+        instructions.push(self.swap_instruction(&wsol_account, &token_account, owner, lamports_in, min_tokens_out));
+        instructions.push(close_account(&wsol_account, owner));
+        instructions
+    }
+
+    /// Instructions selling `tokens_in` raw units of the owner's tokens for SOL.
+    ///
+    /// The proceeds land in the owner's wrapped SOL account, which is closed to unwrap them.
+    pub fn sell_instructions(&self, owner: &Pubkey, tokens_in: u64, min_lamports_out: u64) -> Vec<Instruction> {
+        let wsol_account = get_associated_token_address(owner, &spl_token::native_mint::id());
+        let token_account = get_associated_token_address(owner, &self.token_mint);
+        vec![
+            create_associated_token_account_idempotent(owner, owner, &spl_token::native_mint::id(), &spl_token::id()),
+            self.swap_instruction(&token_account, &wsol_account, owner, tokens_in, min_lamports_out),
+            close_account(&wsol_account, owner),
+        ]
+    }
+}
+
+/// Finds a graduated token's Raydium pool against SOL and reads its current reserves.
+///
+/// # Errors
+/// Returns `PumpFunError::PoolNotFound` if the token has no SOL pool, or an error if
+/// the pool's accounts can't be fetched or decoded.
+pub fn fetch_pool(token_mint: &Pubkey, rpc_client: &RpcClient) -> Result<RaydiumPool> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(AMM_INFO_LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(AMM_BASE_MINT, token_mint.as_ref())),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(AMM_QUOTE_MINT, spl_token::native_mint::id().as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let pools = rpc_client
+        .get_program_accounts_with_config(&RAYDIUM_AMM_PROGRAM_ID, config)
+        .context("Failed to look up Raydium pool")?;
+    let (amm_id, amm_account) = pools
+        .into_iter()
+        .next()
+        .ok_or_else(|| PumpFunError::PoolNotFound { mint: token_mint.to_string() })?;
+
+    let market = pubkey_at(&amm_account.data, AMM_MARKET);
+    let token_vault = pubkey_at(&amm_account.data, AMM_BASE_VAULT);
+    let sol_vault = pubkey_at(&amm_account.data, AMM_QUOTE_VAULT);
+    let accounts = rpc_client
+        .get_multiple_accounts(&[market, token_vault, sol_vault])
+        .context("Failed to fetch Raydium pool accounts")?;
+    let [market_account, token_vault_account, sol_vault_account] = <[_; 3]>::try_from(accounts)
+        .map_err(|_| anyhow::anyhow!("Unexpected account count fetching Raydium pool"))?;
+    let missing = |name: &str| anyhow::anyhow!("Raydium pool {} has no {} account", amm_id, name);
+
+    let mut pool = RaydiumPool::from_accounts(
+        amm_id,
+        &amm_account.data,
+        &market_account.ok_or_else(|| missing("market"))?.data,
+    )?;
+    pool.token_reserve = vault_amount(&token_vault_account.ok_or_else(|| missing("token vault"))?.data)?;
+    pool.sol_reserve = vault_amount(&sol_vault_account.ok_or_else(|| missing("SOL vault"))?.data)?;
+    Ok(pool)
+}
+
+fn vault_amount(data: &[u8]) -> Result<u64> {
+    spl_token::state::Account::unpack(data)
+        .map(|account| account.amount)
+        .context("Failed to decode Raydium vault")
+}
+
+fn sync_native(account: &Pubkey) -> Instruction {
+    spl_token::instruction::sync_native(&spl_token::id(), account)
+        .expect("the token program id is valid")
+}
+
+fn close_account(account: &Pubkey, owner: &Pubkey) -> Instruction {
+    spl_token::instruction::close_account(&spl_token::id(), account, owner, owner, &[])
+        .expect("the token program id is valid")
+}
+
+fn pubkey_at(data: &[u8], offset: usize) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&data[offset..offset + 32]);
+    Pubkey::new_from_array(bytes)
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A pool account for `token_mint` against SOL, with a 0.25% swap fee.
+    pub(crate) fn amm_account_bytes(token_mint: &Pubkey, market: &Pubkey, market_program: &Pubkey) -> Vec<u8> {
+        let (_, nonce) = Pubkey::find_program_address(&[AMM_AUTHORITY_SEED], &RAYDIUM_AMM_PROGRAM_ID);
+        let mut data = vec![0u8; AMM_INFO_LEN];
+        let mut put = |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        put(AMM_NONCE, &(nonce as u64).to_le_bytes());
+        put(AMM_SWAP_FEE_NUMERATOR, &25u64.to_le_bytes());
+        put(AMM_SWAP_FEE_DENOMINATOR, &10_000u64.to_le_bytes());
+        put(AMM_BASE_VAULT, Pubkey::new_unique().as_ref());
+        put(AMM_QUOTE_VAULT, Pubkey::new_unique().as_ref());
+        put(AMM_BASE_MINT, token_mint.as_ref());
+        put(AMM_QUOTE_MINT, spl_token::native_mint::id().as_ref());
+        put(AMM_OPEN_ORDERS, Pubkey::new_unique().as_ref());
+        put(AMM_MARKET, market.as_ref());
+        put(AMM_MARKET_PROGRAM, market_program.as_ref());
+        put(AMM_TARGET_ORDERS, Pubkey::new_unique().as_ref());
+        data
+    }
+
+    /// A market account whose vault signer derives from `market` under `market_program`.
+    pub(crate) fn market_account_bytes(market: &Pubkey, market_program: &Pubkey) -> Vec<u8> {
+        let nonce = (0u64..)
+            .find(|nonce| Pubkey::create_program_address(&[market.as_ref(), &nonce.to_le_bytes()], market_program).is_ok())
+            .unwrap();
+        let mut data = vec![0u8; MARKET_LEN];
+        data[MARKET_VAULT_SIGNER_NONCE..MARKET_VAULT_SIGNER_NONCE + 8].copy_from_slice(&nonce.to_le_bytes());
+        for offset in [MARKET_BASE_VAULT, MARKET_QUOTE_VAULT, MARKET_EVENT_QUEUE, MARKET_BIDS, MARKET_ASKS] {
+            data[offset..offset + 32].copy_from_slice(Pubkey::new_unique().as_ref());
+        }
+        data
+    }
+
+    /// An SPL token account holding `amount`, as a vault is read.
+    pub(crate) fn vault_account_bytes(mint: &Pubkey, amount: u64) -> Vec<u8> {
+        let account = spl_token::state::Account {
+            mint: *mint,
+            owner: Pubkey::new_unique(),
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        data
+    }
+
+    fn test_pool() -> RaydiumPool {
+        let (token_mint, market, market_program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pool = RaydiumPool::from_accounts(
+            Pubkey::new_unique(),
+            &amm_account_bytes(&token_mint, &market, &market_program),
+            &market_account_bytes(&market, &market_program),
+        )
+        .unwrap();
+        pool.sol_reserve = 100_000_000_000; // 100 SOL
+        pool.token_reserve = 200_000_000_000_000; // 200M tokens at 6 decimals
+        pool
+    }
+
+    #[test]
+    fn test_swap_instruction_layout() {
+        let pool = test_pool();
+        let (source, destination, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = pool.swap_instruction(&source, &destination, &owner, 1_000, 900);
+
+        assert_eq!(ix.program_id, RAYDIUM_AMM_PROGRAM_ID);
+        assert_eq!(ix.data[0], SWAP_BASE_IN);
+        assert_eq!(u64_at(&ix.data, 1), 1_000);
+        assert_eq!(u64_at(&ix.data, 9), 900);
+        assert_eq!(ix.accounts.len(), 18);
+        assert_eq!(ix.accounts[1].pubkey, pool.amm_id);
+        assert_eq!(ix.accounts[15].pubkey, source);
+        assert_eq!(ix.accounts[16].pubkey, destination);
+        assert!(ix.accounts[17].is_signer && !ix.accounts[17].is_writable);
+        assert_eq!(ix.accounts.iter().filter(|meta| meta.is_signer).count(), 1);
+    }
+
+    #[test]
+    fn test_quotes_charge_the_swap_fee() {
+        let pool = test_pool();
+        // 1 SOL into 100 SOL / 200M tokens: just under 1.98M tokens before the fee
+        let tokens = pool.quote_buy(1_000_000_000);
+        let fee_free = 200_000_000_000_000u128 * 1_000_000_000 / 101_000_000_000;
+        assert!(tokens < fee_free as u64);
+        assert!(tokens > (fee_free * 997 / 1000) as u64);
+
+        // Selling what was bought returns less than went in
+        assert!(pool.quote_sell(tokens) < 1_000_000_000);
+        assert!(pool.buy_price_impact_pct(1_000_000_000) > pool.buy_price_impact_pct(100_000_000));
+    }
+
+    #[test]
+    fn test_pools_not_paired_against_sol_are_rejected() {
+        let (token_mint, market, market_program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut amm = amm_account_bytes(&token_mint, &market, &market_program);
+        amm[AMM_QUOTE_MINT..AMM_QUOTE_MINT + 32].copy_from_slice(Pubkey::new_unique().as_ref());
+        let market_data = market_account_bytes(&market, &market_program);
+        assert!(RaydiumPool::from_accounts(Pubkey::new_unique(), &amm, &market_data).is_err());
+        assert!(RaydiumPool::from_accounts(Pubkey::new_unique(), &amm[..100], &market_data).is_err());
+    }
+}
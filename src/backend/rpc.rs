@@ -0,0 +1,986 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+use log::{info, warn};
+use serde::Serialize;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking;
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_client::rpc_request::{RpcError, RpcRequest};
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_rpc_client::mock_sender::{MockSender, Mocks};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::transaction::TransactionError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::config::Config;
+use crate::deadline::Deadline;
+
+/// JSON-RPC error code returned by nodes that are behind or unhealthy
+const NODE_UNHEALTHY_CODE: i64 = -32005;
+
+/// Starts the error of every call refused by an open circuit breaker
+const CIRCUIT_OPEN_MESSAGE: &str = "RPC circuit breaker is open";
+
+/// Returns true if an RPC error is worth retrying (timeouts, rate limits, flaky nodes).
+///
+/// Logic errors such as rejected transactions or malformed responses are not transient
+/// and are surfaced to the caller immediately.
+pub fn is_transient_error(error: &ClientError) -> bool {
+    // Retrying a call the breaker refused would only wait out the backoff
+    if is_circuit_open(error) {
+        return false;
+    }
+    match error.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status().is_some_and(|s| s.as_u16() == 429 || s.is_server_error())
+        }
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == NODE_UNHEALTHY_CODE
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if a call was refused because the RPC circuit breaker is open.
+pub fn is_circuit_open(error: &ClientError) -> bool {
+    error.to_string().contains(CIRCUIT_OPEN_MESSAGE)
+}
+
+/// Returns true if a send failed because the transaction's blockhash has expired.
+pub fn is_blockhash_not_found(error: &ClientError) -> bool {
+    matches!(error.get_transaction_error(), Some(TransactionError::BlockhashNotFound))
+        || error.to_string().contains("Blockhash not found")
+}
+
+/// Caches the latest blockhash so batch operations don't refetch it for every transaction.
+pub struct BlockhashCache {
+    ttl: Duration,
+    cached: Mutex<Option<(Hash, Instant)>>,
+}
+
+impl BlockhashCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cached blockhash, calling `fetch` if it is missing or older than the TTL.
+    pub async fn get_or_fetch<F, Fut>(&self, fetch: F) -> Result<Hash, ClientError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Hash, ClientError>>,
+    {
+        self.get_or_fetch_at(Instant::now(), fetch).await
+    }
+
+    async fn get_or_fetch_at<F, Fut>(&self, now: Instant, fetch: F) -> Result<Hash, ClientError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Hash, ClientError>>,
+    {
+        if let Some((hash, fetched_at)) = *self.cached.lock().unwrap() {
+            if now.saturating_duration_since(fetched_at) < self.ttl {
+                return Ok(hash);
+            }
+        }
+
+        // The lock isn't held across the fetch; concurrent misses just fetch twice
+        let hash = fetch().await?;
+        *self.cached.lock().unwrap() = Some((hash, now));
+        Ok(hash)
+    }
+
+    /// Drops the cached blockhash so the next call refetches it.
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap() = None;
+    }
+}
+
+/// Caches rent-exempt minimums by account size; they only change with cluster upgrades.
+#[derive(Default)]
+pub struct RentCache {
+    minimums: Mutex<HashMap<usize, u64>>,
+}
+
+impl RentCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached minimum for `size` bytes, calling `fetch` the first time it is needed.
+    pub async fn get_or_fetch<F, Fut>(&self, size: usize, fetch: F) -> Result<u64, ClientError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<u64, ClientError>>,
+    {
+        if let Some(minimum) = self.minimums.lock().unwrap().get(&size) {
+            return Ok(*minimum);
+        }
+
+        let minimum = fetch().await?;
+        self.minimums.lock().unwrap().insert(size, minimum);
+        Ok(minimum)
+    }
+}
+
+/// Nominal time between slots
+pub const SLOT_DURATION: Duration = Duration::from_millis(400);
+
+/// Estimates how far an RPC node has fallen behind from how its reported slots progress.
+///
+/// The chain should advance one slot per `SLOT_DURATION` from the highest slot seen so far,
+/// so a node reporting less than that is lagging by the difference.
+#[derive(Default)]
+pub struct SlotTracker {
+    reference: Mutex<Option<(u64, Instant)>>,
+}
+
+impl SlotTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a slot reported by the node and returns how many slots behind it is.
+    pub fn observe(&self, slot: u64) -> u64 {
+        self.observe_at(Instant::now(), slot)
+    }
+
+    pub(crate) fn observe_at(&self, now: Instant, slot: u64) -> u64 {
+        let mut reference = self.reference.lock().unwrap();
+        let expected = match *reference {
+            Some((seen, at)) => {
+                seen + (now.saturating_duration_since(at).as_millis() / SLOT_DURATION.as_millis()) as u64
+            }
+            None => slot,
+        };
+
+        // Only a node that keeps up moves the reference, so a stalled one can't reset its own lag
+        if slot >= expected {
+            *reference = Some((slot, now));
+            0
+        } else {
+            expected - slot
+        }
+    }
+}
+
+/// Position of an RPC circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,     // Failing calls fast until the cooldown passes
+    HalfOpen, // Letting one trial call through to test recovery
+}
+
+/// Stops calling a failing RPC node for a while, so requests fail fast instead of each
+/// waiting out a timeout.
+///
+/// The breaker opens after `failure_threshold` consecutive transient failures. Once
+/// `cooldown` has passed it half-opens and lets a single trial call through: success
+/// closes it, failure reopens it for another cooldown. Any answer from the node, even an
+/// error response, counts as success.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Builds a breaker with the configured threshold and cooldown.
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.rpc_breaker_threshold, Duration::from_secs(config.rpc_breaker_cooldown_secs))
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state_at(Instant::now())
+    }
+
+    fn state_at(&self, now: Instant) -> CircuitState {
+        match self.state.lock().unwrap().opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if now.saturating_duration_since(opened_at) < self.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// How long until an open breaker half-opens; zero unless it is open.
+    pub fn retry_after(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        state.opened_at
+            .map(|opened_at| self.cooldown.saturating_sub(opened_at.elapsed()))
+            .unwrap_or_default()
+    }
+
+    /// Returns whether a call may go ahead, claiming the trial call when half-open.
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    pub(crate) fn try_acquire_at(&self, now: Instant) -> bool {
+        let circuit = self.state_at(now);
+        let mut state = self.state.lock().unwrap();
+        match circuit {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if state.trial_in_flight => false,
+            CircuitState::HalfOpen => {
+                state.trial_in_flight = true;
+                true
+            }
+        }
+    }
+
+    /// Records a call the node answered, closing the breaker.
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.opened_at.is_some() {
+            info!("RPC node recovered; closing the circuit breaker");
+        }
+        *state = BreakerState::default();
+    }
+
+    /// Records a transient failure, opening the breaker once the threshold is reached.
+    pub fn record_failure(&self) {
+        self.record_failure_at(Instant::now())
+    }
+
+    pub(crate) fn record_failure_at(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.trial_in_flight = false;
+        state.consecutive_failures += 1;
+        // A failed trial reopens the breaker straight away
+        if state.opened_at.is_some() || state.consecutive_failures >= self.failure_threshold {
+            if state.opened_at.is_none() {
+                warn!(
+                    "RPC failed {} times in a row; failing RPC calls fast for {}s",
+                    state.consecutive_failures,
+                    self.cooldown.as_secs()
+                );
+            }
+            state.opened_at = Some(now);
+        }
+    }
+}
+
+/// RPC transport that passes every call through a circuit breaker.
+///
+/// While the breaker is open, calls fail immediately without reaching the node.
+pub struct BreakerSender<S> {
+    inner: S,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl<S> BreakerSender<S> {
+    pub fn new(inner: S, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for BreakerSender<S> {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value, ClientError> {
+        if !self.breaker.try_acquire() {
+            return Err(ClientErrorKind::Custom(format!(
+                "{}; retrying in {}s",
+                CIRCUIT_OPEN_MESSAGE,
+                self.breaker.retry_after().as_secs().max(1)
+            ))
+            .into());
+        }
+
+        let result = self.inner.send(request, params).await;
+        match &result {
+            Err(e) if is_transient_error(e) => self.breaker.record_failure(),
+            _ => self.breaker.record_success(),
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Several RPC nodes used as one: each call goes to the first node, failing over down
+/// the list on transient errors.
+///
+/// Every node sits behind its own circuit breaker, so one that keeps failing is skipped
+/// until it recovers rather than waited on by every call. With `balance_reads` set, reads
+/// start at each node in turn to spread the load; transactions always go to the first
+/// healthy node.
+///
+/// Dereferences to the `RpcClient` that sends through the pool; async code should call
+/// through `nonblocking` instead, which doesn't tie up a worker thread while it waits.
+pub struct RpcPool {
+    client: RpcClient,
+    breakers: Vec<Arc<CircuitBreaker>>,
+}
+
+impl RpcPool {
+    /// Builds a pool over `senders`, in failover order.
+    ///
+    /// # Panics
+    /// Panics if `senders` is empty.
+    pub fn new<S: RpcSender + Send + Sync + 'static>(
+        senders: Vec<S>,
+        failure_threshold: u32,
+        cooldown: Duration,
+        balance_reads: bool,
+    ) -> Self {
+        assert!(!senders.is_empty(), "an RPC pool needs at least one node");
+        let breakers: Vec<Arc<CircuitBreaker>> = senders.iter()
+            .map(|_| Arc::new(CircuitBreaker::new(failure_threshold, cooldown)))
+            .collect();
+        let endpoints = senders.into_iter()
+            .zip(&breakers)
+            .map(|(sender, breaker)| BreakerSender::new(sender, breaker.clone()))
+            .collect();
+        let sender = FailoverSender { endpoints, balance_reads, next_read: AtomicUsize::new(0) };
+        Self {
+            client: RpcClient::new_sender(sender, RpcClientConfig::with_commitment(CommitmentConfig::default())),
+            breakers,
+        }
+    }
+
+    /// Connects to `solana_rpc_url` and any `fallback_rpc_urls`, bounding every request by
+    /// `rpc_timeout_secs`.
+    pub fn from_config(config: &Config) -> Self {
+        let timeout = Duration::from_secs(config.rpc_timeout_secs);
+        let senders = std::iter::once(&config.solana_rpc_url)
+            .chain(&config.fallback_rpc_urls)
+            .map(|url| HttpSender::new_with_timeout(url.clone(), timeout))
+            .collect();
+        Self::new(
+            senders,
+            config.rpc_breaker_threshold,
+            Duration::from_secs(config.rpc_breaker_cooldown_secs),
+            config.balance_rpc_reads,
+        )
+    }
+
+    /// A pool of one mock node; see `RpcClient::new_mock`.
+    pub fn new_mock(url: impl ToString) -> Self {
+        Self::new_mock_with_mocks(url, Mocks::default())
+    }
+
+    /// A pool of one mock node answering with `mocks`; see `RpcClient::new_mock_with_mocks`.
+    pub fn new_mock_with_mocks(url: impl ToString, mocks: Mocks) -> Self {
+        Self::new(vec![MockSender::new_with_mocks(url, mocks)], 5, Duration::from_secs(30), false)
+    }
+
+    /// The nonblocking client that sends through the pool.
+    pub fn nonblocking(&self) -> &nonblocking::rpc_client::RpcClient {
+        self.client.get_inner_client()
+    }
+
+    /// Each node's circuit breaker, in failover order.
+    pub fn breakers(&self) -> &[Arc<CircuitBreaker>] {
+        &self.breakers
+    }
+
+    /// The pool's circuit: closed while any node's is, and open only once every node's is.
+    pub fn circuit_state(&self) -> CircuitState {
+        let states: Vec<CircuitState> = self.breakers.iter().map(|breaker| breaker.state()).collect();
+        if states.contains(&CircuitState::Closed) {
+            CircuitState::Closed
+        } else if states.contains(&CircuitState::HalfOpen) {
+            CircuitState::HalfOpen
+        } else {
+            CircuitState::Open
+        }
+    }
+
+    /// How long until the first open node half-opens; zero unless the pool is open.
+    pub fn retry_after(&self) -> Duration {
+        self.breakers.iter().map(|breaker| breaker.retry_after()).min().unwrap_or_default()
+    }
+}
+
+impl Deref for RpcPool {
+    type Target = RpcClient;
+
+    fn deref(&self) -> &RpcClient {
+        &self.client
+    }
+}
+
+/// Sends each call to the first node that answers it; see `RpcPool`.
+struct FailoverSender<S> {
+    endpoints: Vec<BreakerSender<S>>,
+    balance_reads: bool,
+    next_read: AtomicUsize,
+}
+
+#[async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for FailoverSender<S> {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value, ClientError> {
+        let count = self.endpoints.len();
+        let first = if self.balance_reads && request != RpcRequest::SendTransaction {
+            self.next_read.fetch_add(1, Ordering::Relaxed) % count
+        } else {
+            0
+        };
+
+        let mut last_error = None;
+        for attempt in 0..count {
+            let index = (first + attempt) % count;
+            let send = self.endpoints[index].send(request, params.clone());
+            // Within a request, no call may outlast what's left of its deadline
+            let result = match Deadline::current() {
+                Some(deadline) => match tokio::time::timeout(deadline.remaining(), send).await {
+                    Ok(result) => result,
+                    Err(_) => return Err(ClientErrorKind::Custom(format!("{} waiting on {}", deadline.exceeded(), request)).into()),
+                },
+                None => send.await,
+            };
+            match result {
+                Err(e) if is_transient_error(&e) || is_circuit_open(&e) => {
+                    if attempt + 1 < count {
+                        warn!("RPC node {} unavailable for {} ({}); failing over", index, request, e);
+                    }
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
+        }
+        Err(last_error.expect("a pool has at least one node"))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.endpoints.iter().fold(RpcTransportStats::default(), |total, endpoint| {
+            let stats = endpoint.get_transport_stats();
+            RpcTransportStats {
+                request_count: total.request_count + stats.request_count,
+                elapsed_time: total.elapsed_time + stats.elapsed_time,
+                rate_limited_time: total.rate_limited_time + stats.rate_limited_time,
+            }
+        })
+    }
+
+    fn url(&self) -> String {
+        self.endpoints[0].url()
+    }
+}
+
+/// Runs an RPC call, retrying transient failures with exponential backoff.
+///
+/// Within a request, a retry whose backoff would outlast the request's deadline isn't made.
+///
+/// # Arguments
+/// * `f` - Starts the RPC call to run; called again for every retry.
+/// * `max_retries` - How many times to retry after the first failure.
+/// * `base_delay` - The delay before the first retry; doubled on every subsequent retry.
+///
+/// # Returns
+/// The first successful result, or the last error once retries are exhausted or a
+/// non-transient error is hit.
+pub async fn retry_rpc<T, F, Fut>(
+    mut f: F,
+    max_retries: u32,
+    base_delay: Duration,
+) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let mut retries = 0;
+
+    loop {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(e) if retries < max_retries && is_transient_error(&e) => {
+                // Exponential backoff
+                let delay = base_delay * 2u32.pow(retries);
+                if Deadline::limit(delay) < delay {
+                    return Err(e);
+                }
+                retries += 1;
+                warn!("Transient RPC failure (attempt {}): {}", retries, e);
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Runs `f` over every item concurrently, with at most `max_concurrency` running at once.
+///
+/// # Returns
+/// Each item's output, in the order the items were given.
+pub async fn join_bounded<I, F, Fut>(items: I, max_concurrency: usize, f: F) -> Vec<Fut::Output>
+where
+    I: IntoIterator,
+    F: Fn(I::Item) -> Fut,
+    Fut: Future,
+{
+    let semaphore = Semaphore::new(max_concurrency.max(1));
+    let semaphore = &semaphore;
+    join_all(items.into_iter().map(|item| {
+        let task = f(item);
+        async move {
+            let _permit = semaphore.acquire().await.expect("the semaphore is never closed");
+            task.await
+        }
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::ready;
+    use std::io;
+
+    fn timeout_error() -> ClientError {
+        io::Error::new(io::ErrorKind::TimedOut, "operation timed out").into()
+    }
+
+    #[tokio::test]
+    async fn test_blockhash_cache_reuses_hash_within_ttl() {
+        let cache = BlockhashCache::new(Duration::from_secs(30));
+        let start = Instant::now();
+        let mut fetches = 0;
+
+        let first = cache
+            .get_or_fetch_at(start, || {
+                fetches += 1;
+                ready(Ok(Hash::new_unique()))
+            })
+            .await
+            .unwrap();
+        let second = cache
+            .get_or_fetch_at(start + Duration::from_secs(10), || {
+                fetches += 1;
+                ready(Ok(Hash::new_unique()))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(fetches, 1);
+    }
+
+    #[tokio::test]
+    async fn test_blockhash_cache_refetches_after_expiry() {
+        let cache = BlockhashCache::new(Duration::from_secs(30));
+        let start = Instant::now();
+
+        let first = cache.get_or_fetch_at(start, || ready(Ok(Hash::new_unique()))).await.unwrap();
+        let second = cache
+            .get_or_fetch_at(start + Duration::from_secs(31), || ready(Ok(Hash::new_unique())))
+            .await
+            .unwrap();
+        assert_ne!(first, second);
+
+        // Invalidation forces a refetch even within the window
+        cache.invalidate();
+        let third = cache
+            .get_or_fetch_at(start + Duration::from_secs(32), || ready(Ok(Hash::new_unique())))
+            .await
+            .unwrap();
+        assert_ne!(second, third);
+    }
+
+    #[tokio::test]
+    async fn test_rent_cache_fetches_each_size_once() {
+        let cache = RentCache::new();
+        let mut fetches = 0;
+
+        for _ in 0..3 {
+            let minimum = cache
+                .get_or_fetch(82, || {
+                    fetches += 1;
+                    ready(Ok(1_461_600))
+                })
+                .await
+                .unwrap();
+            assert_eq!(minimum, 1_461_600);
+        }
+        assert_eq!(fetches, 1);
+
+        // Other sizes are fetched separately
+        assert_eq!(cache.get_or_fetch(165, || ready(Ok(2_039_280))).await.unwrap(), 2_039_280);
+    }
+
+    #[test]
+    fn test_slot_tracker_measures_lag() {
+        let tracker = SlotTracker::new();
+        let start = Instant::now();
+
+        assert_eq!(tracker.observe_at(start, 1000), 0);
+        // 4 seconds later the chain should be 10 slots on
+        assert_eq!(tracker.observe_at(start + Duration::from_secs(4), 1010), 0);
+        // A stalled node falls further behind the longer it stays stuck
+        assert_eq!(tracker.observe_at(start + Duration::from_secs(8), 1010), 10);
+        assert_eq!(tracker.observe_at(start + Duration::from_secs(12), 1010), 20);
+        // Catching up clears the lag
+        assert_eq!(tracker.observe_at(start + Duration::from_secs(12), 1030), 0);
+    }
+
+    #[test]
+    fn test_is_blockhash_not_found() {
+        let expired: ClientError = TransactionError::BlockhashNotFound.into();
+        assert!(is_blockhash_not_found(&expired));
+        assert!(!is_blockhash_not_found(&timeout_error()));
+    }
+
+    #[tokio::test]
+    async fn test_retry_rpc_recovers_after_transient_failures() {
+        let mut calls = 0;
+        let result = retry_rpc(
+            || {
+                calls += 1;
+                ready(if calls <= 2 {
+                    Err(timeout_error())
+                } else {
+                    Ok(42u64)
+                })
+            },
+            3,
+            Duration::from_millis(1),
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_rpc_gives_up_after_max_retries() {
+        let mut calls = 0;
+        let result: Result<u64, _> = retry_rpc(
+            || {
+                calls += 1;
+                ready(Err(timeout_error()))
+            },
+            2,
+            Duration::from_millis(1),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_rpc_does_not_retry_logic_errors() {
+        let mut calls = 0;
+        let result: Result<u64, _> = retry_rpc(
+            || {
+                calls += 1;
+                ready(Err(ClientErrorKind::Custom("insufficient balance".to_string()).into()))
+            },
+            3,
+            Duration::from_millis(1),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_join_bounded_caps_concurrency_and_keeps_order() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let outputs = join_bounded(0..10u64, 3, |i| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(running, Ordering::SeqCst);
+                // Later items finish first, so ordering can't come from completion order
+                tokio::time::sleep(Duration::from_millis(20 - i)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                i * 2
+            }
+        })
+        .await;
+
+        assert_eq!(outputs, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    fn config_for(rpc_url: &str, rpc_timeout_secs: Option<u64>) -> Config {
+        let mut config = serde_json::json!({
+            "telegram_token": "",
+            "solana_rpc_url": rpc_url,
+            "jito_bundle_url": "http://block-engine.test/api/v1/bundles",
+            "pump_fun_program_id": "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P",
+            "fee_address": "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM",
+            "min_sol_amount": 0.01,
+            "jito_tip_amount": 0.0004,
+            "encryption_key": "test-key"
+        });
+        if let Some(secs) = rpc_timeout_secs {
+            config["rpc_timeout_secs"] = secs.into();
+        }
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn test_rpc_timeout_defaults_to_thirty_seconds() {
+        assert_eq!(config_for("http://127.0.0.1:8899", None).rpc_timeout_secs, 30);
+    }
+
+    #[test]
+    fn test_stalled_rpc_node_times_out() {
+        // A node that accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let mut held = Vec::new();
+            for stream in listener.incoming() {
+                held.push(stream);
+            }
+        });
+
+        let mut config = config_for(&url, Some(1));
+        config.rpc_breaker_threshold = 1;
+        let rpc_client = RpcPool::from_config(&config);
+        let start = Instant::now();
+        assert!(rpc_client.get_slot().is_err());
+        assert!(start.elapsed() < Duration::from_secs(10), "took {:?}", start.elapsed());
+
+        // The timeout opened the breaker, so the next call doesn't wait on the node at all
+        assert_eq!(rpc_client.circuit_state(), CircuitState::Open);
+        let start = Instant::now();
+        let err = rpc_client.get_slot().unwrap_err();
+        assert!(err.to_string().contains("circuit breaker is open"), "{}", err);
+        assert!(start.elapsed() < Duration::from_millis(500), "took {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn test_breaker_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        let start = Instant::now();
+
+        breaker.record_failure_at(start);
+        breaker.record_failure_at(start);
+        // An answer in between resets the count
+        breaker.record_success();
+        breaker.record_failure_at(start);
+        breaker.record_failure_at(start);
+        assert_eq!(breaker.state_at(start), CircuitState::Closed);
+        assert!(breaker.try_acquire_at(start));
+
+        breaker.record_failure_at(start);
+        assert_eq!(breaker.state_at(start), CircuitState::Open);
+        assert!(!breaker.try_acquire_at(start + Duration::from_secs(29)));
+    }
+
+    #[test]
+    fn test_breaker_half_opens_for_a_single_trial() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let start = Instant::now();
+        breaker.record_failure_at(start);
+
+        let cooled = start + Duration::from_secs(30);
+        assert_eq!(breaker.state_at(cooled), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire_at(cooled));
+        // Only one trial runs at a time
+        assert!(!breaker.try_acquire_at(cooled));
+
+        // A failed trial reopens it for a fresh cooldown
+        breaker.record_failure_at(cooled);
+        assert_eq!(breaker.state_at(cooled + Duration::from_secs(29)), CircuitState::Open);
+
+        let cooled = cooled + Duration::from_secs(30);
+        assert!(breaker.try_acquire_at(cooled));
+        breaker.record_success();
+        assert_eq!(breaker.state_at(cooled), CircuitState::Closed);
+        assert!(breaker.try_acquire_at(cooled) && breaker.try_acquire_at(cooled));
+    }
+
+    /// A node that times out while `down` is set, and otherwise answers every call
+    #[derive(Clone, Default)]
+    struct FlakyNode {
+        down: Arc<std::sync::atomic::AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl RpcSender for FlakyNode {
+        async fn send(&self, request: RpcRequest, _: serde_json::Value) -> Result<serde_json::Value, ClientError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(timeout_error());
+            }
+            Ok(match request {
+                RpcRequest::GetVersion => serde_json::json!({ "solana-core": "1.18.26", "feature-set": 0 }),
+                _ => serde_json::json!(42),
+            })
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "flaky".to_string()
+        }
+    }
+
+    /// A node that takes far longer to answer than any test waits
+    struct HangingNode;
+
+    #[async_trait]
+    impl RpcSender for HangingNode {
+        async fn send(&self, _: RpcRequest, _: serde_json::Value) -> Result<serde_json::Value, ClientError> {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(serde_json::json!(42))
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "hanging".to_string()
+        }
+    }
+
+    // The blocking RpcClient needs a multi-threaded runtime
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_calls_stop_at_the_request_deadline() {
+        use crate::deadline::DeadlineExceeded;
+
+        // The blocking call can't be cancelled from outside, so the call itself gives up
+        let rpc_client = RpcPool::new(vec![HangingNode], 1, Duration::from_secs(30), false);
+        let start = Instant::now();
+        let result = Deadline::after(Duration::from_millis(100))
+            .run(async { Ok(get_slot(&rpc_client)?) })
+            .await;
+        assert!(start.elapsed() < Duration::from_secs(5), "took {:?}", start.elapsed());
+        assert!(result.unwrap_err().downcast_ref::<DeadlineExceeded>().is_some());
+        // Running out of time isn't the node's fault
+        assert_eq!(rpc_client.circuit_state(), CircuitState::Closed);
+
+        // Nor does a retry back off past the deadline
+        let mut calls = 0;
+        let result: anyhow::Result<u64> = Deadline::after(Duration::from_millis(60))
+            .run(async {
+                let retried = retry_rpc(
+                    || {
+                        calls += 1;
+                        ready(Err(timeout_error()))
+                    },
+                    5,
+                    Duration::from_millis(40),
+                )
+                .await;
+                Ok(retried?)
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_breaker_fails_fast_and_recovers_through_half_open() {
+        let node = FlakyNode::default();
+        node.down.store(true, Ordering::SeqCst);
+        let rpc_client = RpcPool::new(vec![node.clone()], 2, Duration::from_millis(200), false);
+
+        assert!(rpc_client.get_slot().is_err());
+        assert!(rpc_client.get_slot().is_err());
+        assert_eq!(rpc_client.circuit_state(), CircuitState::Open);
+
+        // Open: calls fail without reaching the node
+        let err = rpc_client.get_slot().unwrap_err();
+        assert!(err.to_string().contains("circuit breaker is open"));
+        assert!(is_circuit_open(&err) && !is_transient_error(&err), "retrying an open breaker would only wait");
+        assert_eq!(node.calls.load(Ordering::SeqCst), 2);
+
+        // Half-open: the node has recovered, so the trial call closes the breaker
+        node.down.store(false, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(250));
+        assert_eq!(rpc_client.circuit_state(), CircuitState::HalfOpen);
+        assert_eq!(rpc_client.get_slot().unwrap(), 42);
+        assert_eq!(rpc_client.circuit_state(), CircuitState::Closed);
+    }
+
+    /// Asks the pool for the slot directly, skipping the client's one-off version query
+    fn get_slot(rpc_client: &RpcPool) -> Result<u64, ClientError> {
+        rpc_client.send(RpcRequest::GetSlot, serde_json::Value::Null)
+    }
+
+    #[test]
+    fn test_pool_fails_over_from_a_failing_primary() {
+        let (primary, secondary) = (FlakyNode::default(), FlakyNode::default());
+        primary.down.store(true, Ordering::SeqCst);
+        let rpc_client = RpcPool::new(vec![primary.clone(), secondary.clone()], 2, Duration::from_secs(60), false);
+
+        for _ in 0..4 {
+            assert_eq!(get_slot(&rpc_client).unwrap(), 42);
+        }
+        assert_eq!(secondary.calls.load(Ordering::SeqCst), 4);
+        // Once its breaker opens, the primary is skipped without being called
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(rpc_client.breakers()[0].state(), CircuitState::Open);
+        assert_eq!(rpc_client.circuit_state(), CircuitState::Closed);
+
+        // With every node down the pool fails, and opens once each breaker has
+        secondary.down.store(true, Ordering::SeqCst);
+        assert!(get_slot(&rpc_client).is_err());
+        assert!(get_slot(&rpc_client).is_err());
+        assert_eq!(rpc_client.circuit_state(), CircuitState::Open);
+        assert!(is_circuit_open(&get_slot(&rpc_client).unwrap_err()));
+    }
+
+    #[test]
+    fn test_pool_balances_reads_across_healthy_nodes() {
+        let nodes = vec![FlakyNode::default(), FlakyNode::default(), FlakyNode::default()];
+        let rpc_client = RpcPool::new(nodes.clone(), 2, Duration::from_secs(60), true);
+
+        for _ in 0..6 {
+            get_slot(&rpc_client).unwrap();
+        }
+        let calls: Vec<usize> = nodes.iter().map(|node| node.calls.load(Ordering::SeqCst)).collect();
+        assert_eq!(calls, vec![2, 2, 2]);
+
+        // Without balancing, every read goes to the primary
+        let nodes = vec![FlakyNode::default(), FlakyNode::default()];
+        let rpc_client = RpcPool::new(nodes.clone(), 2, Duration::from_secs(60), false);
+        for _ in 0..3 {
+            get_slot(&rpc_client).unwrap();
+        }
+        assert_eq!(nodes[0].calls.load(Ordering::SeqCst), 3);
+        assert_eq!(nodes[1].calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Hands out ordered wallet ids (`w-000001`, `w-000002`, ...) that survive restarts.
+///
+/// The counter is persisted after every increment while the lock is held, so concurrent
+/// callers never share an id and a crash can't reuse one that was already handed out.
+pub struct WalletIdSequence {
+    path: PathBuf,
+    last: Mutex<u64>,
+}
+
+impl WalletIdSequence {
+    /// Opens the sequence stored at `path`, starting from zero if the file doesn't exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let last = match fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .trim()
+                .parse()
+                .with_context(|| format!("Corrupt wallet sequence file: {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read wallet sequence: {}", path.display()))
+            }
+        };
+
+        Ok(Self {
+            path,
+            last: Mutex::new(last),
+        })
+    }
+
+    /// Reserves and returns the next wallet id.
+    pub fn next_id(&self) -> Result<String> {
+        let mut last = self.last.lock().unwrap();
+        let next = *last + 1;
+        self.persist(next)?;
+        *last = next;
+        Ok(format!("w-{:06}", next))
+    }
+
+    // Write to a temporary file and rename so a crash never leaves a truncated counter
+    fn persist(&self, value: u64) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Failed to create wallet sequence directory")?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, value.to_string()).context("Failed to write wallet sequence")?;
+        fs::rename(&tmp, &self.path).context("Failed to persist wallet sequence")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_concurrent_ids_are_unique_ordered_and_persisted() {
+        let path = std::env::temp_dir().join(format!("wallet-seq-{}", uuid::Uuid::new_v4()));
+        let sequence = Arc::new(WalletIdSequence::open(&path).unwrap());
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let sequence = sequence.clone();
+                std::thread::spawn(move || {
+                    (0..5).map(|_| sequence.next_id().unwrap()).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut ids: Vec<String> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        ids.sort();
+
+        // No gaps or duplicates
+        let expected: Vec<String> = (1..=40).map(|n| format!("w-{:06}", n)).collect();
+        assert_eq!(ids, expected);
+
+        // A reopened sequence carries on where the last one stopped
+        let reopened = WalletIdSequence::open(&path).unwrap();
+        assert_eq!(reopened.next_id().unwrap(), "w-000041");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::types::{Page, TransactionResult};
+
+/// What a wallet did in a logged trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeAction {
+    Create,
+    Launch,
+    Buy,
+    Sell,
+    SellAll,
+}
+
+impl TradeAction {
+    /// The action's name, as it appears in JSON.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeAction::Create => "create",
+            TradeAction::Launch => "launch",
+            TradeAction::Buy => "buy",
+            TradeAction::Sell => "sell",
+            TradeAction::SellAll => "sell_all",
+        }
+    }
+}
+
+/// File formats trade history can be exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Parses a file extension such as `csv`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// The MIME type of an export in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+}
+
+/// Columns of a CSV export, in order
+pub const CSV_COLUMNS: [&str; 10] = [
+    "timestamp",
+    "wallet_id",
+    "action",
+    "token_address",
+    "amount",
+    "success",
+    "signature",
+    "bundle_id",
+    "fee_paid",
+    "error",
+];
+
+/// One wallet's part in a trade, successful or not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub timestamp: u64, // Unix seconds
+    pub wallet_id: String,
+    pub action: TradeAction,
+    pub token_address: Option<String>,
+    pub amount: Option<f64>, // SOL for buys, tokens in base units for sells
+    pub success: bool,
+    pub signature: Option<String>,
+    pub bundle_id: Option<String>,
+    pub fee_paid: Option<f64>,
+    pub error: Option<String>,
+}
+
+impl TradeRecord {
+    /// Records a wallet's part in a trade from the trade's outcome, including failures.
+    pub fn from_outcome(
+        action: TradeAction,
+        wallet_id: &str,
+        token_address: Option<&str>,
+        amount: Option<f64>,
+        outcome: &Result<TransactionResult>,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let mut record = Self {
+            timestamp,
+            wallet_id: wallet_id.to_string(),
+            action,
+            token_address: token_address.map(str::to_string),
+            amount,
+            success: false,
+            signature: None,
+            bundle_id: None,
+            fee_paid: None,
+            error: None,
+        };
+        match outcome {
+            Ok(result) => {
+                record.success = result.success;
+                record.signature = result.signature.clone();
+                record.bundle_id = result.bundle_id.clone();
+                record.fee_paid = result.fee_paid;
+                record.error = result.error.clone();
+            }
+            Err(e) => record.error = Some(e.to_string()),
+        }
+        record
+    }
+
+    /// The record as one CSV line in `CSV_COLUMNS` order, with empty fields for missing values.
+    pub fn csv_row(&self) -> String {
+        let optional = |value: &Option<String>| value.as_deref().map(csv_field).unwrap_or_default();
+        let number = |value: Option<f64>| value.map(|n| n.to_string()).unwrap_or_default();
+        let fields = [
+            self.timestamp.to_string(),
+            csv_field(&self.wallet_id),
+            self.action.as_str().to_string(),
+            optional(&self.token_address),
+            number(self.amount),
+            self.success.to_string(),
+            optional(&self.signature),
+            optional(&self.bundle_id),
+            number(self.fee_paid),
+            optional(&self.error),
+        ];
+        format!("{}\n", fields.join(","))
+    }
+}
+
+/// Quotes a CSV field if it holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The header line of a CSV export.
+pub fn csv_header() -> String {
+    format!("{}\n", CSV_COLUMNS.join(","))
+}
+
+/// Append-only trade history, one JSON record per line.
+pub struct TradeLog {
+    path: PathBuf,
+}
+
+impl TradeLog {
+    /// Opens the log at `path`, creating its directory if needed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create trade log directory")?;
+        }
+        Ok(Self { path })
+    }
+
+    /// Appends records to the end of the log.
+    pub fn append(&self, records: &[TradeRecord]) -> Result<()> {
+        let mut lines = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut lines, record).context("Failed to encode trade record")?;
+            lines.push(b'\n');
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(&lines))
+            .with_context(|| format!("Failed to write trade log: {}", self.path.display()))
+    }
+
+    /// Returns a page of one wallet's trades, newest first.
+    pub fn history(&self, wallet_id: &str, offset: usize, limit: usize) -> Result<Page<TradeRecord>> {
+        let mut records = self.wallet_records(wallet_id)?;
+        records.reverse();
+        Ok(Page::new(records, offset, limit))
+    }
+
+    /// Returns every trade of one wallet made between `from` and `to` (Unix seconds, both
+    /// inclusive), oldest first.
+    pub fn export(&self, wallet_id: &str, from: Option<u64>, to: Option<u64>) -> Result<Vec<TradeRecord>> {
+        let mut records = self.wallet_records(wallet_id)?;
+        records.retain(|record| {
+            from.is_none_or(|from| record.timestamp >= from) && to.is_none_or(|to| record.timestamp <= to)
+        });
+        Ok(records)
+    }
+
+    /// Reads one wallet's trades in the order they were logged.
+    fn wallet_records(&self, wallet_id: &str) -> Result<Vec<TradeRecord>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Vec::new());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read trade log: {}", self.path.display()))
+            }
+        };
+
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.context("Failed to read trade log")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: TradeRecord = serde_json::from_str(&line).context("Corrupt trade log record")?;
+            if record.wallet_id == wallet_id {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log() -> (TradeLog, PathBuf) {
+        let path = std::env::temp_dir().join(format!("trades-{}.jsonl", uuid::Uuid::new_v4()));
+        (TradeLog::open(&path).unwrap(), path)
+    }
+
+    fn record(wallet_id: &str, amount: f64) -> TradeRecord {
+        let outcome = Ok(TransactionResult {
+            success: true,
+            signature: Some(format!("sig-{}", amount)),
+            bundle_id: None,
+            error: None,
+            fee_paid: Some(amount * 0.005),
+            serialized_tx: None,
+            confirmation: None,
+            mint: None,
+            signatures: Vec::new(),
+        });
+        TradeRecord::from_outcome(TradeAction::Buy, wallet_id, Some("mint"), Some(amount), &outcome)
+    }
+
+    #[test]
+    fn test_history_pages_newest_first() {
+        let (log, path) = temp_log();
+        assert_eq!(log.history("w-1", 0, 10).unwrap().total, 0);
+
+        log.append(&[record("w-1", 1.0), record("w-2", 9.0)]).unwrap();
+        log.append(&[record("w-1", 2.0)]).unwrap();
+        log.append(&[record("w-1", 3.0)]).unwrap();
+
+        let page = log.history("w-1", 0, 2).unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items.iter().map(|r| r.amount.unwrap()).collect::<Vec<_>>(), vec![3.0, 2.0]);
+
+        let page = log.history("w-1", 2, 2).unwrap();
+        assert_eq!(page.items.iter().map(|r| r.amount.unwrap()).collect::<Vec<_>>(), vec![1.0]);
+        assert!(log.history("w-1", 5, 2).unwrap().items.is_empty());
+
+        assert_eq!(log.history("w-2", 0, 10).unwrap().total, 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_trades_are_recorded() {
+        let (log, path) = temp_log();
+        let rejected = Ok(TransactionResult::failure("Slippage exceeded".to_string()));
+        let errored = Err(anyhow::anyhow!("RPC unavailable"));
+        log.append(&[
+            TradeRecord::from_outcome(TradeAction::Sell, "w-1", Some("mint"), Some(500.0), &rejected),
+            TradeRecord::from_outcome(TradeAction::Sell, "w-1", Some("mint"), Some(500.0), &errored),
+        ])
+        .unwrap();
+
+        let page = log.history("w-1", 0, 10).unwrap();
+        assert!(page.items.iter().all(|record| !record.success));
+        assert_eq!(page.items[0].error.as_deref(), Some("RPC unavailable"));
+        assert_eq!(page.items[1].error.as_deref(), Some("Slippage exceeded"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_export_filters_by_date_and_writes_stable_csv() {
+        let (log, path) = temp_log();
+        let at = |timestamp: u64, amount: f64| TradeRecord { timestamp, ..record("w-1", amount) };
+        let mut failed = TradeRecord::from_outcome(
+            TradeAction::SellAll,
+            "w-1",
+            None,
+            None,
+            &Ok(TransactionResult::failure("Slippage exceeded, \"max 5%\"".to_string())),
+        );
+        failed.timestamp = 300;
+        log.append(&[at(100, 1.0), at(200, 2.0), failed, at(400, 4.0), record("w-2", 9.0)]).unwrap();
+
+        // Both ends are inclusive, and out-of-range trades are left out
+        let exported = log.export("w-1", Some(200), Some(300)).unwrap();
+        assert_eq!(exported.iter().map(|r| r.timestamp).collect::<Vec<_>>(), vec![200, 300]);
+        assert_eq!(log.export("w-1", None, None).unwrap().len(), 4);
+        assert_eq!(log.export("w-1", Some(401), None).unwrap().len(), 0);
+
+        assert_eq!(
+            csv_header(),
+            "timestamp,wallet_id,action,token_address,amount,success,signature,bundle_id,fee_paid,error\n"
+        );
+        assert_eq!(exported[0].csv_row(), "200,w-1,buy,mint,2,true,sig-2,,0.01,\n");
+        assert_eq!(
+            exported[1].csv_row(),
+            "300,w-1,sell_all,,,false,,,,\"Slippage exceeded, \"\"max 5%\"\"\"\n"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
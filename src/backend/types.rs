@@ -2,39 +2,251 @@ use serde::{Deserialize, Serialize};
 use borsh::{BorshSerialize, BorshDeserialize};
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
+use utoipa::ToSchema;
+use crate::fees::FeeSchedule;
+use crate::jito_bundle::MAX_BUNDLE_TRANSACTIONS;
+use crate::logging::Secret;
 
-#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub description: String,
+    pub image_url: String,
+    #[serde(default)]
+    pub telegram_link: Option<String>,
+    #[serde(default)]
+    pub twitter_link: Option<String>, // Profile URL or @handle
+    #[serde(default)]
+    pub website: Option<String>,
+    #[serde(default)]
+    pub discord: Option<String>,
+    #[serde(default)]
+    pub extra_links: HashMap<String, String>, // Any other links, by label
+    #[serde(default)]
+    pub uri: Option<String>, // Hosted metadata JSON, set once pinned
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+}
+
+/// The metadata layout the create instruction carries on-chain.
+///
+/// Frozen so existing programs keep decoding it; links added to `TokenMetadata` since only
+/// appear in the hosted metadata JSON. A layout change needs a new version alongside this one.
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TokenMetadataV1 {
     pub name: String,
     pub symbol: String,
     pub description: String,
     pub image_url: String,
     pub telegram_link: Option<String>,
     pub twitter_link: Option<String>,
+    pub uri: Option<String>,
+    pub decimals: u8,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl From<&TokenMetadata> for TokenMetadataV1 {
+    fn from(metadata: &TokenMetadata) -> Self {
+        Self {
+            name: metadata.name.clone(),
+            symbol: metadata.symbol.clone(),
+            description: metadata.description.clone(),
+            image_url: metadata.image_url.clone(),
+            telegram_link: metadata.telegram_link.clone(),
+            twitter_link: metadata.twitter_link.clone(),
+            uri: metadata.uri.clone(),
+            decimals: metadata.decimals,
+        }
+    }
+}
+
+fn default_decimals() -> u8 {
+    9
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateTokenRequest {
     pub metadata: TokenMetadata,
     pub user_id: i64,
-    pub wallet_id: String,
-    pub private_key: String, // Base58 encoded private key
+    pub wallet_id: String, // Stored wallet that signs the creation
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub private_key: Option<Secret>, // Deprecated: base58 private key, only honoured when allow_raw_key is set
+    #[serde(default)]
+    pub image_data: Option<String>, // Base64 encoded image to pin instead of image_url
+    #[serde(default)]
+    pub image_mime: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool, // Build and sign, but return the transaction instead of sending it
+    #[serde(default)]
+    pub token_program: TokenProgram, // Program the mint is created under
+    #[serde(default)]
+    pub extensions: Vec<MintExtension>, // Token-2022 only
+    #[serde(default)]
+    pub nonce_account: Option<String>, // Durable nonce account the creator controls, so the creation can be resent without expiring
 }
 
+impl CreateTokenRequest {
+    /// How the new token's mint is set up.
+    pub fn mint_options(&self) -> MintOptions {
+        MintOptions {
+            token_program: self.token_program,
+            extensions: self.extensions.clone(),
+        }
+    }
+}
+
+/// The token program a new mint is created under
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenProgram {
+    #[default]
+    Legacy, // SPL Token
+    Token2022, // Supports mint extensions
+}
+
+impl TokenProgram {
+    /// The program's ID, which owns the mint and is passed to every instruction touching it.
+    pub fn id(self) -> Pubkey {
+        match self {
+            TokenProgram::Legacy => spl_token::id(),
+            TokenProgram::Token2022 => spl_token_2022::id(),
+        }
+    }
+}
+
+/// A Token-2022 extension a new mint is created with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MintExtension {
+    /// Withholds a share of every transfer, up to `maximum_fee` base units, for the creator
+    TransferFee { basis_points: u16, maximum_fee: u64 },
+    /// Stores the token's name, symbol and uri on the mint itself
+    Metadata,
+}
+
+/// How a new token's mint account is set up
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MintOptions {
+    pub token_program: TokenProgram,
+    pub extensions: Vec<MintExtension>,
+}
+
+/// Where the key of a wallet registered through `/api/wallet/register` comes from
+///
+/// Deliberately not `Debug`, so the key can't end up in a log line.
+#[derive(Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RegisterWalletRequest {
+    PrivateKey { private_key: Secret }, // Base58 64-byte keypair, as wallets export it
+    KeypairFile { keypair_file: Vec<u8> }, // The JSON byte array of a Solana CLI keypair file
+    Generate, // A fresh keypair made on the server
+}
+
+/// Creates a token and buys it from the listed wallets in one atomic bundle
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchRequest {
+    pub metadata: TokenMetadata,
+    pub user_id: i64,
+    pub wallet_id: String, // Stored wallet that creates the token
+    #[serde(default)]
+    pub private_key: Option<Secret>, // Deprecated: base58 creator private key, only honoured when allow_raw_key is set
+    pub buy_sol_amounts: Vec<f64>,
+    pub buy_wallet_ids: Vec<String>, // Wallets created through /api/wallet/create
+    #[serde(default)]
+    pub snipe_delay_ms: Option<(u64, u64)>, // Waits a random [min, max] ms between the confirmed create and the buys; launches without a bundle
+}
+
+/// A signed bundle to submit through the pacing queue
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueBundleRequest {
+    pub transactions: Vec<String>, // Base64 signed transactions, in bundle order; those tipping more go first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct BuyRequest {
-    pub tokenAddress: String,
-    pub solAmounts: Vec<f64>,
-    pub walletIds: Vec<String>,
-    pub userId: i64,
+    pub token_address: String,
+    pub sol_amounts: Vec<f64>,
+    pub wallet_ids: Vec<String>,
+    pub user_id: i64,
+    #[serde(default)]
+    pub min_tokens_out: Option<Vec<f64>>, // Per-wallet minimum tokens; defaults from slippage config
+    #[serde(default)]
+    pub max_price_impact_bps: Option<u32>,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub via_bundle: bool, // Submit through Jito instead of the public RPC
+    #[serde(default)]
+    pub strategy: Option<SubmitStrategy>, // Overrides via_bundle when set
+    #[serde(default)]
+    pub referrer: Option<String>, // Wallet address paid a share of the trading fee for referring the trade
+    #[serde(default)]
+    pub max_total_sol: Option<f64>, // Most SOL all wallets may spend together; the buy is refused above it
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl BuyRequest {
+    /// How the buy is submitted: `strategy` when given, else Jito for `via_bundle`.
+    pub fn submit_strategy(&self) -> SubmitStrategy {
+        SubmitStrategy::resolve(self.strategy, self.via_bundle)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct SellRequest {
-    pub tokenAddress: String,
-    pub tokenAmounts: Vec<u64>,
-    pub walletIds: Vec<String>,
-    pub userId: i64,
+    pub token_address: String,
+    #[serde(default)]
+    pub token_amounts: Vec<u64>, // Raw base units
+    #[serde(default)]
+    pub ui_token_amounts: Option<Vec<f64>>, // Whole tokens instead, converted with the mint's decimals
+    pub wallet_ids: Vec<String>,
+    pub user_id: i64,
+    #[serde(default)]
+    pub min_sol_out: Option<Vec<f64>>, // Per-wallet minimum SOL; defaults from slippage config
+    #[serde(default)]
+    pub max_price_impact_bps: Option<u32>,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub via_bundle: bool, // Submit through Jito instead of the public RPC
+    #[serde(default)]
+    pub strategy: Option<SubmitStrategy>, // Overrides via_bundle when set
+    #[serde(default)]
+    pub close_account: bool, // Close token accounts the sell empties, reclaiming their rent
+    #[serde(default)]
+    pub referrer: Option<String>, // Wallet address paid a share of the trading fee for referring the trade
+}
+
+impl SellRequest {
+    /// How the sell is submitted: `strategy` when given, else Jito for `via_bundle`.
+    pub fn submit_strategy(&self) -> SubmitStrategy {
+        SubmitStrategy::resolve(self.strategy, self.via_bundle)
+    }
+
+    /// How many amounts the sell lists, in whichever unit it gives them.
+    pub fn amount_count(&self) -> usize {
+        self.ui_token_amounts.as_ref().map_or(self.token_amounts.len(), Vec::len)
+    }
+
+    /// Checks the sell doesn't give its amounts in both units at once.
+    pub fn check_amount_unit(&self) -> Result<(), String> {
+        if self.ui_token_amounts.is_some() && !self.token_amounts.is_empty() {
+            return Err("Give token amounts either as tokenAmounts or uiTokenAmounts, not both".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Sells each wallet's full balance of a token in one coordinated bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SellAllRequest {
+    pub token_address: String,
+    pub wallet_ids: Vec<String>, // Wallets created through /api/wallet/create
+    pub user_id: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +262,35 @@ pub struct BundleResponse {
     pub transactions: Vec<String>,
 }
 
+/// How a trade's transactions reached the cluster
+/// How a trade's transactions reach the chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmitStrategy {
+    #[default]
+    Rpc, // Sent through the RPC, at the configured priority fee if any
+    Jito, // Bundled through Jito with a tip
+    PriorityFee, // Sent through the RPC, priced from recent prioritization fees
+}
+
+impl SubmitStrategy {
+    /// The strategy a request asks for, falling back to its older `via_bundle` switch.
+    pub fn resolve(strategy: Option<Self>, via_bundle: bool) -> Self {
+        strategy.unwrap_or(if via_bundle { Self::Jito } else { Self::Rpc })
+    }
+
+    pub fn is_bundle(self) -> bool {
+        self == Self::Jito
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmissionRoute {
+    Rpc,
+    Jito,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PumpFunToken {
     pub address: String,
@@ -60,7 +301,7 @@ pub struct PumpFunToken {
     pub telegram_link: Option<String>,
     pub twitter_link: Option<String>,
     pub creator: String,
-    pub creation_time: i64,
+    pub creation_time: Option<i64>, // Unix time; unknown for tokens looked up by mint
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
@@ -70,6 +311,67 @@ pub struct BondingCurveData {
     pub total_supply: u64,
     pub sol_reserve: f64,
     pub token_reserve: f64, // Changed from u64 to f64 to match implementation
+    #[serde(default)]
+    pub complete: bool, // Curve has graduated and trading moved to Raydium
+}
+
+/// The side of a trade a quote is priced from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteInput {
+    Sol(f64),    // SOL to spend
+    Tokens(f64), // Tokens to buy
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeQuote {
+    pub sol_amount: f64,
+    pub token_amount: f64,
+    pub fee: f64, // Denominated in the output asset
+    pub price_impact_pct: f64,
+}
+
+/// Expected proceeds of selling tokens back to the curve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SellQuote {
+    pub token_amount: f64,
+    pub sol_out: f64, // Net of the trading fee
+    pub fee: f64, // In SOL
+    pub price_after: f64, // Spot price in SOL per token once the sell lands
+}
+
+/// A buy or sell to price without submitting it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EstimateRequest {
+    Buy(BuyRequest),
+    Sell(SellRequest),
+}
+
+/// What a trade costs end to end, in SOL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub base_amount: f64, // SOL spent by a buy, or received by a sell before fees
+    pub trading_fee: f64,
+    pub jito_tip: f64,
+    pub network_fee: f64,
+    pub total_fees: f64,
+    pub total_amount: f64, // SOL leaving the wallets for a buy, or arriving net for a sell
+    pub fee_percentage: f64, // Total fees as a percentage of the base amount
+    pub transaction_count: usize,
+}
+
+/// What a buy would fill and cost against the curve as it stands, without submitting it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeSimulation {
+    pub expected_tokens: Vec<f64>, // Per wallet, net of the trading fee
+    pub worst_case_tokens: Vec<f64>, // Per wallet, the least the buy accepts before failing on slippage
+    pub total_expected_tokens: f64,
+    pub total_worst_case_tokens: f64,
+    pub price_before: f64, // Spot price in SOL per token
+    pub price_after: f64, // Spot price once every wallet's buy lands
+    pub price_impact_pct: f64,
+    pub costs: CostEstimate,
+    pub rejection: Option<String>, // Why the buy would be refused if submitted as is
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +381,80 @@ pub struct WalletInfo {
     pub token_balance: Option<u64>,
 }
 
+/// One page of a list endpoint's results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize, // Items across all pages
+    pub offset: usize,
+    pub limit: usize,
+    pub next_offset: Option<usize>, // Where the next page starts; `None` on the last page
+}
+
+impl<T> Page<T> {
+    /// Cuts the page starting at `offset` out of the full, ordered result set.
+    pub fn new(all: Vec<T>, offset: usize, limit: usize) -> Self {
+        let total = all.len();
+        let items: Vec<T> = all.into_iter().skip(offset).take(limit).collect();
+        let end = offset.saturating_add(items.len());
+        Self {
+            next_offset: (!items.is_empty() && end < total).then_some(end),
+            items,
+            total,
+            offset,
+            limit,
+        }
+    }
+}
+
+/// One of a token's largest holders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolderInfo {
+    pub address: String, // Token account address
+    pub amount: f64, // In whole tokens
+    pub percentage: f64, // Share of total supply, 0-100
+}
+
+/// Wallets to sweep back to the configured treasury
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepRequest {
+    pub wallet_ids: Vec<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub via_bundle: bool, // Submit through Jito instead of the public RPC
+}
+
+/// One wallet's part in a sweep
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WalletSweep {
+    pub wallet_id: String,
+    pub address: String,
+    pub balance_lamports: u64,
+    pub swept_lamports: u64, // Zero for a wallet holding no more than it keeps
+}
+
+/// Outcome of sweeping wallets back to the treasury
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SweepResult {
+    pub treasury: String,
+    pub kept_lamports: u64, // Left in each wallet: its rent-exempt minimum plus a fee buffer
+    pub wallets: Vec<WalletSweep>,
+    pub total_swept_lamports: u64,
+    pub transaction: TransactionResult,
+}
+
+/// Readiness of the operator fee-collection wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeWalletStatus {
+    pub address: String,
+    pub balance: f64,
+    pub ready: bool, // Funded to the rent-exempt minimum, so small fee transfers can land
+    pub sweep_recommended: bool,
+    pub issues: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeeCalculation {
     pub base_amount: f64,
@@ -87,13 +463,59 @@ pub struct FeeCalculation {
     pub fee_percentage: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TransactionResult {
     pub success: bool,
     pub signature: Option<String>,
     pub bundle_id: Option<String>,
     pub error: Option<String>,
     pub fee_paid: Option<f64>,
+    /// Base64 wire-format transaction, set only for dry runs
+    #[serde(default)]
+    pub serialized_tx: Option<String>,
+    /// How far an RPC send got; `Submitted` means confirmation timed out and the caller should poll
+    #[serde(default)]
+    pub confirmation: Option<ConfirmationStatus>,
+    /// Address of the token mint a creation made
+    #[serde(default)]
+    pub mint: Option<String>,
+    /// Signature of every transaction a submitted bundle carried, in bundle order
+    #[serde(default)]
+    pub signatures: Vec<String>,
+}
+
+/// How a submitted trade is confirmed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationStrategy {
+    #[default]
+    BundleAware, // RPC sends by signature status, Jito bundles by getBundleStatuses
+    Rpc, // Everything by signature status; a bundle by its first transaction
+}
+
+/// Progress of a transaction sent through the RPC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmationStatus {
+    Confirmed,
+    Submitted, // Sent, but not confirmed before the timeout
+}
+
+impl TransactionResult {
+    /// A failed result carrying only an error message.
+    pub fn failure(error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            signature: None,
+            bundle_id: None,
+            error: Some(error.into()),
+            fee_paid: None,
+            serialized_tx: None,
+            confirmation: None,
+            mint: None,
+            signatures: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,15 +564,45 @@ impl ValidationResult {
     }
 }
 
+impl Default for ValidationResult {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PumpFunConfig {
     pub program_id: String,
     pub fee_address: String,
     pub creation_fee: f64,
-    pub trading_fee: f64, // Added trading_fee field
-    pub fee_percentage: f64,
+    pub fee_splits: Vec<(Pubkey, f64)>, // Creation fee recipients and their shares; empty sends it all to `fee_address`
+    pub fee_schedule: FeeSchedule, // Trading fee charged on buys and sells
+    pub referral_fee_share: f64, // Share of a referred trade's fee paid to its referrer
     pub min_sol_amount: f64,
-    pub max_wallets_per_bundle: usize,
+    pub max_wallets_per_bundle: usize, // Governs request validation, buy packing and bundle size alike
+    pub max_fee_sol: f64, // Absolute sanity cap on any single fee transfer
+    pub max_fee_fraction: f64, // Sanity cap on a fee as a fraction of the trade
+    pub rpc_max_retries: u32,
+    pub rpc_retry_base_delay_ms: u64,
+    pub blockhash_cache_ttl_secs: u64,
+    pub default_max_slippage_pct: f64, // Applied when a trade omits explicit minimums
+    pub log_failed_transactions: bool, // Debug: log base64 of failed sends for replay
+    pub validate_curve_reserves: bool, // Reject quotes against empty or corrupt curves
+    pub fee_sweep_alert_sol: f64, // Flag the fee wallet once this much SOL sits unswept
+    pub initial_virtual_sol_reserve: f64, // Reserves a fresh curve starts from, for quoting launch buys
+    pub initial_virtual_token_reserve: f64,
+    pub initial_supply: u64, // Whole tokens minted to the curve at creation, scaled by the token's decimals
+    pub max_slot_lag: Option<u64>, // Reject trades when the RPC node is this many slots behind
+    pub exit_tip_multiplier: f64, // Coordinated exits tip this many times the usual Jito tip
+    pub verify_mint_owner: bool, // Check a mint is owned by a token program before trading it
+    pub balance_buffer_lamports: u64, // Headroom for transaction fees when checking a creator's balance
+    pub confirm_timeout_secs: u64, // How long to wait for a sent transaction to confirm
+    pub confirmation_strategy: ConfirmationStrategy, // Where bundled trades are confirmed
+    pub priority_fee_micro_lamports: Option<u64>, // Compute unit price; enables simulated compute limits when set
+    pub compute_unit_margin_pct: f64, // Headroom added to a transaction's simulated compute units
+    pub max_concurrency: usize, // Most transactions of one trade sent and confirmed at once
+    pub priority_fee_landing_probability: f64, // Share of recent slots a `PriorityFee` price would have landed in
+    pub max_priority_fee_micro_lamports: u64, // Cap on a compute unit price sized from recent fees
 }
 
 impl Default for PumpFunConfig {
@@ -159,10 +611,99 @@ impl Default for PumpFunConfig {
             program_id: "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P".to_string(),
             fee_address: "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM".to_string(),
             creation_fee: 0.05,
-            trading_fee: 0.005, // Added trading_fee
-            fee_percentage: 0.008, // 0.8%
+            fee_splits: Vec::new(),
+            fee_schedule: FeeSchedule::default(),
+            referral_fee_share: 0.0,
             min_sol_amount: 0.02,
-            max_wallets_per_bundle: 16,
+            max_wallets_per_bundle: MAX_BUNDLE_TRANSACTIONS,
+            max_fee_sol: 1.0,
+            max_fee_fraction: 0.1, // 10%
+            rpc_max_retries: 3,
+            rpc_retry_base_delay_ms: 500,
+            blockhash_cache_ttl_secs: 30,
+            default_max_slippage_pct: 5.0,
+            log_failed_transactions: false,
+            validate_curve_reserves: true,
+            fee_sweep_alert_sol: 10.0,
+            initial_virtual_sol_reserve: 30.0,
+            initial_virtual_token_reserve: 1_073_000_000.0,
+            initial_supply: 1_000_000_000,
+            max_slot_lag: None,
+            exit_tip_multiplier: 5.0,
+            verify_mint_owner: true,
+            balance_buffer_lamports: 1_000_000,
+            confirm_timeout_secs: 30,
+            confirmation_strategy: ConfirmationStrategy::default(),
+            priority_fee_micro_lamports: None,
+            compute_unit_margin_pct: 10.0,
+            max_concurrency: 4,
+            priority_fee_landing_probability: 0.75,
+            max_priority_fee_micro_lamports: 1_000_000,
         }
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_report_where_the_next_one_starts() {
+        let first = Page::new((0..5).collect(), 0, 2);
+        assert_eq!((first.items, first.total, first.next_offset), (vec![0, 1], 5, Some(2)));
+        let last = Page::new((0..5).collect(), 4, 2);
+        assert_eq!((last.items, last.next_offset), (vec![4], None));
+
+        // A page ending exactly on the last item has no successor either
+        assert_eq!(Page::new((0..4).collect::<Vec<_>>(), 2, 2).next_offset, None);
+        let past_the_end = Page::new((0..4).collect::<Vec<i32>>(), 10, 2);
+        assert!(past_the_end.items.is_empty());
+        assert_eq!((past_the_end.total, past_the_end.next_offset), (4, None));
+    }
+
+    #[test]
+    fn test_trade_requests_keep_camel_case_wire_format() {
+        let buy: BuyRequest = serde_json::from_value(serde_json::json!({
+            "tokenAddress": "So11111111111111111111111111111111111111112",
+            "solAmounts": [0.5, 1.0],
+            "walletIds": ["w-000001", "w-000002"],
+            "userId": 7,
+            "minTokensOut": [100.0, 200.0],
+            "maxPriceImpactBps": 300,
+            "dryRun": true,
+            "viaBundle": true
+        }))
+        .unwrap();
+        assert_eq!(buy.token_address, "So11111111111111111111111111111111111111112");
+        assert_eq!(buy.sol_amounts, vec![0.5, 1.0]);
+        assert_eq!(buy.wallet_ids, vec!["w-000001", "w-000002"]);
+        assert_eq!(buy.user_id, 7);
+        assert_eq!(buy.min_tokens_out, Some(vec![100.0, 200.0]));
+        assert_eq!(buy.max_price_impact_bps, Some(300));
+        assert!(buy.dry_run && buy.via_bundle);
+
+        let sell: SellRequest = serde_json::from_value(serde_json::json!({
+            "tokenAddress": "So11111111111111111111111111111111111111112",
+            "tokenAmounts": [1000],
+            "walletIds": ["w-000001"],
+            "userId": 7,
+            "minSolOut": [0.1]
+        }))
+        .unwrap();
+        assert_eq!(sell.token_amounts, vec![1000]);
+        assert_eq!(sell.min_sol_out, Some(vec![0.1]));
+        assert!(!sell.dry_run);
+
+        // Serializing writes the same keys back out
+        let json = serde_json::to_value(&sell).unwrap();
+        assert_eq!(json["userId"], 7);
+        assert!(json.get("user_id").is_none());
+
+        let sell_all: SellAllRequest = serde_json::from_value(serde_json::json!({
+            "tokenAddress": "So11111111111111111111111111111111111111112",
+            "walletIds": ["w-000001"],
+            "userId": 7
+        }))
+        .unwrap();
+        assert_eq!(sell_all.wallet_ids, vec!["w-000001"]);
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use solana_sdk::signature::Keypair;
+use std::collections::HashMap;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+/// Marks a file as a wallet store, and its format version
+const MAGIC: &[u8; 4] = b"PSW1";
+
+const SALT_LEN: usize = 16;
+
+/// PBKDF2 rounds used to stretch `encryption_key` into an AES key
+const KEY_ITERATIONS: u32 = 100_000;
+
+/// Wallet keypairs persisted to disk, encrypted with AES-256-GCM.
+///
+/// The key is derived from the configured `encryption_key` with PBKDF2 and a per-store
+/// random salt. The file holds the magic, salt, nonce and ciphertext in that order; every
+/// save uses a fresh nonce.
+pub struct WalletStore {
+    path: PathBuf,
+    salt: [u8; SALT_LEN],
+    key: LessSafeKey,
+    wallets: HashMap<String, Keypair>,
+}
+
+impl WalletStore {
+    /// Opens the store at `path`, starting empty if the file doesn't exist yet.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read, isn't a wallet store, or doesn't
+    /// decrypt with `encryption_key`.
+    pub fn load(path: impl Into<PathBuf>, encryption_key: &str) -> Result<Self> {
+        let path = path.into();
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut salt = [0u8; SALT_LEN];
+                SystemRandom::new()
+                    .fill(&mut salt)
+                    .map_err(|_| anyhow::anyhow!("Failed to generate wallet store salt"))?;
+                return Ok(Self {
+                    key: derive_key(encryption_key, &salt),
+                    path,
+                    salt,
+                    wallets: HashMap::new(),
+                });
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read wallet store: {}", path.display()))
+            }
+        };
+
+        let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+        if contents.len() < header_len || &contents[..MAGIC.len()] != MAGIC {
+            return Err(anyhow::anyhow!("Not a wallet store: {}", path.display()));
+        }
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&contents[MAGIC.len()..MAGIC.len() + SALT_LEN]);
+        let nonce = Nonce::try_assume_unique_for_key(&contents[MAGIC.len() + SALT_LEN..header_len])
+            .map_err(|_| anyhow::anyhow!("Corrupt wallet store nonce"))?;
+
+        let key = derive_key(encryption_key, &salt);
+        let mut sealed = contents[header_len..].to_vec();
+        let plaintext = key
+            .open_in_place(nonce, Aad::from(MAGIC), &mut sealed)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt wallet store; check encryption_key"))?;
+
+        let encoded: HashMap<String, String> =
+            serde_json::from_slice(plaintext).context("Corrupt wallet store contents")?;
+        let wallets = encoded
+            .into_iter()
+            .map(|(wallet_id, secret)| {
+                let bytes = bs58::decode(&secret)
+                    .into_vec()
+                    .with_context(|| format!("Corrupt key for wallet {}", wallet_id))?;
+                let keypair = Keypair::from_bytes(&bytes)
+                    .map_err(|e| anyhow::anyhow!("Corrupt key for wallet {}: {}", wallet_id, e))?;
+                Ok((wallet_id, keypair))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            path,
+            salt,
+            key,
+            wallets,
+        })
+    }
+
+    /// Encrypts the store and writes it to disk, replacing the previous file atomically.
+    pub fn save(&self) -> Result<()> {
+        let encoded: HashMap<&str, String> = self
+            .wallets
+            .iter()
+            .map(|(wallet_id, keypair)| (wallet_id.as_str(), bs58::encode(keypair.to_bytes()).into_string()))
+            .collect();
+        let mut sealed = serde_json::to_vec(&encoded).context("Failed to encode wallet store")?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow::anyhow!("Failed to generate wallet store nonce"))?;
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(MAGIC), &mut sealed)
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt wallet store"))?;
+
+        let mut contents = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + sealed.len());
+        contents.extend_from_slice(MAGIC);
+        contents.extend_from_slice(&self.salt);
+        contents.extend_from_slice(&nonce);
+        contents.extend_from_slice(&sealed);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Failed to create wallet store directory")?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, contents).context("Failed to write wallet store")?;
+        fs::rename(&tmp, &self.path).context("Failed to persist wallet store")
+    }
+
+    /// Stores a wallet under `wallet_id` and saves the store.
+    pub fn add_wallet(&mut self, wallet_id: String, keypair: Keypair) -> Result<()> {
+        self.wallets.insert(wallet_id, keypair);
+        self.save()
+    }
+
+    /// Looks up a stored wallet's keypair.
+    pub fn get_keypair(&self, wallet_id: &str) -> Option<&Keypair> {
+        self.wallets.get(wallet_id)
+    }
+}
+
+fn derive_key(encryption_key: &str, salt: &[u8]) -> LessSafeKey {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(KEY_ITERATIONS).expect("iterations are non-zero"),
+        salt,
+        encryption_key.as_bytes(),
+        &mut key,
+    );
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &key).expect("key is 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    #[test]
+    fn test_wallets_round_trip_and_require_the_right_key() {
+        let path = std::env::temp_dir().join(format!("wallets-{}.enc", uuid::Uuid::new_v4()));
+        let keypair = Keypair::new();
+        let address = keypair.pubkey();
+
+        let mut store = WalletStore::load(&path, "correct horse").unwrap();
+        assert!(store.get_keypair("w-000001").is_none());
+        store.add_wallet("w-000001".to_string(), keypair).unwrap();
+
+        // The secret key never hits the disk in the clear
+        let contents = fs::read(&path).unwrap();
+        let secret = bs58::encode(store.get_keypair("w-000001").unwrap().to_bytes()).into_string();
+        assert!(!contents.windows(secret.len()).any(|w| w == secret.as_bytes()));
+
+        let reopened = WalletStore::load(&path, "correct horse").unwrap();
+        assert_eq!(reopened.get_keypair("w-000001").unwrap().pubkey(), address);
+
+        let err = WalletStore::load(&path, "battery staple").err().unwrap();
+        assert!(err.to_string().contains("decrypt"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
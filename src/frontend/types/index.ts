@@ -108,6 +108,20 @@ export interface BundleResponse {
   transactions: string[];
 }
 
+export interface WalletTradeResult {
+  wallet_id: string;
+  success: boolean;
+  signature?: string;
+  bundle_id?: string;
+  error?: string;
+  fee_paid?: number;
+  serialized_tx?: string;
+}
+
+export interface SellResponse {
+  wallets: WalletTradeResult[];
+}
+
 export type Command = 
   | '/start'
   | '/create'
@@ -5,7 +5,8 @@ import {
   SellRequest, 
   RustApiResponse,
   CreateTokenResponse,
-  BundleResponse 
+  BundleResponse,
+  SellResponse
 } from '../types';
 
 export class RustApiClient {
@@ -85,9 +86,9 @@ export class RustApiClient {
   }
 
   /**
-   * Sell tokens with Jito bundle
+   * Sell tokens, one transaction per wallet; a wallet that fails doesn't stop the rest
    */
-  async sellTokens(request: SellRequest): Promise<BundleResponse> {
+  async sellTokens(request: SellRequest): Promise<SellResponse> {
     try {
       // Debug log
       console.log('Sending sellTokens request:', JSON.stringify(request, null, 2));
@@ -97,7 +98,7 @@ export class RustApiClient {
         throw new Error(response.data.error || 'Failed to sell tokens');
       }
 
-      return response.data.data as BundleResponse;
+      return response.data.data as SellResponse;
     } catch (error) {
       if (axios.isAxiosError(error)) {
         throw new Error(`API Error: ${error.response?.data?.error || error.message}`);